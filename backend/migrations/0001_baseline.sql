@@ -0,0 +1,330 @@
+-- The schema as init_schema left it. Databases it already set up run this too, so every
+-- statement is a no-op on them; columns added over time are also added one by one below.
+
+CREATE TABLE IF NOT EXISTS polls (
+    id BIGSERIAL PRIMARY KEY,
+    question TEXT NOT NULL,
+    options JSONB NOT NULL,
+    option_meta JSONB NOT NULL DEFAULT '[]'::jsonb,
+    commit_phase_end TIMESTAMPTZ NOT NULL,
+    reveal_phase_end TIMESTAMPTZ NOT NULL,
+    category TEXT NOT NULL DEFAULT 'General',
+    commit_sync_completed BOOLEAN NOT NULL DEFAULT false,
+    membership_root TEXT NOT NULL,
+    owner TEXT NOT NULL DEFAULT '',
+    anonymous_owner BOOLEAN NOT NULL DEFAULT false,
+    reveal_tx_hash TEXT NOT NULL DEFAULT '',
+    correct_option SMALLINT,
+    resolved BOOLEAN NOT NULL DEFAULT false,
+    voided BOOLEAN NOT NULL DEFAULT false,
+    auto_resolve BOOLEAN NOT NULL DEFAULT false,
+    visibility TEXT NOT NULL DEFAULT 'public',
+    indexed BOOLEAN NOT NULL DEFAULT false,
+    deleted_at TIMESTAMPTZ,
+    resolve_tx_hash TEXT,
+    resolved_at TIMESTAMPTZ,
+    membership_snapshot TEXT,
+    membership_hash_scheme TEXT NOT NULL DEFAULT 'poseidon',
+    membership_snapshot_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+-- Databases created before BIGSERIAL have an INTEGER polls.id
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1
+        FROM information_schema.columns
+        WHERE table_name = 'polls' AND column_name = 'id' AND data_type = 'integer'
+    ) THEN
+        ALTER TABLE polls ALTER COLUMN id TYPE BIGINT;
+    END IF;
+END$$;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS category TEXT NOT NULL DEFAULT 'General';
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS commit_sync_completed BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS owner TEXT NOT NULL DEFAULT '';
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS reveal_tx_hash TEXT NOT NULL DEFAULT '';
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS auto_resolve BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS voided BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS visibility TEXT NOT NULL DEFAULT 'public';
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS anonymous_owner BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS option_meta JSONB NOT NULL DEFAULT '[]'::jsonb;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS indexed BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+-- Set from indexed PollResolved logs, for /polls/:id/transactions
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS resolve_tx_hash TEXT;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS resolved_at TIMESTAMPTZ;
+-- The snapshot whose nodes a poll's paths are read from; members are found by `position`
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS membership_snapshot TEXT;
+-- The hash a poll's tree was built with; polls from before the choice are Poseidon ones
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS membership_hash_scheme TEXT NOT NULL DEFAULT 'poseidon';
+-- When membership_root was frozen; 0002 backfills older polls with their creation time
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS membership_snapshot_at TIMESTAMPTZ;
+ALTER TABLE polls ALTER COLUMN membership_snapshot_at SET DEFAULT now();
+
+CREATE TABLE IF NOT EXISTS members (
+    id SERIAL PRIMARY KEY,
+    identity_secret TEXT NOT NULL UNIQUE,
+    leaf_index BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+-- Leaf order of the membership tree; rows from before it existed are numbered at startup
+ALTER TABLE members ADD COLUMN IF NOT EXISTS leaf_index BIGINT;
+CREATE UNIQUE INDEX IF NOT EXISTS members_leaf_index_idx ON members(leaf_index);
+
+CREATE TABLE IF NOT EXISTS user_stats (
+    identity_secret TEXT PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    xp BIGINT NOT NULL DEFAULT 0,
+    total_votes BIGINT NOT NULL DEFAULT 0,
+    correct_votes BIGINT NOT NULL DEFAULT 0,
+    tier TEXT NOT NULL DEFAULT 'Seedling',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS poll_members (
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    identity_secret TEXT NOT NULL,
+    position INTEGER,
+    path_bits JSONB,
+    path_siblings JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE(poll_id, identity_secret)
+);
+-- A poll's members in tree order; NULL for polls whose root was built over sorted members
+ALTER TABLE poll_members ADD COLUMN IF NOT EXISTS position INTEGER;
+-- Each member's Merkle path, stored at poll creation; NULL rows are backfilled on first lookup
+ALTER TABLE poll_members
+    ADD COLUMN IF NOT EXISTS path_bits JSONB,
+    ADD COLUMN IF NOT EXISTS path_siblings JSONB;
+
+CREATE TABLE IF NOT EXISTS poll_secrets (
+    id SERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    identity_secret TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE(poll_id, identity_secret)
+);
+
+CREATE TABLE IF NOT EXISTS commitments (
+    id SERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    commitment TEXT NOT NULL,
+    identity_secret TEXT NOT NULL,
+    secret TEXT NOT NULL DEFAULT '',
+    choice SMALLINT NOT NULL DEFAULT 0,
+    nullifier TEXT NOT NULL DEFAULT '',
+    proof TEXT NOT NULL DEFAULT '',
+    public_inputs TEXT[] NOT NULL DEFAULT '{}',
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    onchain_submitted BOOLEAN NOT NULL DEFAULT false,
+    revision INTEGER NOT NULL DEFAULT 1,
+    reveal_tx_hash TEXT NOT NULL DEFAULT '',
+    auto_reveal BOOLEAN NOT NULL DEFAULT true,
+    choice_enc TEXT NOT NULL DEFAULT '',
+    reveal_expired BOOLEAN NOT NULL DEFAULT false,
+    sync_error TEXT NOT NULL DEFAULT '',
+    sync_attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ,
+    sync_state TEXT NOT NULL DEFAULT 'pending'
+        CHECK (sync_state IN ('pending', 'synced', 'failed', 'dead')),
+    proof_version INTEGER NOT NULL DEFAULT 1,
+    source TEXT NOT NULL DEFAULT 'api',
+    commit_state TEXT NOT NULL DEFAULT 'offchain'
+        CHECK (commit_state IN ('offchain', 'queued', 'confirmed', 'failed', 'dead')),
+    commit_tx_hash TEXT NOT NULL DEFAULT '',
+    commit_attempts INTEGER NOT NULL DEFAULT 0,
+    commit_next_attempt_at TIMESTAMPTZ,
+    commit_error TEXT NOT NULL DEFAULT '',
+    commit_confirmed_at TIMESTAMPTZ
+);
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'commitments' AND column_name = 'id'
+    ) THEN
+        ALTER TABLE commitments ADD COLUMN id BIGSERIAL PRIMARY KEY;
+    END IF;
+END$$;
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS secret TEXT NOT NULL DEFAULT '';
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS choice SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS nullifier TEXT NOT NULL DEFAULT '';
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS proof TEXT NOT NULL DEFAULT '';
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS public_inputs TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS onchain_submitted BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS revision INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS reveal_tx_hash TEXT NOT NULL DEFAULT '';
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS auto_reveal BOOLEAN NOT NULL DEFAULT true;
+-- Sealed choice; when set, `choice` holds -1
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS choice_enc TEXT NOT NULL DEFAULT '';
+-- Set once the reveal window plus grace passed without the relayer submitting it
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS reveal_expired BOOLEAN NOT NULL DEFAULT false;
+-- The relayer's last error submitting the commitment
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS sync_error TEXT NOT NULL DEFAULT '';
+-- Rows from before proof bundles carried a version are version 1
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS proof_version INTEGER NOT NULL DEFAULT 1;
+-- 'api' for commits posted to the backend, 'chain' for ones indexed from the contract
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'api';
+-- Failed batch reveals so far; reveal sync skips the row until next_attempt_at passes
+ALTER TABLE commitments
+    ADD COLUMN IF NOT EXISTS sync_attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ;
+-- Where the commitment is in reveal sync; 'dead' rows are out of it until requeued
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS sync_state TEXT NOT NULL DEFAULT 'pending'
+    CHECK (sync_state IN ('pending', 'synced', 'failed', 'dead'));
+-- On-chain commit() relaying (ONCHAIN_COMMITS); 'offchain' rows are never sent
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS commit_state TEXT NOT NULL DEFAULT 'offchain'
+    CHECK (commit_state IN ('offchain', 'queued', 'confirmed', 'failed', 'dead'));
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS commit_tx_hash TEXT NOT NULL DEFAULT '';
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS commit_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS commit_next_attempt_at TIMESTAMPTZ;
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS commit_error TEXT NOT NULL DEFAULT '';
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS commit_confirmed_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS commitments_commit_queue_idx
+    ON commitments(id) WHERE commit_state IN ('queued', 'failed');
+-- Rebuilt rather than kept: older databases may carry it with another definition
+DROP INDEX IF EXISTS commitments_poll_commitment_idx;
+CREATE INDEX commitments_poll_commitment_idx ON commitments(poll_id, commitment);
+
+CREATE TABLE IF NOT EXISTS votes (
+    id SERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    nullifier TEXT NOT NULL,
+    choice SMALLINT NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    source TEXT NOT NULL DEFAULT 'api' CHECK (source IN ('api', 'relayer', 'chain'))
+);
+ALTER TABLE votes ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'api'
+    CHECK (source IN ('api', 'relayer', 'chain'));
+CREATE UNIQUE INDEX IF NOT EXISTS votes_poll_nullifier_idx ON votes(poll_id, nullifier);
+CREATE UNIQUE INDEX IF NOT EXISTS votes_nullifier_idx ON votes(poll_id, nullifier);
+
+CREATE TABLE IF NOT EXISTS vote_conflicts (
+    id BIGSERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    nullifier TEXT NOT NULL,
+    kept_choice SMALLINT NOT NULL,
+    kept_source TEXT NOT NULL,
+    rejected_choice SMALLINT NOT NULL,
+    rejected_source TEXT NOT NULL,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS vote_conflicts_poll_idx ON vote_conflicts(poll_id);
+
+CREATE TABLE IF NOT EXISTS proof_jobs (
+    id UUID PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'running', 'done', 'failed')),
+    bundle TEXT,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS proof_cache (
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    identity_secret TEXT NOT NULL,
+    request_hash TEXT NOT NULL,
+    bundle TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (poll_id, identity_secret)
+);
+
+-- Frontier of the append-only membership tree, updated as members join
+CREATE TABLE IF NOT EXISTS membership_tree (
+    id BOOLEAN PRIMARY KEY DEFAULT true CHECK (id),
+    leaf_count BIGINT NOT NULL,
+    filled_subtrees JSONB NOT NULL,
+    root TEXT NOT NULL,
+    scheme TEXT NOT NULL DEFAULT 'poseidon',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+ALTER TABLE membership_tree ADD COLUMN IF NOT EXISTS scheme TEXT NOT NULL DEFAULT 'poseidon';
+
+-- Membership trees keyed by a digest of the ordered member list they were built from
+CREATE TABLE IF NOT EXISTS membership_snapshots (
+    digest TEXT PRIMARY KEY,
+    root TEXT NOT NULL,
+    depth INTEGER NOT NULL,
+    member_count INTEGER NOT NULL,
+    paths JSONB NOT NULL,
+    nodes_stored BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+-- The sibling nodes of a snapshot's tree, so a path is `depth` indexed reads. Sets no poll
+-- references are pruned and nodes_stored cleared; they are written again on demand.
+ALTER TABLE membership_snapshots
+    ADD COLUMN IF NOT EXISTS nodes_stored BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE IF NOT EXISTS merkle_nodes (
+    snapshot_digest TEXT NOT NULL REFERENCES membership_snapshots(digest) ON DELETE CASCADE,
+    level INTEGER NOT NULL,
+    node_index BIGINT NOT NULL,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (snapshot_digest, level, node_index)
+);
+
+CREATE TABLE IF NOT EXISTS reveal_batches (
+    id BIGSERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
+    tx_hash TEXT NOT NULL,
+    item_count INTEGER NOT NULL,
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    gas_used BIGINT,
+    effective_gas_price BIGINT,
+    commit_ids BIGINT[] NOT NULL DEFAULT '{}'
+);
+-- Receipt gas and price (wei) for batches the relayer sent, and what each batch revealed
+ALTER TABLE reveal_batches
+    ADD COLUMN IF NOT EXISTS gas_used BIGINT,
+    ADD COLUMN IF NOT EXISTS effective_gas_price BIGINT,
+    ADD COLUMN IF NOT EXISTS commit_ids BIGINT[] NOT NULL DEFAULT '{}';
+CREATE INDEX IF NOT EXISTS reveal_batches_poll_idx ON reveal_batches(poll_id);
+
+-- Last log the indexer processed per contract; it resumes from here after a restart
+CREATE TABLE IF NOT EXISTS indexer_state (
+    contract_address TEXT PRIMARY KEY,
+    last_block BIGINT NOT NULL,
+    last_log_index BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Logs the indexer already handled, so replays are skipped; pruned behind the checkpoint
+CREATE TABLE IF NOT EXISTS processed_logs (
+    tx_hash TEXT NOT NULL,
+    log_index BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    processed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tx_hash, log_index)
+);
+CREATE INDEX IF NOT EXISTS processed_logs_block_idx ON processed_logs(block_number);
+
+-- Logs the indexer failed to apply, retried with backoff until they succeed or go dead
+CREATE TABLE IF NOT EXISTS failed_events (
+    id BIGSERIAL PRIMARY KEY,
+    log JSONB NOT NULL,
+    sender TEXT,
+    error TEXT NOT NULL,
+    attempts INT NOT NULL DEFAULT 1,
+    next_retry_at TIMESTAMPTZ NOT NULL,
+    dead BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS failed_events_due_idx ON failed_events(next_retry_at) WHERE NOT dead;
+
+-- Polls written before createPoll is sent; a sent one whose insert failed is stored by reconcile
+CREATE TABLE IF NOT EXISTS pending_onchain_polls (
+    id BIGSERIAL PRIMARY KEY,
+    payload JSONB NOT NULL,
+    state TEXT NOT NULL DEFAULT 'sending' CHECK (state IN ('sending', 'sent', 'stored', 'failed')),
+    poll_id BIGINT,
+    tx_hash TEXT,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS pending_onchain_polls_sent_idx ON pending_onchain_polls(id) WHERE state = 'sent';
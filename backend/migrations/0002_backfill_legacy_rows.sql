@@ -0,0 +1,28 @@
+-- Data fixups init_schema used to rerun on every boot; they only matter for rows written
+-- before the columns they fill existed.
+
+UPDATE polls SET category = 'General' WHERE category IS NULL OR category = '';
+UPDATE polls SET membership_snapshot_at = created_at WHERE membership_snapshot_at IS NULL;
+
+UPDATE commitments SET onchain_submitted = false WHERE onchain_submitted IS NULL;
+
+-- sync_state replaces the sync_failed flag: carry existing rows over, then drop it
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'commitments' AND column_name = 'sync_failed'
+    ) THEN
+        UPDATE commitments SET sync_state = CASE
+            WHEN onchain_submitted THEN 'synced'
+            WHEN sync_failed THEN 'dead'
+            WHEN sync_attempts > 0 THEN 'failed'
+            ELSE 'pending'
+        END;
+        ALTER TABLE commitments DROP COLUMN sync_failed;
+    END IF;
+END$$;
+
+-- Legacy rows have no identity; the commitment stands in for it, as it does for chain rows
+UPDATE commitments SET identity_secret = commitment
+WHERE identity_secret IS NULL OR identity_secret = '';
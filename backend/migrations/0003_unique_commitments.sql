@@ -0,0 +1,22 @@
+-- One commitment per identity and poll. Databases from before the rule can hold several:
+-- the latest one (by recorded_at, then id) is kept and the rest are deleted, once.
+DELETE FROM commitments c
+USING (
+    SELECT ctid, ROW_NUMBER() OVER (
+        PARTITION BY poll_id, identity_secret ORDER BY recorded_at DESC, id DESC
+    ) AS rn
+    FROM commitments
+) d
+WHERE c.ctid = d.ctid AND d.rn > 1;
+CREATE UNIQUE INDEX IF NOT EXISTS commitments_poll_identity_idx
+    ON commitments(poll_id, identity_secret);
+
+-- Legacy rows default to an empty nullifier; existing duplicates leave the index
+-- uncreated (with a warning) rather than failing the migration.
+DO $$
+BEGIN
+    CREATE UNIQUE INDEX IF NOT EXISTS commitments_poll_nullifier_idx
+        ON commitments(poll_id, nullifier) WHERE nullifier <> '';
+EXCEPTION WHEN unique_violation THEN
+    RAISE WARNING 'duplicate commitment nullifiers; commitments_poll_nullifier_idx not created';
+END$$;
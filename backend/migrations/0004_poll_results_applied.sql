@@ -0,0 +1,5 @@
+-- When the poll's votes were credited to user_stats; set once, so a poll resolved by both
+-- the API and its PollResolved log is only counted the first time
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS results_applied_at TIMESTAMPTZ;
+-- Every resolution so far has applied its results
+UPDATE polls SET results_applied_at = COALESCE(resolved_at, now()) WHERE resolved;
@@ -0,0 +1,2 @@
+-- votes_nullifier_idx repeated votes_poll_nullifier_idx, which record_vote's ON CONFLICT names
+DROP INDEX IF EXISTS votes_nullifier_idx;
@@ -0,0 +1,16 @@
+-- Retention scrubbing (RETENTION_DAYS): once a poll is resolved and its reveals are synced,
+-- its secrets and proofs are removed. Scrubbed commitments keep empty values, not NULLs.
+ALTER TABLE commitments ADD COLUMN IF NOT EXISTS scrubbed_at TIMESTAMPTZ;
+ALTER TABLE polls ADD COLUMN IF NOT EXISTS secrets_scrubbed_at TIMESTAMPTZ;
+
+-- One row per scrub, kept after the poll itself is gone
+CREATE TABLE IF NOT EXISTS secret_scrubs (
+    id BIGSERIAL PRIMARY KEY,
+    poll_id BIGINT NOT NULL,
+    secrets_deleted BIGINT NOT NULL,
+    commitments_scrubbed BIGINT NOT NULL,
+    cached_proofs_deleted BIGINT NOT NULL,
+    requested_by TEXT NOT NULL,
+    scrubbed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS secret_scrubs_poll_idx ON secret_scrubs(poll_id);
@@ -0,0 +1,3 @@
+-- Proof jobs are readable only by the member who requested them. Jobs from before this
+-- migration have no owner and can no longer be fetched.
+ALTER TABLE proof_jobs ADD COLUMN IF NOT EXISTS identity_secret TEXT NOT NULL DEFAULT '';
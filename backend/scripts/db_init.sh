@@ -1,7 +1,8 @@
 #!/usr/bin/env bash
 set -euo pipefail
 
-# Initialize Postgres schema for VeilCast using the bundled SQL file.
+# Wipe VeilCast data (polls, members, commitments, votes) using the bundled SQL file.
+# The backend creates and migrates the schema itself; run it once before this.
 # Usage: ./backend/scripts/db_init.sh
 
 DB_CONTAINER=${DB_CONTAINER:-veilcast-db}
@@ -18,4 +19,4 @@ docker cp "$SQL_FILE_HOST" "$DB_CONTAINER:$SQL_FILE_CONTAINER"
 
 docker exec -i "$DB_CONTAINER" psql -U "$DB_USER" -d "$DB_NAME" -f "$SQL_FILE_CONTAINER"
 
-echo "DB init completed."
+echo "DB reset completed."
@@ -0,0 +1,114 @@
+// Prove / verify the VeilCast circuit with noir_js + bb.js (UltraHonk, keccak transcript).
+// Usage: node scripts/noir_prover.mjs <prove|verify|commitment> <circuit.json> [input.json]
+// The input JSON is read from the file, or from stdin when none is given.
+// prove input:  { poll_id, choice, num_options, secret, identity_secret, membership_root, path_bits?, path_siblings? }
+// num_options is only passed to circuits whose ABI declares it.
+// prove output: { proof: "0x...", public_inputs: [choice, commitment, nullifier, poll_id, membership_root], commitment, nullifier }
+// verify input: { proof: "0x...", public_inputs: [commitment, nullifier, poll_id, membership_root], vk?: "0x..." }
+// vk is the key the server pinned at startup; a circuit deriving a different key never verifies.
+// verify output: { valid: true|false, error? }
+// commitment input: { poll_id, choice, secret, identity_secret }
+// commitment output: { commitment: "0x...", nullifier: "0x..." }, the same values prove binds
+
+import fs from 'fs';
+import { Noir } from '@noir-lang/noir_js';
+import { Barretenberg, Fr, BN254_FR_MODULUS, UltraHonkBackend } from '@aztec/bb.js';
+
+const DEFAULT_DEPTH = 20;
+const MOD = BigInt(BN254_FR_MODULUS.toString());
+const mod = (x) => {
+  const n = typeof x === 'bigint' ? x : BigInt(x);
+  return ((n % MOD) + MOD) % MOD;
+};
+const toHex = (n) => '0x' + BigInt(n).toString(16);
+const ZERO_FR = new Fr(0n);
+
+async function poseidon2Hash(bb, left, right) {
+  const [result] = await bb.poseidon2Permutation([new Fr(mod(left)), new Fr(mod(right)), ZERO_FR, ZERO_FR]);
+  return BigInt(result.toString());
+}
+
+// The circuit's path length, so a build for another MERKLE_DEPTH gets matching arrays.
+function circuitDepth(circuit) {
+  const param = circuit.abi.parameters.find((p) => p.name === 'path_bits');
+  return param?.type?.length ?? DEFAULT_DEPTH;
+}
+
+function padPath(values, depth) {
+  return Array.from({ length: depth }, (_, i) => mod(values?.[i] ?? 0).toString());
+}
+
+async function commitmentFor(bb, data) {
+  const commitment = await poseidon2Hash(bb, data.choice, data.secret);
+  const nullifier = await poseidon2Hash(bb, data.identity_secret, data.poll_id);
+  return { commitment, nullifier };
+}
+
+async function commitment(_circuit, data) {
+  const bb = await Barretenberg.new(1);
+  const { commitment, nullifier } = await commitmentFor(bb, data);
+  return { commitment: toHex(commitment), nullifier: toHex(nullifier) };
+}
+
+async function prove(circuit, data) {
+  const bb = await Barretenberg.new(1);
+  const { commitment, nullifier } = await commitmentFor(bb, data);
+  const depth = circuitDepth(circuit);
+  const input = {
+    commitment: commitment.toString(),
+    nullifier: nullifier.toString(),
+    poll_id: mod(data.poll_id).toString(),
+    membership_root: mod(data.membership_root).toString(),
+    choice: mod(data.choice).toString(),
+    secret: mod(data.secret).toString(),
+    identity_secret: mod(data.identity_secret).toString(),
+    path_bits: padPath(data.path_bits, depth),
+    path_siblings: padPath(data.path_siblings, depth),
+  };
+  if (circuit.abi.parameters.some((p) => p.name === 'num_options')) {
+    input.num_options = String(data.num_options);
+  }
+  const { witness } = await new Noir(circuit).execute(input);
+  const honk = new UltraHonkBackend(circuit.bytecode, { threads: 1 });
+  const { proof, publicInputs } = await honk.generateProof(witness, { keccak: true });
+  return {
+    proof: '0x' + Buffer.from(proof).toString('hex'),
+    public_inputs: [String(data.choice), ...publicInputs.map(toHex)],
+    commitment: toHex(commitment),
+    nullifier: toHex(nullifier),
+  };
+}
+
+async function verify(circuit, data) {
+  const honk = new UltraHonkBackend(circuit.bytecode, { threads: 1 });
+  if (data.vk) {
+    const vk = Buffer.from(await honk.getVerificationKey({ keccak: true })).toString('hex');
+    if (vk !== data.vk.replace(/^0x/, '')) {
+      return { valid: false, error: 'circuit does not match the pinned verification key' };
+    }
+  }
+  const proof = Uint8Array.from(Buffer.from(data.proof.replace(/^0x/, ''), 'hex'));
+  const publicInputs = data.public_inputs.map((v) => '0x' + mod(v).toString(16).padStart(64, '0'));
+  const valid = await honk.verifyProof({ proof, publicInputs }, { keccak: true });
+  return { valid };
+}
+
+async function main() {
+  const [mode, circuitPath, inputPath] = process.argv.slice(2);
+  const modes = { prove, verify, commitment };
+  if (!modes[mode] || !circuitPath) {
+    console.error('usage: noir_prover.mjs <prove|verify|commitment> <circuit.json> [input.json]');
+    process.exit(2);
+  }
+  const circuit = JSON.parse(fs.readFileSync(circuitPath, 'utf8'));
+  // The backend pipes the inputs over stdin so vote secrets never touch the disk.
+  const data = JSON.parse(fs.readFileSync(inputPath ?? 0, 'utf8'));
+  const out = await modes[mode](circuit, data);
+  process.stdout.write(JSON.stringify(out));
+  process.exit(0);
+}
+
+main().catch((err) => {
+  console.error(err?.message ?? err);
+  process.exit(1);
+});
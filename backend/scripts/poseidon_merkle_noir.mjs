@@ -1,6 +1,6 @@
 // Compute Poseidon2 Merkle root/paths using noir_js (same engine/version as circuit).
-// Usage: node scripts/poseidon_merkle_noir.mjs members.json
-// Input JSON: { "members": ["0x...", ...], "depth": 20 }
+// Usage: node scripts/poseidon_merkle_noir.mjs [members.json]
+// Input JSON (from the file, or stdin when none is given): { "members": ["0x...", ...], "depth": 20 }
 // Output JSON: { "root": "0x...", "paths": { "<identity>": { bits: [...], siblings: [...] } } }
 
 import fs from 'fs';
@@ -101,13 +101,9 @@ const normalizeMember = (value) => {
 };
 
 async function main() {
+  // The backend pipes the payload over stdin so member secrets never touch the disk.
   const file = process.argv[2];
-  if (!file) {
-    console.error('Usage: node scripts/poseidon_merkle_noir.mjs members.json');
-    process.exit(1);
-  }
-
-  const raw = fs.readFileSync(file, 'utf8');
+  const raw = fs.readFileSync(file ?? 0, 'utf8');
   const parsed = JSON.parse(raw);
   const memberEntries = (parsed.members || []).map((m) => ({
     original: m.toString(),
@@ -126,7 +122,7 @@ async function main() {
   }
 
   const res = await buildTree(memberEntries, depth);
-  console.log(JSON.stringify({ ...res, depth }, null, 2));
+  process.stdout.write(JSON.stringify({ ...res, depth }));
 }
 
 main().catch((err) => {
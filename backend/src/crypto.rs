@@ -0,0 +1,111 @@
+//! Application-level AES-256-GCM for vote secrets and choices stored in Postgres.
+use crate::error::{AppError, AppResult};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+const PREFIX: &str = "enc:";
+const NONCE_LEN: usize = 12;
+
+/// Seals values as `enc:<key_id>:<hex(nonce || ciphertext)>`. The first key encrypts; any
+/// configured key decrypts, so rows written before a rotation stay readable.
+#[derive(Clone)]
+pub struct FieldCipher {
+    keys: Vec<(String, Aes256Gcm)>,
+}
+
+impl std::fmt::Debug for FieldCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ids: Vec<&str> = self.keys.iter().map(|(id, _)| id.as_str()).collect();
+        f.debug_struct("FieldCipher")
+            .field("key_ids", &ids)
+            .finish()
+    }
+}
+
+impl FieldCipher {
+    /// `keys` are `(key_id, 32-byte key)` pairs; returns `None` when empty.
+    pub fn new(keys: Vec<(String, [u8; 32])>) -> Option<Self> {
+        let keys: Vec<_> = keys
+            .into_iter()
+            .map(|(id, key)| (id, Aes256Gcm::new(&Key::<Aes256Gcm>::from(key))))
+            .collect();
+        (!keys.is_empty()).then_some(Self { keys })
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> AppResult<String> {
+        let (key_id, cipher) = &self.keys[0];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| AppError::External("field encryption failed".into()))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{PREFIX}{key_id}:{}", hex::encode(sealed)))
+    }
+
+    /// Decrypts a sealed value; values without the `enc:` prefix are legacy plaintext.
+    pub fn decrypt(&self, value: &str) -> AppResult<String> {
+        let Some(rest) = value.strip_prefix(PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let (key_id, payload) = rest
+            .split_once(':')
+            .ok_or_else(|| AppError::External("malformed encrypted value".into()))?;
+        let (_, cipher) = self
+            .keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .ok_or_else(|| AppError::External(format!("unknown encryption key id {key_id}")))?;
+        let sealed = hex::decode(payload)
+            .map_err(|_| AppError::External("malformed encrypted value".into()))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(AppError::External("malformed encrypted value".into()));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("split at NONCE_LEN");
+        let plaintext = cipher
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| AppError::External("field decryption failed".into()))?;
+        String::from_utf8(plaintext)
+            .map_err(|_| AppError::External("decrypted value is not utf-8".into()))
+    }
+
+    /// True when `value` is already sealed with the active key.
+    pub fn is_current(&self, value: &str) -> bool {
+        value
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .is_some_and(|(id, _)| id == self.active_key_id())
+    }
+}
+
+/// Parses `FIELD_ENCRYPTION_KEYS`-style `id:hex32,id:hex32` lists. A malformed entry is an
+/// error rather than skipped: dropping a mistyped new key would leave the old one active.
+pub fn parse_keys(raw: &str) -> AppResult<Vec<(String, [u8; 32])>> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .enumerate()
+        .map(|(i, entry)| {
+            let invalid = || {
+                AppError::Validation(format!(
+                    "FIELD_ENCRYPTION_KEYS entry {} is not <id>:<64 hex chars>",
+                    i + 1
+                ))
+            };
+            let (id, key) = entry
+                .split_once(':')
+                .filter(|(id, _)| !id.is_empty())
+                .ok_or_else(invalid)?;
+            let bytes: [u8; 32] = hex::decode(key.trim_start_matches("0x"))
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(invalid)?;
+            Ok((id.to_string(), bytes))
+        })
+        .collect()
+}
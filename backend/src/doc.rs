@@ -1,9 +1,18 @@
 #![allow(dead_code)]
 //! OpenAPI / Swagger documentation definitions.
+use crate::error::{ErrorBody, ProofErrorKind};
 use crate::types::{
-    CommitRequest, CommitResponse, CommitStatusResponse, CreatePollRequest, LoginRequest,
-    LoginResponse, MeResponse, MembershipStatusResponse, PollResponse, ProveRequest, RevealRequest,
-    RevealResponse,
+    BatchCommitError, BatchCommitItem, BatchCommitResult, CommitRequest, CommitResponse,
+    CommitStatusResponse, CommitmentRequest, CommitmentResponse, CreatePollRequest,
+    DeadCommitResponse, FunnelStage, IndexerStatusResponse, LoginRequest, LoginResponse,
+    MeResponse, MembershipRootVerification, MembershipSnapshotResponse, MembershipStatusResponse,
+    MyCommitResponse, MyStatusResponse, OptionMeta, ParticipationResponse,
+    PollMembershipRootResponse, PollResponse, PollSyncStatusResponse, PollTransactionResponse,
+    PollVisibility, ProveJobAccepted, ProveJobResponse, ProveRequest, PruneStalePollsResponse,
+    ReceiptVerifyResponse, RedactedCommitResponse, RelayerStatusResponse, RelayerWalletResponse,
+    RequeueDeadCommitResponse, RequeueDeadEventsResponse, RevealBatchResponse, RevealRequest,
+    RevealResponse, RevealSyncResponse, ScrubSecretsResponse, SyncControlResponse,
+    VoteConflictResponse, VoteSourcesResponse, ZkInfoResponse,
 };
 use crate::zk::ProofBundle;
 use utoipa::OpenApi;
@@ -12,32 +21,96 @@ use utoipa::OpenApi;
 #[openapi(
     paths(
         health_doc,
+        zk_info_doc,
+        indexer_status_doc,
+        relayer_status_doc,
+        metrics_doc,
         create_poll_doc,
         list_polls_doc,
         get_poll_doc,
+        compute_commitment_doc,
         record_commit_doc,
+        withdraw_commit_doc,
+        batch_commit_doc,
         generate_proof_doc,
+        get_proof_job_doc,
         reveal_vote_doc,
+        reveal_batches_doc,
+        poll_transactions_doc,
+        sync_status_doc,
+        list_commits_doc,
+        vote_sources_doc,
+        participation_doc,
+        verify_receipt_doc,
         membership_status_doc,
+        poll_membership_root_doc,
         commit_status_doc,
+        my_status_doc,
+        my_commit_doc,
         login_doc,
-        me_doc
+        me_doc,
+        prune_stale_polls_doc,
+        delete_poll_doc,
+        scrub_poll_secrets_doc,
+        requeue_dead_events_doc,
+        list_dead_commits_doc,
+        requeue_dead_commit_doc,
+        trigger_reveal_sync_doc,
+        pause_reveal_sync_doc,
+        resume_reveal_sync_doc,
+        recompute_membership_snapshot_doc
     ),
     components(
         schemas(
             CreatePollRequest,
+            OptionMeta,
             PollResponse,
+            PollVisibility,
+            CommitmentRequest,
+            CommitmentResponse,
             CommitRequest,
             CommitResponse,
+            BatchCommitItem,
+            BatchCommitResult,
+            BatchCommitError,
             CommitStatusResponse,
             ProveRequest,
+            ProveJobAccepted,
+            ProveJobResponse,
             RevealRequest,
             RevealResponse,
+            RevealBatchResponse,
+            PollTransactionResponse,
+            PollSyncStatusResponse,
+            RedactedCommitResponse,
+            VoteSourcesResponse,
+            VoteConflictResponse,
+            ParticipationResponse,
+            FunnelStage,
+            ReceiptVerifyResponse,
             ProofBundle,
             LoginRequest,
             LoginResponse,
             MeResponse,
-            MembershipStatusResponse
+            MembershipStatusResponse,
+            PollMembershipRootResponse,
+            MembershipRootVerification,
+            MyStatusResponse,
+            MyCommitResponse,
+            PruneStalePollsResponse,
+            ScrubSecretsResponse,
+            RequeueDeadEventsResponse,
+            DeadCommitResponse,
+            RequeueDeadCommitResponse,
+            RevealSyncResponse,
+            SyncControlResponse,
+            MembershipSnapshotResponse,
+            ZkInfoResponse,
+            IndexerStatusResponse,
+            RelayerStatusResponse,
+            RelayerWalletResponse,
+            ErrorBody,
+            ProofErrorKind
         )
     ),
     tags(
@@ -54,18 +127,54 @@ pub struct ApiDoc;
 )]
 pub async fn health_doc() {}
 
+#[utoipa::path(
+    get,
+    path = "/zk/info",
+    responses((status = 200, body = ZkInfoResponse))
+)]
+pub async fn zk_info_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/indexer/status",
+    responses((status = 200, body = IndexerStatusResponse))
+)]
+pub async fn indexer_status_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/relayer/status",
+    responses((status = 200, body = RelayerStatusResponse))
+)]
+pub async fn relayer_status_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses((status = 200, description = "Prometheus text format: proof timings, outcomes and sizes, membership size and capacity", content_type = "text/plain"))
+)]
+pub async fn metrics_doc() {}
+
 #[utoipa::path(
     post,
     path = "/polls",
     request_body = CreatePollRequest,
-    responses((status = 200, body = PollResponse))
+    responses(
+        (status = 200, body = PollResponse),
+        (status = 422, description = "`membership_capacity_exceeded`: more members than the tree holds; `invalid_member`: a member is not a field element", body = ErrorBody)
+    )
 )]
 pub async fn create_poll_doc() {}
 
 #[utoipa::path(
     get,
     path = "/polls",
-    responses((status = 200, body = [PollResponse]))
+    params(
+        ("ids" = Option<String>, Query, description = "Comma-separated poll ids (max 50); missing ids are omitted"),
+        ("sort" = Option<String>, Query, description = "created_at, commit_end or reveal_end, latest first; newest id first when omitted"),
+        ("include_deleted" = Option<bool>, Query, description = "Admins only: list soft-deleted polls too")
+    ),
+    responses((status = 200, body = [PollResponse]), (status = 400, description = "Unknown sort, or include_deleted without admin rights"))
 )]
 pub async fn list_polls_doc() {}
 
@@ -73,39 +182,177 @@ pub async fn list_polls_doc() {}
     get,
     path = "/polls/{id}",
     params(
-        ("id" = i64, Path, description = "Poll id")
+        ("id" = i64, Path, description = "Poll id"),
+        ("include_deleted" = Option<bool>, Query, description = "Admins only: return the poll even if soft-deleted")
     ),
-    responses((status = 200, body = PollResponse))
+    responses(
+        (status = 200, body = PollResponse),
+        (status = 404, description = "No such poll, or it was soft-deleted")
+    )
 )]
 pub async fn get_poll_doc() {}
 
+#[utoipa::path(
+    post,
+    path = "/polls/{id}/commitment",
+    params(("id" = i64, Path, description = "Poll id")),
+    request_body = CommitmentRequest,
+    responses(
+        (status = 200, description = "Commitment and nullifier for the caller's per-poll secret", body = CommitmentResponse),
+        (status = 401, description = "Missing auth header"),
+        (status = 403, description = "Caller is not a member of this poll")
+    )
+)]
+pub async fn compute_commitment_doc() {}
+
 #[utoipa::path(
     post,
     path = "/polls/{id}/commit",
     params(("id" = i64, Path, description = "Poll id")),
     request_body = CommitRequest,
-    responses((status = 200, body = CommitResponse))
+    responses(
+        (status = 200, body = CommitResponse),
+        (status = 400, description = "`proof_invalid` (`kind: invalid_inputs`): the bundle doesn't fit this poll", body = ErrorBody),
+        (status = 422, description = "`proof_invalid` (`kind: verification_failed`): the proof does not verify", body = ErrorBody),
+        (status = 502, description = "`prover_unavailable`: the verifier could not be reached", body = ErrorBody),
+        (status = 504, description = "`prover_timeout`: the verifier did not answer in time", body = ErrorBody)
+    )
 )]
 pub async fn record_commit_doc() {}
 
+#[utoipa::path(
+    delete,
+    path = "/polls/{id}/commit",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses(
+        (status = 204, description = "Commitment withdrawn"),
+        (status = 409, description = "Commit phase over or commitment already on-chain")
+    )
+)]
+pub async fn withdraw_commit_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/commits/batch",
+    request_body = [BatchCommitItem],
+    responses((status = 200, description = "Per-item results, in request order (max 20 items)", body = [BatchCommitResult]))
+)]
+pub async fn batch_commit_doc() {}
+
 #[utoipa::path(
     post,
     path = "/polls/{id}/prove",
-    params(("id" = i64, Path, description = "Poll id")),
+    params(
+        ("id" = i64, Path, description = "Poll id"),
+        ("sync" = Option<bool>, Query, description = "Prove inline and return the bundle (default false); waits for a free prover slot like a job")
+    ),
     request_body = ProveRequest,
-    responses((status = 200, body = ProofBundle))
+    responses(
+        (status = 200, description = "Proof, when `sync=true`; `x-proof-cache` says whether it was cached", body = ProofBundle),
+        (status = 202, description = "Proof job queued; poll `/prove_jobs/{job_id}`", body = ProveJobAccepted),
+        (status = 400, description = "`proof_invalid` (`kind: invalid_inputs`): the choice or poll can't be proven", body = ErrorBody),
+        (status = 401, description = "Missing auth header"),
+        (status = 403, description = "Caller is not a member, has no membership path in this poll, or sent another member's `identity_secret`"),
+        (status = 502, description = "`prover_unavailable`: the prover could not be reached", body = ErrorBody),
+        (status = 504, description = "`prover_timeout`: the prover did not answer in time", body = ErrorBody)
+    )
 )]
 pub async fn generate_proof_doc() {}
 
+#[utoipa::path(
+    get,
+    path = "/prove_jobs/{job_id}",
+    params(("job_id" = String, Path, description = "Job id from `POST /polls/{id}/prove`")),
+    responses(
+        (status = 200, body = ProveJobResponse),
+        (status = 401, description = "Missing auth header"),
+        (status = 404, description = "Unknown job, or one requested by another member")
+    )
+)]
+pub async fn get_proof_job_doc() {}
+
 #[utoipa::path(
     post,
     path = "/polls/{id}/reveal",
     params(("id" = i64, Path, description = "Poll id")),
     request_body = RevealRequest,
-    responses((status = 200, body = RevealResponse))
+    responses(
+        (status = 200, body = RevealResponse),
+        (status = 400, description = "`proof_invalid` (`kind: invalid_inputs`): the bundle doesn't fit this poll", body = ErrorBody),
+        (status = 422, description = "`proof_invalid` (`kind: verification_failed`): the proof does not verify", body = ErrorBody),
+        (status = 502, description = "`prover_unavailable`: the verifier could not be reached", body = ErrorBody),
+        (status = 504, description = "`prover_timeout`: the verifier did not answer in time", body = ErrorBody),
+        (status = 401, description = "Missing auth header or invalid x-api-key"),
+        (status = 403, description = "Caller is not a member or the nullifier is not theirs"),
+        (status = 409, description = "`nullifier_reused`: already revealed with another choice; a repeat of the same choice returns the original record")
+    )
 )]
 pub async fn reveal_vote_doc() {}
 
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/reveal_batches",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses((status = 200, body = [RevealBatchResponse]))
+)]
+pub async fn reveal_batches_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/transactions",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses(
+        (status = 200, description = "The poll's createPoll, commit, batchReveal and resolve transactions, in that order", body = [PollTransactionResponse]),
+        (status = 404, description = "Unknown poll, or one the caller may not view")
+    )
+)]
+pub async fn poll_transactions_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/sync_status",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses((status = 200, body = PollSyncStatusResponse))
+)]
+pub async fn sync_status_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/commits",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses((status = 200, body = [RedactedCommitResponse]))
+)]
+pub async fn list_commits_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/vote_sources",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses((status = 200, body = VoteSourcesResponse))
+)]
+pub async fn vote_sources_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/participation",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses((status = 200, body = ParticipationResponse))
+)]
+pub async fn participation_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/receipt/verify",
+    params(
+        ("id" = i64, Path, description = "Poll id"),
+        ("commitment" = String, Query, description = "Commitment from the commit response"),
+        ("recorded_at" = String, Query, description = "`recorded_at` from the commit response (RFC 3339)"),
+        ("receipt" = String, Query, description = "Receipt from the commit response")
+    ),
+    responses((status = 200, body = ReceiptVerifyResponse))
+)]
+pub async fn verify_receipt_doc() {}
+
 #[utoipa::path(
     get,
     path = "/polls/{id}/membership",
@@ -114,6 +361,20 @@ pub async fn reveal_vote_doc() {}
 )]
 pub async fn membership_status_doc() {}
 
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/membership_root",
+    params(
+        ("id" = i64, Path, description = "Poll id"),
+        ("verify" = Option<bool>, Query, description = "Rebuild the root from the poll's members (owner or admin)")
+    ),
+    responses(
+        (status = 200, body = PollMembershipRootResponse),
+        (status = 409, description = "`no_poll_members`: nothing stored to rebuild the root from", body = ErrorBody)
+    )
+)]
+pub async fn poll_membership_root_doc() {}
+
 #[utoipa::path(
     get,
     path = "/polls/{id}/commit_status",
@@ -122,6 +383,25 @@ pub async fn membership_status_doc() {}
 )]
 pub async fn commit_status_doc() {}
 
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/my_status",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses((status = 200, body = MyStatusResponse))
+)]
+pub async fn my_status_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/polls/{id}/my_commit",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses(
+        (status = 200, body = MyCommitResponse),
+        (status = 404, description = "Caller has no commitment for this poll")
+    )
+)]
+pub async fn my_commit_doc() {}
+
 #[utoipa::path(
     post,
     path = "/auth/login",
@@ -136,3 +416,93 @@ pub async fn login_doc() {}
     responses((status = 200, body = MeResponse))
 )]
 pub async fn me_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/polls/prune",
+    params(
+        ("dry_run" = Option<bool>, Query, description = "Only report stale polls (default true)")
+    ),
+    responses((status = 200, body = PruneStalePollsResponse))
+)]
+pub async fn prune_stale_polls_doc() {}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/polls/{id}",
+    params(("id" = i64, Path, description = "Poll id")),
+    responses((status = 200, description = "The soft-deleted poll; its rows are kept", body = PollResponse))
+)]
+pub async fn delete_poll_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/polls/{id}/scrub_secrets",
+    params(
+        ("id" = i64, Path, description = "Poll id"),
+        ("dry_run" = Option<bool>, Query, description = "Only count what would be removed (default true)")
+    ),
+    responses(
+        (status = 200, body = ScrubSecretsResponse),
+        (status = 409, description = "`secrets_still_needed`: the poll is unresolved or has reveals left to sync", body = ErrorBody)
+    )
+)]
+pub async fn scrub_poll_secrets_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/indexer/failed_events/requeue",
+    responses((status = 200, body = RequeueDeadEventsResponse))
+)]
+pub async fn requeue_dead_events_doc() {}
+
+#[utoipa::path(
+    get,
+    path = "/admin/dead_commits",
+    responses((status = 200, body = [DeadCommitResponse]))
+)]
+pub async fn list_dead_commits_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/dead_commits/{id}/requeue",
+    params(
+        ("id" = i64, Path, description = "Commitment id")
+    ),
+    responses(
+        (status = 200, body = RequeueDeadCommitResponse),
+        (status = 404, description = "No dead commitment with this id")
+    )
+)]
+pub async fn requeue_dead_commit_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/sync_reveals",
+    responses(
+        (status = 200, body = RevealSyncResponse),
+        (status = 409, description = "`reveal_sync_running`: a round is already under way, or `reveal_sync_paused`; the message says since when", body = ErrorBody)
+    )
+)]
+pub async fn trigger_reveal_sync_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/sync/pause",
+    responses((status = 200, body = SyncControlResponse))
+)]
+pub async fn pause_reveal_sync_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/sync/resume",
+    responses((status = 200, body = SyncControlResponse))
+)]
+pub async fn resume_reveal_sync_doc() {}
+
+#[utoipa::path(
+    post,
+    path = "/admin/membership/recompute",
+    responses((status = 200, body = MembershipSnapshotResponse))
+)]
+pub async fn recompute_membership_snapshot_doc() {}
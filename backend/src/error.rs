@@ -1,39 +1,165 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use thiserror::Error;
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// What went wrong with a proof, so clients can tell their own mistakes from prover faults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofErrorKind {
+    /// The bundle or request doesn't fit the poll: wrong shape, version, poll or root.
+    InvalidInputs,
+    /// The inputs are well-formed but the proof does not verify.
+    VerificationFailed,
+    /// The prover could not be reached, crashed or answered with garbage.
+    ProverUnavailable,
+    /// The prover did not answer in time.
+    Timeout,
+}
+
+impl ProofErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProofErrorKind::InvalidInputs => "invalid_inputs",
+            ProofErrorKind::VerificationFailed => "verification_failed",
+            ProofErrorKind::ProverUnavailable => "prover_unavailable",
+            ProofErrorKind::Timeout => "timeout",
+        }
+    }
+}
+
+impl fmt::Display for ProofErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Why a member list cannot go into the membership tree.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MembershipError {
+    #[error("membership has {members} members but a depth-{depth} tree holds {capacity}")]
+    CapacityExceeded {
+        members: u64,
+        capacity: u64,
+        depth: u32,
+    },
+    /// The row is named by its leaf index only: members are identity secrets.
+    #[error("member {index} is not a field element below the BN254 modulus")]
+    InvalidMember { index: usize },
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("not found")]
     NotFound,
     #[error("validation error: {0}")]
     Validation(String),
+    /// A validation failure carrying a machine-readable `code` clients can branch on.
+    #[error("validation error: {message}")]
+    CodedValidation { code: &'static str, message: String },
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
+    /// A conflict carrying a machine-readable `code` clients can branch on.
+    #[error("conflict: {message}")]
+    CodedConflict { code: &'static str, message: String },
     #[error("database error: {0}")]
     Db(#[from] sqlx::Error),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("external error: {0}")]
     External(String),
+    #[error(transparent)]
+    Membership(#[from] MembershipError),
+    /// A ZK failure; `kind` separates bad bundles from prover faults.
+    #[error("proof error ({kind}): {detail}")]
+    Proof {
+        kind: ProofErrorKind,
+        detail: String,
+    },
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    message: String,
+/// JSON body of every error response.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ErrorBody {
+    /// Machine-readable code clients can branch on, when there is one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+    pub message: String,
+    /// Set on proof errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ProofErrorKind>,
+    /// The proof error without the kind prefix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let status = match self {
+impl AppError {
+    pub fn proof(kind: ProofErrorKind, detail: impl Into<String>) -> Self {
+        AppError::Proof {
+            kind,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
             AppError::NotFound => StatusCode::NOT_FOUND,
-            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Validation(_) | AppError::CodedValidation { .. } => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Conflict(_) | AppError::CodedConflict { .. } => StatusCode::CONFLICT,
             AppError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Io(_) | AppError::External(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Membership(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Proof { kind, .. } => match kind {
+                ProofErrorKind::InvalidInputs => StatusCode::BAD_REQUEST,
+                ProofErrorKind::VerificationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+                ProofErrorKind::ProverUnavailable => StatusCode::BAD_GATEWAY,
+                ProofErrorKind::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            },
+        }
+    }
+
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            AppError::CodedConflict { code, .. } | AppError::CodedValidation { code, .. } => {
+                Some(code)
+            }
+            AppError::Proof { kind, .. } => Some(match kind {
+                ProofErrorKind::InvalidInputs | ProofErrorKind::VerificationFailed => {
+                    "proof_invalid"
+                }
+                ProofErrorKind::ProverUnavailable => "prover_unavailable",
+                ProofErrorKind::Timeout => "prover_timeout",
+            }),
+            AppError::Membership(MembershipError::CapacityExceeded { .. }) => {
+                Some("membership_capacity_exceeded")
+            }
+            AppError::Membership(MembershipError::InvalidMember { .. }) => Some("invalid_member"),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        let (kind, detail) = match &self {
+            AppError::Proof { kind, detail } => (Some(*kind), Some(detail.clone())),
+            _ => (None, None),
         };
         let body = axum::Json(ErrorBody {
+            code: self.code(),
             message: self.to_string(),
+            kind,
+            detail,
         });
         (status, body).into_response()
     }
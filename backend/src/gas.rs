@@ -0,0 +1,158 @@
+//! Fees and gas limits for the relayer's `createPoll` and `batchReveal` transactions.
+//! EIP-1559 chains get `maxFeePerGas`/`maxPriorityFeePerGas`; chains whose blocks carry no base
+//! fee get a legacy `gasPrice`. Both are held to the configured caps.
+use crate::error::{AppError, AppResult};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockNumber, Eip1559TransactionRequest, TransactionRequest, U256};
+
+const WEI_PER_GWEI: u64 = 1_000_000_000;
+
+/// `MAX_FEE_PER_GAS_GWEI`, `MAX_PRIORITY_FEE_GWEI`, `GAS_LIMIT_CAP` and
+/// `BATCH_REVEAL_MAX_GAS`; whatever is unset is left to the provider's estimates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasConfig {
+    pub max_fee_per_gas_gwei: Option<u64>,
+    pub max_priority_fee_gwei: Option<u64>,
+    pub gas_limit_cap: Option<u64>,
+    /// batchReveal calls estimated above this are split into smaller batches.
+    pub batch_reveal_max_gas: Option<u64>,
+}
+
+impl GasConfig {
+    pub fn parse(var: impl Fn(&str) -> Option<String>) -> AppResult<Self> {
+        let positive = |key: &str| -> AppResult<Option<u64>> {
+            let Some(raw) = var(key).filter(|v| !v.trim().is_empty()) else {
+                return Ok(None);
+            };
+            match raw.trim().parse::<u64>() {
+                Ok(value) if value > 0 => Ok(Some(value)),
+                _ => Err(AppError::Validation(format!(
+                    "{key} must be a positive integer, got {raw:?}"
+                ))),
+            }
+        };
+        let config = Self {
+            max_fee_per_gas_gwei: positive("MAX_FEE_PER_GAS_GWEI")?,
+            max_priority_fee_gwei: positive("MAX_PRIORITY_FEE_GWEI")?,
+            gas_limit_cap: positive("GAS_LIMIT_CAP")?,
+            batch_reveal_max_gas: positive("BATCH_REVEAL_MAX_GAS")?,
+        };
+        if let (Some(max_fee), Some(priority)) =
+            (config.max_fee_per_gas_gwei, config.max_priority_fee_gwei)
+        {
+            if priority > max_fee {
+                return Err(AppError::Validation(format!(
+                    "MAX_PRIORITY_FEE_GWEI ({priority}) cannot exceed MAX_FEE_PER_GAS_GWEI ({max_fee})"
+                )));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Where batchReveal calls get split: `BATCH_REVEAL_MAX_GAS`, else `GAS_LIMIT_CAP` so a
+    /// batch over the cap is split rather than refused.
+    pub fn batch_reveal_threshold(&self) -> Option<u64> {
+        self.batch_reveal_max_gas.or(self.gas_limit_cap)
+    }
+}
+
+/// How the chain prices gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeMode {
+    Eip1559,
+    Legacy,
+}
+
+impl FeeMode {
+    /// EIP-1559 when the latest block carries a base fee, legacy otherwise.
+    pub async fn detect<M: Middleware>(client: &M) -> AppResult<Self> {
+        let block = client
+            .get_block(BlockNumber::Latest)
+            .await
+            .map_err(|e| AppError::External(format!("latest block lookup failed: {e}")))?
+            .ok_or_else(|| AppError::External("latest block not found".into()))?;
+        Ok(if block.base_fee_per_gas.is_some() {
+            Self::Eip1559
+        } else {
+            Self::Legacy
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Eip1559 => "eip1559",
+            Self::Legacy => "legacy",
+        }
+    }
+}
+
+fn gwei(value: u64) -> U256 {
+    U256::from(value) * U256::from(WEI_PER_GWEI)
+}
+
+/// Fills in `tx`'s fees for `mode` and sets its gas limit to the provider's estimate. Fails
+/// with `AppError::External`, before anything is sent, when that estimate is above
+/// `GAS_LIMIT_CAP`. `label` names the call in errors.
+pub async fn price_tx<M: Middleware>(
+    client: &M,
+    tx: &mut TypedTransaction,
+    config: &GasConfig,
+    mode: FeeMode,
+    label: &str,
+) -> AppResult<()> {
+    let max_fee = config.max_fee_per_gas_gwei.map(gwei);
+    match mode {
+        FeeMode::Eip1559 => {
+            let (max_fee, priority) = match (max_fee, config.max_priority_fee_gwei.map(gwei)) {
+                (Some(max_fee), Some(priority)) => (max_fee, priority),
+                (max_fee, priority) => {
+                    let (estimated_max_fee, estimated_priority) =
+                        client.estimate_eip1559_fees(None).await.map_err(|e| {
+                            AppError::External(format!("{label} fee estimation failed: {e}"))
+                        })?;
+                    (
+                        max_fee.unwrap_or(estimated_max_fee),
+                        priority.unwrap_or(estimated_priority),
+                    )
+                }
+            };
+            let priority = priority.min(max_fee);
+            let request: Eip1559TransactionRequest = tx.clone().into();
+            *tx = TypedTransaction::Eip1559(
+                request
+                    .max_fee_per_gas(max_fee)
+                    .max_priority_fee_per_gas(priority),
+            );
+        }
+        FeeMode::Legacy => {
+            let price = client
+                .get_gas_price()
+                .await
+                .map_err(|e| AppError::External(format!("{label} gas price lookup failed: {e}")))?;
+            let price = max_fee.map_or(price, |cap| price.min(cap));
+            let request = match tx.clone() {
+                TypedTransaction::Eip1559(inner) => inner.into(),
+                TypedTransaction::Legacy(inner) => inner,
+                TypedTransaction::Eip2930(inner) => inner.tx,
+            };
+            *tx = TypedTransaction::Legacy(TransactionRequest {
+                gas_price: Some(price),
+                ..request
+            });
+        }
+    }
+    let estimate = client
+        .estimate_gas(tx, None)
+        .await
+        .map_err(|e| AppError::External(format!("{label} gas estimation failed: {e}")))?;
+    if let Some(cap) = config.gas_limit_cap {
+        if estimate > U256::from(cap) {
+            return Err(AppError::External(format!(
+                "{label} needs an estimated {estimate} gas, above GAS_LIMIT_CAP ({cap}); not sent"
+            )));
+        }
+    }
+    tx.set_gas(estimate);
+    Ok(())
+}
@@ -1,13 +1,42 @@
 use crate::error::{AppError, AppResult};
-use crate::repo::{NewPoll, PollIndexSink};
+use crate::repo::{IndexerCheckpoint, NewPoll, PollIndexSink};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use ethers::abi::RawLog;
 use ethers::contract::EthEvent;
-use ethers::core::types::{Filter, Log, H160, U256, U64};
-use ethers::providers::{Middleware, Provider, StreamExt, Ws};
-use std::sync::Arc;
+use ethers::core::types::{Filter, Log, H160, H256, U256, U64};
+use ethers::providers::{Http, Middleware, Provider, StreamExt, Ws};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
 use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
+
+/// First reconnect delay; doubles per failed attempt up to `IndexerConfig::max_backoff`.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+pub const DEFAULT_MAX_BACKOFF_SECS: u64 = 60;
+pub const DEFAULT_LOG_CHUNK_BLOCKS: u64 = 2_000;
+pub const DEFAULT_CONFIRMATIONS: u64 = 3;
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 5_000;
+const CHECKPOINT_EVERY_LOGS: u64 = 100;
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+/// How often the indexer logs its counters.
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+/// New heads a log naming an unindexed poll is retried on before it is dead-lettered.
+const MAX_PENDING_ATTEMPTS: u32 = 64;
+/// Logs held for missing polls at once; more are dead-lettered straight away.
+const MAX_PENDING_LOGS: usize = 1_024;
+/// Failed attempts at a log, the first included, before it is marked dead.
+pub const MAX_FAILED_EVENT_ATTEMPTS: i32 = 8;
+/// Wait before the first retry of a failed log; doubles per attempt up to an hour.
+const FAILED_EVENT_RETRY_BASE_SECS: i64 = 10;
+const FAILED_EVENT_RETRY_MAX_SECS: i64 = 60 * 60;
+const FAILED_EVENT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const FAILED_EVENTS_PER_ROUND: i64 = 100;
 
 #[derive(Debug, Clone, EthEvent)]
 #[ethevent(
@@ -32,6 +61,15 @@ pub struct VoteRevealedEvent {
     pub nullifier: U256,
 }
 
+/// `commit(pollId, commitment)` called on the contract directly.
+#[derive(Debug, Clone, EthEvent)]
+#[ethevent(name = "Committed", abi = "Committed(uint256,bytes32)")]
+pub struct CommitSubmittedEvent {
+    #[ethevent(indexed)]
+    pub poll_id: U256,
+    pub commitment: [u8; 32],
+}
+
 #[derive(Debug, Clone, EthEvent)]
 #[ethevent(name = "PollResolved", abi = "PollResolved(uint256,uint8)")]
 pub struct PollResolvedEvent {
@@ -39,57 +77,1090 @@ pub struct PollResolvedEvent {
     pub correct_option: u8,
 }
 
+/// How the indexer reaches the node.
+#[derive(Clone, Debug)]
+pub enum IndexerTransport {
+    /// Websocket subscriptions (`RPC_WS`).
+    Ws(String),
+    /// `eth_getLogs` over HTTP every `interval` (`RPC_URL`), for providers without websockets.
+    HttpPoll { url: String, interval: Duration },
+}
+
 #[derive(Clone, Debug)]
 pub struct IndexerConfig {
-    pub rpc_ws: String,
+    pub transport: IndexerTransport,
     pub contract_address: H160,
     pub from_block: Option<u64>,
+    /// Longest wait between reconnect attempts after the websocket drops.
+    pub max_backoff: Duration,
+    /// Blocks per `eth_getLogs` call while catching up on missed history.
+    pub log_chunk_blocks: u64,
+    /// Blocks that must be mined on top of a log before it is applied.
+    pub confirmations: u64,
+}
+
+/// What a feed delivers: the contract's logs, and the chain head as it advances.
+#[derive(Debug, Clone)]
+pub enum FeedEvent {
+    Log(Box<Log>),
+    Head(u64),
+}
+
+/// Where the indexer's logs come from; one call is one connection.
+#[async_trait]
+pub trait LogFeed: Send + Sync {
+    /// Sends the contract's logs from `from_block` on into `tx`, in chain order, returning once
+    /// the connection ends. An error means the connection failed.
+    async fn stream(&self, from_block: u64, tx: mpsc::Sender<FeedEvent>) -> AppResult<()>;
+
+    /// Who sent transaction `tx_hash`; `None` when the feed cannot look it up.
+    async fn transaction_sender(&self, _tx_hash: H256) -> AppResult<Option<H160>> {
+        Ok(None)
+    }
+
+    /// When block `block` was mined; `None` when the feed cannot look it up.
+    async fn block_time(&self, _block: u64) -> AppResult<Option<DateTime<Utc>>> {
+        Ok(None)
+    }
+}
+
+/// Indexer progress, shared with `GET /indexer/status`.
+#[derive(Debug, Default)]
+pub struct IndexerStatus {
+    pub running: AtomicBool,
+    /// Whether the current connection has delivered anything yet.
+    pub connected: AtomicBool,
+    pub confirmations: AtomicU64,
+    pub head_block: AtomicU64,
+    /// Block of the last applied log.
+    pub last_block: AtomicU64,
+    /// Logs applied to the store since startup.
+    pub confirmed_events: AtomicU64,
+    /// Logs staged until enough blocks confirm them.
+    pub unconfirmed_events: AtomicU64,
+    /// Staged logs dropped because their block was reorged out.
+    pub discarded_events: AtomicU64,
+    /// When the last log was applied.
+    pub last_event_at: RwLock<Option<DateTime<Utc>>>,
+    /// Why the last connection failed or the last log could not be applied.
+    pub last_error: RwLock<Option<String>>,
+    pub poll_created_events: AtomicU64,
+    pub committed_events: AtomicU64,
+    pub vote_revealed_events: AtomicU64,
+    pub poll_resolved_events: AtomicU64,
+    /// Logs from the contract matching no event signature the indexer knows.
+    pub unknown_logs: AtomicU64,
+    /// Logs `handle_log` failed on.
+    pub handler_errors: AtomicU64,
+    /// Times the feed was reconnected after dropping or failing.
+    pub reconnects: AtomicU64,
+    /// Logs waiting for the `PollCreated` of the poll they name.
+    pub pending_logs: AtomicU64,
+    /// Logs given up on because their poll never showed up.
+    pub dead_lettered_logs: AtomicU64,
+    /// Failed logs waiting in `failed_events` for a retry.
+    pub failed_events: AtomicU64,
+    /// Failed logs out of retries, waiting for an admin requeue.
+    pub dead_events: AtomicU64,
+}
+
+impl IndexerStatus {
+    fn record_error(&self, err: &AppError) {
+        *self.last_error.write().unwrap() = Some(err.to_string());
+    }
+
+    /// Counts a log `handle_log` got through; replays of an applied log count as nothing.
+    pub fn record_outcome(&self, outcome: LogOutcome) {
+        let counter = match outcome {
+            LogOutcome::PollCreated => &self.poll_created_events,
+            LogOutcome::Committed => &self.committed_events,
+            LogOutcome::VoteRevealed => &self.vote_revealed_events,
+            LogOutcome::PollResolved => &self.poll_resolved_events,
+            LogOutcome::Unknown => &self.unknown_logs,
+            LogOutcome::Duplicate | LogOutcome::Deferred => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.confirmed_events.fetch_add(1, Ordering::Relaxed);
+        *self.last_event_at.write().unwrap() = Some(Utc::now());
+    }
+
+    pub fn record_failed_event_counts(&self, waiting: u64, dead: u64) {
+        self.failed_events.store(waiting, Ordering::Relaxed);
+        self.dead_events.store(dead, Ordering::Relaxed);
+    }
+
+    /// Blocks between the head and the last applied log, once a head is known.
+    pub fn lag_blocks(&self) -> Option<u64> {
+        let head = self.head_block.load(Ordering::Relaxed);
+        (head > 0).then(|| head.saturating_sub(self.last_block.load(Ordering::Relaxed)))
+    }
+
+    fn log_summary(&self) {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        info!(
+            head_block = load(&self.head_block),
+            last_block = load(&self.last_block),
+            lag_blocks = self.lag_blocks(),
+            poll_created = load(&self.poll_created_events),
+            committed = load(&self.committed_events),
+            vote_revealed = load(&self.vote_revealed_events),
+            poll_resolved = load(&self.poll_resolved_events),
+            unknown_logs = load(&self.unknown_logs),
+            handler_errors = load(&self.handler_errors),
+            reconnects = load(&self.reconnects),
+            pending_logs = load(&self.pending_logs),
+            dead_lettered_logs = load(&self.dead_lettered_logs),
+            failed_events = load(&self.failed_events),
+            dead_events = load(&self.dead_events),
+            "indexer summary"
+        );
+    }
+}
+
+/// What `handle_log` made of a log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogOutcome {
+    PollCreated,
+    Committed,
+    VoteRevealed,
+    PollResolved,
+    /// No known event signature matched; nothing was stored.
+    Unknown,
+    /// Already applied once; skipped.
+    Duplicate,
+    /// Names a poll that is not indexed yet; held in [`PendingLogs`] until it is.
+    Deferred,
+}
+
+/// Over a fresh `Provider<Ws>` per connection: pages through history with `eth_getLogs`
+/// up to the head, then subscribes. Subscriptions only carry new logs, so the blocks mined
+/// while catching up are fetched once more after subscribing; `run_indexer` drops the live
+/// copies of any log it already handled.
+#[derive(Clone, Debug)]
+pub struct WsLogFeed {
+    pub rpc_ws: String,
+    pub contract_address: H160,
+    pub log_chunk_blocks: u64,
+}
+
+#[async_trait]
+impl LogFeed for WsLogFeed {
+    async fn stream(&self, from_block: u64, tx: mpsc::Sender<FeedEvent>) -> AppResult<()> {
+        let provider = Provider::<Ws>::connect(self.rpc_ws.clone())
+            .await
+            .map_err(|e| AppError::External(format!("ws connect failed: {e}")))?;
+        let head = head_block(&provider).await?;
+        if from_block <= head {
+            info!(from_block, head, "indexer catching up on past logs");
+            backfill_logs(
+                &provider,
+                self.contract_address,
+                from_block..=head,
+                self.log_chunk_blocks,
+                &tx,
+            )
+            .await?;
+        }
+        let _ = tx.send(FeedEvent::Head(head)).await;
+        let filter = Filter::new()
+            .address(self.contract_address)
+            .from_block(U64::from(from_block));
+        let mut stream = provider
+            .subscribe_logs(&filter)
+            .await
+            .map_err(|e| AppError::External(format!("subscribe failed: {e}")))?;
+        let mut blocks = provider
+            .subscribe_blocks()
+            .await
+            .map_err(|e| AppError::External(format!("subscribe failed: {e}")))?;
+        let caught_up = head_block(&provider).await?;
+        let gap_start = from_block.max(head + 1);
+        if gap_start <= caught_up {
+            backfill_logs(
+                &provider,
+                self.contract_address,
+                gap_start..=caught_up,
+                self.log_chunk_blocks,
+                &tx,
+            )
+            .await?;
+        }
+        let _ = tx.send(FeedEvent::Head(caught_up)).await;
+        info!(
+            "Indexer listening on {} for contract {:?}, from_block={}",
+            self.rpc_ws, self.contract_address, from_block
+        );
+        loop {
+            let event = tokio::select! {
+                log = stream.next() => match log {
+                    Some(log) => FeedEvent::Log(Box::new(log)),
+                    None => break,
+                },
+                block = blocks.next() => match block {
+                    Some(block) => match block.number {
+                        Some(number) => FeedEvent::Head(number.as_u64()),
+                        None => continue,
+                    },
+                    None => break,
+                },
+            };
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens a connection of its own; only `PollCreated` logs need a lookup.
+    async fn transaction_sender(&self, tx_hash: H256) -> AppResult<Option<H160>> {
+        let provider = Provider::<Ws>::connect(self.rpc_ws.clone())
+            .await
+            .map_err(|e| AppError::External(format!("ws connect failed: {e}")))?;
+        transaction_sender(&provider, tx_hash).await
+    }
+
+    async fn block_time(&self, block: u64) -> AppResult<Option<DateTime<Utc>>> {
+        let provider = Provider::<Ws>::connect(self.rpc_ws.clone())
+            .await
+            .map_err(|e| AppError::External(format!("ws connect failed: {e}")))?;
+        block_time(&provider, block).await
+    }
 }
 
-pub async fn spawn_indexer<S>(cfg: IndexerConfig, store: Arc<S>) -> JoinHandle<()>
+/// Polls `eth_blockNumber` and `eth_getLogs` over plain HTTP. Each round re-reads the last
+/// `confirmations` blocks as well, so a reorg of a staged block shows up as a new block hash;
+/// `run_indexer` drops the logs it has already seen in the overlap.
+#[derive(Clone, Debug)]
+pub struct HttpPollLogFeed<M> {
+    pub provider: M,
+    pub contract_address: H160,
+    pub log_chunk_blocks: u64,
+    pub poll_interval: Duration,
+    pub confirmations: u64,
+}
+
+#[async_trait]
+impl<M: Middleware> LogFeed for HttpPollLogFeed<M> {
+    async fn stream(&self, from_block: u64, tx: mpsc::Sender<FeedEvent>) -> AppResult<()> {
+        let mut next = from_block;
+        let mut ticker = tokio::time::interval(self.poll_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        info!(
+            "Indexer polling every {:?} for contract {:?}, from_block={}",
+            self.poll_interval, self.contract_address, from_block
+        );
+        loop {
+            ticker.tick().await;
+            let head = head_block(&self.provider).await?;
+            if next <= head {
+                backfill_logs(
+                    &self.provider,
+                    self.contract_address,
+                    next..=head,
+                    self.log_chunk_blocks,
+                    &tx,
+                )
+                .await?;
+                next = (head + 1)
+                    .saturating_sub(self.confirmations)
+                    .max(from_block);
+            }
+            if tx.send(FeedEvent::Head(head)).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn transaction_sender(&self, tx_hash: H256) -> AppResult<Option<H160>> {
+        transaction_sender(&self.provider, tx_hash).await
+    }
+
+    async fn block_time(&self, block: u64) -> AppResult<Option<DateTime<Utc>>> {
+        block_time(&self.provider, block).await
+    }
+}
+
+async fn transaction_sender<M: Middleware>(provider: &M, tx_hash: H256) -> AppResult<Option<H160>> {
+    provider
+        .get_transaction(tx_hash)
+        .await
+        .map(|tx| tx.map(|tx| tx.from))
+        .map_err(|e| AppError::External(format!("eth_getTransactionByHash failed: {e}")))
+}
+
+async fn block_time<M: Middleware>(provider: &M, block: u64) -> AppResult<Option<DateTime<Utc>>> {
+    let block = provider
+        .get_block(block)
+        .await
+        .map_err(|e| AppError::External(format!("eth_getBlockByNumber failed: {e}")))?;
+    Ok(block.and_then(|b| DateTime::from_timestamp(b.timestamp.low_u64() as i64, 0)))
+}
+
+async fn head_block<M: Middleware>(provider: &M) -> AppResult<u64> {
+    provider
+        .get_block_number()
+        .await
+        .map(|n| n.as_u64())
+        .map_err(|e| AppError::External(format!("eth_blockNumber failed: {e}")))
+}
+
+/// Sends the contract's logs in `blocks` into `tx`, `chunk` blocks per `eth_getLogs` call. A
+/// range the provider refuses as too large is halved and retried.
+pub async fn backfill_logs<M: Middleware>(
+    provider: &M,
+    contract: H160,
+    blocks: RangeInclusive<u64>,
+    chunk: u64,
+    tx: &mpsc::Sender<FeedEvent>,
+) -> AppResult<()> {
+    let (mut start, end) = blocks.into_inner();
+    let mut chunk = chunk.max(1);
+    while start <= end {
+        let to = end.min(start.saturating_add(chunk - 1));
+        let filter = Filter::new()
+            .address(contract)
+            .from_block(U64::from(start))
+            .to_block(U64::from(to));
+        match provider.get_logs(&filter).await {
+            Ok(logs) => {
+                for log in logs {
+                    if tx.send(FeedEvent::Log(Box::new(log))).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                start = to + 1;
+            }
+            Err(err) if chunk > 1 && too_many_results(&err.to_string()) => {
+                chunk /= 2;
+                warn!(
+                    start,
+                    to, chunk, "eth_getLogs range too large; retrying smaller"
+                );
+            }
+            Err(err) => {
+                return Err(AppError::External(format!(
+                    "eth_getLogs {start}..={to} failed: {err}"
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Providers word their result and range caps differently (Infura: "query returned more
+/// than 10000 results", Alchemy: "Log response size exceeded", others: "block range").
+fn too_many_results(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    [
+        "more than",
+        "too many",
+        "size exceeded",
+        "block range",
+        "limit exceeded",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+/// Runs the indexer until `shutdown` flips to `true` (or its sender is dropped).
+pub async fn spawn_indexer<S>(
+    cfg: IndexerConfig,
+    store: Arc<S>,
+    status: Arc<IndexerStatus>,
+    shutdown: watch::Receiver<bool>,
+) -> AppResult<JoinHandle<()>>
 where
     S: PollIndexSink + Send + Sync + 'static,
 {
-    tokio::spawn(async move {
-        if let Err(e) = run_indexer(cfg, store.clone()).await {
-            error!("indexer exited with error: {:?}", e);
+    let handle = match cfg.transport.clone() {
+        IndexerTransport::Ws(rpc_ws) => {
+            let feed = WsLogFeed {
+                rpc_ws,
+                contract_address: cfg.contract_address,
+                log_chunk_blocks: cfg.log_chunk_blocks,
+            };
+            tokio::spawn(run_indexer(
+                feed,
+                cfg,
+                store.clone(),
+                status.clone(),
+                shutdown.clone(),
+            ))
         }
-    })
+        IndexerTransport::HttpPoll { url, interval } => {
+            let provider = Provider::<Http>::try_from(url.as_str())
+                .map_err(|e| AppError::Validation(format!("invalid RPC_URL: {e}")))?;
+            let feed = HttpPollLogFeed {
+                provider,
+                contract_address: cfg.contract_address,
+                log_chunk_blocks: cfg.log_chunk_blocks,
+                poll_interval: interval,
+                confirmations: cfg.confirmations,
+            };
+            tokio::spawn(run_indexer(
+                feed,
+                cfg,
+                store.clone(),
+                status.clone(),
+                shutdown.clone(),
+            ))
+        }
+    };
+    let retries = tokio::spawn(run_failed_event_retries(store, status, shutdown));
+    Ok(tokio::spawn(async move {
+        let _ = tokio::join!(handle, retries);
+    }))
+}
+
+/// Retries failed logs every `FAILED_EVENT_RETRY_INTERVAL` until `shutdown`.
+async fn run_failed_event_retries<S>(
+    store: Arc<S>,
+    status: Arc<IndexerStatus>,
+    mut shutdown: watch::Receiver<bool>,
+) where
+    S: PollIndexSink + Send + Sync + 'static,
+{
+    let mut ticker = tokio::time::interval(FAILED_EVENT_RETRY_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = wait_for_shutdown(&mut shutdown) => break,
+        }
+        if let Err(err) = retry_failed_events(&store, &status, Utc::now()).await {
+            warn!(?err, "failed event retry round failed");
+        }
+    }
 }
 
-async fn run_indexer<S>(cfg: IndexerConfig, store: Arc<S>) -> AppResult<()>
+/// Replays the failed logs due by `now`. One that fails again is retried after an exponential
+/// backoff, or marked dead once it has failed `MAX_FAILED_EVENT_ATTEMPTS` times.
+pub async fn retry_failed_events<S>(
+    store: &Arc<S>,
+    status: &IndexerStatus,
+    now: DateTime<Utc>,
+) -> AppResult<()>
 where
     S: PollIndexSink + Send + Sync + 'static,
 {
-    let provider = Provider::<Ws>::connect(cfg.rpc_ws.clone())
-        .await
-        .map_err(|e| AppError::Validation(format!("ws connect failed: {e}")))?;
+    for event in store
+        .due_failed_events(now, FAILED_EVENTS_PER_ROUND)
+        .await?
+    {
+        let log = match serde_json::from_value::<Log>(event.log) {
+            Ok(log) => log,
+            Err(err) => {
+                let reason = format!("stored log does not parse: {err}");
+                store
+                    .reschedule_failed_event(event.id, &reason, None)
+                    .await?;
+                continue;
+            }
+        };
+        let sender = event.sender.as_deref().and_then(|s| s.parse().ok());
+        match handle_log(store, log, sender, None).await {
+            Ok(outcome) => {
+                info!(
+                    id = event.id,
+                    attempts = event.attempts,
+                    "retried a failed log"
+                );
+                status.record_outcome(outcome);
+                store.delete_failed_event(event.id).await?;
+            }
+            Err(err) => {
+                let attempts = event.attempts + 1;
+                let next_retry_at = (attempts < MAX_FAILED_EVENT_ATTEMPTS && !is_permanent(&err))
+                    .then(|| now + failed_event_backoff(attempts));
+                if next_retry_at.is_none() {
+                    error!(id = event.id, attempts, ?err, "giving up on a failed log");
+                } else {
+                    warn!(id = event.id, attempts, ?err, "failed log failed again");
+                }
+                store
+                    .reschedule_failed_event(event.id, &err.to_string(), next_retry_at)
+                    .await?;
+            }
+        }
+    }
+    let (waiting, dead) = store.failed_event_counts().await?;
+    status.record_failed_event_counts(waiting, dead);
+    Ok(())
+}
+
+/// Wait after a log's `attempts`-th failure.
+fn failed_event_backoff(attempts: i32) -> chrono::Duration {
+    let secs = FAILED_EVENT_RETRY_BASE_SECS
+        .saturating_mul(1 << (attempts - 1).clamp(0, 20))
+        .min(FAILED_EVENT_RETRY_MAX_SECS);
+    chrono::Duration::seconds(secs)
+}
+
+/// A log rejected as invalid fails the same way every time, so it is not worth retrying.
+fn is_permanent(err: &AppError) -> bool {
+    matches!(err, AppError::Validation(_))
+}
+
+/// Counts a log `handle_log` failed on and queues it in `failed_events` for a retry, or as dead
+/// straight away when retrying cannot help.
+async fn queue_failed_log<S>(
+    store: &Arc<S>,
+    log: &Log,
+    sender: Option<H160>,
+    err: &AppError,
+    status: &IndexerStatus,
+) where
+    S: PollIndexSink + Send + Sync + 'static,
+{
+    record_handler_error(err, status);
+    let next_retry_at = (!is_permanent(err)).then(|| Utc::now() + failed_event_backoff(1));
+    let queued = match serde_json::to_value(log) {
+        Ok(raw) => {
+            let sender = sender.map(|from| format!("{from:#x}"));
+            store
+                .record_failed_event(&raw, sender.as_deref(), &err.to_string(), next_retry_at)
+                .await
+        }
+        Err(e) => Err(AppError::External(format!("log does not serialize: {e}"))),
+    };
+    match queued {
+        Ok(()) if next_retry_at.is_some() => {
+            status.failed_events.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(()) => {
+            error!(%err, "a log can never apply; recorded it as dead");
+            status.dead_events.fetch_add(1, Ordering::Relaxed);
+        }
+        Err(queue_err) => error!(?queue_err, "could not queue a failed log; it is lost"),
+    }
+}
 
-    let from_block = cfg.from_block.map(U64::from);
-    let filter = Filter::new()
-        .address(cfg.contract_address)
-        .from_block(from_block.unwrap_or_else(|| U64::from(0u64)));
+/// Consumes `feed`, reconnecting with jittered exponential backoff whenever it drops. Starts
+/// from the stored checkpoint when there is one, else `cfg.from_block`. Logs are staged until
+/// `cfg.confirmations` blocks are mined on top of them, then applied in chain order. Each
+/// (re)subscription begins at the last applied block; logs at or before the last one applied
+/// are skipped, and staged ones are matched by block hash, so nothing is indexed twice.
+pub async fn run_indexer<F, S>(
+    feed: F,
+    cfg: IndexerConfig,
+    store: Arc<S>,
+    status: Arc<IndexerStatus>,
+    mut shutdown: watch::Receiver<bool>,
+) where
+    F: LogFeed,
+    S: PollIndexSink + Send + Sync + 'static,
+{
+    let from_block = cfg.from_block.unwrap_or(0);
+    let mut checkpoints = Checkpoints::new(
+        store.clone(),
+        format!("{:?}", cfg.contract_address),
+        cfg.confirmations,
+    );
+    let mut last_applied = match store.load_checkpoint(&checkpoints.contract).await {
+        Ok(checkpoint) => checkpoint,
+        Err(err) => {
+            warn!(
+                ?err,
+                "could not load the indexer checkpoint; starting from from_block"
+            );
+            None
+        }
+    };
+    if let Some(checkpoint) = last_applied {
+        info!(
+            block = checkpoint.block,
+            log_index = checkpoint.log_index,
+            "indexer resuming from checkpoint"
+        );
+        status.last_block.store(checkpoint.block, Ordering::Relaxed);
+    }
+    status.running.store(true, Ordering::Relaxed);
+    status
+        .confirmations
+        .store(cfg.confirmations, Ordering::Relaxed);
+    let mut staged = StagedLogs::default();
+    let mut pending = PendingLogs::default();
+    let mut head = 0u64;
+    let mut summarized_at = Instant::now();
+    let mut backoff = INITIAL_BACKOFF.min(cfg.max_backoff);
+    loop {
+        let resume_from = last_applied.map_or(from_block, |c| c.block);
+        let started = Instant::now();
+        let (tx, mut rx) = mpsc::channel(256);
+        let consume = async {
+            let mut handled = 0u64;
+            while let Some(event) = rx.recv().await {
+                status.connected.store(true, Ordering::Relaxed);
+                let log = match event {
+                    FeedEvent::Head(block) => {
+                        if block > head && !pending.is_empty() {
+                            pending.retry(&store, &status).await;
+                        }
+                        head = head.max(block);
+                        None
+                    }
+                    FeedEvent::Log(log) => Some(*log),
+                };
+                if let Some(log) = log {
+                    handled += 1;
+                    let position = log_position(&log);
+                    if position.is_some() && position <= last_applied {
+                        if log.removed == Some(true) {
+                            warn!(
+                                ?position,
+                                "an applied log was reorged out; raise CONFIRMATIONS"
+                            );
+                        }
+                        continue;
+                    }
+                    let Some(position) = position else {
+                        // Not mined yet, so there is nothing to confirm it against.
+                        apply_log(&feed, &store, log, &status, &mut pending).await;
+                        continue;
+                    };
+                    head = head.max(position.block);
+                    let discarded = staged.stage(log);
+                    if discarded > 0 {
+                        warn!(
+                            block = position.block,
+                            discarded, "chain reorg: dropped staged logs"
+                        );
+                        status
+                            .discarded_events
+                            .fetch_add(discarded as u64, Ordering::Relaxed);
+                    }
+                }
+                for log in staged.take_confirmed(head, cfg.confirmations) {
+                    let position = log_position(&log);
+                    if apply_log(&feed, &store, log, &status, &mut pending).await {
+                        if let Some(position) = position {
+                            checkpoints.record(position).await;
+                            status.last_block.store(position.block, Ordering::Relaxed);
+                        }
+                    }
+                    last_applied = position.or(last_applied);
+                }
+                status.head_block.store(head, Ordering::Relaxed);
+                status
+                    .unconfirmed_events
+                    .store(staged.len() as u64, Ordering::Relaxed);
+                if summarized_at.elapsed() >= SUMMARY_INTERVAL {
+                    status.log_summary();
+                    summarized_at = Instant::now();
+                }
+            }
+            handled
+        };
+        let connection = async { tokio::join!(feed.stream(resume_from, tx), consume) };
+        let outcome = tokio::select! {
+            out = connection => Some(out),
+            _ = wait_for_shutdown(&mut shutdown) => None,
+        };
+        checkpoints.flush().await;
+        status.connected.store(false, Ordering::Relaxed);
+        let Some((result, handled)) = outcome else {
+            break;
+        };
+        match result {
+            Ok(()) => warn!(handled, "indexer log stream ended; reconnecting"),
+            Err(err) => {
+                warn!(?err, "indexer feed failed; retrying");
+                status.record_error(&err);
+            }
+        }
+        // A connection that did some work, or held up for a while, starts the backoff over.
+        if handled > 0 || started.elapsed() >= cfg.max_backoff {
+            backoff = INITIAL_BACKOFF.min(cfg.max_backoff);
+        }
+        let delay = jittered(backoff);
+        status.reconnects.fetch_add(1, Ordering::Relaxed);
+        info!(
+            delay_ms = delay.as_millis() as u64,
+            from_block = last_applied.map_or(from_block, |c| c.block),
+            "indexer reconnecting"
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = wait_for_shutdown(&mut shutdown) => break,
+        }
+        backoff = (backoff * 2).min(cfg.max_backoff);
+    }
+    status.running.store(false, Ordering::Relaxed);
+    info!("indexer stopped");
+}
 
-    let mut stream = provider
-        .subscribe_logs(&filter)
+/// `handle_log` through `pending`, logging rather than returning a failure; `true` when the
+/// log was applied or deferred. A `PollCreated` log's sender is looked up first to own the poll,
+/// and its block's time to date it.
+async fn apply_log<F, S>(
+    feed: &F,
+    store: &Arc<S>,
+    log: Log,
+    status: &IndexerStatus,
+    pending: &mut PendingLogs,
+) -> bool
+where
+    F: LogFeed,
+    S: PollIndexSink + Send + Sync + 'static,
+{
+    let creates_poll = log.topics.first() == Some(&PollCreatedEvent::signature());
+    let sender = match log.transaction_hash {
+        Some(tx_hash) if creates_poll => match feed.transaction_sender(tx_hash).await {
+            Ok(sender) => sender,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    ?tx_hash,
+                    "could not look up who created a poll; only admins can resolve it"
+                );
+                None
+            }
+        },
+        _ => None,
+    };
+    let block_time = match log.block_number {
+        Some(block) if creates_poll => match feed.block_time(block.as_u64()).await {
+            Ok(time) => time,
+            Err(err) => {
+                warn!(?err, %block, "could not look up a poll's block time; dating it now");
+                None
+            }
+        },
+        _ => None,
+    };
+    match pending
+        .apply(store, log.clone(), sender, block_time, status)
         .await
-        .map_err(|e| AppError::Validation(format!("subscribe failed: {e}")))?;
+    {
+        Ok(outcome) => {
+            status.record_outcome(outcome);
+            true
+        }
+        Err(err) => {
+            queue_failed_log(store, &log, sender, &err, status).await;
+            false
+        }
+    }
+}
 
-    info!(
-        "Indexer listening on {} for contract {:?}, from_block={:?}",
-        cfg.rpc_ws, cfg.contract_address, from_block
+/// Logs that reached the indexer before their poll's `PollCreated`, as can happen across
+/// backfills or providers, by poll id. They are replayed once the poll is indexed and retried
+/// on every new head in case another indexer writes it; after `MAX_PENDING_ATTEMPTS` heads
+/// they are dead-lettered: logged and counted, not applied. The buffer lives in memory only.
+#[derive(Default)]
+pub struct PendingLogs {
+    by_poll: BTreeMap<i64, Vec<(Log, u32)>>,
+}
+
+impl PendingLogs {
+    pub fn len(&self) -> usize {
+        self.by_poll.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_poll.is_empty()
+    }
+
+    /// `handle_log`, holding back a log whose poll is missing instead of failing on it. Logs
+    /// replayed after a `PollCreated` are counted on `status` directly.
+    pub async fn apply<S>(
+        &mut self,
+        store: &Arc<S>,
+        log: Log,
+        sender: Option<H160>,
+        block_time: Option<DateTime<Utc>>,
+        status: &IndexerStatus,
+    ) -> AppResult<LogOutcome>
+    where
+        S: PollIndexSink + Send + Sync + 'static,
+    {
+        let poll_id = log_poll_id(&log);
+        let outcome = match (
+            handle_log(store, log.clone(), sender, block_time).await,
+            poll_id,
+        ) {
+            (Err(AppError::NotFound), Some(poll_id)) => {
+                if self.len() >= MAX_PENDING_LOGS {
+                    dead_letter(&log, poll_id, "the pending buffer is full", status);
+                } else {
+                    debug!(poll_id, "holding a log until its poll is indexed");
+                    self.by_poll.entry(poll_id).or_default().push((log, 0));
+                }
+                LogOutcome::Deferred
+            }
+            (result, _) => result?,
+        };
+        if outcome == LogOutcome::PollCreated {
+            if let Some(waiting) = poll_id.and_then(|id| self.by_poll.remove(&id)) {
+                info!(
+                    poll_id,
+                    replayed = waiting.len(),
+                    "replaying logs that arrived before PollCreated"
+                );
+                for (log, _) in waiting {
+                    replay(store, log, status).await;
+                }
+            }
+        }
+        status
+            .pending_logs
+            .store(self.len() as u64, Ordering::Relaxed);
+        Ok(outcome)
+    }
+
+    /// Tries every held log again, dead-lettering those out of attempts.
+    pub async fn retry<S>(&mut self, store: &Arc<S>, status: &IndexerStatus)
+    where
+        S: PollIndexSink + Send + Sync + 'static,
+    {
+        for (poll_id, waiting) in std::mem::take(&mut self.by_poll) {
+            for (log, attempts) in waiting {
+                match handle_log(store, log.clone(), None, None).await {
+                    Ok(outcome) => status.record_outcome(outcome),
+                    Err(AppError::NotFound) if attempts + 1 >= MAX_PENDING_ATTEMPTS => {
+                        dead_letter(&log, poll_id, "its poll was never indexed", status);
+                    }
+                    Err(AppError::NotFound) => self
+                        .by_poll
+                        .entry(poll_id)
+                        .or_default()
+                        .push((log, attempts + 1)),
+                    Err(err) => queue_failed_log(store, &log, None, &err, status).await,
+                }
+            }
+        }
+        status
+            .pending_logs
+            .store(self.len() as u64, Ordering::Relaxed);
+    }
+}
+
+async fn replay<S>(store: &Arc<S>, log: Log, status: &IndexerStatus)
+where
+    S: PollIndexSink + Send + Sync + 'static,
+{
+    match handle_log(store, log.clone(), None, None).await {
+        Ok(outcome) => status.record_outcome(outcome),
+        Err(err) => queue_failed_log(store, &log, None, &err, status).await,
+    }
+}
+
+fn record_handler_error(err: &AppError, status: &IndexerStatus) {
+    error!("indexer handle_log error: {err:?}");
+    status.handler_errors.fetch_add(1, Ordering::Relaxed);
+    status.record_error(err);
+}
+
+fn dead_letter(log: &Log, poll_id: i64, reason: &str, status: &IndexerStatus) {
+    error!(
+        poll_id,
+        tx_hash = ?log.transaction_hash,
+        log_index = ?log.log_index,
+        block = ?log.block_number,
+        "dead-lettering an indexer log: {reason}"
     );
+    status.dead_lettered_logs.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The poll a log names, for the events that write under an existing poll.
+fn log_poll_id(log: &Log) -> Option<i64> {
+    let raw: RawLog = log.clone().into();
+    let poll_id = if let Ok(ev) = VoteRevealedEvent::decode_log(&raw) {
+        ev.poll_id
+    } else if let Ok(ev) = PollResolvedEvent::decode_log(&raw) {
+        ev.poll_id
+    } else if let Ok(ev) = CommitSubmittedEvent::decode_log(&raw) {
+        ev.poll_id
+    } else if let Ok(ev) = PollCreatedEvent::decode_log(&raw) {
+        ev.poll_id
+    } else {
+        return None;
+    };
+    Some(poll_id.as_u64() as i64)
+}
+
+/// Logs waiting for confirmations, by block height, with the hash of the block they came in.
+#[derive(Default)]
+struct StagedLogs {
+    heights: BTreeMap<u64, (Option<H256>, Vec<Log>)>,
+}
 
-    while let Some(log) = stream.next().await {
-        if let Err(err) = handle_log(&store, log).await {
-            error!("indexer handle_log error: {err:?}");
+impl StagedLogs {
+    fn len(&self) -> usize {
+        self.heights.values().map(|(_, logs)| logs.len()).sum()
+    }
+
+    /// Stages a mined log; returns how many staged logs a reorg discarded. A log from another
+    /// block at a staged height, or one the node marks `removed`, means the chain from that
+    /// height on was replaced, so everything staged there and above goes.
+    fn stage(&mut self, log: Log) -> usize {
+        let Some(block) = log.block_number.map(|b| b.as_u64()) else {
+            return 0;
+        };
+        let reorged = log.removed == Some(true)
+            || self
+                .heights
+                .get(&block)
+                .is_some_and(|(hash, _)| *hash != log.block_hash);
+        let discarded = if reorged {
+            let dropped = self.heights.split_off(&block);
+            dropped.values().map(|(_, logs)| logs.len()).sum()
+        } else {
+            0
+        };
+        if log.removed == Some(true) {
+            return discarded;
         }
+        let (_, logs) = self
+            .heights
+            .entry(block)
+            .or_insert_with(|| (log.block_hash, Vec::new()));
+        let seen = logs.iter().any(|staged| {
+            staged.transaction_hash == log.transaction_hash && staged.log_index == log.log_index
+        });
+        if !seen {
+            logs.push(log);
+        }
+        discarded
     }
 
-    Ok(())
+    /// Unstages the logs with at least `confirmations` blocks on top of them at `head`.
+    fn take_confirmed(&mut self, head: u64, confirmations: u64) -> Vec<Log> {
+        let Some(cutoff) = head.checked_sub(confirmations) else {
+            return Vec::new();
+        };
+        let pending = self.heights.split_off(&(cutoff + 1));
+        let confirmed = std::mem::replace(&mut self.heights, pending);
+        confirmed
+            .into_values()
+            .flat_map(|(_, mut logs)| {
+                logs.sort_by_key(|log| log.log_index);
+                logs
+            })
+            .collect()
+    }
+}
+
+/// Batches checkpoint writes: one save per `CHECKPOINT_EVERY_LOGS` processed logs or
+/// `CHECKPOINT_INTERVAL`, whichever comes first, plus one whenever a connection ends. Each
+/// save also prunes the processed-log guard up to `prune_margin` blocks behind the checkpoint;
+/// nothing older is replayed.
+struct Checkpoints<S> {
+    store: Arc<S>,
+    contract: String,
+    prune_margin: u64,
+    unsaved: Option<IndexerCheckpoint>,
+    unsaved_logs: u64,
+    saved_at: Instant,
+}
+
+impl<S> Checkpoints<S>
+where
+    S: PollIndexSink + Send + Sync,
+{
+    fn new(store: Arc<S>, contract: String, prune_margin: u64) -> Self {
+        Self {
+            store,
+            contract,
+            prune_margin,
+            unsaved: None,
+            unsaved_logs: 0,
+            saved_at: Instant::now(),
+        }
+    }
+
+    async fn record(&mut self, position: IndexerCheckpoint) {
+        self.unsaved = Some(position);
+        self.unsaved_logs += 1;
+        if self.unsaved_logs >= CHECKPOINT_EVERY_LOGS
+            || self.saved_at.elapsed() >= CHECKPOINT_INTERVAL
+        {
+            self.flush().await;
+        }
+    }
+
+    async fn flush(&mut self) {
+        let Some(checkpoint) = self.unsaved.take() else {
+            return;
+        };
+        match self.store.save_checkpoint(&self.contract, checkpoint).await {
+            Ok(()) => {
+                self.unsaved_logs = 0;
+                self.saved_at = Instant::now();
+                let below = checkpoint.block.saturating_sub(self.prune_margin);
+                match self.store.prune_processed_logs(below).await {
+                    Ok(pruned) => debug!(pruned, below, "pruned processed logs"),
+                    Err(err) => warn!(?err, "could not prune processed logs"),
+                }
+            }
+            // Kept for the next attempt; the indexer only replays a little more on restart.
+            Err(err) => {
+                warn!(?err, "could not save the indexer checkpoint");
+                self.unsaved = Some(checkpoint);
+            }
+        }
+    }
+}
+
+async fn wait_for_shutdown(shutdown: &mut watch::Receiver<bool>) {
+    // A dropped sender counts as a shutdown too.
+    let _ = shutdown.wait_for(|stop| *stop).await;
+}
+
+/// Half of `backoff` plus up to as much again at random, so restarted backends spread out.
+fn jittered(backoff: Duration) -> Duration {
+    let half = backoff / 2;
+    half + half.mul_f64(rand::thread_rng().gen::<f64>())
 }
 
-pub async fn handle_log<S>(store: &Arc<S>, log: Log) -> AppResult<()>
+fn log_position(log: &Log) -> Option<IndexerCheckpoint> {
+    Some(IndexerCheckpoint {
+        block: log.block_number?.as_u64(),
+        log_index: log.log_index?.as_u64(),
+    })
+}
+
+/// Applies one contract log. A log is applied at most once per (tx hash, log index), since
+/// reconnects, backfills and overlapping polls can deliver it again. `sender`, the log's
+/// transaction sender when the caller could look it up, owns a poll it creates; without it the
+/// poll has no owner and only admins can resolve it. `block_time`, likewise, dates the poll.
+pub async fn handle_log<S>(
+    store: &Arc<S>,
+    log: Log,
+    sender: Option<H160>,
+    block_time: Option<DateTime<Utc>>,
+) -> AppResult<LogOutcome>
+where
+    S: PollIndexSink + Send + Sync + 'static,
+{
+    let key = match (log.transaction_hash, log.log_index) {
+        (Some(tx_hash), Some(log_index)) => Some((format!("{tx_hash:#x}"), log_index.as_u64())),
+        _ => None,
+    };
+    if let Some((tx_hash, log_index)) = &key {
+        if store.log_processed(tx_hash, *log_index).await? {
+            debug!(
+                tx_hash,
+                log_index, "skipping a log that was already applied"
+            );
+            return Ok(LogOutcome::Duplicate);
+        }
+    }
+    let block = log.block_number.map_or(0, |b| b.as_u64());
+    let outcome = dispatch_log(store, log, sender, block_time).await?;
+    if let Some((tx_hash, log_index)) = key {
+        store
+            .record_processed_log(&tx_hash, log_index, block)
+            .await?;
+    }
+    Ok(outcome)
+}
+
+async fn dispatch_log<S>(
+    store: &Arc<S>,
+    log: Log,
+    sender: Option<H160>,
+    block_time: Option<DateTime<Utc>>,
+) -> AppResult<LogOutcome>
 where
     S: PollIndexSink + Send + Sync + 'static,
 {
@@ -102,7 +1173,8 @@ where
         let options_owned = ev.options.clone();
         let membership_owned = ev.membership_root.to_string();
         let category_owned = "General".to_string();
-        let owner_owned = String::new();
+        // Canonical lowercase `0x…`, as `resolve_poll` compares it.
+        let owner_owned = sender.map(|from| format!("{from:#x}")).unwrap_or_default();
         let np = NewPoll {
             question: &question_owned,
             options: &options_owned,
@@ -111,41 +1183,89 @@ where
             membership_root: &membership_owned,
             category: &category_owned,
             owner: &owner_owned,
+            auto_resolve: false,
+            visibility: "public",
+            anonymous_owner: false,
+            option_meta: &[],
         };
-        store.upsert_poll_from_chain(poll_id, np).await?;
+        store
+            .upsert_poll_from_chain(poll_id, np, block_time)
+            .await?;
         info!("Indexed PollCreated poll_id={}", poll_id);
-        return Ok(());
+        return Ok(LogOutcome::PollCreated);
     }
 
     if let Ok(ev) = VoteRevealedEvent::decode_log(&raw) {
         let poll_id = ev.poll_id.as_u64() as i64;
+        let nullifier = ev.nullifier.to_string();
         store
-            .upsert_vote_from_chain(poll_id, &ev.nullifier.to_string(), ev.choice_index)
+            .upsert_vote_from_chain(poll_id, &nullifier, ev.choice_index)
             .await?;
+        // Whoever sent the reveal, the commitment is on-chain now; this also covers relayer
+        // batches that landed before a crash kept `mark_commit_synced` from running.
+        if let Some(tx_hash) = log.transaction_hash {
+            store
+                .confirm_reveal_from_chain(poll_id, &nullifier, &format!("{tx_hash:#x}"))
+                .await?;
+        }
         info!(
             "Indexed VoteRevealed poll_id={} nullifier={}",
             poll_id, ev.nullifier
         );
-        return Ok(());
+        return Ok(LogOutcome::VoteRevealed);
+    }
+
+    if let Ok(ev) = CommitSubmittedEvent::decode_log(&raw) {
+        let poll_id = ev.poll_id.as_u64() as i64;
+        // Decimal, like the field elements the API path stores.
+        let commitment = U256::from_big_endian(&ev.commitment).to_string();
+        store.upsert_commit_from_chain(poll_id, &commitment).await?;
+        info!(
+            "Indexed Committed poll_id={} commitment={}",
+            poll_id, commitment
+        );
+        return Ok(LogOutcome::Committed);
     }
 
     if let Ok(ev) = PollResolvedEvent::decode_log(&raw) {
         let poll_id = ev.poll_id.as_u64() as i64;
+        let tx_hash = log.transaction_hash.map(|tx_hash| format!("{tx_hash:#x}"));
         store
-            .resolve_poll_from_chain(poll_id, ev.correct_option)
+            .resolve_poll_from_chain(poll_id, ev.correct_option, tx_hash.as_deref())
             .await?;
         info!(
             "Indexed PollResolved poll_id={} correct={}",
             poll_id, ev.correct_option
         );
-        return Ok(());
+        return Ok(LogOutcome::PollResolved);
     }
 
-    Ok(())
+    Ok(LogOutcome::Unknown)
+}
+
+/// Earliest timestamp (2000-01-01) a poll phase may end at, on chain or off.
+pub const MIN_PHASE_END_SECS: i64 = 946_684_800;
+/// Latest timestamp (2200-01-01) a poll phase may end at, on chain or off.
+pub const MAX_PHASE_END_SECS: i64 = 7_258_118_400;
+
+/// Rejects `secs` outside `MIN_PHASE_END_SECS..=MAX_PHASE_END_SECS`.
+pub fn check_phase_end(secs: i64) -> AppResult<i64> {
+    if (MIN_PHASE_END_SECS..=MAX_PHASE_END_SECS).contains(&secs) {
+        Ok(secs)
+    } else {
+        Err(AppError::Validation(format!(
+            "timestamp {secs} is outside years 2000-2200"
+        )))
+    }
 }
 
 fn to_ts(ts: U256) -> AppResult<DateTime<Utc>> {
-    let secs = ts.as_u64() as i64;
+    if ts > U256::from(i64::MAX as u64) {
+        return Err(AppError::Validation(format!(
+            "timestamp {ts} does not fit in i64 seconds"
+        )));
+    }
+    let secs = check_phase_end(ts.as_u64() as i64)?;
     DateTime::from_timestamp(secs, 0)
-        .ok_or_else(|| AppError::Validation("invalid timestamp".into()))
+        .ok_or_else(|| AppError::Validation(format!("invalid timestamp {secs}")))
 }
@@ -1,6 +1,12 @@
+pub mod crypto;
 pub mod doc;
 pub mod error;
+pub mod gas;
 pub mod indexer;
+pub mod merkle;
+pub mod metrics;
+pub mod receipt;
+pub mod relayer;
 pub mod repo;
 pub mod types;
 pub mod zk;
@@ -0,0 +1,975 @@
+//! Poseidon2 (BN254, width 4) and the membership tree the circuit checks, computed in-process.
+//! Mirrors `scripts/poseidon_merkle_noir.mjs` output for output, so either can back `PgStore`.
+//! Trees can also be hashed with keccak256 for deployments whose contract verifies those.
+use crate::error::{AppError, AppResult, MembershipError};
+use crate::repo::{MerklePath, MerkleResult};
+use async_trait::async_trait;
+use num_bigint::BigUint;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+const MERKLE_SCRIPT: &str = "./scripts/poseidon_merkle_noir.mjs";
+const DEFAULT_MERKLE_SCRIPT_TIMEOUT_SECS: u64 = 120;
+const MAX_MERKLE_SCRIPT_OUTPUT_BYTES: usize = 256 * 1024 * 1024;
+/// How much of the script's stderr is kept for error messages.
+const MERKLE_SCRIPT_STDERR_BYTES: usize = 4096;
+
+/// Tree depth when `MERKLE_DEPTH` is unset; the bundled circuit is compiled for it.
+pub const DEFAULT_MERKLE_DEPTH: u32 = 20;
+/// Largest depth `MERKLE_DEPTH` accepts; leaf counts stay well inside `u64`.
+pub const MAX_MERKLE_DEPTH: u32 = 32;
+
+const BN254_FR_MODULUS: &str =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+const WIDTH: usize = 4;
+
+/// Internal matrix diagonal minus one; the matrix is `diag(d) + 1` (barretenberg's parameters).
+const INTERNAL_DIAGONAL: [&str; WIDTH] = [
+    "0x10dc6e9c006ea38b04b1e03b4bd9490c0d03f98929ca1d7fb56821fd19d3b6e7",
+    "0x0c28145b6a44df3e0149b3d0a30b3bb599df9756d4dd9b84a86b38cfb45a740b",
+    "0x00544b8338791518b2c7645a50392798b21f75bb60e3596170067d00141cac15",
+    "0x222c01175718386f2e2e82eb122789e352e105a3b8fa852613bc534433ee428b",
+];
+
+/// Round constants of the four leading full rounds.
+const FULL_ROUNDS_BEGIN: [[&str; WIDTH]; 4] = [
+    [
+        "0x19b849f69450b06848da1d39bd5e4a4302bb86744edc26238b0878e269ed23e5",
+        "0x265ddfe127dd51bd7239347b758f0a1320eb2cc7450acc1dad47f80c8dcf34d6",
+        "0x199750ec472f1809e0f66a545e1e51624108ac845015c2aa3dfc36bab497d8aa",
+        "0x157ff3fe65ac7208110f06a5f74302b14d743ea25067f0ffd032f787c7f1cdf8",
+    ],
+    [
+        "0x2e49c43c4569dd9c5fd35ac45fca33f10b15c590692f8beefe18f4896ac94902",
+        "0x0e35fb89981890520d4aef2b6d6506c3cb2f0b6973c24fa82731345ffa2d1f1e",
+        "0x251ad47cb15c4f1105f109ae5e944f1ba9d9e7806d667ffec6fe723002e0b996",
+        "0x13da07dc64d428369873e97160234641f8beb56fdd05e5f3563fa39d9c22df4e",
+    ],
+    [
+        "0x0c009b84e650e6d23dc00c7dccef7483a553939689d350cd46e7b89055fd4738",
+        "0x011f16b1c63a854f01992e3956f42d8b04eb650c6d535eb0203dec74befdca06",
+        "0x0ed69e5e383a688f209d9a561daa79612f3f78d0467ad45485df07093f367549",
+        "0x04dba94a7b0ce9e221acad41472b6bbe3aec507f5eb3d33f463672264c9f789b",
+    ],
+    [
+        "0x0a3f2637d840f3a16eb094271c9d237b6036757d4bb50bf7ce732ff1d4fa28e8",
+        "0x259a666f129eea198f8a1c502fdb38fa39b1f075569564b6e54a485d1182323f",
+        "0x28bf7459c9b2f4c6d8e7d06a4ee3a47f7745d4271038e5157a32fdf7ede0d6a1",
+        "0x0a1ca941f057037526ea200f489be8d4c37c85bbcce6a2aeec91bd6941432447",
+    ],
+];
+/// Round constants of the partial rounds, applied to the first lane only.
+const PARTIAL_ROUNDS: [&str; 56] = [
+    "0x0c6f8f958be0e93053d7fd4fc54512855535ed1539f051dcb43a26fd926361cf",
+    "0x123106a93cd17578d426e8128ac9d90aa9e8a00708e296e084dd57e69caaf811",
+    "0x26e1ba52ad9285d97dd3ab52f8e840085e8fa83ff1e8f1877b074867cd2dee75",
+    "0x1cb55cad7bd133de18a64c5c47b9c97cbe4d8b7bf9e095864471537e6a4ae2c5",
+    "0x1dcd73e46acd8f8e0e2c7ce04bde7f6d2a53043d5060a41c7143f08e6e9055d0",
+    "0x011003e32f6d9c66f5852f05474a4def0cda294a0eb4e9b9b12b9bb4512e5574",
+    "0x2b1e809ac1d10ab29ad5f20d03a57dfebadfe5903f58bafed7c508dd2287ae8c",
+    "0x2539de1785b735999fb4dac35ee17ed0ef995d05ab2fc5faeaa69ae87bcec0a5",
+    "0x0c246c5a2ef8ee0126497f222b3e0a0ef4e1c3d41c86d46e43982cb11d77951d",
+    "0x192089c4974f68e95408148f7c0632edbb09e6a6ad1a1c2f3f0305f5d03b527b",
+    "0x1eae0ad8ab68b2f06a0ee36eeb0d0c058529097d91096b756d8fdc2fb5a60d85",
+    "0x179190e5d0e22179e46f8282872abc88db6e2fdc0dee99e69768bd98c5d06bfb",
+    "0x29bb9e2c9076732576e9a81c7ac4b83214528f7db00f31bf6cafe794a9b3cd1c",
+    "0x225d394e42207599403efd0c2464a90d52652645882aac35b10e590e6e691e08",
+    "0x064760623c25c8cf753d238055b444532be13557451c087de09efd454b23fd59",
+    "0x10ba3a0e01df92e87f301c4b716d8a394d67f4bf42a75c10922910a78f6b5b87",
+    "0x0e070bf53f8451b24f9c6e96b0c2a801cb511bc0c242eb9d361b77693f21471c",
+    "0x1b94cd61b051b04dd39755ff93821a73ccd6cb11d2491d8aa7f921014de252fb",
+    "0x1d7cb39bafb8c744e148787a2e70230f9d4e917d5713bb050487b5aa7d74070b",
+    "0x2ec93189bd1ab4f69117d0fe980c80ff8785c2961829f701bb74ac1f303b17db",
+    "0x2db366bfdd36d277a692bb825b86275beac404a19ae07a9082ea46bd83517926",
+    "0x062100eb485db06269655cf186a68532985275428450359adc99cec6960711b8",
+    "0x0761d33c66614aaa570e7f1e8244ca1120243f92fa59e4f900c567bf41f5a59b",
+    "0x20fc411a114d13992c2705aa034e3f315d78608a0f7de4ccf7a72e494855ad0d",
+    "0x25b5c004a4bdfcb5add9ec4e9ab219ba102c67e8b3effb5fc3a30f317250bc5a",
+    "0x23b1822d278ed632a494e58f6df6f5ed038b186d8474155ad87e7dff62b37f4b",
+    "0x22734b4c5c3f9493606c4ba9012499bf0f14d13bfcfcccaa16102a29cc2f69e0",
+    "0x26c0c8fe09eb30b7e27a74dc33492347e5bdff409aa3610254413d3fad795ce5",
+    "0x070dd0ccb6bd7bbae88eac03fa1fbb26196be3083a809829bbd626df348ccad9",
+    "0x12b6595bdb329b6fb043ba78bb28c3bec2c0a6de46d8c5ad6067c4ebfd4250da",
+    "0x248d97d7f76283d63bec30e7a5876c11c06fca9b275c671c5e33d95bb7e8d729",
+    "0x1a306d439d463b0816fc6fd64cc939318b45eb759ddde4aa106d15d9bd9baaaa",
+    "0x28a8f8372e3c38daced7c00421cb4621f4f1b54ddc27821b0d62d3d6ec7c56cf",
+    "0x0094975717f9a8a8bb35152f24d43294071ce320c829f388bc852183e1e2ce7e",
+    "0x04d5ee4c3aa78f7d80fde60d716480d3593f74d4f653ae83f4103246db2e8d65",
+    "0x2a6cf5e9aa03d4336349ad6fb8ed2269c7bef54b8822cc76d08495c12efde187",
+    "0x2304d31eaab960ba9274da43e19ddeb7f792180808fd6e43baae48d7efcba3f3",
+    "0x03fd9ac865a4b2a6d5e7009785817249bff08a7e0726fcb4e1c11d39d199f0b0",
+    "0x00b7258ded52bbda2248404d55ee5044798afc3a209193073f7954d4d63b0b64",
+    "0x159f81ada0771799ec38fca2d4bf65ebb13d3a74f3298db36272c5ca65e92d9a",
+    "0x1ef90e67437fbc8550237a75bc28e3bb9000130ea25f0c5471e144cf4264431f",
+    "0x1e65f838515e5ff0196b49aa41a2d2568df739bc176b08ec95a79ed82932e30d",
+    "0x2b1b045def3a166cec6ce768d079ba74b18c844e570e1f826575c1068c94c33f",
+    "0x0832e5753ceb0ff6402543b1109229c165dc2d73bef715e3f1c6e07c168bb173",
+    "0x02f614e9cedfb3dc6b762ae0a37d41bab1b841c2e8b6451bc5a8e3c390b6ad16",
+    "0x0e2427d38bd46a60dd640b8e362cad967370ebb777bedff40f6a0be27e7ed705",
+    "0x0493630b7c670b6deb7c84d414e7ce79049f0ec098c3c7c50768bbe29214a53a",
+    "0x22ead100e8e482674decdab17066c5a26bb1515355d5461a3dc06cc85327cea9",
+    "0x25b3e56e655b42cdaae2626ed2554d48583f1ae35626d04de5084e0b6d2a6f16",
+    "0x1e32752ada8836ef5837a6cde8ff13dbb599c336349e4c584b4fdc0a0cf6f9d0",
+    "0x2fa2a871c15a387cc50f68f6f3c3455b23c00995f05078f672a9864074d412e5",
+    "0x2f569b8a9a4424c9278e1db7311e889f54ccbf10661bab7fcd18e7c7a7d83505",
+    "0x044cb455110a8fdd531ade530234c518a7df93f7332ffd2144165374b246b43d",
+    "0x227808de93906d5d420246157f2e42b191fe8c90adfe118178ddc723a5319025",
+    "0x02fcca2934e046bc623adead873579865d03781ae090ad4a8579d2e7a6800355",
+    "0x0ef915f0ac120b876abccceb344a1d36bad3f3c5ab91a8ddcbec2e060d8befac",
+];
+/// Round constants of the four trailing full rounds.
+const FULL_ROUNDS_END: [[&str; WIDTH]; 4] = [
+    [
+        "0x1797130f4b7a3e1777eb757bc6f287f6ab0fb85f6be63b09f3b16ef2b1405d38",
+        "0x0a76225dc04170ae3306c85abab59e608c7f497c20156d4d36c668555decc6e5",
+        "0x1fffb9ec1992d66ba1e77a7b93209af6f8fa76d48acb664796174b5326a31a5c",
+        "0x25721c4fc15a3f2853b57c338fa538d85f8fbba6c6b9c6090611889b797b9c5f",
+    ],
+    [
+        "0x0c817fd42d5f7a41215e3d07ba197216adb4c3790705da95eb63b982bfcaf75a",
+        "0x13abe3f5239915d39f7e13c2c24970b6df8cf86ce00a22002bc15866e52b5a96",
+        "0x2106feea546224ea12ef7f39987a46c85c1bc3dc29bdbd7a92cd60acb4d391ce",
+        "0x21ca859468a746b6aaa79474a37dab49f1ca5a28c748bc7157e1b3345bb0f959",
+    ],
+    [
+        "0x05ccd6255c1e6f0c5cf1f0df934194c62911d14d0321662a8f1a48999e34185b",
+        "0x0f0e34a64b70a626e464d846674c4c8816c4fb267fe44fe6ea28678cb09490a4",
+        "0x0558531a4e25470c6157794ca36d0e9647dbfcfe350d64838f5b1a8a2de0d4bf",
+        "0x09d3dca9173ed2faceea125157683d18924cadad3f655a60b72f5864961f1455",
+    ],
+    [
+        "0x0328cbd54e8c0913493f866ed03d218bf23f92d68aaec48617d4c722e5bd4335",
+        "0x2bf07216e2aff0a223a487b1a7094e07e79e7bcc9798c648ee3347dd5329d34b",
+        "0x1daf345a58006b736499c583cb76c316d6f78ed6a6dffc82111e11a63fe412df",
+        "0x176563472456aaa746b694c60e1823611ef39039b2edc7ff391e6f2293d2c404",
+    ],
+];
+
+/// Limbs of the BN254 scalar field modulus, least significant first.
+const MODULUS_LIMBS: [u64; 4] = [
+    0x43e1f593f0000001,
+    0x2833e84879b97091,
+    0xb85045b68181585d,
+    0x30644e72e131a029,
+];
+/// `-p^-1 mod 2^64`, for Montgomery reduction.
+const MONTGOMERY_INV: u64 = 0xc2e1f593efffffff;
+/// `R^2 mod p` with `R = 2^256`, to move values into Montgomery form.
+const MONTGOMERY_R2: Fr = Fr([
+    0x1bb8e645ae216da7,
+    0x53fe3ab1e35c59e3,
+    0x8c49833d53bb8085,
+    0x0216d0b17f4e44a5,
+]);
+
+/// Field element in Montgomery form. The permutation runs on fixed-size limbs because
+/// `BigUint` arithmetic is far too slow for the thousands of hashes a tree needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fr([u64; 4]);
+
+impl Fr {
+    const ZERO: Fr = Fr([0; 4]);
+
+    fn from_biguint(value: &BigUint) -> Self {
+        let reduced = value % &*MODULUS;
+        let mut limbs = [0u64; 4];
+        for (limb, digit) in limbs.iter_mut().zip(reduced.iter_u64_digits()) {
+            *limb = digit;
+        }
+        Fr(limbs).mul(MONTGOMERY_R2)
+    }
+
+    fn to_biguint(self) -> BigUint {
+        let Fr(limbs) = self.mul(Fr([1, 0, 0, 0]));
+        BigUint::new(
+            limbs
+                .iter()
+                .flat_map(|limb| [*limb as u32, (limb >> 32) as u32])
+                .collect(),
+        )
+    }
+
+    fn hex(value: &str) -> Self {
+        Self::from_biguint(
+            &BigUint::parse_bytes(value.trim_start_matches("0x").as_bytes(), 16)
+                .expect("valid constant"),
+        )
+    }
+
+    /// Subtracts the modulus once if `limbs >= p`.
+    fn reduce_once(mut limbs: [u64; 4]) -> Self {
+        let geq = limbs
+            .iter()
+            .rev()
+            .zip(MODULUS_LIMBS.iter().rev())
+            .find(|(a, b)| a != b)
+            .is_none_or(|(a, b)| a > b);
+        if geq {
+            let mut borrow = false;
+            for (limb, p) in limbs.iter_mut().zip(MODULUS_LIMBS) {
+                let (diff, b1) = limb.overflowing_sub(p);
+                let (diff, b2) = diff.overflowing_sub(borrow as u64);
+                *limb = diff;
+                borrow = b1 || b2;
+            }
+        }
+        Fr(limbs)
+    }
+
+    fn add(self, rhs: Fr) -> Fr {
+        // Both operands are below p < 2^254, so the sum cannot overflow 256 bits.
+        let mut limbs = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let (sum, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        Self::reduce_once(limbs)
+    }
+
+    /// Montgomery multiplication (CIOS): `self * rhs / R mod p`.
+    fn mul(self, rhs: Fr) -> Fr {
+        let mut t = [0u64; 6];
+        for &b in &rhs.0 {
+            let mut carry = 0u128;
+            for (j, &a) in self.0.iter().enumerate() {
+                let acc = t[j] as u128 + a as u128 * b as u128 + carry;
+                t[j] = acc as u64;
+                carry = acc >> 64;
+            }
+            let acc = t[4] as u128 + carry;
+            t[4] = acc as u64;
+            t[5] = (acc >> 64) as u64;
+
+            let m = t[0].wrapping_mul(MONTGOMERY_INV);
+            let mut carry = (t[0] as u128 + m as u128 * MODULUS_LIMBS[0] as u128) >> 64;
+            for j in 1..4 {
+                let acc = t[j] as u128 + m as u128 * MODULUS_LIMBS[j] as u128 + carry;
+                t[j - 1] = acc as u64;
+                carry = acc >> 64;
+            }
+            let acc = t[4] as u128 + carry;
+            t[3] = acc as u64;
+            t[4] = t[5] + (acc >> 64) as u64;
+        }
+        Self::reduce_once([t[0], t[1], t[2], t[3]])
+    }
+
+    fn double(self) -> Fr {
+        self.add(self)
+    }
+}
+
+type State = [Fr; WIDTH];
+
+struct Params {
+    begin: Vec<State>,
+    partial: Vec<Fr>,
+    end: Vec<State>,
+    diagonal: State,
+}
+
+fn hex_state(values: &[&str; WIDTH]) -> State {
+    values.map(Fr::hex)
+}
+
+static MODULUS: Lazy<BigUint> =
+    Lazy::new(|| BigUint::parse_bytes(BN254_FR_MODULUS.as_bytes(), 10).expect("valid modulus"));
+
+static PARAMS: Lazy<Params> = Lazy::new(|| Params {
+    begin: FULL_ROUNDS_BEGIN.iter().map(hex_state).collect(),
+    partial: PARTIAL_ROUNDS.iter().map(|c| Fr::hex(c)).collect(),
+    end: FULL_ROUNDS_END.iter().map(hex_state).collect(),
+    diagonal: hex_state(&INTERNAL_DIAGONAL),
+});
+
+impl Params {
+    fn sbox(x: Fr) -> Fr {
+        let x2 = x.mul(x);
+        let x4 = x2.mul(x2);
+        x4.mul(x)
+    }
+
+    /// The `M4` external matrix, in barretenberg's addition chain.
+    fn external(s: &mut State) {
+        let t0 = s[0].add(s[1]);
+        let t1 = s[2].add(s[3]);
+        let t2 = s[1].double().add(t1);
+        let t3 = s[3].double().add(t0);
+        let t4 = t1.double().double().add(t3);
+        let t5 = t0.double().double().add(t2);
+        let t6 = t3.add(t5);
+        let t7 = t2.add(t4);
+        *s = [t6, t5, t7, t4];
+    }
+
+    fn internal(&self, s: &mut State) {
+        let sum = s.iter().fold(Fr::ZERO, |acc, lane| acc.add(*lane));
+        for (lane, d) in s.iter_mut().zip(&self.diagonal) {
+            *lane = lane.mul(*d).add(sum);
+        }
+    }
+
+    fn full_round(s: &mut State, constants: &State) {
+        for (lane, c) in s.iter_mut().zip(constants) {
+            *lane = Self::sbox(lane.add(*c));
+        }
+        Self::external(s);
+    }
+
+    fn permute(&self, mut s: State) -> State {
+        Self::external(&mut s);
+        for constants in &self.begin {
+            Self::full_round(&mut s, constants);
+        }
+        for c in &self.partial {
+            s[0] = Self::sbox(s[0].add(*c));
+            self.internal(&mut s);
+        }
+        for constants in &self.end {
+            Self::full_round(&mut s, constants);
+        }
+        s
+    }
+}
+
+/// Poseidon2 permutation over BN254 with width 4, as `poseidon2_permutation(_, 4)` in Noir.
+pub fn poseidon2_permutation(state: [BigUint; WIDTH]) -> [BigUint; WIDTH] {
+    PARAMS
+        .permute(state.map(|v| Fr::from_biguint(&v)))
+        .map(Fr::to_biguint)
+}
+
+/// The circuit's one-input hash (leaves).
+pub fn hash1(x: &BigUint) -> BigUint {
+    let [out, ..] = poseidon2_permutation([x.clone(), 0u32.into(), 0u32.into(), 0u32.into()]);
+    out
+}
+
+/// The circuit's two-input hash (inner nodes, commitments and nullifiers).
+pub fn hash2(left: &BigUint, right: &BigUint) -> BigUint {
+    let [out, ..] = poseidon2_permutation([left.clone(), right.clone(), 0u32.into(), 0u32.into()]);
+    out
+}
+
+/// Field element a member identity stands for: decimal or `0x` hex values reduced into the
+/// field, anything else the sha256 of the (trimmed) string.
+pub fn member_field(member: &str) -> BigUint {
+    let modulus = &*MODULUS;
+    let raw = member.trim();
+    if raw.is_empty() {
+        return BigUint::default();
+    }
+    let (negative, digits) = match raw.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw),
+    };
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        let value = BigUint::parse_bytes(digits.as_bytes(), 10).expect("decimal digits") % modulus;
+        return if negative {
+            (modulus - value) % modulus
+        } else {
+            value
+        };
+    }
+    if let Some(hex) = raw.strip_prefix("0x") {
+        if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return BigUint::parse_bytes(hex.as_bytes(), 16).expect("hex digits") % modulus;
+        }
+    }
+    BigUint::from_bytes_be(&Sha256::digest(raw.as_bytes())) % modulus
+}
+
+/// Leaves a depth-`depth` tree holds.
+pub fn capacity(depth: u32) -> u64 {
+    1u64 << depth
+}
+
+/// Whether `members` leaves fill more than 80% of a depth-`depth` tree, time to plan a
+/// move to a deeper one.
+pub fn near_capacity(members: u64, depth: u32) -> bool {
+    members * 5 > capacity(depth) * 4
+}
+
+pub fn check_capacity(members: u64, depth: u32) -> Result<(), MembershipError> {
+    let capacity = capacity(depth);
+    if members > capacity {
+        return Err(MembershipError::CapacityExceeded {
+            members,
+            capacity,
+            depth,
+        });
+    }
+    Ok(())
+}
+
+/// Rejects member lists `build_tree` cannot represent: more leaves than the tree holds, or
+/// a member that is not a decimal or `0x` hex field element below the modulus.
+pub fn check_members(members: &[String], depth: u32) -> Result<(), MembershipError> {
+    check_capacity(members.len() as u64, depth)?;
+    let parse = |member: &str| match member.strip_prefix("0x") {
+        Some(hex) if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            BigUint::parse_bytes(hex.as_bytes(), 16)
+        }
+        None if !member.is_empty() && member.bytes().all(|b| b.is_ascii_digit()) => {
+            BigUint::parse_bytes(member.as_bytes(), 10)
+        }
+        _ => None,
+    };
+    match members
+        .iter()
+        .position(|m| parse(m).is_none_or(|value| value >= *MODULUS))
+    {
+        Some(index) => Err(MembershipError::InvalidMember { index }),
+        None => Ok(()),
+    }
+}
+
+/// The two hashes a membership tree is built from.
+pub trait MerkleHasher: Send + Sync {
+    /// Leaf of a member's field element.
+    fn leaf(&self, member: &BigUint) -> BigUint;
+    /// Parent of two child nodes.
+    fn node(&self, left: &BigUint, right: &BigUint) -> BigUint;
+}
+
+/// The circuit's hashes: `hash1` leaves and `hash2` nodes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoseidonHasher;
+
+impl MerkleHasher for PoseidonHasher {
+    fn leaf(&self, member: &BigUint) -> BigUint {
+        hash1(member)
+    }
+
+    fn node(&self, left: &BigUint, right: &BigUint) -> BigUint {
+        hash2(left, right)
+    }
+}
+
+/// keccak256 over 32-byte big-endian words, as a Solidity verifier hashes
+/// `abi.encodePacked(bytes32)` leaves and `abi.encodePacked(left, right)` nodes. Pairs are
+/// positional, not sorted. Values are full 256-bit words, not reduced into the field.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeccakHasher;
+
+fn word(value: &BigUint) -> [u8; 32] {
+    let bytes = value.to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+impl MerkleHasher for KeccakHasher {
+    fn leaf(&self, member: &BigUint) -> BigUint {
+        BigUint::from_bytes_be(&ethers::utils::keccak256(word(member)))
+    }
+
+    fn node(&self, left: &BigUint, right: &BigUint) -> BigUint {
+        let mut pair = [0u8; 64];
+        pair[..32].copy_from_slice(&word(left));
+        pair[32..].copy_from_slice(&word(right));
+        BigUint::from_bytes_be(&ethers::utils::keccak256(pair))
+    }
+}
+
+/// Which hasher builds membership trees, chosen per deployment by `MERKLE_HASH` and recorded
+/// on every poll, so a poll's root and paths are always rebuilt with the hash it froze.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashScheme {
+    #[default]
+    Poseidon,
+    Keccak,
+}
+
+impl HashScheme {
+    pub fn parse(var: impl Fn(&str) -> Option<String>) -> AppResult<Self> {
+        let raw = var("MERKLE_HASH").unwrap_or_default();
+        match raw.trim() {
+            "" => Ok(Self::default()),
+            value => value.parse(),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Poseidon => "poseidon",
+            Self::Keccak => "keccak",
+        }
+    }
+
+    pub fn hasher(self) -> &'static dyn MerkleHasher {
+        match self {
+            Self::Poseidon => &PoseidonHasher,
+            Self::Keccak => &KeccakHasher,
+        }
+    }
+
+    /// Roots of all-zero subtrees: `[h]` is a height-`h` subtree of zero leaves.
+    fn zero_subtrees(self) -> &'static [BigUint] {
+        match self {
+            Self::Poseidon => &POSEIDON_ZERO_SUBTREES,
+            Self::Keccak => &KECCAK_ZERO_SUBTREES,
+        }
+    }
+}
+
+impl std::str::FromStr for HashScheme {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> AppResult<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "poseidon" => Ok(Self::Poseidon),
+            "keccak" => Ok(Self::Keccak),
+            other => Err(AppError::Validation(format!(
+                "unknown MERKLE_HASH {other:?} (expected poseidon or keccak)"
+            ))),
+        }
+    }
+}
+
+/// `build_tree_with` the circuit's Poseidon hashes.
+#[allow(dead_code)]
+pub fn build_tree(members: &[String], depth: u32) -> MerkleResult {
+    build_tree_with(members, depth, &PoseidonHasher)
+}
+
+/// Builds the membership tree over `members` (in order) and every member's path. The tree is
+/// the smallest power of two holding the leaves, zero-padded, then hashed with zero up to
+/// `depth`; roots and siblings are decimal strings, bits `"0"` (left) or `"1"` (right).
+/// The members must fit the tree (`check_capacity`), or the root comes out wrong.
+pub fn build_tree_with(members: &[String], depth: u32, hasher: &dyn MerkleHasher) -> MerkleResult {
+    let zero = BigUint::default();
+    let mut leaves: Vec<BigUint> = members
+        .iter()
+        .map(|m| hasher.leaf(&member_field(m)))
+        .collect();
+    leaves.resize(members.len().max(1).next_power_of_two(), zero.clone());
+
+    let mut levels = vec![leaves];
+    while let Some(prev) = levels.last().filter(|level| level.len() > 1) {
+        let next = prev
+            .chunks(2)
+            .map(|pair| hasher.node(&pair[0], pair.get(1).unwrap_or(&zero)))
+            .collect();
+        levels.push(next);
+    }
+    while levels.len() < depth as usize + 1 {
+        let extended = hasher.node(&levels[levels.len() - 1][0], &zero);
+        levels.push(vec![extended]);
+    }
+
+    let mut paths = HashMap::with_capacity(members.len());
+    for (i, member) in members.iter().enumerate() {
+        let mut idx = i;
+        let mut path = MerklePath {
+            bits: Vec::with_capacity(depth as usize),
+            siblings: Vec::with_capacity(depth as usize),
+        };
+        for level in &levels[..depth as usize] {
+            let is_left = idx % 2 == 0;
+            let sibling = if is_left { idx + 1 } else { idx - 1 };
+            path.bits.push(if is_left { "0" } else { "1" }.to_string());
+            path.siblings
+                .push(level.get(sibling).unwrap_or(&zero).to_str_radix(10));
+            idx = if level.len() == 1 { 0 } else { idx / 2 };
+        }
+        paths.insert(member.clone(), path);
+    }
+
+    MerkleResult {
+        root: levels[depth as usize][0].to_str_radix(10),
+        paths,
+        depth,
+    }
+}
+
+/// Tree nodes `(level, index, hash)` that some member's path uses as a sibling: exactly what
+/// `assemble_path` needs to rebuild every path. Zero padding is left out.
+pub fn tree_nodes(members: &[String], tree: &MerkleResult) -> Vec<(u32, u64, String)> {
+    let mut nodes = HashMap::new();
+    for (leaf, member) in members.iter().enumerate() {
+        let Some(path) = tree.paths.get(member) else {
+            continue;
+        };
+        for (level, sibling) in path.siblings.iter().enumerate() {
+            if sibling != "0" {
+                let index = sibling_index(leaf as u64, level as u32);
+                nodes.insert((level as u32, index), sibling.clone());
+            }
+        }
+    }
+    nodes
+        .into_iter()
+        .map(|((level, index), hash)| (level, index, hash))
+        .collect()
+}
+
+/// Index of the sibling of the leaf's ancestor at `level`.
+pub fn sibling_index(leaf_index: u64, level: u32) -> u64 {
+    leaf_index.checked_shr(level).unwrap_or(0) ^ 1
+}
+
+/// The path of leaf `leaf_index` from the sibling nodes found for it; absent nodes are zero.
+pub fn assemble_path(
+    leaf_index: u64,
+    depth: u32,
+    nodes: &HashMap<(u32, u64), String>,
+) -> MerklePath {
+    let (bits, siblings) = (0..depth)
+        .map(|level| {
+            let bit = leaf_index.checked_shr(level).unwrap_or(0) & 1;
+            let sibling = nodes
+                .get(&(level, sibling_index(leaf_index, level)))
+                .cloned()
+                .unwrap_or_else(|| "0".to_string());
+            (bit.to_string(), sibling)
+        })
+        .unzip();
+    MerklePath { bits, siblings }
+}
+
+fn zero_subtrees(hasher: &dyn MerkleHasher) -> Vec<BigUint> {
+    let mut zeros = vec![BigUint::default()];
+    for h in 0..MAX_MERKLE_DEPTH as usize {
+        let next = hasher.node(&zeros[h], &zeros[h]);
+        zeros.push(next);
+    }
+    zeros
+}
+
+static POSEIDON_ZERO_SUBTREES: Lazy<Vec<BigUint>> = Lazy::new(|| zero_subtrees(&PoseidonHasher));
+static KECCAK_ZERO_SUBTREES: Lazy<Vec<BigUint>> = Lazy::new(|| zero_subtrees(&KeccakHasher));
+
+fn decimal_field(value: &str) -> AppResult<BigUint> {
+    BigUint::parse_bytes(value.as_bytes(), 10)
+        .ok_or_else(|| AppError::External(format!("membership tree: bad field element {value:?}")))
+}
+
+/// Append-only form of the `build_tree` root: the standard incremental construction's
+/// frontier of filled subtrees, so adding a member costs `O(depth)` hashes and the root is
+/// kept ready to read. Leaves are in insertion order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncrementalTree {
+    pub leaf_count: u64,
+    /// Decimal roots of the latest complete left subtree at each height `0..=depth`.
+    pub filled_subtrees: Vec<String>,
+    pub root: String,
+    /// Frontiers stored before schemes existed are Poseidon ones.
+    #[serde(default)]
+    pub scheme: HashScheme,
+}
+
+impl IncrementalTree {
+    /// Empty tree of the given depth.
+    pub fn new(depth: u32, scheme: HashScheme) -> Self {
+        let mut tree = Self {
+            leaf_count: 0,
+            filled_subtrees: vec!["0".to_string(); depth as usize + 1],
+            root: String::new(),
+            scheme,
+        };
+        tree.root = tree.compute_root().expect("empty frontier is valid");
+        tree
+    }
+
+    pub fn from_members(members: &[String], depth: u32, scheme: HashScheme) -> AppResult<Self> {
+        let mut tree = Self::new(depth, scheme);
+        for member in members {
+            tree.push(member)?;
+        }
+        Ok(tree)
+    }
+
+    /// Depth the frontier was sized for.
+    pub fn depth(&self) -> u32 {
+        self.filled_subtrees.len().saturating_sub(1) as u32
+    }
+
+    /// Appends `member` as the next leaf and refreshes `root`.
+    pub fn push(&mut self, member: &str) -> AppResult<()> {
+        check_capacity(self.leaf_count + 1, self.depth())?;
+        let depth = self.depth() as usize;
+        let mut filled = self
+            .filled_subtrees
+            .iter()
+            .map(|v| decimal_field(v))
+            .collect::<AppResult<Vec<_>>>()?;
+        let hasher = self.scheme.hasher();
+        let mut node = hasher.leaf(&member_field(member));
+        let mut idx = self.leaf_count;
+        for (height, zero) in self
+            .scheme
+            .zero_subtrees()
+            .iter()
+            .enumerate()
+            .take(depth + 1)
+        {
+            let is_left = idx & 1 == 0;
+            if is_left {
+                filled[height] = node.clone();
+            }
+            if height == depth {
+                break;
+            }
+            node = if is_left {
+                hasher.node(&node, zero)
+            } else {
+                hasher.node(&filled[height], &node)
+            };
+            idx >>= 1;
+        }
+        self.filled_subtrees = filled.iter().map(|v| v.to_str_radix(10)).collect();
+        self.leaf_count += 1;
+        self.root = self.compute_root()?;
+        Ok(())
+    }
+
+    /// The root `build_tree` would give for the same leaves: the smallest power-of-two tree
+    /// over them, then hashed with a literal zero up to the tree's depth.
+    fn compute_root(&self) -> AppResult<String> {
+        let (hasher, zeros) = (self.scheme.hasher(), self.scheme.zero_subtrees());
+        let n = self.leaf_count;
+        let height = n.max(1).next_power_of_two().trailing_zeros() as usize;
+        let filled = |h: usize| {
+            self.filled_subtrees
+                .get(h)
+                .map_or(Ok(BigUint::default()), |v| decimal_field(v))
+        };
+        let mut node = if n.is_power_of_two() {
+            // The leaves exactly fill the subtree, which `push` recorded as a left node.
+            filled(height)?
+        } else {
+            // Otherwise the subtree ends in zero padding: walk up from the next free slot.
+            let mut node = BigUint::default();
+            for (h, zero) in zeros.iter().enumerate().take(height) {
+                node = if (n >> h) & 1 == 1 {
+                    hasher.node(&filled(h)?, &node)
+                } else {
+                    hasher.node(&node, zero)
+                };
+            }
+            node
+        };
+        let zero = BigUint::default();
+        for _ in height..self.depth() as usize {
+            node = hasher.node(&node, &zero);
+        }
+        Ok(node.to_str_radix(10))
+    }
+}
+
+/// Which implementation computes membership trees, chosen by `MERKLE_IMPL`; `node` keeps the
+/// script around as a fallback should the roots ever diverge.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MerkleImpl {
+    #[default]
+    Native,
+    Node(NodeMerkleScript),
+}
+
+impl MerkleImpl {
+    pub fn parse(var: impl Fn(&str) -> Option<String>) -> AppResult<Self> {
+        match var("MERKLE_IMPL")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "" | "native" => Ok(Self::Native),
+            "node" => {
+                let timeout_secs = var("MERKLE_SCRIPT_TIMEOUT_SECS")
+                    .and_then(|s| s.trim().parse().ok())
+                    .unwrap_or(DEFAULT_MERKLE_SCRIPT_TIMEOUT_SECS);
+                Ok(Self::Node(NodeMerkleScript {
+                    timeout: Duration::from_secs(timeout_secs),
+                    ..NodeMerkleScript::default()
+                }))
+            }
+            other => Err(AppError::Validation(format!(
+                "unknown MERKLE_IMPL {other:?} (expected native or node)"
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Node(_) => "node",
+        }
+    }
+}
+
+/// `MERKLE_DEPTH`, which must match the depth the circuit was compiled for.
+pub fn parse_depth(var: impl Fn(&str) -> Option<String>) -> AppResult<u32> {
+    let Some(raw) = var("MERKLE_DEPTH").filter(|v| !v.trim().is_empty()) else {
+        return Ok(DEFAULT_MERKLE_DEPTH);
+    };
+    match raw.trim().parse::<u32>() {
+        Ok(depth) if (1..=MAX_MERKLE_DEPTH).contains(&depth) => Ok(depth),
+        _ => Err(AppError::Validation(format!(
+            "MERKLE_DEPTH must be between 1 and {MAX_MERKLE_DEPTH}, got {raw:?}"
+        ))),
+    }
+}
+
+/// Builds the depth-`depth` tree over a member list; stores hold one so tests can
+/// substitute (and count) builds.
+#[async_trait]
+pub trait MerkleRunner: Send + Sync {
+    async fn build(
+        &self,
+        members: &[String],
+        depth: u32,
+        scheme: HashScheme,
+    ) -> AppResult<MerkleResult>;
+}
+
+#[async_trait]
+impl MerkleRunner for MerkleImpl {
+    async fn build(
+        &self,
+        members: &[String],
+        depth: u32,
+        scheme: HashScheme,
+    ) -> AppResult<MerkleResult> {
+        check_members(members, depth)?;
+        match self {
+            Self::Native => {
+                let members = members.to_vec();
+                tokio::task::spawn_blocking(move || {
+                    build_tree_with(&members, depth, scheme.hasher())
+                })
+                .await
+                .map_err(|e| AppError::External(format!("merkle tree task failed: {e}")))
+            }
+            Self::Node(_) if scheme != HashScheme::Poseidon => Err(AppError::Validation(format!(
+                "the node Merkle script only builds poseidon trees, not {}",
+                scheme.as_str()
+            ))),
+            Self::Node(script) => script.build(members, depth).await,
+        }
+    }
+}
+
+/// `node` running the Merkle script, the `MERKLE_IMPL=node` fallback. Members go in over
+/// stdin and the tree comes back on stdout, so no identity secret is ever written to disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeMerkleScript {
+    pub script: PathBuf,
+    /// The script is killed and the build fails past this.
+    pub timeout: Duration,
+    /// Larger results are rejected rather than buffered.
+    pub max_output_bytes: usize,
+}
+
+impl Default for NodeMerkleScript {
+    fn default() -> Self {
+        Self {
+            script: MERKLE_SCRIPT.into(),
+            timeout: Duration::from_secs(DEFAULT_MERKLE_SCRIPT_TIMEOUT_SECS),
+            max_output_bytes: MAX_MERKLE_SCRIPT_OUTPUT_BYTES,
+        }
+    }
+}
+
+/// Reads `pipe` to the end, keeping at most `limit` bytes; the flag is set when more came.
+async fn read_capped(
+    pipe: Option<impl AsyncRead + Unpin>,
+    limit: usize,
+) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut buf = Vec::new();
+    let Some(mut pipe) = pipe else {
+        return Ok((buf, false));
+    };
+    (&mut pipe).take(limit as u64).read_to_end(&mut buf).await?;
+    // Keep draining so the script never blocks on a full pipe.
+    let rest = tokio::io::copy(&mut pipe, &mut tokio::io::sink()).await?;
+    Ok((buf, rest > 0))
+}
+
+impl NodeMerkleScript {
+    pub async fn build(&self, members: &[String], depth: u32) -> AppResult<MerkleResult> {
+        let payload = serde_json::to_vec(&serde_json::json!({
+            "members": members,
+            "depth": depth,
+        }))
+        .map_err(|e| AppError::External(e.to_string()))?;
+        let mut child = Command::new("node")
+            .arg(&self.script)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AppError::External(format!("poseidon merkle script: {e}")))?;
+        let mut stdin = child.stdin.take();
+        let write = async move {
+            if let Some(stdin) = stdin.as_mut() {
+                stdin.write_all(&payload).await?;
+                stdin.shutdown().await?;
+            }
+            // Dropping the handle closes the pipe: the script reads until EOF.
+            drop(stdin);
+            std::io::Result::Ok(())
+        };
+        let stdout = read_capped(child.stdout.take(), self.max_output_bytes);
+        let stderr = read_capped(child.stderr.take(), MERKLE_SCRIPT_STDERR_BYTES);
+        let run = async { tokio::join!(write, stdout, stderr, child.wait()) };
+        let Ok((written, stdout, stderr, status)) = tokio::time::timeout(self.timeout, run).await
+        else {
+            let _ = child.start_kill();
+            let _ = child.wait().await;
+            return Err(AppError::External(format!(
+                "poseidon merkle script did not finish within {}s",
+                self.timeout.as_secs()
+            )));
+        };
+        let status = status.map_err(AppError::Io)?;
+        if !status.success() {
+            let (stderr, truncated) = stderr.unwrap_or_default();
+            let mut message = String::from_utf8_lossy(&stderr).trim().to_string();
+            if truncated {
+                message.push_str(" [stderr truncated]");
+            }
+            return Err(AppError::External(format!(
+                "poseidon merkle script failed ({status}): {message}"
+            )));
+        }
+        // A script that exited cleanly without reading its input is still an error.
+        written.map_err(AppError::Io)?;
+        let (stdout, truncated) = stdout.map_err(AppError::Io)?;
+        if truncated {
+            return Err(AppError::External(format!(
+                "poseidon merkle script output exceeds {} bytes",
+                self.max_output_bytes
+            )));
+        }
+        serde_json::from_slice(&stdout).map_err(|e| AppError::External(e.to_string()))
+    }
+}
+
+/// Identifies an ordered member list (and the tree depth and hash), keying stored snapshots.
+/// Poseidon digests leave the scheme out, so snapshots from before schemes stay valid.
+pub fn members_digest(members: &[String], depth: u32, scheme: HashScheme) -> String {
+    let mut hasher = Sha256::new();
+    if scheme != HashScheme::Poseidon {
+        hasher.update(scheme.as_str().as_bytes());
+    }
+    hasher.update(depth.to_be_bytes());
+    for member in members {
+        hasher.update((member.len() as u64).to_be_bytes());
+        hasher.update(member.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
@@ -0,0 +1,305 @@
+//! In-process counters and histograms, rendered in the Prometheus text format at `/metrics`.
+use crate::error::AppResult;
+use crate::indexer::IndexerStatus;
+use crate::merkle::HashScheme;
+use crate::repo::PollRecord;
+use crate::zk::{CircuitInfo, Commitment, CommitmentInput, ProofBundle, ProofRequest, ZkBackend};
+use async_trait::async_trait;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::info;
+
+const SECONDS_BUCKETS: &[f64] = &[0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 120.0];
+const BYTES_BUCKETS: &[f64] = &[256.0, 1024.0, 4096.0, 8192.0, 16384.0, 32768.0, 65536.0];
+
+/// Cumulative histogram over fixed bucket bounds.
+#[derive(Debug)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    state: Mutex<HistogramState>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HistogramState {
+    /// Observations per bucket (not cumulative); the last slot is `+Inf`.
+    pub buckets: Vec<u64>,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            state: Mutex::new(HistogramState {
+                buckets: vec![0; bounds.len() + 1],
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let idx = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        let mut state = self.state.lock().expect("histogram lock");
+        state.buckets[idx] += 1;
+        state.sum += value;
+        state.count += 1;
+    }
+
+    pub fn snapshot(&self) -> HistogramState {
+        self.state.lock().expect("histogram lock").clone()
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let state = self.snapshot();
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&state.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", state.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", state.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", state.count);
+    }
+}
+
+/// Outcome counters for one kind of ZK operation.
+#[derive(Debug, Default)]
+pub struct OutcomeCounters {
+    pub ok: AtomicU64,
+    pub error: AtomicU64,
+}
+
+impl OutcomeCounters {
+    fn record<T>(&self, result: &AppResult<T>) {
+        let counter = if result.is_ok() {
+            &self.ok
+        } else {
+            &self.error
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        for (outcome, counter) in [("ok", &self.ok), ("error", &self.error)] {
+            let _ = writeln!(
+                out,
+                "{name}{{{labels},outcome=\"{outcome}\"}} {}",
+                counter.load(Ordering::Relaxed)
+            );
+        }
+    }
+}
+
+/// Timings, outcomes and proof sizes of the active `ZkBackend`.
+#[derive(Debug)]
+pub struct ZkMetrics {
+    pub prove_seconds: Histogram,
+    pub verify_seconds: Histogram,
+    pub proof_bytes: Histogram,
+    pub prove_total: OutcomeCounters,
+    pub verify_total: OutcomeCounters,
+}
+
+impl Default for ZkMetrics {
+    fn default() -> Self {
+        Self {
+            prove_seconds: Histogram::new(SECONDS_BUCKETS),
+            verify_seconds: Histogram::new(SECONDS_BUCKETS),
+            proof_bytes: Histogram::new(BYTES_BUCKETS),
+            prove_total: OutcomeCounters::default(),
+            verify_total: OutcomeCounters::default(),
+        }
+    }
+}
+
+impl ZkMetrics {
+    fn render(&self, out: &mut String, backend: &str) {
+        let labels = format!("backend=\"{backend}\"");
+        let _ = writeln!(out, "# TYPE veilcast_zk_prove_seconds histogram");
+        self.prove_seconds
+            .render(out, "veilcast_zk_prove_seconds", &labels);
+        let _ = writeln!(out, "# TYPE veilcast_zk_verify_seconds histogram");
+        self.verify_seconds
+            .render(out, "veilcast_zk_verify_seconds", &labels);
+        let _ = writeln!(out, "# TYPE veilcast_zk_proof_bytes histogram");
+        self.proof_bytes
+            .render(out, "veilcast_zk_proof_bytes", &labels);
+        let _ = writeln!(out, "# TYPE veilcast_zk_prove_total counter");
+        self.prove_total
+            .render(out, "veilcast_zk_prove_total", &labels);
+        let _ = writeln!(out, "# TYPE veilcast_zk_verify_total counter");
+        self.verify_total
+            .render(out, "veilcast_zk_verify_total", &labels);
+    }
+}
+
+/// Membership size against what the tree holds, as of the last poll creation.
+#[derive(Debug, Default)]
+pub struct MembershipMetrics {
+    pub members: AtomicU64,
+    pub capacity: AtomicU64,
+}
+
+impl MembershipMetrics {
+    pub fn set(&self, members: u64, capacity: u64) {
+        self.members.store(members, Ordering::Relaxed);
+        self.capacity.store(capacity, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String) {
+        for (name, gauge) in [
+            ("veilcast_membership_members", &self.members),
+            ("veilcast_membership_capacity", &self.capacity),
+        ] {
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            let _ = writeln!(out, "{name} {}", gauge.load(Ordering::Relaxed));
+        }
+    }
+}
+
+/// The indexer's counters, read straight from the status `/indexer/status` serves.
+fn render_indexer(out: &mut String, status: &IndexerStatus) {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    let _ = writeln!(out, "# TYPE veilcast_indexer_events_total counter");
+    for (event, counter) in [
+        ("PollCreated", &status.poll_created_events),
+        ("Committed", &status.committed_events),
+        ("VoteRevealed", &status.vote_revealed_events),
+        ("PollResolved", &status.poll_resolved_events),
+    ] {
+        let _ = writeln!(
+            out,
+            "veilcast_indexer_events_total{{event=\"{event}\"}} {}",
+            load(counter)
+        );
+    }
+    for (name, counter) in [
+        ("veilcast_indexer_unknown_logs_total", &status.unknown_logs),
+        (
+            "veilcast_indexer_handler_errors_total",
+            &status.handler_errors,
+        ),
+        ("veilcast_indexer_reconnects_total", &status.reconnects),
+        (
+            "veilcast_indexer_dead_lettered_logs_total",
+            &status.dead_lettered_logs,
+        ),
+    ] {
+        let _ = writeln!(out, "# TYPE {name} counter");
+        let _ = writeln!(out, "{name} {}", load(counter));
+    }
+    for (name, gauge) in [
+        ("veilcast_indexer_pending_logs", &status.pending_logs),
+        ("veilcast_indexer_failed_events", &status.failed_events),
+        ("veilcast_indexer_dead_events", &status.dead_events),
+    ] {
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        let _ = writeln!(out, "{name} {}", load(gauge));
+    }
+    if let Some(lag) = status.lag_blocks() {
+        let _ = writeln!(out, "# TYPE veilcast_indexer_lag_blocks gauge");
+        let _ = writeln!(out, "veilcast_indexer_lag_blocks {lag}");
+    }
+}
+
+/// Everything `/metrics` reports.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub zk: Arc<ZkMetrics>,
+    pub membership: MembershipMetrics,
+}
+
+impl Metrics {
+    pub fn render(&self, zk_backend: &str, indexer: &IndexerStatus) -> String {
+        let mut out = String::new();
+        self.zk.render(&mut out, zk_backend);
+        self.membership.render(&mut out);
+        render_indexer(&mut out, indexer);
+        out
+    }
+}
+
+/// Decorator timing and counting every `prove`/`verify` of the wrapped backend.
+#[derive(Debug, Clone)]
+pub struct MeteredZkBackend<B> {
+    inner: B,
+    metrics: Arc<ZkMetrics>,
+}
+
+impl<B> MeteredZkBackend<B> {
+    pub fn new(inner: B, metrics: Arc<ZkMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+/// Decoded size of a `0x` hex proof.
+fn proof_len(proof: &str) -> usize {
+    proof.trim_start_matches("0x").len() / 2
+}
+
+#[async_trait]
+impl<B> ZkBackend for MeteredZkBackend<B>
+where
+    B: ZkBackend + Send + Sync,
+{
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn circuit(&self) -> Option<CircuitInfo> {
+        self.inner.circuit()
+    }
+
+    fn supports_hash_scheme(&self, scheme: HashScheme) -> bool {
+        self.inner.supports_hash_scheme(scheme)
+    }
+
+    async fn commitment(&self, input: CommitmentInput<'_>) -> AppResult<Commitment> {
+        self.inner.commitment(input).await
+    }
+
+    async fn prove(&self, req: ProofRequest<'_>) -> AppResult<ProofBundle> {
+        let started = Instant::now();
+        let result = self.inner.prove(req).await;
+        let elapsed = started.elapsed();
+        self.metrics.prove_seconds.observe(elapsed.as_secs_f64());
+        self.metrics.prove_total.record(&result);
+        let proof_bytes = result.as_ref().ok().map(|b| proof_len(&b.proof));
+        if let Some(bytes) = proof_bytes {
+            self.metrics.proof_bytes.observe(bytes as f64);
+        }
+        info!(
+            poll_id = req.poll_id,
+            backend = self.name(),
+            elapsed_ms = elapsed.as_millis() as u64,
+            ok = result.is_ok(),
+            proof_bytes,
+            "zk prove"
+        );
+        result
+    }
+
+    async fn verify(&self, poll: &PollRecord, bundle: &ProofBundle) -> AppResult<()> {
+        let started = Instant::now();
+        let result = self.inner.verify(poll, bundle).await;
+        let elapsed = started.elapsed();
+        self.metrics.verify_seconds.observe(elapsed.as_secs_f64());
+        self.metrics.verify_total.record(&result);
+        info!(
+            poll_id = poll.id,
+            backend = self.name(),
+            elapsed_ms = elapsed.as_millis() as u64,
+            ok = result.is_ok(),
+            proof_bytes = proof_len(&bundle.proof),
+            "zk verify"
+        );
+        result
+    }
+}
@@ -0,0 +1,73 @@
+//! HMAC receipts proving the server accepted a commitment at a given time.
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Receipt signing keys. The first key signs new receipts; every key still verifies, so
+/// receipts issued before a rotation stay valid as long as their key id is kept around.
+#[derive(Debug, Clone)]
+pub struct ReceiptKeys {
+    keys: Vec<(String, Vec<u8>)>,
+}
+
+impl ReceiptKeys {
+    /// Returns `None` when `keys` is empty.
+    pub fn new(keys: Vec<(String, Vec<u8>)>) -> Option<Self> {
+        (!keys.is_empty()).then_some(Self { keys })
+    }
+
+    pub fn single(key_id: &str, secret: &[u8]) -> Self {
+        Self {
+            keys: vec![(key_id.to_string(), secret.to_vec())],
+        }
+    }
+
+    /// Fallback for deployments without `RECEIPT_KEYS`: a key derived from `identity_salt`
+    /// under its own label, so the salt itself never doubles as a signing key.
+    pub fn derived(identity_salt: &str) -> Self {
+        let mut mac = HmacSha256::new_from_slice(identity_salt.as_bytes())
+            .expect("hmac accepts any key length");
+        mac.update(b"veilcast commit receipts v1");
+        Self::single("derived", &mac.finalize().into_bytes())
+    }
+
+    /// Produces `<key_id>.<hex hmac>` over `poll_id || commitment || recorded_at`.
+    pub fn sign(&self, poll_id: i64, commitment: &str, recorded_at: DateTime<Utc>) -> String {
+        let (key_id, secret) = &self.keys[0];
+        let mac = receipt_mac(secret, poll_id, commitment, recorded_at).finalize();
+        format!("{}.{}", key_id, hex::encode(mac.into_bytes()))
+    }
+
+    /// Returns the id of the key that signed `receipt`, or `None` if it does not verify.
+    pub fn verify(
+        &self,
+        receipt: &str,
+        poll_id: i64,
+        commitment: &str,
+        recorded_at: DateTime<Utc>,
+    ) -> Option<&str> {
+        let (key_id, tag) = receipt.rsplit_once('.')?;
+        let tag = hex::decode(tag).ok()?;
+        let (key_id, secret) = self.keys.iter().find(|(id, _)| id == key_id)?;
+        receipt_mac(secret, poll_id, commitment, recorded_at)
+            .verify_slice(&tag)
+            .ok()
+            .map(|_| key_id.as_str())
+    }
+}
+
+fn receipt_mac(
+    secret: &[u8],
+    poll_id: i64,
+    commitment: &str,
+    recorded_at: DateTime<Utc>,
+) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts any key length");
+    mac.update(&poll_id.to_be_bytes());
+    mac.update(commitment.as_bytes());
+    // Microseconds: the precision Postgres keeps for TIMESTAMPTZ.
+    mac.update(&recorded_at.timestamp_micros().to_be_bytes());
+    mac
+}
@@ -0,0 +1,205 @@
+//! Relayer wallets taking turns sending transactions. Each send goes to the next healthy wallet,
+//! round-robin; a wallet is benched while its balance is under `RELAYER_MIN_BALANCE_WEI` or after
+//! `RELAYER_MAX_CONSECUTIVE_FAILURES` sends in a row failed, and comes back on its next success
+//! or top-up.
+use crate::error::{AppError, AppResult};
+use async_trait::async_trait;
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::Middleware;
+use ethers::signers::LocalWallet;
+use ethers::types::{H160, U256};
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::RwLock;
+use tracing::{error, warn};
+
+pub const DEFAULT_MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// An account the relayer signs and pays with.
+#[async_trait]
+pub trait RelayerWallet: Send + Sync {
+    fn address(&self) -> H160;
+
+    async fn balance(&self) -> AppResult<U256>;
+}
+
+#[async_trait]
+impl<M: Middleware> RelayerWallet for SignerMiddleware<M, LocalWallet> {
+    fn address(&self) -> H160 {
+        SignerMiddleware::address(self)
+    }
+
+    async fn balance(&self) -> AppResult<U256> {
+        self.get_balance(SignerMiddleware::address(self), None)
+            .await
+            .map_err(|e| AppError::External(format!("relayer balance lookup failed: {e}")))
+    }
+}
+
+#[derive(Default)]
+struct Health {
+    /// From the last balance check; `None` before the first.
+    balance: RwLock<Option<U256>>,
+    consecutive_failures: AtomicU32,
+    last_error: RwLock<Option<String>>,
+}
+
+/// One wallet's standing, as `/relayer/status` reports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletHealth {
+    pub address: H160,
+    pub balance: Option<U256>,
+    /// The last balance check came in under `RELAYER_MIN_BALANCE_WEI`.
+    pub low_funds: bool,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// Neither short of funds nor benched for failures, so sends go to it.
+    pub healthy: bool,
+}
+
+pub struct RelayerPool<W> {
+    wallets: Vec<(W, Health)>,
+    next: AtomicUsize,
+    min_balance_wei: Option<u128>,
+    max_failures: u32,
+}
+
+impl<W: RelayerWallet> RelayerPool<W> {
+    /// `None` without any wallet.
+    pub fn new(wallets: Vec<W>, min_balance_wei: Option<u128>, max_failures: u32) -> Option<Self> {
+        if wallets.is_empty() {
+            return None;
+        }
+        Some(Self {
+            wallets: wallets
+                .into_iter()
+                .map(|wallet| (wallet, Health::default()))
+                .collect(),
+            next: AtomicUsize::new(0),
+            min_balance_wei,
+            max_failures: max_failures.max(1),
+        })
+    }
+
+    /// The first configured wallet, whose settings the others share.
+    pub fn primary(&self) -> &W {
+        &self.wallets[0].0
+    }
+
+    fn is_low(&self, health: &Health) -> bool {
+        let balance = *health.balance.read().unwrap();
+        matches!((balance, self.min_balance_wei), (Some(balance), Some(min)) if balance < U256::from(min))
+    }
+
+    fn is_benched(&self, health: &Health) -> bool {
+        health.consecutive_failures.load(Ordering::Relaxed) >= self.max_failures
+    }
+
+    /// The next healthy wallet after the last one picked. When every wallet is benched, the next
+    /// one with funds, so a failing wallet is retried rather than sends stopping altogether.
+    pub fn pick(&self) -> AppResult<(usize, &W)> {
+        let count = self.wallets.len();
+        let start = self.next.load(Ordering::Relaxed) % count;
+        let order = (0..count).map(|offset| (start + offset) % count);
+        let funded = |index: &usize| !self.is_low(&self.wallets[*index].1);
+        order
+            .clone()
+            .filter(funded)
+            .find(|index| !self.is_benched(&self.wallets[*index].1))
+            .or_else(|| order.clone().find(funded))
+            .map(|index| {
+                // Skipped wallets don't hand their turn to the one after them.
+                self.next.store(index + 1, Ordering::Relaxed);
+                (index, &self.wallets[index].0)
+            })
+            .ok_or_else(|| {
+                AppError::External(
+                    "every relayer wallet is below RELAYER_MIN_BALANCE_WEI; nothing can be sent"
+                        .into(),
+                )
+            })
+    }
+
+    pub fn record_success(&self, index: usize) {
+        let (_, health) = &self.wallets[index];
+        health.consecutive_failures.store(0, Ordering::Relaxed);
+        *health.last_error.write().unwrap() = None;
+    }
+
+    pub fn record_failure(&self, index: usize, err: impl ToString) {
+        let (wallet, health) = &self.wallets[index];
+        let failures = health.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        let err = err.to_string();
+        if failures == self.max_failures {
+            warn!(
+                address = ?wallet.address(),
+                failures,
+                err,
+                "relayer wallet benched after repeated failed sends"
+            );
+        }
+        *health.last_error.write().unwrap() = Some(err);
+    }
+
+    /// Runs `op` with the next healthy wallet and records how it went.
+    pub async fn send<'a, T, F, Fut>(&'a self, op: F) -> AppResult<T>
+    where
+        F: FnOnce(&'a W) -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let (index, wallet) = self.pick()?;
+        let result = op(wallet).await;
+        match &result {
+            Ok(_) => self.record_success(index),
+            Err(err) => self.record_failure(index, err),
+        }
+        result
+    }
+
+    /// Asks the provider for every wallet's balance, logging an error for each one under
+    /// `RELAYER_MIN_BALANCE_WEI`; a failed lookup keeps the previous one. Returns the highest
+    /// balance known, since that wallet can still send.
+    pub async fn refresh_balances(&self) -> Option<U256> {
+        for (wallet, health) in &self.wallets {
+            match wallet.balance().await {
+                Ok(balance) => {
+                    *health.balance.write().unwrap() = Some(balance);
+                    if let Some(min_balance_wei) =
+                        self.min_balance_wei.filter(|_| self.is_low(health))
+                    {
+                        error!(
+                            address = ?wallet.address(),
+                            balance_wei = %balance,
+                            min_balance_wei,
+                            "relayer wallet balance below RELAYER_MIN_BALANCE_WEI; skipped until it is topped up"
+                        );
+                    }
+                }
+                Err(err) => {
+                    warn!(address = ?wallet.address(), ?err, "relayer balance check failed")
+                }
+            }
+        }
+        self.wallets
+            .iter()
+            .filter_map(|(_, health)| *health.balance.read().unwrap())
+            .max()
+    }
+
+    pub fn health(&self) -> Vec<WalletHealth> {
+        self.wallets
+            .iter()
+            .map(|(wallet, health)| {
+                let low_funds = self.is_low(health);
+                WalletHealth {
+                    address: wallet.address(),
+                    balance: *health.balance.read().unwrap(),
+                    low_funds,
+                    consecutive_failures: health.consecutive_failures.load(Ordering::Relaxed),
+                    last_error: health.last_error.read().unwrap().clone(),
+                    healthy: !low_funds && !self.is_benched(health),
+                }
+            })
+            .collect()
+    }
+}
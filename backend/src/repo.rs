@@ -1,36 +1,171 @@
+use crate::crypto::FieldCipher;
 use crate::error::{AppError, AppResult};
+use crate::merkle::{
+    self, HashScheme, IncrementalTree, MerkleImpl, MerkleRunner, DEFAULT_MERKLE_DEPTH,
+};
+use crate::types::{OptionMeta, PollVisibility};
+use crate::zk::{ProofBundle, PublicInputs};
 use async_trait::async_trait;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
 use num_bigint::BigUint;
 use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Row};
-use std::collections::{HashMap, HashSet};
+use sqlx::migrate::Migrator;
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Row, Transaction};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::process::Command;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::info;
 use uuid::Uuid;
 
-const MERKLE_SCRIPT: &str = "./scripts/poseidon_merkle_noir.mjs";
-const MERKLE_DEPTH: u32 = 20;
+const ORPHANED_PROOF_JOB_ERROR: &str = "interrupted by a server restart";
+/// Rows per `merkle_nodes` insert.
+const MERKLE_NODE_BATCH: usize = 10_000;
 const BN254_FR_MODULUS: &str =
     "21888242871839275222246405745257275088548364400416034343698204186575808495617";
 const XP_CORRECT: i64 = 20;
 const XP_PARTICIPATION: i64 = 5;
+/// Tries at a resolution transaction before a serialization failure or deadlock is returned.
+const RESOLVE_TX_ATTEMPTS: u32 = 3;
+pub const DEFAULT_DB_MAX_CONNECTIONS: u32 = 5;
+/// sqlx's own default.
+pub const DEFAULT_DB_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+/// Stable per-poll pseudonym for a committer; the poll id salts it so the same
+/// member cannot be correlated across polls.
+pub(crate) fn commit_pseudonym(poll_id: i64, identity_secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(poll_id.to_be_bytes());
+    hasher.update(identity_secret.as_bytes());
+    hex::encode(&hasher.finalize()[..8])
+}
 
-pub(crate) fn hash_members(members: &[String]) -> String {
-    if members.is_empty() {
-        return "0x0".to_string();
+/// The contract keys reveals on the commitment, so it must be unique within a poll.
+fn duplicate_commitment() -> AppError {
+    AppError::CodedConflict {
+        code: "duplicate_commitment",
+        message: "commitment already used in this poll".into(),
+    }
+}
+
+/// Only one of two commitments sharing a nullifier could ever be revealed.
+pub(crate) fn nullifier_taken() -> AppError {
+    AppError::CodedConflict {
+        code: "nullifier_taken",
+        message: "nullifier already used by another commitment in this poll".into(),
+    }
+}
+
+/// Secrets are kept until the poll is resolved and its reveals are on chain.
+fn secrets_still_needed() -> AppError {
+    AppError::CodedConflict {
+        code: "secrets_still_needed",
+        message: "poll must be resolved with every reveal synced before its secrets are scrubbed"
+            .into(),
+    }
+}
+
+/// A different commitment already exists for this identity; carries when it was recorded.
+fn already_committed(recorded_at: DateTime<Utc>) -> AppError {
+    AppError::CodedConflict {
+        code: "already_committed",
+        message: format!(
+            "already committed for this poll at {}",
+            recorded_at.to_rfc3339()
+        ),
+    }
+}
+
+/// Resolves a failed insert for `commit`: a byte-identical existing commit is returned as is.
+fn existing_commit_or_conflict(
+    existing: Option<StoredCommitRecord>,
+    commit: &StoredCommit<'_>,
+) -> AppResult<StoredCommitRecord> {
+    match existing {
+        Some(existing) if existing.matches(commit) => Ok(existing),
+        Some(existing) => Err(already_committed(existing.recorded_at)),
+        None => Err(duplicate_commitment()),
     }
+}
+
+/// The nullifier was already revealed with another choice; carries when it was first recorded.
+fn nullifier_reused(recorded_at: DateTime<Utc>) -> AppError {
+    AppError::CodedConflict {
+        code: "nullifier_reused",
+        message: format!("nullifier already revealed at {}", recorded_at.to_rfc3339()),
+    }
+}
+
+/// Resolves a reveal of an already-recorded nullifier: the same choice returns the first record.
+fn existing_vote_or_conflict(
+    kept: StoredVoteRecord,
+    vote: &StoredVote<'_>,
+) -> AppResult<StoredVoteRecord> {
+    if kept.choice == vote.choice as i16 {
+        Ok(kept)
+    } else {
+        Err(nullifier_reused(kept.recorded_at))
+    }
+}
+
+fn sha_node(parts: &[&str]) -> String {
     let mut hasher = Sha256::new();
-    for m in members {
-        hasher.update(m.as_bytes());
+    for part in parts {
+        hasher.update(part.as_bytes());
     }
     format!("0x{}", hex::encode(hasher.finalize()))
 }
 
+/// Levels of a sha256 tree over `members`, laid out like `merkle::build_tree`: odd nodes pair
+/// with `"0"`, and the root keeps hashing with `"0"` until `depth` levels sit above the leaves.
+fn sha_levels(members: &[String], depth: u32) -> Vec<Vec<String>> {
+    let mut levels = vec![members.iter().map(|m| sha_node(&[m])).collect::<Vec<_>>()];
+    for _ in 0..depth {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| sha_node(&[&pair[0], pair.get(1).map_or("0", String::as_str)]))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root of the sha256 stand-in for the Poseidon tree that `InMemoryStore` uses without a
+/// runner; `"0x0"` for no members.
+pub(crate) fn hash_members(members: &[String], depth: u32) -> String {
+    if members.is_empty() {
+        return "0x0".to_string();
+    }
+    sha_levels(members, depth).pop().expect("leaf level")[0].clone()
+}
+
+/// The stand-in tree with every member's path, which folds back to `hash_members`.
+fn hashed_tree(members: &[String], depth: u32) -> MerkleResult {
+    let levels = sha_levels(members, depth);
+    let mut paths = HashMap::with_capacity(members.len());
+    for (leaf, member) in members.iter().enumerate() {
+        let mut idx = leaf;
+        let mut path = MerklePath {
+            bits: Vec::with_capacity(depth as usize),
+            siblings: Vec::with_capacity(depth as usize),
+        };
+        for level in &levels[..depth as usize] {
+            path.bits.push((idx % 2).to_string());
+            path.siblings
+                .push(level.get(idx ^ 1).cloned().unwrap_or_else(|| "0".into()));
+            idx /= 2;
+        }
+        paths.insert(member.clone(), path);
+    }
+    let root = match levels[depth as usize].first() {
+        Some(root) => root.clone(),
+        None => "0x0".to_string(),
+    };
+    MerkleResult { root, paths, depth }
+}
+
 fn generate_secret() -> String {
     let mut buf = [0u8; 32];
     OsRng.fill_bytes(&mut buf);
@@ -52,24 +187,47 @@ fn tier_for_xp(xp: i64) -> &'static str {
     }
 }
 
+/// Serialization failures and deadlocks: Postgres rolled the transaction back and running
+/// it again may succeed.
+fn is_retryable_tx_error(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == "40001" || code == "40P01")
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PollRecord {
     pub id: i64,
     pub question: String,
     pub options: Vec<String>,
+    pub option_meta: Vec<OptionMeta>,
     pub commit_phase_end: DateTime<Utc>,
     pub reveal_phase_end: DateTime<Utc>,
     pub category: String,
     pub membership_root: String,
+    /// Hash the poll's membership tree was built with.
+    pub membership_hash_scheme: HashScheme,
     pub owner: String,
+    pub anonymous_owner: bool,
     pub reveal_tx_hash: String,
     pub correct_option: Option<i16>,
     pub resolved: bool,
+    pub voided: bool,
     pub commit_sync_completed: bool,
+    pub auto_resolve: bool,
+    pub visibility: String,
+    /// When the poll was stored, or the block time of its `PollCreated` when indexed from the
+    /// chain and the block could be looked up.
+    pub created_at: DateTime<Utc>,
+    /// Set once the poll is soft-deleted; reads skip it unless they ask to include deleted polls.
+    pub deleted_at: Option<DateTime<Utc>>,
     pub vote_counts: Vec<i64>,
+    pub commit_count: i64,
+    pub revealed_count: i64,
 }
 
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct UserStatsRecord {
     pub identity_secret: String,
     pub username: String,
@@ -88,6 +246,10 @@ pub struct NewPoll<'a> {
     pub membership_root: &'a str,
     pub category: &'a str,
     pub owner: &'a str,
+    pub auto_resolve: bool,
+    pub visibility: &'a str,
+    pub anonymous_owner: bool,
+    pub option_meta: &'a [OptionMeta],
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -100,6 +262,10 @@ pub struct StoredCommit<'a> {
     pub nullifier: &'a str,
     pub proof: &'a str,
     pub public_inputs: &'a [String],
+    pub proof_version: u16,
+    pub auto_reveal: bool,
+    /// Queue for `commit()` on chain (`ONCHAIN_COMMITS`) in the same write that stores it.
+    pub onchain_commit: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -114,6 +280,58 @@ pub struct StoredCommitRecord {
     pub nullifier: String,
     pub proof: String,
     pub public_inputs: Vec<String>,
+    pub proof_version: u16,
+    pub revision: i32,
+    pub auto_reveal: bool,
+    /// When retention blanked `secret`, `proof` and `public_inputs`.
+    pub scrubbed_at: Option<DateTime<Utc>>,
+}
+
+impl StoredCommitRecord {
+    /// True when `commit` carries exactly the payload already stored.
+    pub fn matches(&self, commit: &StoredCommit<'_>) -> bool {
+        self.poll_id == commit.poll_id
+            && self.identity_secret == commit.identity_secret
+            && self.choice == commit.choice
+            && self.commitment == commit.commitment
+            && self.secret == commit.secret
+            && self.nullifier == commit.nullifier
+            && self.proof == commit.proof
+            && self.public_inputs == commit.public_inputs
+            && self.proof_version == commit.proof_version
+            && self.auto_reveal == commit.auto_reveal
+    }
+}
+
+/// Path a revealed vote reached the `votes` table through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoteSource {
+    /// `POST /polls/:id/reveal`.
+    Api,
+    /// Indexed back from a `batchReveal` the relayer submitted.
+    Relayer,
+    /// Indexed from a reveal someone submitted to the contract directly.
+    Chain,
+}
+
+impl VoteSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VoteSource::Api => "api",
+            VoteSource::Relayer => "relayer",
+            VoteSource::Chain => "chain",
+        }
+    }
+
+    /// Unknown values read back as `Api`, the column default for rows predating it.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "relayer" => VoteSource::Relayer,
+            "chain" => VoteSource::Chain,
+            _ => VoteSource::Api,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -121,13 +339,93 @@ pub struct StoredVote<'a> {
     pub poll_id: i64,
     pub nullifier: &'a str,
     pub choice: u8,
+    pub source: VoteSource,
 }
 
 #[derive(Debug, Clone)]
 pub struct StoredVoteRecord {
     pub poll_id: i64,
     pub nullifier: String,
+    pub choice: i16,
     pub recorded_at: DateTime<Utc>,
+    pub source: VoteSource,
+}
+
+/// Participation funnel for one poll. Each stage counts members who also reached the previous
+/// one: a commit counts as revealed once a vote exists for its nullifier or the relayer
+/// submitted it, and as synced once it is on-chain via the relayer or a direct reveal.
+/// `committed` also counts commitments indexed from the contract, which belong to no member.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParticipationRecord {
+    pub eligible: i64,
+    pub fetched_secret: i64,
+    pub committed: i64,
+    pub revealed: i64,
+    pub synced: i64,
+    /// Committed members who opted out of relayer reveals.
+    pub self_reveal: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VoteSourceCounts {
+    pub api: i64,
+    pub relayer: i64,
+    pub chain: i64,
+}
+
+/// A second reveal of an already-recorded nullifier that disagreed on the choice. The first
+/// row is kept; the rejected one is recorded here so it can be investigated.
+#[derive(Debug, Clone)]
+pub struct VoteConflictRecord {
+    pub poll_id: i64,
+    pub nullifier: String,
+    pub kept_choice: i16,
+    pub kept_source: VoteSource,
+    pub rejected_choice: i16,
+    pub rejected_source: VoteSource,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofJobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl ProofJobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProofJobStatus::Pending => "pending",
+            ProofJobStatus::Running => "running",
+            ProofJobStatus::Done => "done",
+            ProofJobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "running" => ProofJobStatus::Running,
+            "done" => ProofJobStatus::Done,
+            "failed" => ProofJobStatus::Failed,
+            _ => ProofJobStatus::Pending,
+        }
+    }
+}
+
+/// A background `ZkBackend::prove` run; `bundle` is set once done, `error` once failed.
+#[derive(Debug, Clone)]
+pub struct ProofJobRecord {
+    pub id: Uuid,
+    pub poll_id: i64,
+    /// The member who requested it; nobody else may read the bundle.
+    pub identity_secret: String,
+    pub status: ProofJobStatus,
+    pub bundle: Option<ProofBundle>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, sqlx::FromRow)]
@@ -140,724 +438,3862 @@ pub struct CommitSyncRow {
     pub nullifier: String,
     pub proof: String,
     pub public_inputs: Vec<String>,
+    pub proof_version: u16,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MerklePath {
-    pub bits: Vec<String>,
-    pub siblings: Vec<String>,
+impl CommitSyncRow {
+    /// The bundle as it was submitted at commit time.
+    pub fn bundle(&self) -> AppResult<ProofBundle> {
+        Ok(ProofBundle {
+            proof: self.proof.clone(),
+            public_inputs: PublicInputs::try_from_vec(self.public_inputs.clone())?,
+            commitment: self.commitment.clone(),
+            nullifier: self.nullifier.clone(),
+            version: self.proof_version,
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MerkleResult {
-    pub root: String,
-    pub paths: std::collections::HashMap<String, MerklePath>,
-    pub depth: u32,
+/// How long the relayer waits before resubmitting commitments whose batch reveal failed, and
+/// when it gives up on them.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncRetryPolicy {
+    /// Failed submissions after which a commitment goes `dead`.
+    pub max_attempts: i32,
+    /// Wait after the first failure; doubled on each further one.
+    pub base_delay: chrono::Duration,
+    pub max_delay: chrono::Duration,
 }
 
-#[async_trait]
-pub trait PollStore {
-    async fn create_poll(&self, poll: NewPoll<'_>) -> AppResult<PollRecord>;
-    async fn create_poll_with_id(
-        &self,
-        poll_id: i64,
-        poll: NewPoll<'_>,
-        membership_root: String,
-        members: Vec<String>,
-    ) -> AppResult<PollRecord>;
-    async fn list_polls(&self, limit: i64) -> AppResult<Vec<PollRecord>>;
-    async fn get_poll(&self, poll_id: i64) -> AppResult<PollRecord>;
-    async fn record_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord>;
-    async fn record_vote(&self, vote: StoredVote<'_>) -> AppResult<StoredVoteRecord>;
-    async fn membership_root_snapshot(&self) -> AppResult<String>;
-    async fn merkle_path_for_member(
-        &self,
-        poll_id: i64,
-        identity_secret: &str,
-    ) -> AppResult<Option<MerklePath>>;
-    async fn list_members(&self) -> AppResult<Vec<String>>;
-    async fn ensure_member(&self, username: &str, identity_secret: &str) -> AppResult<()>;
-    async fn poll_includes_member(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool>;
-    async fn nullifier_used(&self, poll_id: i64, nullifier: &str) -> AppResult<bool>;
-    async fn has_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool>;
-    async fn resolve_poll(&self, poll_id: i64, correct_option: u8) -> AppResult<PollRecord>;
-    async fn get_or_create_secret(&self, poll_id: i64, identity_secret: &str) -> AppResult<String>;
-    async fn commits_to_sync(
-        &self,
-        now: DateTime<Utc>,
-        limit: i64,
-    ) -> AppResult<Vec<CommitSyncRow>>;
-    async fn mark_commit_synced(&self, commit_id: i64) -> AppResult<()>;
-    async fn poll_has_pending_commits(&self, poll_id: i64) -> AppResult<bool>;
-    async fn mark_poll_sync_complete(&self, poll_id: i64) -> AppResult<()>;
-    async fn set_reveal_tx_hash(&self, poll_id: i64, tx: &str) -> AppResult<()>;
-    async fn mark_polls_without_pending_commits(&self, now: DateTime<Utc>) -> AppResult<()>;
-    async fn backfill_user_stats(&self) -> AppResult<()>;
-    async fn user_stats(&self, identity_secret: &str) -> AppResult<UserStatsRecord>;
-    async fn leaderboard(&self, limit: i64) -> AppResult<Vec<UserStatsRecord>>;
+impl Default for SyncRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            base_delay: chrono::Duration::seconds(30),
+            max_delay: chrono::Duration::hours(1),
+        }
+    }
 }
 
-#[async_trait]
-pub trait PollIndexSink {
-    async fn upsert_poll_from_chain(&self, poll_id: i64, poll: NewPoll<'_>) -> AppResult<()>;
-    async fn upsert_vote_from_chain(
-        &self,
-        poll_id: i64,
-        nullifier: &str,
-        choice: u8,
-    ) -> AppResult<()>;
-    async fn resolve_poll_from_chain(&self, poll_id: i64, correct_option: u8) -> AppResult<()>;
+impl SyncRetryPolicy {
+    /// When a commitment that has now failed `attempts` times goes out again; `None` once it
+    /// has used up `max_attempts`.
+    pub fn next_attempt_at(&self, attempts: i32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+        let doublings = attempts.clamp(1, 31) as u32 - 1;
+        let delay = self
+            .base_delay
+            .checked_mul(1i32.checked_shl(doublings).unwrap_or(i32::MAX))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        Some(now + delay)
+    }
 }
 
-/// Postgres-backed store.
-#[derive(Clone)]
-pub struct PgStore {
-    pool: Pool<Postgres>,
+/// Where a commitment is in reveal sync (`commitments.sync_state`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitSyncState {
+    /// Not submitted yet.
+    Pending,
+    /// Revealed on chain, by the relayer or directly.
+    Synced,
+    /// A batch reveal including it failed; the relayer retries after backing off.
+    Failed,
+    /// The relayer gave up on it: its proof failed verification, it can't be batched even on
+    /// its own, or its batches failed too many times. Only an admin requeue brings it back.
+    Dead,
 }
 
-impl PgStore {
-    pub async fn connect(url: &str) -> AppResult<Self> {
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(url)
-            .await
-            .map_err(AppError::Db)?;
-        init_schema(&pool).await?;
-        Ok(Self { pool })
+impl CommitSyncState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CommitSyncState::Pending => "pending",
+            CommitSyncState::Synced => "synced",
+            CommitSyncState::Failed => "failed",
+            CommitSyncState::Dead => "dead",
+        }
     }
 
-    async fn populate_vote_counts(&self, records: &mut [PollRecord]) -> AppResult<()> {
-        if records.is_empty() {
-            return Ok(());
-        }
-        let mut counts_map: HashMap<i64, Vec<i64>> = records
-            .iter()
-            .map(|r| (r.id, vec![0; r.options.len()]))
-            .collect();
-        let ids: Vec<i64> = counts_map.keys().cloned().collect();
-        let rows = sqlx::query(
-            r#"SELECT poll_id, choice, COUNT(*)::BIGINT as count FROM votes WHERE poll_id = ANY($1) GROUP BY poll_id, choice"#,
-        )
-        .bind(&ids)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(AppError::Db)?;
-        for row in rows {
-            let poll_id: i64 = row.get("poll_id");
-            let choice: i16 = row.get("choice");
-            let count: i64 = row.get("count");
-            if let Some(vec) = counts_map.get_mut(&poll_id) {
-                let idx = choice as usize;
-                if idx < vec.len() {
-                    vec[idx] = count;
-                }
-            }
-        }
-        let fallback_ids: Vec<i64> = records
-            .iter()
-            .filter_map(|r| {
-                counts_map
-                    .get(&r.id)
-                    .filter(|vec| vec.iter().all(|&c| c == 0))
-                    .map(|_| r.id)
-            })
-            .collect();
-        if !fallback_ids.is_empty() {
-            let rows = sqlx::query(
-                r#"SELECT poll_id, choice, COUNT(*)::BIGINT as count FROM commitments WHERE poll_id = ANY($1) GROUP BY poll_id, choice"#,
-            )
-            .bind(&fallback_ids)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(AppError::Db)?;
-            for row in rows {
-                let poll_id: i64 = row.get("poll_id");
-                let choice: i16 = row.get("choice");
-                let count: i64 = row.get("count");
-                if let Some(vec) = counts_map.get_mut(&poll_id) {
-                    let idx = choice as usize;
-                    if idx < vec.len() {
-                        vec[idx] = count;
-                    }
-                }
-            }
-        }
-        for record in records.iter_mut() {
-            if let Some(vec) = counts_map.remove(&record.id) {
-                record.vote_counts = vec;
-            }
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "synced" => CommitSyncState::Synced,
+            "failed" => CommitSyncState::Failed,
+            "dead" => CommitSyncState::Dead,
+            _ => CommitSyncState::Pending,
         }
-        Ok(())
     }
+}
 
-    async fn apply_poll_results(&self, poll_id: i64, correct_option: u8) -> AppResult<()> {
-        let commits =
-            sqlx::query(r#"SELECT identity_secret, choice FROM commitments WHERE poll_id = $1"#)
-                .bind(poll_id)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(AppError::Db)?;
+/// Where a commitment is in relaying `commit()` on chain (`commitments.commit_state`); only
+/// used with `ONCHAIN_COMMITS`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnchainCommitState {
+    /// Not sent on chain: `ONCHAIN_COMMITS` was off when it was recorded.
+    #[default]
+    Offchain,
+    /// Waiting for the relayer to send it.
+    Queued,
+    /// The commit tx was mined; reveal sync may now include the commitment.
+    Confirmed,
+    /// A commit tx failed; the relayer retries after backing off.
+    Failed,
+    /// The relayer gave up on it, so it is never revealed.
+    Dead,
+}
 
-        for commit in commits {
-            let identity_secret: String = commit.get("identity_secret");
-            let choice: i16 = commit.get("choice");
-            let correct = choice as u8 == correct_option;
-            self.bump_user_stats(&identity_secret, correct).await?;
+impl OnchainCommitState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OnchainCommitState::Offchain => "offchain",
+            OnchainCommitState::Queued => "queued",
+            OnchainCommitState::Confirmed => "confirmed",
+            OnchainCommitState::Failed => "failed",
+            OnchainCommitState::Dead => "dead",
         }
-        Ok(())
     }
 
-    async fn bump_user_stats(&self, identity_secret: &str, correct: bool) -> AppResult<()> {
-        let xp_delta = if correct {
-            XP_CORRECT
-        } else {
-            XP_PARTICIPATION
-        };
-        let correct_inc = if correct { 1 } else { 0 };
-        let updated = sqlx::query(
-            r#"
-            UPDATE user_stats
-            SET xp = xp + $2,
-                total_votes = total_votes + 1,
-                correct_votes = correct_votes + $3,
-                updated_at = now()
-            WHERE identity_secret = $1
-            RETURNING xp, tier
-            "#,
-        )
-        .bind(identity_secret)
-        .bind(xp_delta)
-        .bind(correct_inc)
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(AppError::Db)?;
-
-        if let Some(row) = updated {
-            let xp: i64 = row.get("xp");
-            let current_tier: String = row.get("tier");
-            let new_tier = tier_for_xp(xp);
-            if new_tier != current_tier {
-                sqlx::query(r#"UPDATE user_stats SET tier = $2 WHERE identity_secret = $1"#)
-                    .bind(identity_secret)
-                    .bind(new_tier)
-                    .execute(&self.pool)
-                    .await
-                    .map_err(AppError::Db)?;
-            }
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "queued" => OnchainCommitState::Queued,
+            "confirmed" => OnchainCommitState::Confirmed,
+            "failed" => OnchainCommitState::Failed,
+            "dead" => OnchainCommitState::Dead,
+            _ => OnchainCommitState::Offchain,
         }
-        Ok(())
     }
+}
 
-    async fn poll_member_list(&self, poll_id: i64) -> AppResult<Vec<String>> {
-        let rows = sqlx::query(
-            r#"
-            SELECT identity_secret
-            FROM poll_members
-            WHERE poll_id = $1
-            ORDER BY identity_secret
-            "#,
-        )
-        .bind(poll_id)
-        .fetch_all(&self.pool)
-        .await
+/// A commitment waiting for the relayer to send `commit(pollId, commitment)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnchainCommitRow {
+    pub id: i64,
+    pub poll_id: i64,
+    pub commitment: String,
+}
+
+/// How a poll's relayed commitments are spread over the sync states.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommitSyncStateCounts {
+    pub pending: i64,
+    pub synced: i64,
+    pub failed: i64,
+    pub dead: i64,
+}
+
+/// A commitment reveal sync gave up on, for `GET /admin/dead_commits`.
+#[derive(Debug, Clone)]
+pub struct DeadCommitRecord {
+    pub id: i64,
+    pub poll_id: i64,
+    pub commitment: String,
+    pub recorded_at: DateTime<Utc>,
+    pub sync_attempts: i32,
+    pub sync_error: Option<String>,
+}
+
+/// What a receipt check reports about a stored commitment.
+#[derive(Debug, Clone, Copy)]
+pub struct CommitReceiptStatus {
+    /// Included in an on-chain batch reveal.
+    pub synced: bool,
+    /// Its secret and proof were removed by retention.
+    pub scrubbed: bool,
+}
+
+/// What `scrub_poll_secrets` removed, or would remove on a dry run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SecretScrubCounts {
+    pub secrets_deleted: u64,
+    pub commitments_scrubbed: u64,
+    pub cached_proofs_deleted: u64,
+}
+
+/// Where the caller's own commitment is in the commit -> reveal pipeline.
+#[derive(Debug, Clone)]
+pub struct MyCommitStatusRecord {
+    pub onchain_submitted: bool,
+    pub expired: bool,
+    pub revealed: bool,
+    pub reveal_tx_hash: Option<String>,
+    pub auto_reveal: bool,
+    /// The relayer's last error submitting the commitment, if any.
+    pub sync_error: Option<String>,
+    /// Failed batch reveals that included the commitment.
+    pub sync_attempts: i32,
+    /// Set while the relayer is backing off before resubmitting it.
+    pub next_sync_attempt_at: Option<DateTime<Utc>>,
+    pub sync_state: CommitSyncState,
+}
+
+/// A commitment with everything but its timing and sync state stripped.
+#[derive(Debug, Clone)]
+pub struct RedactedCommitRecord {
+    pub pseudonym: String,
+    pub recorded_at: DateTime<Utc>,
+    pub onchain_submitted: bool,
+    pub sync_state: CommitSyncState,
+    pub sync_attempts: i32,
+    pub sync_error: Option<String>,
+    pub commit_state: OnchainCommitState,
+    pub commit_tx_hash: Option<String>,
+    pub commit_error: Option<String>,
+}
+
+/// Position of the last log the indexer processed for a contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IndexerCheckpoint {
+    pub block: u64,
+    pub log_index: u64,
+}
+
+/// A poll as it was sent to `createPoll`, written to `pending_onchain_polls` before the tx
+/// goes out so it can still be stored if the insert after the receipt fails.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollIntent {
+    pub question: String,
+    pub options: Vec<String>,
+    pub commit_phase_end: DateTime<Utc>,
+    pub reveal_phase_end: DateTime<Utc>,
+    pub membership_root: String,
+    pub category: String,
+    pub owner: String,
+    pub auto_resolve: bool,
+    pub visibility: String,
+    pub anonymous_owner: bool,
+    pub option_meta: Vec<OptionMeta>,
+    /// The poll's members are the first this many: members are only ever appended.
+    pub member_count: usize,
+}
+
+impl PollIntent {
+    pub fn new(poll: &NewPoll<'_>, member_count: usize) -> Self {
+        Self {
+            question: poll.question.to_string(),
+            options: poll.options.to_vec(),
+            commit_phase_end: poll.commit_phase_end,
+            reveal_phase_end: poll.reveal_phase_end,
+            membership_root: poll.membership_root.to_string(),
+            category: poll.category.to_string(),
+            owner: poll.owner.to_string(),
+            auto_resolve: poll.auto_resolve,
+            visibility: poll.visibility.to_string(),
+            anonymous_owner: poll.anonymous_owner,
+            option_meta: poll.option_meta.to_vec(),
+            member_count,
+        }
+    }
+
+    pub fn new_poll(&self) -> NewPoll<'_> {
+        NewPoll {
+            question: &self.question,
+            options: &self.options,
+            commit_phase_end: self.commit_phase_end,
+            reveal_phase_end: self.reveal_phase_end,
+            membership_root: &self.membership_root,
+            category: &self.category,
+            owner: &self.owner,
+            auto_resolve: self.auto_resolve,
+            visibility: &self.visibility,
+            anonymous_owner: self.anonymous_owner,
+            option_meta: &self.option_meta,
+        }
+    }
+}
+
+/// A `createPoll` that went through on chain but whose poll may not be stored yet.
+#[derive(Debug, Clone)]
+pub struct SentPollIntentRecord {
+    pub id: i64,
+    pub poll_id: i64,
+    pub tx_hash: String,
+    pub intent: PollIntent,
+}
+
+/// A log `handle_log` failed on, kept for the indexer to retry.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FailedEventRecord {
+    pub id: i64,
+    /// The raw log, as `ethers` serializes it.
+    pub log: serde_json::Value,
+    /// `0x…` sender of a `PollCreated` log's transaction, when it was looked up.
+    pub sender: Option<String>,
+    /// Why the last attempt failed.
+    pub error: String,
+    pub attempts: i32,
+    pub next_retry_at: DateTime<Utc>,
+    /// Out of attempts; only an admin requeue brings it back.
+    pub dead: bool,
+}
+
+/// A batchReveal the relayer got through, with what its receipt says it cost.
+#[derive(Debug, Clone, Copy)]
+pub struct NewRevealBatch<'a> {
+    pub poll_id: i64,
+    /// `None` when nothing was sent, as with a simulated reveal.
+    pub tx_hash: Option<&'a str>,
+    pub commit_ids: &'a [i64],
+    pub gas_used: Option<u64>,
+    /// Wei per gas.
+    pub effective_gas_price: Option<u64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RevealBatchRecord {
+    pub poll_id: i64,
+    /// Empty for a simulated batch.
+    pub tx_hash: String,
+    pub item_count: i32,
+    pub submitted_at: DateTime<Utc>,
+    /// Unset for batches only seen through indexed `VoteRevealed` logs.
+    pub gas_used: Option<i64>,
+    pub effective_gas_price: Option<i64>,
+    /// Commitments the batch revealed.
+    pub commit_ids: Vec<i64>,
+}
+
+/// What a poll's reveal batches cost, over the batches whose receipts were recorded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RevealCostRecord {
+    pub batch_count: i64,
+    pub item_count: i64,
+    pub gas_used: i64,
+    pub cost_wei: u128,
+}
+
+/// What a transaction in `/polls/:id/transactions` did, in the order a poll goes through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PollTransactionKind {
+    Create,
+    Commit,
+    RevealBatch,
+    Resolve,
+}
+
+impl PollTransactionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PollTransactionKind::Create => "create",
+            PollTransactionKind::Commit => "commit",
+            PollTransactionKind::RevealBatch => "reveal_batch",
+            PollTransactionKind::Resolve => "resolve",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "create" => Some(PollTransactionKind::Create),
+            "commit" => Some(PollTransactionKind::Commit),
+            "reveal_batch" => Some(PollTransactionKind::RevealBatch),
+            "resolve" => Some(PollTransactionKind::Resolve),
+            _ => None,
+        }
+    }
+}
+
+/// How the backend learned a poll transaction landed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollTransactionStatus {
+    /// The relayer sent it and got a successful receipt.
+    Confirmed,
+    /// Only seen through the contract's logs.
+    Indexed,
+}
+
+impl PollTransactionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PollTransactionStatus::Confirmed => "confirmed",
+            PollTransactionStatus::Indexed => "indexed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "indexed" => PollTransactionStatus::Indexed,
+            _ => PollTransactionStatus::Confirmed,
+        }
+    }
+}
+
+/// One chain interaction for a poll, gathered from poll intents, on-chain commits, reveal
+/// batches (and the `reveal_tx_hash` they replaced) and the indexed resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PollTransactionRecord {
+    pub kind: PollTransactionKind,
+    pub tx_hash: String,
+    /// `None` for reveals and on-chain commits recorded before their times were.
+    pub timestamp: Option<DateTime<Utc>>,
+    pub status: PollTransactionStatus,
+    /// Commitments a reveal batch carried; `None` for the other kinds and legacy polls.
+    pub item_count: Option<i32>,
+}
+
+/// Lifecycle order, then time (untimed last), so each store lists a poll's transactions alike.
+fn sort_poll_transactions(txs: &mut [PollTransactionRecord]) {
+    txs.sort_by(|a, b| {
+        let key = |tx: &PollTransactionRecord| (tx.kind, tx.timestamp.is_none(), tx.timestamp);
+        (key(a), &a.tx_hash).cmp(&(key(b), &b.tx_hash))
+    });
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerklePath {
+    pub bits: Vec<String>,
+    pub siblings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MerkleResult {
+    pub root: String,
+    pub paths: std::collections::HashMap<String, MerklePath>,
+    pub depth: u32,
+}
+
+/// Outcome of `check_membership_tree`.
+#[derive(Debug, Clone)]
+pub struct MembershipTreeCheck {
+    pub member_count: usize,
+    pub incremental_root: String,
+    pub rebuilt_root: String,
+}
+
+impl MembershipTreeCheck {
+    pub fn diverged(&self) -> bool {
+        self.incremental_root != self.rebuilt_root
+    }
+}
+
+/// Outcome of `check_stored_path`: one sampled poll member's stored path against a fresh build.
+#[derive(Debug, Clone)]
+pub struct StoredPathCheck {
+    pub poll_id: i64,
+    pub leaf_index: u64,
+    pub matches: bool,
+}
+
+/// A stored tree over the current member list, keyed by `merkle::members_digest`.
+#[derive(Debug, Clone)]
+pub struct MembershipSnapshotRecord {
+    pub digest: String,
+    pub root: String,
+    pub member_count: usize,
+    pub depth: u32,
+}
+
+/// Narrows and orders `PollStore::list_polls`; `None` fields match every poll.
+#[derive(Debug, Clone, Default)]
+pub struct PollFilter {
+    pub category: Option<String>,
+    pub resolved: Option<bool>,
+    pub sort: PollSort,
+    /// Soft-deleted polls are listed too (admins only).
+    pub include_deleted: bool,
+    pub audience: PollAudience,
+}
+
+/// Who a listing is for; members-only polls are left out of the pages of anyone else.
+#[derive(Debug, Clone, Default)]
+pub enum PollAudience {
+    /// Every poll, whatever its visibility.
+    #[default]
+    All,
+    /// Public polls only: anonymous callers.
+    Public,
+    /// Public polls and the members-only polls `username` owns or is a member of.
+    Member {
+        username: String,
+        identity_secret: String,
+    },
+}
+
+impl PollAudience {
+    /// `(everything, username, identity_secret)` as bound into the listing queries.
+    fn binds(&self) -> (bool, Option<&str>, Option<&str>) {
+        match self {
+            PollAudience::All => (true, None, None),
+            PollAudience::Public => (false, None, None),
+            PollAudience::Member {
+                username,
+                identity_secret,
+            } => (false, Some(username), Some(identity_secret)),
+        }
+    }
+
+    /// The in-memory equivalent of the queries' visibility predicate.
+    fn can_see(&self, poll: &PollRecord, members: &HashMap<i64, Vec<String>>) -> bool {
+        if PollVisibility::from_db(&poll.visibility) == PollVisibility::Public {
+            return true;
+        }
+        match self {
+            PollAudience::All => true,
+            PollAudience::Public => false,
+            PollAudience::Member {
+                username,
+                identity_secret,
+            } => {
+                poll.owner == *username
+                    || members
+                        .get(&poll.id)
+                        .is_some_and(|list| list.contains(identity_secret))
+            }
+        }
+    }
+}
+
+/// What `PollStore::list_polls` orders by, descending; ties go to the higher id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PollSort {
+    #[default]
+    Id,
+    CreatedAt,
+    CommitEnd,
+    RevealEnd,
+}
+
+impl PollSort {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "id" => Some(PollSort::Id),
+            "created_at" => Some(PollSort::CreatedAt),
+            "commit_end" => Some(PollSort::CommitEnd),
+            "reveal_end" => Some(PollSort::RevealEnd),
+            _ => None,
+        }
+    }
+
+    fn column(self) -> &'static str {
+        match self {
+            PollSort::Id => "id",
+            PollSort::CreatedAt => "created_at",
+            PollSort::CommitEnd => "commit_phase_end",
+            PollSort::RevealEnd => "reveal_phase_end",
+        }
+    }
+
+    /// The in-memory equivalent of `column`, comparable across polls.
+    fn key(self, poll: &PollRecord) -> (i64, i64) {
+        let at = match self {
+            PollSort::Id => poll.id,
+            PollSort::CreatedAt => poll.created_at.timestamp_micros(),
+            PollSort::CommitEnd => poll.commit_phase_end.timestamp_micros(),
+            PollSort::RevealEnd => poll.reveal_phase_end.timestamp_micros(),
+        };
+        (at, poll.id)
+    }
+}
+
+/// One page of polls in `PollSort` order. When `has_more` is set the next page starts after
+/// the last poll, passed as `before_id`.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct PollPage {
+    pub polls: Vec<PollRecord>,
+    pub has_more: bool,
+}
+
+impl PollPage {
+    /// Builds a page from up to `limit + 1` polls fetched newest first.
+    fn from_fetched(mut polls: Vec<PollRecord>, limit: i64) -> Self {
+        let has_more = polls.len() as i64 > limit;
+        polls.truncate(limit.max(0) as usize);
+        Self { polls, has_more }
+    }
+}
+
+/// What a poll's frozen `membership_root` was built from.
+#[derive(Debug, Clone)]
+pub struct PollMembershipRootRecord {
+    pub root: String,
+    /// `merkle::members_digest` of the poll's member list.
+    pub snapshot_digest: String,
+    pub member_count: i64,
+    pub snapshot_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait PollStore {
+    async fn create_poll(&self, poll: NewPoll<'_>) -> AppResult<PollRecord>;
+    async fn create_poll_with_id(
+        &self,
+        poll_id: i64,
+        poll: NewPoll<'_>,
+        membership_root: String,
+        members: Vec<String>,
+    ) -> AppResult<PollRecord>;
+    /// Writes a `sending` intent before `createPoll` goes out; returns its id.
+    async fn record_poll_intent(&self, intent: &PollIntent) -> AppResult<i64>;
+    /// The tx went through: the intent now names the poll id the chain assigned.
+    async fn mark_poll_intent_sent(&self, id: i64, poll_id: i64, tx_hash: &str) -> AppResult<()>;
+    /// The tx failed, so there is nothing on chain to store.
+    async fn mark_poll_intent_failed(&self, id: i64, error: &str) -> AppResult<()>;
+    async fn mark_poll_intent_stored(&self, id: i64) -> AppResult<()>;
+    /// Intents sent on chain and not yet marked stored, oldest first.
+    async fn sent_poll_intents(&self) -> AppResult<Vec<SentPollIntentRecord>>;
+    /// Up to `limit` polls matching `filter`, in its order, starting after poll `before_id`.
+    async fn list_polls(
+        &self,
+        limit: i64,
+        before_id: Option<i64>,
+        filter: PollFilter,
+    ) -> AppResult<PollPage>;
+    /// `NotFound` for soft-deleted polls too.
+    async fn get_poll(&self, poll_id: i64) -> AppResult<PollRecord>;
+    /// Like `get_poll`, but also returns soft-deleted polls.
+    async fn get_poll_including_deleted(&self, poll_id: i64) -> AppResult<PollRecord>;
+    /// Hides the poll from every read by setting `deleted_at`; its commitments, votes and
+    /// other rows are kept. Deleting a deleted poll returns it unchanged.
+    async fn soft_delete_poll(&self, poll_id: i64) -> AppResult<PollRecord>;
+    /// Fetches the given polls in one query, in input order; unknown ids and polls hidden from
+    /// `audience` are skipped.
+    async fn get_polls_by_ids(
+        &self,
+        ids: &[i64],
+        audience: &PollAudience,
+    ) -> AppResult<Vec<PollRecord>>;
+    async fn record_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord>;
+    /// Overwrites the caller's existing commitment and bumps its revision. Returns `Conflict`
+    /// once that commitment has already been submitted on-chain.
+    async fn replace_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord>;
+    /// Removes the caller's commitment. Returns `Conflict` once it has been submitted on-chain.
+    async fn delete_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<()>;
+    async fn record_vote(&self, vote: StoredVote<'_>) -> AppResult<StoredVoteRecord>;
+    async fn membership_root_snapshot(&self) -> AppResult<String>;
+    /// Compares the incrementally maintained root against a full rebuild of the current
+    /// members with the configured tree builder.
+    async fn check_membership_tree(&self) -> AppResult<MembershipTreeCheck>;
+    /// Rebuilds the current members' tree even if a snapshot exists, replacing it.
+    async fn recompute_membership_snapshot(&self) -> AppResult<MembershipSnapshotRecord>;
+    /// Compares the stored path of one randomly sampled poll member against a fresh build of
+    /// that poll's tree; `None` when no poll has stored paths to sample.
+    async fn check_stored_path(&self) -> AppResult<Option<StoredPathCheck>>;
+    /// Drops the stored tree nodes no live poll (nor the latest snapshot) refers to. Returns
+    /// how many snapshots lost their nodes.
+    async fn prune_merkle_nodes(&self) -> AppResult<u64>;
+    async fn merkle_path_for_member(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<MerklePath>>;
+    async fn poll_membership_root(&self, poll_id: i64) -> AppResult<PollMembershipRootRecord>;
+    /// Root of a fresh build over the poll's stored members, bypassing every stored snapshot;
+    /// `None` when the poll has no members stored (polls only seen on-chain).
+    async fn rebuild_poll_root(&self, poll_id: i64) -> AppResult<Option<String>>;
+    async fn list_members(&self) -> AppResult<Vec<String>>;
+    async fn ensure_member(&self, username: &str, identity_secret: &str) -> AppResult<()>;
+    async fn poll_includes_member(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool>;
+    async fn has_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool>;
+    async fn commitment_for_identity(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<StoredCommitRecord>>;
+    async fn commitment_exists(&self, poll_id: i64, commitment: &str) -> AppResult<bool>;
+    /// True when a commitment of another identity in this poll already uses `nullifier`.
+    /// Legacy rows with an empty nullifier never match.
+    async fn nullifier_taken(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        identity_secret: &str,
+    ) -> AppResult<bool>;
+    /// `None` if the commitment is not stored.
+    async fn commitment_receipt_status(
+        &self,
+        poll_id: i64,
+        commitment: &str,
+    ) -> AppResult<Option<CommitReceiptStatus>>;
+    async fn my_commit_status(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<MyCommitStatusRecord>>;
+    async fn commit_count(&self, poll_id: i64) -> AppResult<i64>;
+    async fn poll_member_count(&self, poll_id: i64) -> AppResult<i64>;
+    async fn participation(&self, poll_id: i64) -> AppResult<ParticipationRecord>;
+    /// Moves `commit_phase_end` to `now` for a poll that is still in its commit phase.
+    async fn close_commit_phase(&self, poll_id: i64, now: DateTime<Utc>) -> AppResult<PollRecord>;
+    /// Resolves the poll; `None` voids it so only participation XP is awarded.
+    async fn resolve_poll(&self, poll_id: i64, correct_option: Option<u8>)
+        -> AppResult<PollRecord>;
+    /// Unresolved `auto_resolve` polls whose reveals are all synced and whose reveal window,
+    /// `grace` included, closed by `now`: no late reveal can change their counts any more.
+    async fn auto_resolve_candidates(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+    ) -> AppResult<Vec<i64>>;
+    /// Stops auto-resolving the poll (a tie); it stays open for the owner to resolve.
+    async fn disable_auto_resolve(&self, poll_id: i64) -> AppResult<()>;
+    /// Soft-deletes unresolved, off-chain polls created and closed before `before` that never
+    /// saw a commitment, vote or reveal tx. With `dry_run` nothing is changed; the ids are
+    /// returned either way.
+    async fn prune_stale_polls(&self, before: DateTime<Utc>, dry_run: bool) -> AppResult<Vec<i64>>;
+    /// Resolved polls with every reveal synced whose reveal phase and resolution both ended
+    /// before `before`, and whose secrets are still kept. Polls with no recorded resolution
+    /// time (resolved before it was tracked) go by their reveal phase alone.
+    async fn secret_scrub_candidates(&self, before: DateTime<Utc>) -> AppResult<Vec<i64>>;
+    /// Deletes the poll's secrets and cached proofs and blanks `secret`, `proof` and
+    /// `public_inputs` on its commitments, recording the scrub in `secret_scrubs` under
+    /// `requested_by`. Only resolved polls with every reveal synced qualify. With `dry_run`
+    /// nothing changes; the counts are what would be removed.
+    async fn scrub_poll_secrets(
+        &self,
+        poll_id: i64,
+        requested_by: &str,
+        dry_run: bool,
+    ) -> AppResult<SecretScrubCounts>;
+    async fn revealed_vote_counts(&self, poll_id: i64) -> AppResult<Vec<i64>>;
+    async fn get_or_create_secret(&self, poll_id: i64, identity_secret: &str) -> AppResult<String>;
+    /// Unsynced commits of polls past their commit phase, including polls whose reveal
+    /// phase ended less than `grace` plus the contract's `slack` ago. Commits backing off
+    /// after a failed batch are skipped, and with `onchain_commits` so is every commitment
+    /// whose `commit()` has not confirmed.
+    async fn commits_to_sync(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+        slack: chrono::Duration,
+        onchain_commits: bool,
+        limit: i64,
+    ) -> AppResult<Vec<CommitSyncRow>>;
+    /// Flags unsynced commits on not-yet-completed polls whose reveal phase plus `grace` and
+    /// `slack` has passed as expired; returns the ids newly flagged.
+    async fn expire_unsynced_commits(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+        slack: chrono::Duration,
+    ) -> AppResult<Vec<i64>>;
+    /// Flags the commitment as revealed on-chain, remembering the batch tx hash if one is known.
+    async fn mark_commit_synced(&self, commit_id: i64, tx_hash: Option<&str>) -> AppResult<()>;
+    /// Moves the commitment to `dead` because its proof failed verification or its
+    /// batchReveal can't go out even on its own.
+    async fn mark_commit_dead(&self, commit_id: i64, error: &str) -> AppResult<()>;
+    /// Counts a failed batch reveal against each commitment and holds it back from
+    /// `commits_to_sync` until `policy` says to retry; commitments out of attempts go `dead`.
+    /// Returns the ids given up on.
+    async fn record_sync_failure(
+        &self,
+        commit_ids: &[i64],
+        error: &str,
+        now: DateTime<Utc>,
+        policy: &SyncRetryPolicy,
+    ) -> AppResult<Vec<i64>>;
+    /// Queues the commitment for `commit()` on chain, starting its attempts over.
+    async fn queue_onchain_commit(&self, commit_id: i64) -> AppResult<()>;
+    /// Queued on-chain commits, and failed ones whose backoff has passed, oldest first.
+    async fn onchain_commits_to_send(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<OnchainCommitRow>>;
+    /// Records the mined commit tx, unless the commitment was replaced since it was sent.
+    async fn mark_onchain_commit_confirmed(
+        &self,
+        commit: &OnchainCommitRow,
+        tx_hash: &str,
+    ) -> AppResult<()>;
+    /// Counts a failed commit tx against each commitment and holds it back until `policy`
+    /// says to retry; commitments out of attempts go `dead`. Returns the ids given up on.
+    async fn record_onchain_commit_failure(
+        &self,
+        commit_ids: &[i64],
+        error: &str,
+        now: DateTime<Utc>,
+        policy: &SyncRetryPolicy,
+    ) -> AppResult<Vec<i64>>;
+    /// Whether the poll still has relayed commitments that are neither synced nor dead.
+    async fn poll_has_pending_commits(&self, poll_id: i64) -> AppResult<bool>;
+    async fn mark_poll_sync_complete(&self, poll_id: i64) -> AppResult<()>;
+    /// The poll's relayed (`auto_reveal`) commitments by sync state.
+    async fn commit_sync_state_counts(&self, poll_id: i64) -> AppResult<CommitSyncStateCounts>;
+    /// Auto-reveal commitments across all polls still waiting to go on chain; dead and
+    /// expired ones are not counted.
+    async fn pending_commit_count(&self) -> AppResult<i64>;
+    /// Dead commitments across all polls, oldest first.
+    async fn list_dead_commits(&self) -> AppResult<Vec<DeadCommitRecord>>;
+    /// Puts a dead commitment back to `pending` with its attempts reset and reopens its poll's
+    /// reveal sync; returns the poll id. `NotFound` unless the commitment is dead.
+    async fn requeue_dead_commit(&self, commit_id: i64) -> AppResult<i64>;
+    async fn set_reveal_tx_hash(&self, poll_id: i64, tx: &str) -> AppResult<()>;
+    async fn record_reveal_batch(&self, batch: NewRevealBatch<'_>) -> AppResult<()>;
+    async fn list_reveal_batches(&self, poll_id: i64) -> AppResult<Vec<RevealBatchRecord>>;
+    /// Batches, items, gas and wei (gas used times effective price) the poll's reveals took.
+    async fn reveal_cost(&self, poll_id: i64) -> AppResult<RevealCostRecord>;
+    /// Every transaction the backend knows of for the poll: its `createPoll`, relayed commits,
+    /// reveal batches and the indexed resolution. Simulated ones, with no tx hash, are left out.
+    async fn list_poll_transactions(&self, poll_id: i64) -> AppResult<Vec<PollTransactionRecord>>;
+    async fn list_commits_redacted(&self, poll_id: i64) -> AppResult<Vec<RedactedCommitRecord>>;
+    async fn vote_source_counts(&self, poll_id: i64) -> AppResult<VoteSourceCounts>;
+    async fn list_vote_conflicts(&self, poll_id: i64) -> AppResult<Vec<VoteConflictRecord>>;
+    async fn create_proof_job(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<ProofJobRecord>;
+    async fn get_proof_job(&self, job_id: Uuid) -> AppResult<ProofJobRecord>;
+    async fn start_proof_job(&self, job_id: Uuid) -> AppResult<()>;
+    async fn complete_proof_job(&self, job_id: Uuid, bundle: &ProofBundle) -> AppResult<()>;
+    async fn fail_proof_job(&self, job_id: Uuid, error: &str) -> AppResult<()>;
+    /// Fails jobs left pending or running by a previous process; returns how many.
+    async fn fail_orphaned_proof_jobs(&self) -> AppResult<u64>;
+    /// The bundle last proven for this member, if it was proven from `request_hash`.
+    async fn cached_proof(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+        request_hash: &str,
+    ) -> AppResult<Option<ProofBundle>>;
+    /// Keeps one bundle per member and poll; a different request replaces it.
+    async fn cache_proof(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+        request_hash: &str,
+        bundle: &ProofBundle,
+    ) -> AppResult<()>;
+    async fn invalidate_cached_proof(&self, poll_id: i64, identity_secret: &str) -> AppResult<()>;
+    /// Completes polls with no unsynced commits left inside the reveal window plus `grace`.
+    async fn mark_polls_without_pending_commits(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+    ) -> AppResult<()>;
+    async fn backfill_user_stats(&self) -> AppResult<()>;
+    /// Seals plaintext (or old-key) commit secrets, choices (including `public_inputs[0]`) and
+    /// poll secrets with the active encryption key; returns the number of rows rewritten.
+    async fn encrypt_stored_secrets(&self) -> AppResult<u64>;
+    async fn user_stats(&self, identity_secret: &str) -> AppResult<UserStatsRecord>;
+    async fn leaderboard(&self, limit: i64) -> AppResult<Vec<UserStatsRecord>>;
+}
+
+#[async_trait]
+pub trait PollIndexSink {
+    /// `created_at` is the `PollCreated` block's time when known; a new row falls back to now
+    /// and an existing one keeps its own.
+    async fn upsert_poll_from_chain(
+        &self,
+        poll_id: i64,
+        poll: NewPoll<'_>,
+        created_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()>;
+    /// Like the other `*_from_chain` writes for a poll, fails with `NotFound` while the poll
+    /// itself is not indexed yet.
+    async fn upsert_vote_from_chain(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        choice: u8,
+    ) -> AppResult<()>;
+    /// `tx_hash` is the `PollResolved` log's transaction, listed in `/polls/:id/transactions`.
+    async fn resolve_poll_from_chain(
+        &self,
+        poll_id: i64,
+        correct_option: u8,
+        tx_hash: Option<&str>,
+    ) -> AppResult<()>;
+    /// Records a commitment submitted to the contract directly. It has no identity or secret,
+    /// so the relayer never reveals it; one the API already stored is left as it is.
+    async fn upsert_commit_from_chain(&self, poll_id: i64, commitment: &str) -> AppResult<()>;
+    /// Marks the commitment behind `nullifier` submitted by reveal tx `tx_hash`, whoever sent
+    /// it, and counts it towards that tx's reveal batch. Replays of the same reveal are no-ops.
+    async fn confirm_reveal_from_chain(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        tx_hash: &str,
+    ) -> AppResult<()>;
+    /// Whether the log at (`tx_hash`, `log_index`) was already handled.
+    async fn log_processed(&self, tx_hash: &str, log_index: u64) -> AppResult<bool>;
+    async fn record_processed_log(
+        &self,
+        tx_hash: &str,
+        log_index: u64,
+        block: u64,
+    ) -> AppResult<()>;
+    /// Forgets logs from blocks before `block`; returns how many.
+    async fn prune_processed_logs(&self, block: u64) -> AppResult<u64>;
+    /// Queues a log whose first attempt failed with `error`; with no `next_retry_at` it is
+    /// dead from the start.
+    async fn record_failed_event(
+        &self,
+        log: &serde_json::Value,
+        sender: Option<&str>,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()>;
+    /// Live failed events due by `now`, oldest due first.
+    async fn due_failed_events(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<FailedEventRecord>>;
+    /// Counts another failed attempt; a `next_retry_at` of `None` marks the event dead.
+    async fn reschedule_failed_event(
+        &self,
+        id: i64,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()>;
+    async fn delete_failed_event(&self, id: i64) -> AppResult<()>;
+    /// (waiting for a retry, dead)
+    async fn failed_event_counts(&self) -> AppResult<(u64, u64)>;
+    /// Gives every dead event a fresh set of attempts from `now`; returns how many.
+    async fn requeue_dead_events(&self, now: DateTime<Utc>) -> AppResult<u64>;
+    /// Where indexing of `contract` (a `0x` address) stopped; `None` before its first save.
+    async fn load_checkpoint(&self, contract: &str) -> AppResult<Option<IndexerCheckpoint>>;
+    async fn save_checkpoint(&self, contract: &str, checkpoint: IndexerCheckpoint)
+        -> AppResult<()>;
+}
+
+/// A write naming a poll that is not there yet trips the `poll_id` foreign key.
+fn missing_poll(err: sqlx::Error) -> AppError {
+    match err {
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => AppError::NotFound,
+        err => AppError::Db(err),
+    }
+}
+
+/// How [`PgStore::connect`] sizes its pool and bounds its connections: `DB_MAX_CONNECTIONS`,
+/// `DB_MIN_CONNECTIONS`, `DB_ACQUIRE_TIMEOUT_SECS`, `DB_STATEMENT_TIMEOUT_MS` and
+/// `SKIP_MIGRATIONS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DbSettings {
+    pub max_connections: u32,
+    /// Connections kept open while the pool is idle.
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before failing.
+    pub acquire_timeout: Duration,
+    /// Each connection's `statement_timeout`; `None` leaves the server's.
+    pub statement_timeout: Option<Duration>,
+    /// The schema is managed elsewhere; see [`PgStore::connect`].
+    pub skip_migrations: bool,
+}
+
+impl Default for DbSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: DEFAULT_DB_MAX_CONNECTIONS,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(DEFAULT_DB_ACQUIRE_TIMEOUT_SECS),
+            statement_timeout: None,
+            skip_migrations: false,
+        }
+    }
+}
+
+impl DbSettings {
+    pub fn parse(var: impl Fn(&str) -> Option<String>) -> AppResult<Self> {
+        let read = |key: &str, default: u64, min: u64| -> AppResult<u64> {
+            let Some(raw) = var(key).filter(|v| !v.trim().is_empty()) else {
+                return Ok(default);
+            };
+            raw.trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|value| *value >= min)
+                .ok_or_else(|| {
+                    let kind = if min > 0 {
+                        "a positive"
+                    } else {
+                        "a non-negative"
+                    };
+                    AppError::Validation(format!("{key} must be {kind} integer, got {raw:?}"))
+                })
+        };
+        let connections = |key: &str, default: u32, min: u64| -> AppResult<u32> {
+            u32::try_from(read(key, default.into(), min)?)
+                .map_err(|_| AppError::Validation(format!("{key} is too large")))
+        };
+        let max_connections = connections("DB_MAX_CONNECTIONS", DEFAULT_DB_MAX_CONNECTIONS, 1)?;
+        let min_connections = connections("DB_MIN_CONNECTIONS", 0, 0)?;
+        if min_connections > max_connections {
+            return Err(AppError::Validation(format!(
+                "DB_MIN_CONNECTIONS ({min_connections}) cannot exceed DB_MAX_CONNECTIONS ({max_connections})"
+            )));
+        }
+        let acquire_timeout = Duration::from_secs(read(
+            "DB_ACQUIRE_TIMEOUT_SECS",
+            DEFAULT_DB_ACQUIRE_TIMEOUT_SECS,
+            1,
+        )?);
+        // 0, like Postgres' own setting, means no limit.
+        let statement_timeout = Some(read("DB_STATEMENT_TIMEOUT_MS", 0, 0)?)
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis);
+        let skip_migrations = var("SKIP_MIGRATIONS")
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(false);
+        Ok(Self {
+            max_connections,
+            min_connections,
+            acquire_timeout,
+            statement_timeout,
+            skip_migrations,
+        })
+    }
+}
+
+/// Postgres-backed store.
+#[derive(Clone)]
+pub struct PgStore {
+    pool: Pool<Postgres>,
+    /// Encrypts commit secrets/choices and poll secrets at rest; `None` stores plaintext.
+    cipher: Option<Arc<FieldCipher>>,
+    merkle: Arc<dyn MerkleRunner>,
+    /// Depth of every tree this store builds; must match the circuit's.
+    merkle_depth: u32,
+    /// Hash of the membership tree and of new polls; existing polls keep the one they record.
+    hash_scheme: HashScheme,
+}
+
+impl PgStore {
+    /// Runs the pending migrations unless `settings.skip_migrations` (`SKIP_MIGRATIONS`) says
+    /// the schema is managed elsewhere; either way a database ahead of this binary is refused.
+    pub async fn connect(
+        url: &str,
+        settings: &DbSettings,
+        merkle_depth: u32,
+        hash_scheme: HashScheme,
+    ) -> AppResult<Self> {
+        let mut options = PgPoolOptions::new()
+            .max_connections(settings.max_connections)
+            .min_connections(settings.min_connections)
+            .acquire_timeout(settings.acquire_timeout);
+        if let Some(timeout) = settings.statement_timeout {
+            // SET takes no bind parameters; the value is a plain integer.
+            let statement = format!("SET statement_timeout = {}", timeout.as_millis());
+            options = options.after_connect(move |conn, _meta| {
+                let statement = statement.clone();
+                Box::pin(async move {
+                    sqlx::query(&statement).execute(conn).await?;
+                    Ok(())
+                })
+            });
+        }
+        let pool = options.connect(url).await.map_err(AppError::Db)?;
+        if settings.skip_migrations {
+            let applied = check_schema_version(&pool).await?;
+            tracing::warn!(?applied, "SKIP_MIGRATIONS set; leaving the schema as it is");
+        } else {
+            run_migrations(&pool).await?;
+        }
+        let store = Self {
+            pool,
+            cipher: None,
+            merkle: Arc::new(MerkleImpl::default()),
+            merkle_depth,
+            hash_scheme,
+        };
+        store.sync_membership_tree(false).await?;
+        Ok(store)
+    }
+
+    pub fn with_merkle_runner(mut self, runner: Arc<dyn MerkleRunner>) -> Self {
+        self.merkle = runner;
+        self
+    }
+
+    pub fn with_cipher(mut self, cipher: FieldCipher) -> Self {
+        self.cipher = Some(Arc::new(cipher));
+        self
+    }
+
+    fn seal(&self, value: &str) -> AppResult<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(value),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    fn open(&self, value: &str) -> AppResult<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(value),
+            None if value.starts_with("enc:") => Err(AppError::External(
+                "encrypted value found but no encryption key is configured".into(),
+            )),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Returns the `(choice, choice_enc)` column pair; sealed choices leave `choice` at -1.
+    fn seal_choice(&self, choice: i16) -> AppResult<(i16, String)> {
+        match &self.cipher {
+            Some(cipher) => Ok((-1, cipher.encrypt(&choice.to_string())?)),
+            None => Ok((choice, String::new())),
+        }
+    }
+
+    fn open_choice(&self, choice: i16, choice_enc: &str) -> AppResult<i16> {
+        if choice_enc.is_empty() {
+            return Ok(choice);
+        }
+        self.open(choice_enc)?
+            .parse()
+            .map_err(|_| AppError::External("corrupt encrypted choice".into()))
+    }
+
+    /// Seals element 0 of `public_inputs` (the choice); the rest is public on chain anyway.
+    fn seal_public_inputs(&self, inputs: &[String]) -> AppResult<Vec<String>> {
+        let mut sealed = inputs.to_vec();
+        if let (Some(cipher), Some(choice)) = (&self.cipher, sealed.first_mut()) {
+            *choice = cipher.encrypt(choice)?;
+        }
+        Ok(sealed)
+    }
+
+    fn open_public_inputs(&self, mut inputs: Vec<String>) -> AppResult<Vec<String>> {
+        if let Some(choice) = inputs.first_mut() {
+            *choice = self.open(choice)?;
+        }
+        Ok(inputs)
+    }
+
+    fn open_commit(&self, value: DbCommit) -> AppResult<StoredCommitRecord> {
+        Ok(StoredCommitRecord {
+            id: value.id as i64,
+            poll_id: value.poll_id,
+            choice: self.open_choice(value.choice, &value.choice_enc)?,
+            commitment: value.commitment,
+            recorded_at: value.recorded_at,
+            identity_secret: value.identity_secret,
+            secret: self.open(&value.secret)?,
+            nullifier: value.nullifier,
+            proof: value.proof,
+            public_inputs: self.open_public_inputs(value.public_inputs)?,
+            proof_version: proof_version_from_db(value.proof_version)?,
+            revision: value.revision,
+            auto_reveal: value.auto_reveal,
+            scrubbed_at: value.scrubbed_at,
+        })
+    }
+
+    async fn stored_vote(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+    ) -> AppResult<Option<StoredVoteRecord>> {
+        let rec = sqlx::query_as::<_, DbVote>(
+            r#"
+            SELECT poll_id, nullifier, choice, recorded_at, source
+            FROM votes
+            WHERE poll_id = $1 AND nullifier = $2
+            "#,
+        )
+        .bind(poll_id)
+        .bind(nullifier)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(rec.map(Into::into))
+    }
+
+    /// Records `vote` as a conflict when its nullifier is already stored with another choice;
+    /// returns the stored vote, if any.
+    async fn note_vote_conflict(
+        &self,
+        vote: StoredVote<'_>,
+    ) -> AppResult<Option<StoredVoteRecord>> {
+        let Some(kept) = self.stored_vote(vote.poll_id, vote.nullifier).await? else {
+            return Ok(None);
+        };
+        if kept.choice == vote.choice as i16 {
+            return Ok(Some(kept));
+        }
+        tracing::warn!(
+            poll_id = vote.poll_id,
+            nullifier = vote.nullifier,
+            kept_choice = kept.choice,
+            kept_source = kept.source.as_str(),
+            rejected_choice = vote.choice,
+            rejected_source = vote.source.as_str(),
+            "Conflicting reveals for the same nullifier"
+        );
+        sqlx::query(
+            r#"
+            INSERT INTO vote_conflicts
+                (poll_id, nullifier, kept_choice, kept_source, rejected_choice, rejected_source)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(vote.poll_id)
+        .bind(vote.nullifier)
+        .bind(kept.choice)
+        .bind(kept.source.as_str())
+        .bind(vote.choice as i16)
+        .bind(vote.source.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(Some(kept))
+    }
+
+    /// Answers a reveal whose nullifier is already stored (see `existing_vote_or_conflict`).
+    async fn settle_duplicate_vote(&self, vote: StoredVote<'_>) -> AppResult<StoredVoteRecord> {
+        match self.note_vote_conflict(vote).await? {
+            Some(kept) => existing_vote_or_conflict(kept, &vote),
+            None => Err(AppError::Conflict("nullifier already used".into())),
+        }
+    }
+
+    /// `get_poll`, or `get_poll_including_deleted` with `include_deleted`.
+    async fn fetch_poll(&self, poll_id: i64, include_deleted: bool) -> AppResult<PollRecord> {
+        let rec = sqlx::query_as::<_, DbPoll>(
+            r#"
+            SELECT id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, voided, commit_sync_completed, auto_resolve, visibility, anonymous_owner, option_meta, membership_hash_scheme, created_at, deleted_at
+            FROM polls
+            WHERE id = $1 AND ($2 OR deleted_at IS NULL)
+            "#,
+        )
+        .bind(poll_id)
+        .bind(include_deleted)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+
+        match rec {
+            Some(row) => {
+                let mut record: PollRecord = row.into();
+                self.populate_vote_counts(std::slice::from_mut(&mut record))
+                    .await?;
+                Ok(record)
+            }
+            None => Err(AppError::NotFound),
+        }
+    }
+
+    async fn populate_vote_counts(&self, records: &mut [PollRecord]) -> AppResult<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut counts_map: HashMap<i64, Vec<i64>> = records
+            .iter()
+            .map(|r| (r.id, vec![0; r.options.len()]))
+            .collect();
+        let ids: Vec<i64> = counts_map.keys().cloned().collect();
+        let rows = sqlx::query(
+            r#"SELECT poll_id, choice, COUNT(*)::BIGINT as count FROM votes WHERE poll_id = ANY($1) GROUP BY poll_id, choice"#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        for row in rows {
+            let poll_id: i64 = row.get("poll_id");
+            let choice: i16 = row.get("choice");
+            let count: i64 = row.get("count");
+            if let Some(vec) = counts_map.get_mut(&poll_id) {
+                let idx = choice as usize;
+                if idx < vec.len() {
+                    vec[idx] = count;
+                }
+            }
+        }
+        let revealed_map: HashMap<i64, i64> = counts_map
+            .iter()
+            .map(|(id, vec)| (*id, vec.iter().sum()))
+            .collect();
+        let commit_rows = sqlx::query(
+            r#"SELECT poll_id, COUNT(*)::BIGINT as count FROM commitments WHERE poll_id = ANY($1) GROUP BY poll_id"#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        let commit_map: HashMap<i64, i64> = commit_rows
+            .into_iter()
+            .map(|row| (row.get("poll_id"), row.get("count")))
+            .collect();
+        // Commit choices are only tallied once a poll is resolved; before that they would leak the
+        // running result.
+        let fallback_ids: Vec<i64> = records
+            .iter()
+            .filter(|r| r.resolved)
+            .filter_map(|r| {
+                counts_map
+                    .get(&r.id)
+                    .filter(|vec| vec.iter().all(|&c| c == 0))
+                    .map(|_| r.id)
+            })
+            .collect();
+        if !fallback_ids.is_empty() {
+            // Choices may be sealed, so they are tallied here rather than grouped in SQL.
+            let rows = sqlx::query(
+                r#"SELECT poll_id, choice, choice_enc FROM commitments WHERE poll_id = ANY($1)"#,
+            )
+            .bind(&fallback_ids)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+            for row in rows {
+                let poll_id: i64 = row.get("poll_id");
+                let choice = self.open_choice(row.get("choice"), row.get("choice_enc"))?;
+                if let Some(vec) = counts_map.get_mut(&poll_id) {
+                    if let Some(slot) = vec.get_mut(choice as usize) {
+                        *slot += 1;
+                    }
+                }
+            }
+        }
+        for record in records.iter_mut() {
+            if let Some(vec) = counts_map.remove(&record.id) {
+                record.vote_counts = vec;
+            }
+            record.commit_count = commit_map.get(&record.id).copied().unwrap_or(0);
+            record.revealed_count = revealed_map.get(&record.id).copied().unwrap_or(0);
+        }
+        Ok(())
+    }
+
+    /// Flips the poll to resolved and credits every voter's stats in one transaction, so a
+    /// failure part way leaves neither the poll resolved nor anyone credited.
+    async fn resolve_poll_once(
+        &self,
+        poll_id: i64,
+        correct_option: Option<u8>,
+    ) -> AppResult<PollRecord> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let rec = sqlx::query_as::<_, DbPoll>(
+            r#"
+            UPDATE polls
+            SET resolved = true, correct_option = $2, voided = $2 IS NULL
+            WHERE id = $1 AND resolved = false
+            RETURNING id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, voided, commit_sync_completed, auto_resolve, visibility, anonymous_owner, option_meta, membership_hash_scheme, created_at, deleted_at
+            "#,
+        )
+        .bind(poll_id)
+        .bind(correct_option.map(i16::from))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        // Only the caller that flips `resolved` applies XP; a concurrent resolver gets an error.
+        let Some(rec) = rec else {
+            drop(tx);
+            self.get_poll(poll_id).await?;
+            return Err(AppError::Validation("poll already resolved".into()));
+        };
+        self.apply_poll_results(&mut tx, poll_id, correct_option)
+            .await?;
+        tx.commit().await.map_err(AppError::Db)?;
+        Ok(rec.into())
+    }
+
+    /// Runs `attempt` (one whole transaction) again when Postgres aborts it as a serialization
+    /// failure or deadlock, up to `RESOLVE_TX_ATTEMPTS` times.
+    async fn retry_transaction<T, F, Fut>(&self, what: &str, attempt: F) -> AppResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = AppResult<T>>,
+    {
+        let mut tries = 1;
+        loop {
+            match attempt().await {
+                Err(AppError::Db(e))
+                    if tries < RESOLVE_TX_ATTEMPTS && is_retryable_tx_error(&e) =>
+                {
+                    tracing::warn!(error = %e, tries, "{what} transaction conflicted; retrying");
+                    tries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn apply_poll_results(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        poll_id: i64,
+        correct_option: Option<u8>,
+    ) -> AppResult<()> {
+        // The API and the indexer can both resolve a poll; only the first counts its votes.
+        let first = sqlx::query(
+            r#"UPDATE polls SET results_applied_at = now() WHERE id = $1 AND results_applied_at IS NULL"#,
+        )
+        .bind(poll_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::Db)?
+        .rows_affected()
+            > 0;
+        if !first {
+            return Ok(());
+        }
+        let commits = sqlx::query(
+            r#"SELECT identity_secret, choice, choice_enc FROM commitments WHERE poll_id = $1 ORDER BY id"#,
+        )
+        .bind(poll_id)
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(AppError::Db)?;
+
+        for commit in commits {
+            let identity_secret: String = commit.get("identity_secret");
+            let choice = self.open_choice(commit.get("choice"), commit.get("choice_enc"))?;
+            let correct = correct_option == Some(choice as u8);
+            self.bump_user_stats(tx, &identity_secret, correct).await?;
+        }
+        Ok(())
+    }
+
+    async fn bump_user_stats(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        identity_secret: &str,
+        correct: bool,
+    ) -> AppResult<()> {
+        let xp_delta = if correct {
+            XP_CORRECT
+        } else {
+            XP_PARTICIPATION
+        };
+        let correct_inc = if correct { 1 } else { 0 };
+        let updated = sqlx::query(
+            r#"
+            UPDATE user_stats
+            SET xp = xp + $2,
+                total_votes = total_votes + 1,
+                correct_votes = correct_votes + $3,
+                updated_at = now()
+            WHERE identity_secret = $1
+            RETURNING xp, tier
+            "#,
+        )
+        .bind(identity_secret)
+        .bind(xp_delta)
+        .bind(correct_inc)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(AppError::Db)?;
+
+        if let Some(row) = updated {
+            let xp: i64 = row.get("xp");
+            let current_tier: String = row.get("tier");
+            let new_tier = tier_for_xp(xp);
+            if new_tier != current_tier {
+                sqlx::query(r#"UPDATE user_stats SET tier = $2 WHERE identity_secret = $1"#)
+                    .bind(identity_secret)
+                    .bind(new_tier)
+                    .execute(&mut **tx)
+                    .await
+                    .map_err(AppError::Db)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll_member_list(&self, poll_id: i64) -> AppResult<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT identity_secret
+            FROM poll_members
+            WHERE poll_id = $1
+            ORDER BY position NULLS LAST, identity_secret
+            "#,
+        )
+        .bind(poll_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|r| r.try_get::<String, _>("identity_secret").ok())
+            .collect())
+    }
+
+    /// Tree over `members`, read from `membership_snapshots` when this exact member list was
+    /// built before; `force` rebuilds and overwrites the stored snapshot.
+    async fn membership_tree(
+        &self,
+        members: &[String],
+        scheme: HashScheme,
+        force: bool,
+    ) -> AppResult<MerkleResult> {
+        let digest = merkle::members_digest(members, self.merkle_depth, scheme);
+        if !force {
+            let row = sqlx::query(
+                r#"
+                SELECT root, depth, paths, nodes_stored
+                FROM membership_snapshots
+                WHERE digest = $1
+                "#,
+            )
+            .bind(&digest)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+            if let Some(row) = row {
+                let paths: serde_json::Value = row.get("paths");
+                let tree = MerkleResult {
+                    root: row.get("root"),
+                    depth: row.get::<i32, _>("depth") as u32,
+                    paths: serde_json::from_value(paths)
+                        .map_err(|e| AppError::External(format!("membership snapshot: {e}")))?,
+                };
+                if !row.get::<bool, _>("nodes_stored") {
+                    self.store_merkle_nodes(&digest, members, &tree).await?;
+                }
+                return Ok(tree);
+            }
+        }
+        let tree = self
+            .merkle
+            .build(members, self.merkle_depth, scheme)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO membership_snapshots (digest, root, depth, member_count, paths)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (digest)
+            DO UPDATE SET root = EXCLUDED.root, depth = EXCLUDED.depth, paths = EXCLUDED.paths,
+                nodes_stored = false, created_at = now()
+            "#,
+        )
+        .bind(&digest)
+        .bind(&tree.root)
+        .bind(tree.depth as i32)
+        .bind(members.len() as i32)
+        .bind(serde_json::to_value(&tree.paths).map_err(|e| AppError::External(e.to_string()))?)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        self.store_merkle_nodes(&digest, members, &tree).await?;
+        Ok(tree)
+    }
+
+    /// Replaces the snapshot's `merkle_nodes` with the sibling nodes of `tree`.
+    async fn store_merkle_nodes(
+        &self,
+        digest: &str,
+        members: &[String],
+        tree: &MerkleResult,
+    ) -> AppResult<()> {
+        let nodes = merkle::tree_nodes(members, tree);
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        sqlx::query(r#"DELETE FROM merkle_nodes WHERE snapshot_digest = $1"#)
+            .bind(digest)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        for chunk in nodes.chunks(MERKLE_NODE_BATCH) {
+            let levels: Vec<i32> = chunk.iter().map(|(level, _, _)| *level as i32).collect();
+            let indexes: Vec<i64> = chunk.iter().map(|(_, index, _)| *index as i64).collect();
+            let hashes: Vec<&str> = chunk.iter().map(|(_, _, hash)| hash.as_str()).collect();
+            sqlx::query(
+                r#"
+                INSERT INTO merkle_nodes (snapshot_digest, level, node_index, hash)
+                SELECT $1, * FROM UNNEST($2::INTEGER[], $3::BIGINT[], $4::TEXT[])
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(digest)
+            .bind(&levels)
+            .bind(&indexes)
+            .bind(&hashes)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        }
+        sqlx::query(r#"UPDATE membership_snapshots SET nodes_stored = true WHERE digest = $1"#)
+            .bind(digest)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        tx.commit().await.map_err(AppError::Db)
+    }
+
+    /// Path of leaf `leaf_index` read from the snapshot's stored nodes, one indexed read per
+    /// level; `None` when the snapshot's nodes are not (or no longer) stored.
+    async fn path_from_nodes(
+        &self,
+        snapshot: &str,
+        leaf_index: u64,
+    ) -> AppResult<Option<MerklePath>> {
+        let depth: Option<i32> = sqlx::query_scalar(
+            r#"SELECT depth FROM membership_snapshots WHERE digest = $1 AND nodes_stored"#,
+        )
+        .bind(snapshot)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        let Some(depth) = depth.map(|d| d as u32) else {
+            return Ok(None);
+        };
+        let levels: Vec<i32> = (0..depth as i32).collect();
+        let indexes: Vec<i64> = (0..depth)
+            .map(|level| merkle::sibling_index(leaf_index, level) as i64)
+            .collect();
+        let rows = sqlx::query(
+            r#"
+            SELECT n.level, n.node_index, n.hash
+            FROM UNNEST($2::INTEGER[], $3::BIGINT[]) AS s(level, node_index)
+            JOIN merkle_nodes n
+              ON n.snapshot_digest = $1 AND n.level = s.level AND n.node_index = s.node_index
+            "#,
+        )
+        .bind(snapshot)
+        .bind(&levels)
+        .bind(&indexes)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        let nodes = rows
+            .into_iter()
+            .map(|row| {
+                let key = (
+                    row.get::<i32, _>("level") as u32,
+                    row.get::<i64, _>("node_index") as u64,
+                );
+                (key, row.get::<String, _>("hash"))
+            })
+            .collect();
+        Ok(Some(merkle::assemble_path(leaf_index, depth, &nodes)))
+    }
+
+    /// Points a poll created before snapshots were recorded at `digest`, numbering its
+    /// members in the order the tree was built over.
+    async fn attach_membership_snapshot(
+        &self,
+        poll_id: i64,
+        digest: &str,
+        members: &[String],
+    ) -> AppResult<()> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        for (position, member) in members.iter().enumerate() {
+            sqlx::query(
+                r#"
+                UPDATE poll_members SET position = $3
+                WHERE poll_id = $1 AND identity_secret = $2 AND position IS NULL
+                "#,
+            )
+            .bind(poll_id)
+            .bind(member)
+            .bind(position as i32)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        }
+        sqlx::query(r#"UPDATE polls SET membership_snapshot = $2 WHERE id = $1"#)
+            .bind(poll_id)
+            .bind(digest)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        tx.commit().await.map_err(AppError::Db)
+    }
+
+    /// Numbers members without a leaf (rows from before the incremental tree, or inserted
+    /// behind `ensure_member`'s back) and, when any were, `rebuild` is set or the stored
+    /// frontier has another depth or hash, recomputes it from every member in leaf order.
+    async fn sync_membership_tree(&self, rebuild: bool) -> AppResult<IncrementalTree> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let created = sqlx::query(
+            r#"
+            INSERT INTO membership_tree (id, leaf_count, filled_subtrees, root)
+            VALUES (true, 0, '[]'::jsonb, '')
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Db)?
+        .rows_affected()
+            > 0;
+        let row = sqlx::query(
+            r#"SELECT leaf_count, filled_subtrees, root, scheme FROM membership_tree WHERE id FOR UPDATE"#,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        let numbered = sqlx::query(
+            r#"
+            UPDATE members m SET leaf_index = n.leaf_index
+            FROM (
+                SELECT id,
+                    (SELECT COALESCE(MAX(leaf_index), -1) FROM members)
+                        + ROW_NUMBER() OVER (ORDER BY id) AS leaf_index
+                FROM members
+                WHERE leaf_index IS NULL
+            ) n
+            WHERE m.id = n.id
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Db)?
+        .rows_affected();
+        let stored = membership_tree_from_row(&row)?;
+        if !created
+            && !rebuild
+            && numbered == 0
+            && stored.depth() == self.merkle_depth
+            && stored.scheme == self.hash_scheme
+        {
+            tx.commit().await.map_err(AppError::Db)?;
+            return Ok(stored);
+        }
+        let members: Vec<String> =
+            sqlx::query_scalar(r#"SELECT identity_secret FROM members ORDER BY leaf_index"#)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(AppError::Db)?;
+        let (depth, scheme) = (self.merkle_depth, self.hash_scheme);
+        let tree = tokio::task::spawn_blocking(move || {
+            IncrementalTree::from_members(&members, depth, scheme)
+        })
+        .await
+        .map_err(|e| AppError::External(format!("membership tree task failed: {e}")))??;
+        save_membership_tree(&mut tx, &tree).await?;
+        tx.commit().await.map_err(AppError::Db)?;
+        if numbered > 0 {
+            tracing::info!(numbered, root = %tree.root, "Membership tree rebuilt");
+        }
+        Ok(tree)
+    }
+
+    /// Current members in leaf order with the root over them, read under one lock so a
+    /// concurrent join cannot slip between the two.
+    async fn current_membership(&self) -> AppResult<(Vec<String>, String)> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let root: String =
+            sqlx::query_scalar(r#"SELECT root FROM membership_tree WHERE id FOR SHARE"#)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(AppError::Db)?;
+        let members: Vec<String> = sqlx::query_scalar(
+            r#"SELECT identity_secret FROM members ORDER BY leaf_index NULLS LAST, id"#,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        tx.commit().await.map_err(AppError::Db)?;
+        Ok((members, root))
+    }
+
+    async fn current_members(&self) -> AppResult<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT identity_secret FROM members ORDER BY leaf_index NULLS LAST, id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|r| r.try_get::<String, _>("identity_secret").ok())
+            .collect())
+    }
+    async fn next_poll_sequence(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT nextval(pg_get_serial_sequence('polls','id'))")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn insert_poll_with_members(
+        &self,
+        poll_id: i64,
+        poll: NewPoll<'_>,
+        membership_root: String,
+        members: Vec<String>,
+        adjust_sequence: bool,
+    ) -> AppResult<PollRecord> {
+        // The member list is frozen here: the poll reads its paths from this snapshot's nodes.
+        let snapshot = if members.is_empty() {
+            None
+        } else {
+            self.membership_tree(&members, self.hash_scheme, false)
+                .await?;
+            Some(merkle::members_digest(
+                &members,
+                self.merkle_depth,
+                self.hash_scheme,
+            ))
+        };
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let rec = sqlx::query_as::<_, DbPoll>(
+            r#"
+            INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, commit_sync_completed, auto_resolve, visibility, anonymous_owner, option_meta, membership_hash_scheme)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, false, $10, $11, $12, $13, $14)
+            ON CONFLICT (id) DO UPDATE SET
+                question = EXCLUDED.question,
+                options = EXCLUDED.options,
+                commit_phase_end = EXCLUDED.commit_phase_end,
+                reveal_phase_end = EXCLUDED.reveal_phase_end,
+                category = EXCLUDED.category,
+                membership_root = EXCLUDED.membership_root,
+                owner = EXCLUDED.owner,
+                reveal_tx_hash = EXCLUDED.reveal_tx_hash,
+                auto_resolve = EXCLUDED.auto_resolve,
+                visibility = EXCLUDED.visibility,
+                anonymous_owner = EXCLUDED.anonymous_owner,
+                option_meta = EXCLUDED.option_meta,
+                membership_hash_scheme = EXCLUDED.membership_hash_scheme
+            RETURNING id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, voided, commit_sync_completed, auto_resolve, visibility, anonymous_owner, option_meta, membership_hash_scheme, created_at, deleted_at
+            "#,
+        )
+        .bind(poll_id)
+        .bind(poll.question)
+        .bind(serde_json::to_value(poll.options).unwrap())
+        .bind(poll.commit_phase_end)
+        .bind(poll.reveal_phase_end)
+        .bind(poll.category)
+        .bind(membership_root)
+        .bind(poll.owner)
+        .bind("") // initial reveal tx hash
+        .bind(poll.auto_resolve)
+        .bind(poll.visibility)
+        .bind(poll.anonymous_owner)
+        .bind(serde_json::to_value(poll.option_meta).unwrap())
+        .bind(self.hash_scheme.as_str())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+
+        sqlx::query(
+            r#"UPDATE polls SET membership_snapshot = $2, membership_snapshot_at = now() WHERE id = $1"#,
+        )
+            .bind(poll_id)
+            .bind(&snapshot)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        for (position, m) in members.into_iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO poll_members (poll_id, identity_secret, position)
+                VALUES ($1, $2, $3)
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(poll_id)
+            .bind(m)
+            .bind(position as i32)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        }
+
+        if adjust_sequence {
+            sqlx::query(
+                r#"
+                SELECT setval(
+                    pg_get_serial_sequence('polls','id'),
+                    GREATEST($1, (SELECT COALESCE(MAX(id),0) + 1 FROM polls))
+                )
+                "#,
+            )
+            .bind(poll_id + 1)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+        }
+
+        tx.commit().await.map_err(AppError::Db)?;
+        let mut record: PollRecord = rec.into();
+        record.vote_counts = vec![0; record.options.len()];
+        Ok(record)
+    }
+}
+
+#[async_trait]
+impl PollStore for PgStore {
+    async fn create_poll(&self, poll: NewPoll<'_>) -> AppResult<PollRecord> {
+        let (members, computed_root) = self.current_membership().await?;
+        let poll_id = self.next_poll_sequence().await.map_err(AppError::Db)?;
+        self.insert_poll_with_members(poll_id, poll, computed_root, members, false)
+            .await
+    }
+
+    async fn create_poll_with_id(
+        &self,
+        poll_id: i64,
+        poll: NewPoll<'_>,
+        membership_root: String,
+        members: Vec<String>,
+    ) -> AppResult<PollRecord> {
+        self.insert_poll_with_members(poll_id, poll, membership_root, members, true)
+            .await
+    }
+
+    async fn record_poll_intent(&self, intent: &PollIntent) -> AppResult<i64> {
+        let payload = serde_json::to_value(intent)
+            .map_err(|e| AppError::External(format!("poll intent encode failed: {e}")))?;
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO pending_onchain_polls (payload) VALUES ($1) RETURNING id
+            "#,
+        )
+        .bind(payload)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::Db)
+    }
+
+    async fn mark_poll_intent_sent(&self, id: i64, poll_id: i64, tx_hash: &str) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE pending_onchain_polls
+            SET state = 'sent', poll_id = $2, tx_hash = $3, updated_at = now()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(poll_id)
+        .bind(tx_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn mark_poll_intent_failed(&self, id: i64, error: &str) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE pending_onchain_polls
+            SET state = 'failed', error = $2, updated_at = now()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn mark_poll_intent_stored(&self, id: i64) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE pending_onchain_polls SET state = 'stored', updated_at = now() WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn sent_poll_intents(&self) -> AppResult<Vec<SentPollIntentRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, poll_id, tx_hash, payload
+            FROM pending_onchain_polls
+            WHERE state = 'sent'
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        rows.into_iter()
+            .map(|row| {
+                let intent = serde_json::from_value(row.get("payload"))
+                    .map_err(|e| AppError::External(format!("poll intent decode failed: {e}")))?;
+                Ok(SentPollIntentRecord {
+                    id: row.get("id"),
+                    poll_id: row.get("poll_id"),
+                    tx_hash: row.get("tx_hash"),
+                    intent,
+                })
+            })
+            .collect()
+    }
+
+    async fn list_polls(
+        &self,
+        limit: i64,
+        before_id: Option<i64>,
+        filter: PollFilter,
+    ) -> AppResult<PollPage> {
+        // One row past the page says whether another page follows.
+        let column = filter.sort.column();
+        let query = format!(
+            r#"
+            SELECT id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, voided, commit_sync_completed, auto_resolve, visibility, anonymous_owner, option_meta, membership_hash_scheme, created_at, deleted_at
+            FROM polls
+            WHERE ($5 OR deleted_at IS NULL)
+              AND ($2::BIGINT IS NULL OR ({column}, id) < (SELECT {column}, id FROM polls WHERE id = $2))
+              AND ($3::TEXT IS NULL OR category = $3)
+              AND ($4::BOOLEAN IS NULL OR resolved = $4)
+              AND ($6 OR visibility <> 'members' OR owner = $7
+                   OR EXISTS (SELECT 1 FROM poll_members m WHERE m.poll_id = polls.id AND m.identity_secret = $8))
+            ORDER BY {column} DESC, id DESC
+            LIMIT $1 + 1
+            "#
+        );
+        let (everyone, username, identity_secret) = filter.audience.binds();
+        let rows = sqlx::query_as::<_, DbPoll>(&query)
+            .bind(limit.max(0))
+            .bind(before_id)
+            .bind(filter.category.as_deref())
+            .bind(filter.resolved)
+            .bind(filter.include_deleted)
+            .bind(everyone)
+            .bind(username)
+            .bind(identity_secret)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+        let mut page = PollPage::from_fetched(rows.into_iter().map(Into::into).collect(), limit);
+        self.populate_vote_counts(&mut page.polls).await?;
+        Ok(page)
+    }
+
+    async fn get_poll(&self, poll_id: i64) -> AppResult<PollRecord> {
+        self.fetch_poll(poll_id, false).await
+    }
+
+    async fn get_poll_including_deleted(&self, poll_id: i64) -> AppResult<PollRecord> {
+        self.fetch_poll(poll_id, true).await
+    }
+
+    async fn soft_delete_poll(&self, poll_id: i64) -> AppResult<PollRecord> {
+        sqlx::query(r#"UPDATE polls SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL"#)
+            .bind(poll_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+        self.get_poll_including_deleted(poll_id).await
+    }
+
+    async fn get_polls_by_ids(
+        &self,
+        ids: &[i64],
+        audience: &PollAudience,
+    ) -> AppResult<Vec<PollRecord>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (everyone, username, identity_secret) = audience.binds();
+        let rows = sqlx::query_as::<_, DbPoll>(
+            r#"
+            SELECT id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, voided, commit_sync_completed, auto_resolve, visibility, anonymous_owner, option_meta, membership_hash_scheme, created_at, deleted_at
+            FROM polls
+            WHERE id = ANY($1) AND deleted_at IS NULL
+              AND ($2 OR visibility <> 'members' OR owner = $3
+                   OR EXISTS (SELECT 1 FROM poll_members m WHERE m.poll_id = polls.id AND m.identity_secret = $4))
+            "#,
+        )
+        .bind(ids)
+        .bind(everyone)
+        .bind(username)
+        .bind(identity_secret)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        let mut by_id: HashMap<i64, PollRecord> = rows
+            .into_iter()
+            .map(|row| {
+                let record: PollRecord = row.into();
+                (record.id, record)
+            })
+            .collect();
+        let mut records: Vec<PollRecord> = ids.iter().filter_map(|id| by_id.remove(id)).collect();
+        self.populate_vote_counts(&mut records).await?;
+        Ok(records)
+    }
+
+    async fn record_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord> {
+        let (choice, choice_enc) = self.seal_choice(commit.choice)?;
+        let rec = sqlx::query_as::<_, DbCommit>(
+            r#"
+            INSERT INTO commitments (poll_id, choice, commitment, identity_secret, secret, nullifier, proof, public_inputs, auto_reveal, choice_enc, proof_version, commit_state)
+            SELECT $1::BIGINT, $2::SMALLINT, $3::TEXT, $4::TEXT, $5::TEXT, $6::TEXT, $7::TEXT, $8::TEXT[], $9::BOOLEAN, $10::TEXT, $11::INTEGER,
+                   CASE WHEN $12::BOOLEAN THEN 'queued' ELSE 'offchain' END
+            WHERE NOT EXISTS (
+                SELECT 1 FROM commitments WHERE poll_id = $1 AND commitment = $3
+            )
+            RETURNING id, poll_id, choice, choice_enc, commitment, identity_secret, secret, nullifier, proof, public_inputs, proof_version, recorded_at, revision, auto_reveal, scrubbed_at
+            "#,
+        )
+        .bind(commit.poll_id)
+        .bind(choice)
+        .bind(commit.commitment)
+        .bind(commit.identity_secret)
+        .bind(self.seal(commit.secret)?)
+        .bind(commit.nullifier)
+        .bind(commit.proof)
+        .bind(self.seal_public_inputs(commit.public_inputs)?)
+        .bind(commit.auto_reveal)
+        .bind(choice_enc)
+        .bind(i32::from(commit.proof_version))
+        .bind(commit.onchain_commit)
+        .fetch_optional(&self.pool)
+        .await;
+        match rec {
+            Ok(Some(rec)) => self.open_commit(rec),
+            // Either the commitment is taken or a concurrent request for this identity won the
+            // race on commitments_poll_identity_idx.
+            Ok(None) => existing_commit_or_conflict(
+                self.commitment_for_identity(commit.poll_id, commit.identity_secret)
+                    .await?,
+                &commit,
+            ),
+            Err(sqlx::Error::Database(err))
+                if err.constraint() == Some("commitments_poll_nullifier_idx") =>
+            {
+                Err(nullifier_taken())
+            }
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+                existing_commit_or_conflict(
+                    self.commitment_for_identity(commit.poll_id, commit.identity_secret)
+                        .await?,
+                    &commit,
+                )
+            }
+            Err(err) => Err(AppError::Db(err)),
+        }
+    }
+
+    async fn replace_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord> {
+        let (choice, choice_enc) = self.seal_choice(commit.choice)?;
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let taken = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT 1 FROM commitments
+            WHERE poll_id = $1 AND commitment = $2 AND identity_secret <> $3
+            LIMIT 1
+            "#,
+        )
+        .bind(commit.poll_id)
+        .bind(commit.commitment)
+        .bind(commit.identity_secret)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        if taken.is_some() {
+            return Err(duplicate_commitment());
+        }
+        let rec = sqlx::query_as::<_, DbCommit>(
+            r#"
+            UPDATE commitments
+            SET choice = $3,
+                commitment = $4,
+                secret = $5,
+                nullifier = $6,
+                proof = $7,
+                public_inputs = $8,
+                auto_reveal = $9,
+                choice_enc = $10,
+                proof_version = $11,
+                revision = revision + 1,
+                onchain_submitted = false,
+                sync_state = 'pending',
+                sync_error = '',
+                sync_attempts = 0,
+                next_attempt_at = NULL,
+                commit_state = CASE WHEN $12 THEN 'queued' ELSE 'offchain' END,
+                commit_tx_hash = '',
+                commit_attempts = 0,
+                commit_next_attempt_at = NULL,
+                commit_error = '',
+                commit_confirmed_at = NULL,
+                recorded_at = now()
+            WHERE poll_id = $1 AND identity_secret = $2 AND onchain_submitted = false
+              AND commit_state IN ('offchain', 'queued')
+            RETURNING id, poll_id, choice, choice_enc, commitment, identity_secret, secret, nullifier, proof, public_inputs, proof_version, recorded_at, revision, auto_reveal, scrubbed_at
+            "#,
+        )
+        .bind(commit.poll_id)
+        .bind(commit.identity_secret)
+        .bind(choice)
+        .bind(commit.commitment)
+        .bind(self.seal(commit.secret)?)
+        .bind(commit.nullifier)
+        .bind(commit.proof)
+        .bind(self.seal_public_inputs(commit.public_inputs)?)
+        .bind(commit.auto_reveal)
+        .bind(choice_enc)
+        .bind(i32::from(commit.proof_version))
+        .bind(commit.onchain_commit)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(db)
+                if db.constraint() == Some("commitments_poll_nullifier_idx") =>
+            {
+                nullifier_taken()
+            }
+            err => AppError::Db(err),
+        })?;
+        tx.commit().await.map_err(AppError::Db)?;
+        match rec {
+            Some(rec) => self.open_commit(rec),
+            None if self
+                .has_commit(commit.poll_id, commit.identity_secret)
+                .await? =>
+            {
+                Err(AppError::Conflict(
+                    "commitment already submitted on-chain".into(),
+                ))
+            }
+            None => Err(AppError::NotFound),
+        }
+    }
+
+    async fn delete_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<()> {
+        let deleted = sqlx::query_scalar::<_, i64>(
+            r#"
+            DELETE FROM commitments
+            WHERE poll_id = $1 AND identity_secret = $2 AND onchain_submitted = false
+              AND commit_state IN ('offchain', 'queued')
+            RETURNING id::BIGINT
+            "#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        match deleted {
+            Some(_) => Ok(()),
+            None if self.has_commit(poll_id, identity_secret).await? => Err(AppError::Conflict(
+                "commitment already submitted on-chain".into(),
+            )),
+            None => Err(AppError::NotFound),
+        }
+    }
+
+    async fn record_vote(&self, vote: StoredVote<'_>) -> AppResult<StoredVoteRecord> {
+        // No row back means the nullifier is already stored, by an earlier or concurrent reveal.
+        let inserted = sqlx::query_as::<_, DbVote>(
+            r#"
+            INSERT INTO votes (poll_id, nullifier, choice, source)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (poll_id, nullifier) DO NOTHING
+            RETURNING poll_id, nullifier, choice, recorded_at, source
+            "#,
+        )
+        .bind(vote.poll_id)
+        .bind(vote.nullifier)
+        .bind(vote.choice as i16)
+        .bind(vote.source.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        match inserted {
+            Some(rec) => Ok(rec.into()),
+            None => self.settle_duplicate_vote(vote).await,
+        }
+    }
+
+    async fn membership_root_snapshot(&self) -> AppResult<String> {
+        sqlx::query_scalar(r#"SELECT root FROM membership_tree WHERE id"#)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(AppError::Db)
+    }
+
+    async fn check_membership_tree(&self) -> AppResult<MembershipTreeCheck> {
+        let (members, incremental_root) = self.current_membership().await?;
+        let rebuilt = self
+            .merkle
+            .build(&members, self.merkle_depth, self.hash_scheme)
+            .await?;
+        Ok(MembershipTreeCheck {
+            member_count: members.len(),
+            incremental_root,
+            rebuilt_root: rebuilt.root,
+        })
+    }
+
+    async fn recompute_membership_snapshot(&self) -> AppResult<MembershipSnapshotRecord> {
+        self.sync_membership_tree(true).await?;
+        let members = self.current_members().await?;
+        let tree = self
+            .membership_tree(&members, self.hash_scheme, true)
+            .await?;
+        Ok(MembershipSnapshotRecord {
+            digest: merkle::members_digest(&members, self.merkle_depth, self.hash_scheme),
+            root: tree.root,
+            member_count: members.len(),
+            depth: tree.depth,
+        })
+    }
+
+    async fn poll_membership_root(&self, poll_id: i64) -> AppResult<PollMembershipRootRecord> {
+        let row = sqlx::query(
+            r#"
+            SELECT p.membership_root, p.membership_snapshot, p.membership_hash_scheme,
+                COALESCE(p.membership_snapshot_at, p.created_at) AS snapshot_at,
+                (SELECT COUNT(*) FROM poll_members m WHERE m.poll_id = p.id) AS member_count
+            FROM polls p
+            WHERE p.id = $1 AND p.deleted_at IS NULL
+            "#,
+        )
+        .bind(poll_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?
+        .ok_or(AppError::NotFound)?;
+        let snapshot_digest = match row.get::<Option<String>, _>("membership_snapshot") {
+            Some(digest) => digest,
+            None => merkle::members_digest(
+                &self.poll_member_list(poll_id).await?,
+                self.merkle_depth,
+                stored_scheme(&row)?,
+            ),
+        };
+        Ok(PollMembershipRootRecord {
+            root: row.get("membership_root"),
+            snapshot_digest,
+            member_count: row.get("member_count"),
+            snapshot_at: row.get("snapshot_at"),
+        })
+    }
+
+    async fn rebuild_poll_root(&self, poll_id: i64) -> AppResult<Option<String>> {
+        let members = self.poll_member_list(poll_id).await?;
+        if members.is_empty() {
+            return Ok(None);
+        }
+        let scheme = self.get_poll(poll_id).await?.membership_hash_scheme;
+        Ok(Some(
+            self.merkle
+                .build(&members, self.merkle_depth, scheme)
+                .await?
+                .root,
+        ))
+    }
+
+    async fn check_stored_path(&self) -> AppResult<Option<StoredPathCheck>> {
+        let row = sqlx::query(
+            r#"
+            SELECT m.poll_id, m.position, m.identity_secret, p.membership_snapshot,
+                p.membership_hash_scheme
+            FROM poll_members m
+            JOIN polls p ON p.id = m.poll_id
+            WHERE p.deleted_at IS NULL
+              AND p.membership_snapshot IS NOT NULL
+              AND m.position IS NOT NULL
+            ORDER BY random()
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let poll_id: i64 = row.get("poll_id");
+        let leaf_index = row.get::<i32, _>("position") as u64;
+        let identity_secret: String = row.get("identity_secret");
+        let snapshot: String = row.get("membership_snapshot");
+        let Some(stored) = self.path_from_nodes(&snapshot, leaf_index).await? else {
+            return Ok(None);
+        };
+        let members = self.poll_member_list(poll_id).await?;
+        let fresh = self
+            .merkle
+            .build(&members, self.merkle_depth, stored_scheme(&row)?)
+            .await?;
+        Ok(Some(StoredPathCheck {
+            poll_id,
+            leaf_index,
+            matches: fresh.paths.get(&identity_secret) == Some(&stored),
+        }))
+    }
+
+    async fn prune_merkle_nodes(&self) -> AppResult<u64> {
+        let pruned = sqlx::query(
+            r#"
+            WITH unused AS (
+                UPDATE membership_snapshots s SET nodes_stored = false
+                WHERE s.nodes_stored
+                  AND NOT EXISTS (
+                      SELECT 1 FROM polls p
+                      WHERE p.membership_snapshot = s.digest AND p.deleted_at IS NULL
+                  )
+                  AND s.digest <> (
+                      SELECT digest FROM membership_snapshots ORDER BY created_at DESC LIMIT 1
+                  )
+                RETURNING s.digest
+            ), dropped AS (
+                DELETE FROM merkle_nodes n USING unused u WHERE n.snapshot_digest = u.digest
+            )
+            SELECT digest FROM unused
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(pruned.len() as u64)
+    }
+
+    async fn list_members(&self) -> AppResult<Vec<String>> {
+        self.current_members().await
+    }
+
+    async fn merkle_path_for_member(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<MerklePath>> {
+        let row = sqlx::query(
+            r#"
+            SELECT m.position, m.path_bits, m.path_siblings, p.membership_snapshot,
+                p.membership_hash_scheme
+            FROM poll_members m
+            JOIN polls p ON p.id = m.poll_id
+            WHERE m.poll_id = $1 AND m.identity_secret = $2
+            "#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let bits: Option<serde_json::Value> = row.try_get("path_bits").map_err(AppError::Db)?;
+        let siblings: Option<serde_json::Value> =
+            row.try_get("path_siblings").map_err(AppError::Db)?;
+        if let (Some(bits), Some(siblings)) = (bits, siblings) {
+            let path = |value| {
+                serde_json::from_value(value)
+                    .map_err(|e| AppError::External(format!("stored member path: {e}")))
+            };
+            return Ok(Some(MerklePath {
+                bits: path(bits)?,
+                siblings: path(siblings)?,
+            }));
+        }
+        let snapshot: Option<String> = row.get("membership_snapshot");
+        let position: Option<i32> = row.get("position");
+        if let (Some(snapshot), Some(position)) = (snapshot.as_deref(), position) {
+            if let Some(path) = self.path_from_nodes(snapshot, position as u64).await? {
+                return Ok(Some(path));
+            }
+        }
+        // Polls from before snapshots were recorded, or whose nodes were pruned: building the
+        // tree stores its nodes again, and the poll is pointed at them for next time.
+        let scheme = stored_scheme(&row)?;
+        let members = self.poll_member_list(poll_id).await?;
+        let merkle = self.membership_tree(&members, scheme, false).await?;
+        let digest = merkle::members_digest(&members, self.merkle_depth, scheme);
+        self.attach_membership_snapshot(poll_id, &digest, &members)
+            .await?;
+        Ok(merkle.paths.get(identity_secret).cloned())
+    }
+
+    async fn ensure_member(&self, username: &str, identity_secret: &str) -> AppResult<()> {
+        let known =
+            sqlx::query_scalar::<_, i32>(r#"SELECT 1 FROM members WHERE identity_secret = $1"#)
+                .bind(identity_secret)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(AppError::Db)?;
+        if known.is_none() {
+            // The tree row lock orders joins, so leaf indexes match the frontier.
+            let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+            let row = sqlx::query(
+                r#"SELECT leaf_count, filled_subtrees, root, scheme FROM membership_tree WHERE id FOR UPDATE"#,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+            let mut tree = membership_tree_from_row(&row)?;
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO members (identity_secret, leaf_index)
+                VALUES ($1, $2)
+                ON CONFLICT (identity_secret) DO NOTHING
+                "#,
+            )
+            .bind(identity_secret)
+            .bind(tree.leaf_count as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?
+            .rows_affected();
+            if inserted > 0 {
+                tree.push(identity_secret)?;
+                save_membership_tree(&mut tx, &tree).await?;
+            }
+            tx.commit().await.map_err(AppError::Db)?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_stats (identity_secret, username)
+            VALUES ($1, $2)
+            ON CONFLICT (identity_secret)
+            DO UPDATE SET username = EXCLUDED.username, updated_at = now()
+            "#,
+        )
+        .bind(identity_secret)
+        .bind(username)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn poll_includes_member(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool> {
+        let row = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT 1 FROM poll_members WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1
+            "#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.is_some())
+    }
+
+    async fn has_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool> {
+        let row = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT 1 FROM commitments WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1
+            "#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.is_some())
+    }
+
+    async fn commitment_for_identity(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<StoredCommitRecord>> {
+        let rec = sqlx::query_as::<_, DbCommit>(
+            r#"
+            SELECT id, poll_id, choice, choice_enc, commitment, identity_secret, secret, nullifier, proof, public_inputs, proof_version, recorded_at, revision, auto_reveal, scrubbed_at
+            FROM commitments
+            WHERE poll_id = $1 AND identity_secret = $2
+            "#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        rec.map(|rec| self.open_commit(rec)).transpose()
+    }
+
+    async fn commitment_receipt_status(
+        &self,
+        poll_id: i64,
+        commitment: &str,
+    ) -> AppResult<Option<CommitReceiptStatus>> {
+        let row = sqlx::query(
+            r#"
+            SELECT onchain_submitted, scrubbed_at IS NOT NULL AS scrubbed
+            FROM commitments WHERE poll_id = $1 AND commitment = $2 LIMIT 1
+            "#,
+        )
+        .bind(poll_id)
+        .bind(commitment)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.map(|row| CommitReceiptStatus {
+            synced: row.get("onchain_submitted"),
+            scrubbed: row.get("scrubbed"),
+        }))
+    }
+
+    async fn commitment_exists(&self, poll_id: i64, commitment: &str) -> AppResult<bool> {
+        let row = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT 1 FROM commitments WHERE poll_id = $1 AND commitment = $2 LIMIT 1
+            "#,
+        )
+        .bind(poll_id)
+        .bind(commitment)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.is_some())
+    }
+
+    async fn nullifier_taken(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        identity_secret: &str,
+    ) -> AppResult<bool> {
+        let row = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT 1 FROM commitments
+            WHERE poll_id = $1 AND nullifier = $2 AND nullifier <> '' AND identity_secret <> $3
+            LIMIT 1
+            "#,
+        )
+        .bind(poll_id)
+        .bind(nullifier)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.is_some())
+    }
+
+    async fn my_commit_status(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<MyCommitStatusRecord>> {
+        let row = sqlx::query(
+            r#"
+            SELECT c.onchain_submitted,
+                   c.reveal_expired,
+                   c.reveal_tx_hash,
+                   c.auto_reveal,
+                   c.sync_error,
+                   c.sync_attempts,
+                   c.next_attempt_at,
+                   c.sync_state,
+                   EXISTS (
+                       SELECT 1 FROM votes v WHERE v.poll_id = c.poll_id AND v.nullifier = c.nullifier
+                   ) AS revealed
+            FROM commitments c
+            WHERE c.poll_id = $1 AND c.identity_secret = $2
+            "#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.map(|row| {
+            let tx: String = row.get("reveal_tx_hash");
+            let sync_error: String = row.get("sync_error");
+            MyCommitStatusRecord {
+                onchain_submitted: row.get("onchain_submitted"),
+                expired: row.get("reveal_expired"),
+                revealed: row.get("revealed"),
+                reveal_tx_hash: (!tx.is_empty()).then_some(tx),
+                auto_reveal: row.get("auto_reveal"),
+                sync_error: (!sync_error.is_empty()).then_some(sync_error),
+                sync_attempts: row.get("sync_attempts"),
+                next_sync_attempt_at: row.get("next_attempt_at"),
+                sync_state: CommitSyncState::parse(row.get("sync_state")),
+            }
+        }))
+    }
+
+    async fn commit_count(&self, poll_id: i64) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*)::BIGINT FROM commitments WHERE poll_id = $1"#,
+        )
+        .bind(poll_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(count)
+    }
+
+    async fn poll_member_count(&self, poll_id: i64) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*)::BIGINT FROM poll_members WHERE poll_id = $1"#,
+        )
+        .bind(poll_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(count)
+    }
+
+    async fn participation(&self, poll_id: i64) -> AppResult<ParticipationRecord> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                (SELECT COUNT(*)::BIGINT FROM poll_members WHERE poll_id = $1) AS eligible,
+                (
+                    SELECT COUNT(*)::BIGINT
+                    FROM poll_secrets s
+                    JOIN poll_members m
+                      ON m.poll_id = s.poll_id AND m.identity_secret = s.identity_secret
+                    WHERE s.poll_id = $1
+                ) AS fetched_secret,
+                COUNT(c.id)::BIGINT AS committed,
+                (
+                    SELECT COUNT(*)::BIGINT FROM commitments
+                    WHERE poll_id = $1 AND source = 'chain'
+                ) AS chain_committed,
+                COUNT(c.id) FILTER (
+                    WHERE v.nullifier IS NOT NULL OR c.onchain_submitted
+                )::BIGINT AS revealed,
+                COUNT(c.id) FILTER (
+                    WHERE c.onchain_submitted OR v.source IN ('relayer', 'chain')
+                )::BIGINT AS synced,
+                COUNT(c.id) FILTER (WHERE NOT c.auto_reveal)::BIGINT AS self_reveal
+            FROM commitments c
+            JOIN poll_members m
+              ON m.poll_id = c.poll_id AND m.identity_secret = c.identity_secret
+            LEFT JOIN votes v ON v.poll_id = c.poll_id AND v.nullifier = c.nullifier
+            WHERE c.poll_id = $1
+            "#,
+        )
+        .bind(poll_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(ParticipationRecord {
+            eligible: row.get("eligible"),
+            fetched_secret: row.get("fetched_secret"),
+            committed: row.get::<i64, _>("committed") + row.get::<i64, _>("chain_committed"),
+            revealed: row.get("revealed"),
+            synced: row.get("synced"),
+            self_reveal: row.get("self_reveal"),
+        })
+    }
+
+    async fn close_commit_phase(&self, poll_id: i64, now: DateTime<Utc>) -> AppResult<PollRecord> {
+        let rec = sqlx::query_as::<_, DbPoll>(
+            r#"
+            UPDATE polls
+            SET commit_phase_end = $2
+            WHERE id = $1 AND resolved = false AND commit_phase_end > $2
+            RETURNING id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, voided, commit_sync_completed, auto_resolve, visibility, anonymous_owner, option_meta, membership_hash_scheme, created_at, deleted_at
+            "#,
+        )
+        .bind(poll_id)
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        let Some(rec) = rec else {
+            self.get_poll(poll_id).await?;
+            return Err(AppError::Validation("commit phase already closed".into()));
+        };
+        let mut record: PollRecord = rec.into();
+        self.populate_vote_counts(std::slice::from_mut(&mut record))
+            .await?;
+        Ok(record)
+    }
+
+    async fn get_or_create_secret(&self, poll_id: i64, identity_secret: &str) -> AppResult<String> {
+        if let Some(existing) = sqlx::query_scalar::<_, String>(
+            r#"SELECT secret FROM poll_secrets WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1"#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?
+        {
+            return self.open(&existing);
+        }
+
+        let secret = self.seal(&generate_secret())?;
+        sqlx::query(
+            r#"
+            INSERT INTO poll_secrets (poll_id, identity_secret, secret)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (poll_id, identity_secret) DO NOTHING
+            "#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .bind(&secret)
+        .execute(&self.pool)
+        .await
         .map_err(AppError::Db)?;
-        Ok(rows
-            .into_iter()
-            .filter_map(|r| r.try_get::<String, _>("identity_secret").ok())
-            .collect())
+
+        let saved = sqlx::query_scalar::<_, String>(
+            r#"SELECT secret FROM poll_secrets WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1"#,
+        )
+        .bind(poll_id)
+        .bind(identity_secret)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        self.open(&saved)
     }
 
-    async fn run_poseidon_merkle(&self, members: &[String]) -> AppResult<MerkleResult> {
-        // Write members to temp file
-        let tmp_path = std::env::temp_dir().join(format!("members-{}.json", Uuid::new_v4()));
-        let payload = serde_json::json!({
-            "members": members,
-            "depth": MERKLE_DEPTH,
-        });
-        tokio::fs::write(&tmp_path, payload.to_string())
+    async fn resolve_poll(
+        &self,
+        poll_id: i64,
+        correct_option: Option<u8>,
+    ) -> AppResult<PollRecord> {
+        let mut record = self
+            .retry_transaction("poll resolution", || {
+                self.resolve_poll_once(poll_id, correct_option)
+            })
+            .await?;
+        self.populate_vote_counts(std::slice::from_mut(&mut record))
+            .await?;
+        Ok(record)
+    }
+
+    async fn auto_resolve_candidates(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+    ) -> AppResult<Vec<i64>> {
+        let ids = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT id FROM polls
+            WHERE auto_resolve = true
+              AND resolved = false
+              AND deleted_at IS NULL
+              AND commit_sync_completed = true
+              AND reveal_phase_end <= $1
+            ORDER BY id
+            "#,
+        )
+        .bind(now - grace)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(ids)
+    }
+
+    async fn disable_auto_resolve(&self, poll_id: i64) -> AppResult<()> {
+        sqlx::query(r#"UPDATE polls SET auto_resolve = false WHERE id = $1"#)
+            .bind(poll_id)
+            .execute(&self.pool)
             .await
-            .map_err(AppError::Io)?;
+            .map_err(AppError::Db)?;
+        Ok(())
+    }
 
-        let output = Command::new("node")
-            .arg(MERKLE_SCRIPT)
-            .arg(&tmp_path)
-            .output()
+    async fn prune_stale_polls(&self, before: DateTime<Utc>, dry_run: bool) -> AppResult<Vec<i64>> {
+        let stale = r#"
+            SELECT p.id FROM polls p
+            WHERE p.deleted_at IS NULL
+              AND p.created_at < $1
+              AND p.reveal_phase_end < $1
+              AND p.resolved = false
+              AND p.indexed = false
+              AND p.reveal_tx_hash = ''
+              AND NOT EXISTS (SELECT 1 FROM commitments c WHERE c.poll_id = p.id)
+              AND NOT EXISTS (SELECT 1 FROM votes v WHERE v.poll_id = p.id)
+        "#;
+        let query = if dry_run {
+            format!("{stale} ORDER BY p.id")
+        } else {
+            format!("UPDATE polls SET deleted_at = now() WHERE id IN ({stale}) RETURNING id")
+        };
+        let mut ids = sqlx::query_scalar::<_, i64>(&query)
+            .bind(before)
+            .fetch_all(&self.pool)
             .await
-            .map_err(|e| AppError::External(e.to_string()))?;
+            .map_err(AppError::Db)?;
+        ids.sort();
+        Ok(ids)
+    }
 
-        // Clean up temp file
-        let _ = tokio::fs::remove_file(&tmp_path).await;
+    async fn secret_scrub_candidates(&self, before: DateTime<Utc>) -> AppResult<Vec<i64>> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT id FROM polls
+            WHERE resolved AND commit_sync_completed
+              AND secrets_scrubbed_at IS NULL
+              AND reveal_phase_end < $1
+              AND COALESCE(results_applied_at, reveal_phase_end) < $1
+            ORDER BY id
+            "#,
+        )
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(AppError::External(format!(
-                "poseidon merkle script failed: {stderr}"
-            )));
+    async fn scrub_poll_secrets(
+        &self,
+        poll_id: i64,
+        requested_by: &str,
+        dry_run: bool,
+    ) -> AppResult<SecretScrubCounts> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let poll: Option<(bool, bool)> = sqlx::query_as(
+            r#"SELECT resolved, commit_sync_completed FROM polls WHERE id = $1 FOR UPDATE"#,
+        )
+        .bind(poll_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        match poll {
+            None => return Err(AppError::NotFound),
+            Some((true, true)) => {}
+            Some(_) => return Err(secrets_still_needed()),
         }
-        let res: MerkleResult = serde_json::from_slice(&output.stdout)
-            .map_err(|e| AppError::External(e.to_string()))?;
-        Ok(res)
+        let counts = if dry_run {
+            let row = sqlx::query(
+                r#"
+                SELECT
+                    (SELECT COUNT(*)::BIGINT FROM poll_secrets WHERE poll_id = $1) AS secrets,
+                    (
+                        SELECT COUNT(*)::BIGINT FROM commitments
+                        WHERE poll_id = $1 AND scrubbed_at IS NULL
+                    ) AS commitments,
+                    (SELECT COUNT(*)::BIGINT FROM proof_cache WHERE poll_id = $1) AS proofs
+                "#,
+            )
+            .bind(poll_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+            SecretScrubCounts {
+                secrets_deleted: row.get::<i64, _>("secrets") as u64,
+                commitments_scrubbed: row.get::<i64, _>("commitments") as u64,
+                cached_proofs_deleted: row.get::<i64, _>("proofs") as u64,
+            }
+        } else {
+            let secrets_deleted = sqlx::query(r#"DELETE FROM poll_secrets WHERE poll_id = $1"#)
+                .bind(poll_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Db)?
+                .rows_affected();
+            let commitments_scrubbed = sqlx::query(
+                r#"
+                UPDATE commitments
+                SET secret = '', proof = '', public_inputs = '{}', scrubbed_at = now()
+                WHERE poll_id = $1 AND scrubbed_at IS NULL
+                "#,
+            )
+            .bind(poll_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?
+            .rows_affected();
+            let cached_proofs_deleted =
+                sqlx::query(r#"DELETE FROM proof_cache WHERE poll_id = $1"#)
+                    .bind(poll_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(AppError::Db)?
+                    .rows_affected();
+            sqlx::query(r#"UPDATE polls SET secrets_scrubbed_at = now() WHERE id = $1"#)
+                .bind(poll_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Db)?;
+            sqlx::query(
+                r#"
+                INSERT INTO secret_scrubs
+                    (poll_id, secrets_deleted, commitments_scrubbed, cached_proofs_deleted, requested_by)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(poll_id)
+            .bind(secrets_deleted as i64)
+            .bind(commitments_scrubbed as i64)
+            .bind(cached_proofs_deleted as i64)
+            .bind(requested_by)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
+            SecretScrubCounts {
+                secrets_deleted,
+                commitments_scrubbed,
+                cached_proofs_deleted,
+            }
+        };
+        tx.commit().await.map_err(AppError::Db)?;
+        Ok(counts)
     }
 
-    async fn current_members(&self) -> AppResult<Vec<String>> {
+    async fn revealed_vote_counts(&self, poll_id: i64) -> AppResult<Vec<i64>> {
+        let poll = self.get_poll(poll_id).await?;
+        let mut counts = vec![0; poll.options.len()];
         let rows = sqlx::query(
+            r#"SELECT choice, COUNT(*)::BIGINT as count FROM votes WHERE poll_id = $1 GROUP BY choice"#,
+        )
+        .bind(poll_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        for row in rows {
+            let choice: i16 = row.get("choice");
+            let count: i64 = row.get("count");
+            if let Some(slot) = counts.get_mut(choice as usize) {
+                *slot = count;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn commits_to_sync(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+        slack: chrono::Duration,
+        onchain_commits: bool,
+        limit: i64,
+    ) -> AppResult<Vec<CommitSyncRow>> {
+        let rows = sqlx::query_as::<_, DbCommitSyncRow>(
+            r#"
+            SELECT c.id::BIGINT as id, c.poll_id, c.choice, c.choice_enc, c.commitment, c.secret, c.nullifier, c.proof, c.public_inputs, c.proof_version
+            FROM commitments c
+            JOIN polls p ON p.id = c.poll_id
+            WHERE p.commit_phase_end <= $1
+              AND p.reveal_phase_end > $2
+              AND p.commit_sync_completed = false
+              AND p.deleted_at IS NULL
+              AND c.onchain_submitted = false
+              AND c.auto_reveal = true
+              AND c.sync_state <> 'dead'
+              AND (c.next_attempt_at IS NULL OR c.next_attempt_at <= $1)
+              AND (c.commit_state = 'confirmed' OR (NOT $4 AND c.commit_state = 'offchain'))
+            ORDER BY c.id
+            LIMIT $3
+            "#,
+        )
+        .bind(now)
+        .bind(now - grace - slack)
+        .bind(limit)
+        .bind(onchain_commits)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(CommitSyncRow {
+                    id: row.id,
+                    poll_id: row.poll_id,
+                    choice: self.open_choice(row.choice, &row.choice_enc)?,
+                    commitment: row.commitment,
+                    secret: self.open(&row.secret)?,
+                    nullifier: row.nullifier,
+                    proof: row.proof,
+                    public_inputs: self.open_public_inputs(row.public_inputs)?,
+                    proof_version: proof_version_from_db(row.proof_version)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn expire_unsynced_commits(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+        slack: chrono::Duration,
+    ) -> AppResult<Vec<i64>> {
+        let mut ids = sqlx::query_scalar::<_, i64>(
             r#"
-            SELECT identity_secret FROM members ORDER BY identity_secret
+            UPDATE commitments c
+            SET reveal_expired = true
+            FROM polls p
+            WHERE p.id = c.poll_id
+              AND p.reveal_phase_end <= $1
+              AND p.commit_sync_completed = false
+              AND c.onchain_submitted = false
+              AND c.auto_reveal = true
+              AND c.sync_state <> 'dead'
+              AND c.reveal_expired = false
+            RETURNING c.id::BIGINT
             "#,
         )
+        .bind(now - grace - slack)
         .fetch_all(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(rows
-            .into_iter()
-            .filter_map(|r| r.try_get::<String, _>("identity_secret").ok())
-            .collect())
+        ids.sort_unstable();
+        Ok(ids)
     }
-    async fn next_poll_sequence(&self) -> Result<i64, sqlx::Error> {
-        sqlx::query_scalar("SELECT nextval(pg_get_serial_sequence('polls','id'))")
-            .fetch_one(&self.pool)
-            .await
+
+    async fn mark_commit_synced(&self, commit_id: i64, tx_hash: Option<&str>) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE commitments
+            SET onchain_submitted = true, sync_state = 'synced', reveal_tx_hash = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(commit_id)
+        .bind(tx_hash.unwrap_or_default())
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
     }
 
-    async fn insert_poll_with_members(
+    async fn mark_commit_dead(&self, commit_id: i64, error: &str) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE commitments SET sync_state = 'dead', sync_error = $2 WHERE id = $1
+            "#,
+        )
+        .bind(commit_id)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn record_sync_failure(
         &self,
-        poll_id: i64,
-        poll: NewPoll<'_>,
-        membership_root: String,
-        members: Vec<String>,
-        adjust_sequence: bool,
-    ) -> AppResult<PollRecord> {
+        commit_ids: &[i64],
+        error: &str,
+        now: DateTime<Utc>,
+        policy: &SyncRetryPolicy,
+    ) -> AppResult<Vec<i64>> {
         let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
-        let rec = sqlx::query_as::<_, DbPoll>(
+        let rows = sqlx::query(
             r#"
-            INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, commit_sync_completed)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, false)
-            ON CONFLICT (id) DO UPDATE SET
-                question = EXCLUDED.question,
-                options = EXCLUDED.options,
-                commit_phase_end = EXCLUDED.commit_phase_end,
-                reveal_phase_end = EXCLUDED.reveal_phase_end,
-                category = EXCLUDED.category,
-                membership_root = EXCLUDED.membership_root,
-                owner = EXCLUDED.owner,
-                reveal_tx_hash = EXCLUDED.reveal_tx_hash
-            RETURNING id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, commit_sync_completed
+            UPDATE commitments
+            SET sync_attempts = sync_attempts + 1, sync_error = $2
+            WHERE id = ANY($1) AND onchain_submitted = false AND sync_state <> 'dead'
+            RETURNING id::BIGINT AS id, sync_attempts
             "#,
         )
-        .bind(poll_id)
-        .bind(poll.question)
-        .bind(serde_json::to_value(poll.options).unwrap())
-        .bind(poll.commit_phase_end)
-        .bind(poll.reveal_phase_end)
-        .bind(poll.category)
-        .bind(membership_root)
-        .bind(poll.owner)
-        .bind("") // initial reveal tx hash
-        .fetch_one(&mut *tx)
+        .bind(commit_ids)
+        .bind(error)
+        .fetch_all(&mut *tx)
         .await
         .map_err(AppError::Db)?;
-
-        for m in members {
+        let mut given_up = Vec::new();
+        for row in rows {
+            let id: i64 = row.get("id");
+            let next = policy.next_attempt_at(row.get("sync_attempts"), now);
             sqlx::query(
                 r#"
-                INSERT INTO poll_members (poll_id, identity_secret)
-                VALUES ($1, $2)
-                ON CONFLICT DO NOTHING
+                UPDATE commitments SET next_attempt_at = $2, sync_state = $3 WHERE id = $1
                 "#,
             )
-            .bind(poll_id)
-            .bind(m)
+            .bind(id)
+            .bind(next)
+            .bind(match next {
+                Some(_) => CommitSyncState::Failed.as_str(),
+                None => CommitSyncState::Dead.as_str(),
+            })
             .execute(&mut *tx)
             .await
             .map_err(AppError::Db)?;
+            if next.is_none() {
+                given_up.push(id);
+            }
         }
+        tx.commit().await.map_err(AppError::Db)?;
+        given_up.sort_unstable();
+        Ok(given_up)
+    }
 
-        if adjust_sequence {
+    async fn queue_onchain_commit(&self, commit_id: i64) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE commitments
+            SET commit_state = 'queued', commit_tx_hash = '', commit_attempts = 0,
+                commit_next_attempt_at = NULL, commit_error = '', commit_confirmed_at = NULL
+            WHERE id = $1
+            "#,
+        )
+        .bind(commit_id)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn onchain_commits_to_send(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<OnchainCommitRow>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id::BIGINT AS id, poll_id, commitment
+            FROM commitments
+            WHERE commit_state IN ('queued', 'failed')
+              AND (commit_next_attempt_at IS NULL OR commit_next_attempt_at <= $1)
+            ORDER BY id
+            LIMIT $2
+            "#,
+        )
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| OnchainCommitRow {
+                id: row.get("id"),
+                poll_id: row.get("poll_id"),
+                commitment: row.get("commitment"),
+            })
+            .collect())
+    }
+
+    async fn mark_onchain_commit_confirmed(
+        &self,
+        commit: &OnchainCommitRow,
+        tx_hash: &str,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE commitments
+            SET commit_state = 'confirmed', commit_tx_hash = $3, commit_error = '',
+                commit_confirmed_at = now()
+            WHERE id = $1 AND commitment = $2 AND commit_state IN ('queued', 'failed')
+            "#,
+        )
+        .bind(commit.id)
+        .bind(&commit.commitment)
+        .bind(tx_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn record_onchain_commit_failure(
+        &self,
+        commit_ids: &[i64],
+        error: &str,
+        now: DateTime<Utc>,
+        policy: &SyncRetryPolicy,
+    ) -> AppResult<Vec<i64>> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let rows = sqlx::query(
+            r#"
+            UPDATE commitments
+            SET commit_attempts = commit_attempts + 1, commit_error = $2
+            WHERE id = ANY($1) AND commit_state IN ('queued', 'failed')
+            RETURNING id::BIGINT AS id, commit_attempts
+            "#,
+        )
+        .bind(commit_ids)
+        .bind(error)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        let mut given_up = Vec::new();
+        for row in rows {
+            let id: i64 = row.get("id");
+            let next = policy.next_attempt_at(row.get("commit_attempts"), now);
             sqlx::query(
                 r#"
-                SELECT setval(
-                    pg_get_serial_sequence('polls','id'),
-                    GREATEST($1, (SELECT COALESCE(MAX(id),0) + 1 FROM polls))
-                )
+                UPDATE commitments SET commit_next_attempt_at = $2, commit_state = $3 WHERE id = $1
                 "#,
             )
-            .bind(poll_id + 1)
-            .fetch_one(&mut *tx)
+            .bind(id)
+            .bind(next)
+            .bind(match next {
+                Some(_) => OnchainCommitState::Failed.as_str(),
+                None => OnchainCommitState::Dead.as_str(),
+            })
+            .execute(&mut *tx)
             .await
             .map_err(AppError::Db)?;
+            if next.is_none() {
+                given_up.push(id);
+            }
         }
-
         tx.commit().await.map_err(AppError::Db)?;
-        let mut record: PollRecord = rec.into();
-        record.vote_counts = vec![0; record.options.len()];
-        Ok(record)
-    }
-}
-
-#[async_trait]
-impl PollStore for PgStore {
-    async fn create_poll(&self, poll: NewPoll<'_>) -> AppResult<PollRecord> {
-        let members = self.current_members().await?;
-        let merkle = self.run_poseidon_merkle(&members).await?;
-        let computed_root = merkle.root;
-        let poll_id = self.next_poll_sequence().await.map_err(AppError::Db)?;
-        self.insert_poll_with_members(poll_id, poll, computed_root, members, false)
-            .await
+        given_up.sort_unstable();
+        Ok(given_up)
     }
 
-    async fn create_poll_with_id(
-        &self,
-        poll_id: i64,
-        poll: NewPoll<'_>,
-        membership_root: String,
-        members: Vec<String>,
-    ) -> AppResult<PollRecord> {
-        self.insert_poll_with_members(poll_id, poll, membership_root, members, true)
-            .await
+    async fn poll_has_pending_commits(&self, poll_id: i64) -> AppResult<bool> {
+        let row = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT 1 FROM commitments
+            WHERE poll_id = $1 AND onchain_submitted = false AND auto_reveal = true
+              AND sync_state <> 'dead'
+            LIMIT 1
+            "#,
+        )
+        .bind(poll_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.is_some())
     }
 
-    async fn list_polls(&self, limit: i64) -> AppResult<Vec<PollRecord>> {
-        let rows = sqlx::query_as::<_, DbPoll>(
+    async fn mark_poll_sync_complete(&self, poll_id: i64) -> AppResult<()> {
+        sqlx::query(
             r#"
-            SELECT id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, commit_sync_completed
-            FROM polls
-            ORDER BY id DESC
-            LIMIT $1
+            UPDATE polls SET commit_sync_completed = true WHERE id = $1
             "#,
         )
-        .bind(limit)
-        .fetch_all(&self.pool)
+        .bind(poll_id)
+        .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        let mut records: Vec<PollRecord> = rows.into_iter().map(Into::into).collect();
-        self.populate_vote_counts(&mut records).await?;
-        Ok(records)
+        Ok(())
     }
 
-    async fn get_poll(&self, poll_id: i64) -> AppResult<PollRecord> {
-        let rec = sqlx::query_as::<_, DbPoll>(
+    async fn commit_sync_state_counts(&self, poll_id: i64) -> AppResult<CommitSyncStateCounts> {
+        let rows = sqlx::query(
             r#"
-            SELECT id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, commit_sync_completed
-            FROM polls
-            WHERE id = $1
+            SELECT sync_state, COUNT(*)::BIGINT as count
+            FROM commitments
+            WHERE poll_id = $1 AND auto_reveal = true
+            GROUP BY sync_state
             "#,
         )
         .bind(poll_id)
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(AppError::Db)?;
-
-        match rec {
-            Some(row) => {
-                let mut record: PollRecord = row.into();
-                self.populate_vote_counts(std::slice::from_mut(&mut record))
-                    .await?;
-                Ok(record)
+        let mut counts = CommitSyncStateCounts::default();
+        for row in rows {
+            let count: i64 = row.get("count");
+            match CommitSyncState::parse(row.get("sync_state")) {
+                CommitSyncState::Pending => counts.pending += count,
+                CommitSyncState::Synced => counts.synced += count,
+                CommitSyncState::Failed => counts.failed += count,
+                CommitSyncState::Dead => counts.dead += count,
             }
-            None => Err(AppError::NotFound),
         }
+        Ok(counts)
     }
 
-    async fn record_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord> {
-        let rec = sqlx::query_as::<_, DbCommit>(
+    async fn pending_commit_count(&self) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
             r#"
-            INSERT INTO commitments (poll_id, choice, commitment, identity_secret, secret, nullifier, proof, public_inputs)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            RETURNING id, poll_id, choice, commitment, identity_secret, secret, nullifier, proof, public_inputs, recorded_at
+            SELECT COUNT(*)::BIGINT FROM commitments
+            WHERE onchain_submitted = false AND auto_reveal = true
+              AND sync_state <> 'dead' AND reveal_expired = false
             "#,
         )
-        .bind(commit.poll_id)
-        .bind(commit.choice)
-        .bind(commit.commitment)
-        .bind(commit.identity_secret)
-        .bind(commit.secret)
-        .bind(commit.nullifier)
-        .bind(commit.proof)
-        .bind(commit.public_inputs)
         .fetch_one(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(rec.into())
+        Ok(count)
     }
 
-    async fn record_vote(&self, vote: StoredVote<'_>) -> AppResult<StoredVoteRecord> {
-        if self.nullifier_used(vote.poll_id, vote.nullifier).await? {
-            return Err(AppError::Validation("nullifier already used".into()));
-        }
-        let rec = sqlx::query_as::<_, DbVote>(
+    async fn list_dead_commits(&self) -> AppResult<Vec<DeadCommitRecord>> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO votes (poll_id, nullifier, choice)
-            VALUES ($1, $2, $3)
-            RETURNING poll_id, nullifier, recorded_at
+            SELECT id::BIGINT AS id, poll_id, commitment, recorded_at, sync_attempts, sync_error
+            FROM commitments
+            WHERE sync_state = 'dead'
+            ORDER BY recorded_at, id
             "#,
         )
-        .bind(vote.poll_id)
-        .bind(vote.nullifier)
-        .bind(vote.choice as i16)
-        .fetch_one(&self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(rec.into())
-    }
-
-    async fn membership_root_snapshot(&self) -> AppResult<String> {
-        let members = self.current_members().await?;
-        let merkle = self.run_poseidon_merkle(&members).await?;
-        Ok(merkle.root)
-    }
-
-    async fn list_members(&self) -> AppResult<Vec<String>> {
-        self.current_members().await
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let sync_error: String = row.get("sync_error");
+                DeadCommitRecord {
+                    id: row.get("id"),
+                    poll_id: row.get("poll_id"),
+                    commitment: row.get("commitment"),
+                    recorded_at: row.get("recorded_at"),
+                    sync_attempts: row.get("sync_attempts"),
+                    sync_error: (!sync_error.is_empty()).then_some(sync_error),
+                }
+            })
+            .collect())
     }
 
-    async fn merkle_path_for_member(
-        &self,
-        poll_id: i64,
-        identity_secret: &str,
-    ) -> AppResult<Option<MerklePath>> {
-        let members = self.poll_member_list(poll_id).await?;
-        if members.is_empty() {
-            return Ok(None);
-        }
-        if !members.iter().any(|m| m == identity_secret) {
-            return Ok(None);
-        }
-        let merkle = self.run_poseidon_merkle(&members).await?;
-        Ok(merkle.paths.get(identity_secret).cloned())
+    async fn requeue_dead_commit(&self, commit_id: i64) -> AppResult<i64> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let poll_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            UPDATE commitments
+            SET sync_state = 'pending', sync_attempts = 0, next_attempt_at = NULL,
+                sync_error = '', reveal_expired = false
+            WHERE id = $1 AND sync_state = 'dead'
+            RETURNING poll_id
+            "#,
+        )
+        .bind(commit_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::Db)?
+        .ok_or(AppError::NotFound)?;
+        sqlx::query(
+            r#"
+            UPDATE polls SET commit_sync_completed = false WHERE id = $1
+            "#,
+        )
+        .bind(poll_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        tx.commit().await.map_err(AppError::Db)?;
+        Ok(poll_id)
     }
 
-    async fn ensure_member(&self, username: &str, identity_secret: &str) -> AppResult<()> {
+    async fn set_reveal_tx_hash(&self, poll_id: i64, tx: &str) -> AppResult<()> {
         sqlx::query(
             r#"
-            INSERT INTO members (identity_secret)
-            VALUES ($1)
-            ON CONFLICT (identity_secret) DO NOTHING
+            UPDATE polls SET reveal_tx_hash = $2, commit_sync_completed = true WHERE id = $1
             "#,
         )
-        .bind(identity_secret)
+        .bind(poll_id)
+        .bind(tx)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
+        Ok(())
+    }
 
+    async fn record_reveal_batch(&self, batch: NewRevealBatch<'_>) -> AppResult<()> {
         sqlx::query(
             r#"
-            INSERT INTO user_stats (identity_secret, username)
-            VALUES ($1, $2)
-            ON CONFLICT (identity_secret)
-            DO UPDATE SET username = EXCLUDED.username, updated_at = now()
+            INSERT INTO reveal_batches
+                (poll_id, tx_hash, item_count, gas_used, effective_gas_price, commit_ids)
+            VALUES ($1, $2, $3, $4, $5, $6)
             "#,
         )
-        .bind(identity_secret)
-        .bind(username)
+        .bind(batch.poll_id)
+        .bind(batch.tx_hash.unwrap_or_default())
+        .bind(batch.commit_ids.len() as i32)
+        .bind(batch.gas_used.map(|gas| gas as i64))
+        .bind(batch.effective_gas_price.map(|price| price as i64))
+        .bind(batch.commit_ids)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
         Ok(())
     }
 
-    async fn poll_includes_member(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool> {
-        let row = sqlx::query_scalar::<_, i32>(
+    async fn list_reveal_batches(&self, poll_id: i64) -> AppResult<Vec<RevealBatchRecord>> {
+        let rows = sqlx::query_as::<_, RevealBatchRecord>(
             r#"
-            SELECT 1 FROM poll_members WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1
+            SELECT poll_id, tx_hash, item_count, submitted_at, gas_used, effective_gas_price,
+                   commit_ids
+            FROM reveal_batches
+            WHERE poll_id = $1
+            ORDER BY submitted_at, id
             "#,
         )
         .bind(poll_id)
-        .bind(identity_secret)
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(row.is_some())
+        Ok(rows)
     }
 
-    async fn nullifier_used(&self, poll_id: i64, nullifier: &str) -> AppResult<bool> {
-        let row = sqlx::query_scalar::<_, i32>(
+    async fn reveal_cost(&self, poll_id: i64) -> AppResult<RevealCostRecord> {
+        let row = sqlx::query(
             r#"
-            SELECT 1 FROM votes WHERE poll_id = $1 AND nullifier = $2 LIMIT 1
+            SELECT COUNT(*)::BIGINT AS batch_count,
+                   COALESCE(SUM(item_count), 0)::BIGINT AS item_count,
+                   COALESCE(SUM(gas_used), 0)::BIGINT AS gas_used,
+                   COALESCE(SUM(gas_used::NUMERIC * effective_gas_price), 0)::TEXT AS cost_wei
+            FROM reveal_batches
+            WHERE poll_id = $1 AND gas_used IS NOT NULL AND effective_gas_price IS NOT NULL
             "#,
         )
         .bind(poll_id)
-        .bind(nullifier)
-        .fetch_optional(&self.pool)
+        .fetch_one(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(row.is_some())
+        let cost_wei: String = row.get("cost_wei");
+        Ok(RevealCostRecord {
+            batch_count: row.get("batch_count"),
+            item_count: row.get("item_count"),
+            gas_used: row.get("gas_used"),
+            cost_wei: cost_wei.parse().map_err(|_| {
+                AppError::External(format!("reveal cost {cost_wei:?} is not a wei amount"))
+            })?,
+        })
     }
 
-    async fn has_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool> {
-        let row = sqlx::query_scalar::<_, i32>(
+    async fn list_poll_transactions(&self, poll_id: i64) -> AppResult<Vec<PollTransactionRecord>> {
+        // Legacy reveals are the commitment and poll reveal_tx_hash values no batch row covers.
+        let rows = sqlx::query(
             r#"
-            SELECT 1 FROM commitments WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1
+            SELECT 'create' AS kind, i.tx_hash, p.created_at AS at, 'confirmed' AS status,
+                   NULL::INTEGER AS item_count
+            FROM pending_onchain_polls i
+            JOIN polls p ON p.id = i.poll_id
+            WHERE i.poll_id = $1 AND i.state IN ('sent', 'stored') AND i.tx_hash IS NOT NULL
+            UNION ALL
+            SELECT 'commit', commit_tx_hash, commit_confirmed_at, 'confirmed', NULL
+            FROM commitments
+            WHERE poll_id = $1 AND commit_state = 'confirmed' AND commit_tx_hash <> ''
+            UNION ALL
+            SELECT 'reveal_batch', tx_hash, submitted_at,
+                   CASE WHEN gas_used IS NULL THEN 'indexed' ELSE 'confirmed' END, item_count
+            FROM reveal_batches
+            WHERE poll_id = $1 AND tx_hash <> ''
+            UNION ALL
+            SELECT 'reveal_batch', c.reveal_tx_hash, NULL, 'confirmed', COUNT(*)::INTEGER
+            FROM commitments c
+            WHERE c.poll_id = $1 AND c.reveal_tx_hash <> ''
+              AND NOT EXISTS (
+                  SELECT 1 FROM reveal_batches b
+                  WHERE b.poll_id = $1 AND b.tx_hash = c.reveal_tx_hash
+              )
+            GROUP BY c.reveal_tx_hash
+            UNION ALL
+            SELECT 'reveal_batch', p.reveal_tx_hash, NULL, 'confirmed', NULL
+            FROM polls p
+            WHERE p.id = $1 AND p.reveal_tx_hash <> ''
+              AND NOT EXISTS (
+                  SELECT 1 FROM reveal_batches b
+                  WHERE b.poll_id = $1 AND b.tx_hash = p.reveal_tx_hash
+              )
+              AND NOT EXISTS (
+                  SELECT 1 FROM commitments c
+                  WHERE c.poll_id = $1 AND c.reveal_tx_hash = p.reveal_tx_hash
+              )
+            UNION ALL
+            SELECT 'resolve', resolve_tx_hash, resolved_at, 'indexed', NULL
+            FROM polls
+            WHERE id = $1 AND resolve_tx_hash IS NOT NULL
             "#,
         )
         .bind(poll_id)
-        .bind(identity_secret)
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(row.is_some())
+        let mut txs = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(PollTransactionRecord {
+                    kind: PollTransactionKind::parse(row.get("kind"))?,
+                    tx_hash: row.get("tx_hash"),
+                    timestamp: row.get("at"),
+                    status: PollTransactionStatus::parse(row.get("status")),
+                    item_count: row.get("item_count"),
+                })
+            })
+            .collect::<Vec<_>>();
+        sort_poll_transactions(&mut txs);
+        Ok(txs)
     }
 
-    async fn get_or_create_secret(&self, poll_id: i64, identity_secret: &str) -> AppResult<String> {
-        if let Some(existing) = sqlx::query_scalar::<_, String>(
-            r#"SELECT secret FROM poll_secrets WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1"#,
+    async fn list_commits_redacted(&self, poll_id: i64) -> AppResult<Vec<RedactedCommitRecord>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT identity_secret, recorded_at, onchain_submitted, sync_state, sync_attempts,
+                   sync_error, commit_state, commit_tx_hash, commit_error
+            FROM commitments
+            WHERE poll_id = $1
+            ORDER BY recorded_at, id
+            "#,
         )
         .bind(poll_id)
-        .bind(identity_secret)
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await
-        .map_err(AppError::Db)?
-        {
-            return Ok(existing);
-        }
+        .map_err(AppError::Db)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let sync_error: String = row.get("sync_error");
+                let commit_tx_hash: String = row.get("commit_tx_hash");
+                let commit_error: String = row.get("commit_error");
+                RedactedCommitRecord {
+                    pseudonym: commit_pseudonym(poll_id, row.get("identity_secret")),
+                    recorded_at: row.get("recorded_at"),
+                    onchain_submitted: row.get("onchain_submitted"),
+                    sync_state: CommitSyncState::parse(row.get("sync_state")),
+                    sync_attempts: row.get("sync_attempts"),
+                    sync_error: (!sync_error.is_empty()).then_some(sync_error),
+                    commit_state: OnchainCommitState::parse(row.get("commit_state")),
+                    commit_tx_hash: (!commit_tx_hash.is_empty()).then_some(commit_tx_hash),
+                    commit_error: (!commit_error.is_empty()).then_some(commit_error),
+                }
+            })
+            .collect())
+    }
 
-        let secret = generate_secret();
-        sqlx::query(
+    async fn vote_source_counts(&self, poll_id: i64) -> AppResult<VoteSourceCounts> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO poll_secrets (poll_id, identity_secret, secret)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (poll_id, identity_secret) DO NOTHING
+            SELECT source, COUNT(*)::BIGINT as count FROM votes WHERE poll_id = $1 GROUP BY source
             "#,
         )
         .bind(poll_id)
-        .bind(identity_secret)
-        .bind(&secret)
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(AppError::Db)?;
+        let mut counts = VoteSourceCounts::default();
+        for row in rows {
+            let count: i64 = row.get("count");
+            match VoteSource::parse(row.get("source")) {
+                VoteSource::Api => counts.api += count,
+                VoteSource::Relayer => counts.relayer += count,
+                VoteSource::Chain => counts.chain += count,
+            }
+        }
+        Ok(counts)
+    }
 
-        let saved = sqlx::query_scalar::<_, String>(
-            r#"SELECT secret FROM poll_secrets WHERE poll_id = $1 AND identity_secret = $2 LIMIT 1"#,
+    async fn create_proof_job(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<ProofJobRecord> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO proof_jobs (id, poll_id, identity_secret)
+            VALUES ($1, $2, $3)
+            RETURNING id, poll_id, identity_secret, status, bundle, error, created_at, updated_at
+            "#,
         )
+        .bind(Uuid::new_v4())
         .bind(poll_id)
         .bind(identity_secret)
         .fetch_one(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(saved)
+        proof_job_from_row(&row)
     }
 
-    async fn resolve_poll(&self, poll_id: i64, correct_option: u8) -> AppResult<PollRecord> {
-        let rec = sqlx::query_as::<_, DbPoll>(
+    async fn get_proof_job(&self, job_id: Uuid) -> AppResult<ProofJobRecord> {
+        let row = sqlx::query(
             r#"
-            UPDATE polls
-            SET resolved = true, correct_option = $2
+            SELECT id, poll_id, identity_secret, status, bundle, error, created_at, updated_at
+            FROM proof_jobs
             WHERE id = $1
-            RETURNING id, question, options, commit_phase_end, reveal_phase_end, category, membership_root, owner, reveal_tx_hash, correct_option, resolved, commit_sync_completed
             "#,
         )
-        .bind(poll_id)
-        .bind(correct_option as i16)
-        .fetch_one(&self.pool)
+        .bind(job_id)
+        .fetch_optional(&self.pool)
         .await
-        .map_err(AppError::Db)?;
-        let mut record: PollRecord = rec.into();
-        self.apply_poll_results(poll_id, correct_option).await?;
-        self.populate_vote_counts(std::slice::from_mut(&mut record))
-            .await?;
-        Ok(record)
+        .map_err(AppError::Db)?
+        .ok_or(AppError::NotFound)?;
+        proof_job_from_row(&row)
     }
 
-    async fn commits_to_sync(
-        &self,
-        now: DateTime<Utc>,
-        limit: i64,
-    ) -> AppResult<Vec<CommitSyncRow>> {
-        let rows = sqlx::query_as::<_, CommitSyncRow>(
+    async fn start_proof_job(&self, job_id: Uuid) -> AppResult<()> {
+        sqlx::query("UPDATE proof_jobs SET status = 'running', updated_at = now() WHERE id = $1")
+            .bind(job_id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn complete_proof_job(&self, job_id: Uuid, bundle: &ProofBundle) -> AppResult<()> {
+        let bundle =
+            serde_json::to_string(bundle).map_err(|e| AppError::External(e.to_string()))?;
+        sqlx::query(
             r#"
-            SELECT c.id::BIGINT as id, c.poll_id, c.choice, c.commitment, c.secret, c.nullifier, c.proof, c.public_inputs
-            FROM commitments c
-            JOIN polls p ON p.id = c.poll_id
-            WHERE p.commit_phase_end <= $1
-              AND p.reveal_phase_end > $1
-              AND p.commit_sync_completed = false
-              AND c.onchain_submitted = false
-            ORDER BY c.id
-            LIMIT $2
+            UPDATE proof_jobs SET status = 'done', bundle = $2, updated_at = now()
+            WHERE id = $1
             "#,
         )
-        .bind(now)
-        .bind(limit)
-        .fetch_all(&self.pool)
+        .bind(job_id)
+        .bind(bundle)
+        .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(rows)
+        Ok(())
     }
 
-    async fn mark_commit_synced(&self, commit_id: i64) -> AppResult<()> {
+    async fn fail_proof_job(&self, job_id: Uuid, error: &str) -> AppResult<()> {
         sqlx::query(
             r#"
-            UPDATE commitments SET onchain_submitted = true WHERE id = $1
+            UPDATE proof_jobs SET status = 'failed', error = $2, updated_at = now()
+            WHERE id = $1
             "#,
         )
-        .bind(commit_id)
+        .bind(job_id)
+        .bind(error)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
         Ok(())
     }
 
-    async fn poll_has_pending_commits(&self, poll_id: i64) -> AppResult<bool> {
-        let row = sqlx::query_scalar::<_, i32>(
+    async fn fail_orphaned_proof_jobs(&self) -> AppResult<u64> {
+        let res = sqlx::query(
+            r#"
+            UPDATE proof_jobs
+            SET status = 'failed', error = $1, updated_at = now()
+            WHERE status IN ('pending', 'running')
+            "#,
+        )
+        .bind(ORPHANED_PROOF_JOB_ERROR)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(res.rows_affected())
+    }
+
+    async fn cached_proof(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+        request_hash: &str,
+    ) -> AppResult<Option<ProofBundle>> {
+        let bundle: Option<String> = sqlx::query_scalar(
             r#"
-            SELECT 1 FROM commitments WHERE poll_id = $1 AND onchain_submitted = false LIMIT 1
+            SELECT bundle FROM proof_cache
+            WHERE poll_id = $1 AND identity_secret = $2 AND request_hash = $3
             "#,
         )
         .bind(poll_id)
+        .bind(identity_secret)
+        .bind(request_hash)
         .fetch_optional(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(row.is_some())
+        bundle
+            .map(|raw| serde_json::from_str(&raw))
+            .transpose()
+            .map_err(|e| AppError::External(format!("cached proof bundle: {e}")))
     }
 
-    async fn mark_poll_sync_complete(&self, poll_id: i64) -> AppResult<()> {
+    async fn cache_proof(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+        request_hash: &str,
+        bundle: &ProofBundle,
+    ) -> AppResult<()> {
+        let bundle =
+            serde_json::to_string(bundle).map_err(|e| AppError::External(e.to_string()))?;
         sqlx::query(
             r#"
-            UPDATE polls SET commit_sync_completed = true WHERE id = $1
+            INSERT INTO proof_cache (poll_id, identity_secret, request_hash, bundle)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (poll_id, identity_secret)
+            DO UPDATE SET request_hash = EXCLUDED.request_hash, bundle = EXCLUDED.bundle,
+                created_at = now()
             "#,
         )
         .bind(poll_id)
+        .bind(identity_secret)
+        .bind(request_hash)
+        .bind(bundle)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
         Ok(())
     }
 
-    async fn set_reveal_tx_hash(&self, poll_id: i64, tx: &str) -> AppResult<()> {
-        sqlx::query(
+    async fn invalidate_cached_proof(&self, poll_id: i64, identity_secret: &str) -> AppResult<()> {
+        sqlx::query("DELETE FROM proof_cache WHERE poll_id = $1 AND identity_secret = $2")
+            .bind(poll_id)
+            .bind(identity_secret)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn list_vote_conflicts(&self, poll_id: i64) -> AppResult<Vec<VoteConflictRecord>> {
+        let rows = sqlx::query(
             r#"
-            UPDATE polls SET reveal_tx_hash = $2, commit_sync_completed = true WHERE id = $1
+            SELECT nullifier, kept_choice, kept_source, rejected_choice, rejected_source, detected_at
+            FROM vote_conflicts
+            WHERE poll_id = $1
+            ORDER BY detected_at, id
             "#,
         )
         .bind(poll_id)
-        .bind(tx)
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        Ok(())
+        Ok(rows
+            .into_iter()
+            .map(|row| VoteConflictRecord {
+                poll_id,
+                nullifier: row.get("nullifier"),
+                kept_choice: row.get("kept_choice"),
+                kept_source: VoteSource::parse(row.get("kept_source")),
+                rejected_choice: row.get("rejected_choice"),
+                rejected_source: VoteSource::parse(row.get("rejected_source")),
+                detected_at: row.get("detected_at"),
+            })
+            .collect())
     }
 
-    async fn mark_polls_without_pending_commits(&self, now: DateTime<Utc>) -> AppResult<()> {
+    async fn mark_polls_without_pending_commits(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+    ) -> AppResult<()> {
         sqlx::query(
             r#"
             UPDATE polls
             SET commit_sync_completed = true
             WHERE commit_phase_end <= $1
               AND commit_sync_completed = false
-              AND NOT EXISTS (
-                    SELECT 1 FROM commitments c
-                    WHERE c.poll_id = polls.id
-                      AND c.onchain_submitted = false
+              AND (
+                    reveal_phase_end <= $2
+                    OR NOT EXISTS (
+                        SELECT 1 FROM commitments c
+                        WHERE c.poll_id = polls.id
+                          AND c.onchain_submitted = false
+                          AND c.auto_reveal = true
+                          AND c.sync_state <> 'dead'
+                    )
                 )
             "#,
         )
         .bind(now)
+        .bind(now - grace)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
         Ok(())
     }
 
+    async fn encrypt_stored_secrets(&self) -> AppResult<u64> {
+        let Some(cipher) = self.cipher.clone() else {
+            return Err(AppError::Validation(
+                "FIELD_ENCRYPTION_KEYS is not configured".into(),
+            ));
+        };
+        let mut rewritten = 0;
+        let commits = sqlx::query(
+            r#"SELECT id::BIGINT AS id, choice, choice_enc, secret, public_inputs FROM commitments"#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        for row in commits {
+            let choice_enc: String = row.get("choice_enc");
+            let secret: String = row.get("secret");
+            let public_inputs: Vec<String> = row.get("public_inputs");
+            // Scrubbed rows have no inputs left to seal.
+            let inputs_current = public_inputs
+                .first()
+                .is_none_or(|choice| cipher.is_current(choice));
+            if cipher.is_current(&choice_enc) && cipher.is_current(&secret) && inputs_current {
+                continue;
+            }
+            let choice = self.open_choice(row.get("choice"), &choice_enc)?;
+            let public_inputs = self.open_public_inputs(public_inputs)?;
+            sqlx::query(
+                r#"UPDATE commitments SET choice = -1, choice_enc = $2, secret = $3, public_inputs = $4 WHERE id = $1"#,
+            )
+            .bind(row.get::<i64, _>("id"))
+            .bind(cipher.encrypt(&choice.to_string())?)
+            .bind(cipher.encrypt(&self.open(&secret)?)?)
+            .bind(self.seal_public_inputs(&public_inputs)?)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+            rewritten += 1;
+        }
+        let secrets = sqlx::query(r#"SELECT poll_id, identity_secret, secret FROM poll_secrets"#)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+        for row in secrets {
+            let secret: String = row.get("secret");
+            if cipher.is_current(&secret) {
+                continue;
+            }
+            sqlx::query(
+                r#"UPDATE poll_secrets SET secret = $3 WHERE poll_id = $1 AND identity_secret = $2"#,
+            )
+            .bind(row.get::<i64, _>("poll_id"))
+            .bind(row.get::<String, _>("identity_secret"))
+            .bind(cipher.encrypt(&self.open(&secret)?)?)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+            rewritten += 1;
+        }
+        Ok(rewritten)
+    }
+
     async fn backfill_user_stats(&self) -> AppResult<()> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
         sqlx::query(
             r#"
             UPDATE user_stats
@@ -869,23 +4305,28 @@ impl PollStore for PgStore {
             "#,
         )
         .bind(tier_for_xp(0))
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
         .map_err(AppError::Db)?;
+        sqlx::query(r#"UPDATE polls SET results_applied_at = NULL"#)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?;
 
         let polls = sqlx::query(
-            r#"SELECT id, correct_option FROM polls WHERE resolved = true AND correct_option IS NOT NULL"#,
+            r#"SELECT id, correct_option FROM polls WHERE resolved = true AND (correct_option IS NOT NULL OR voided)"#,
         )
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *tx)
         .await
         .map_err(AppError::Db)?;
 
         for row in polls {
             let poll_id: i64 = row.get("id");
-            let correct: i16 = row.get("correct_option");
-            self.apply_poll_results(poll_id, correct as u8).await?;
+            let correct: Option<i16> = row.get("correct_option");
+            self.apply_poll_results(&mut tx, poll_id, correct.map(|c| c as u8))
+                .await?;
         }
-        Ok(())
+        tx.commit().await.map_err(AppError::Db)
     }
 
     async fn user_stats(&self, identity_secret: &str) -> AppResult<UserStatsRecord> {
@@ -949,18 +4390,24 @@ impl PollStore for PgStore {
 
 #[async_trait]
 impl PollIndexSink for PgStore {
-    async fn upsert_poll_from_chain(&self, poll_id: i64, poll: NewPoll<'_>) -> AppResult<()> {
+    async fn upsert_poll_from_chain(
+        &self,
+        poll_id: i64,
+        poll: NewPoll<'_>,
+        created_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
         sqlx::query(
             r#"
-            INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root, category, owner, resolved)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, false)
+            INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root, category, owner, visibility, resolved, indexed, membership_hash_scheme, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, false, true, $10, COALESCE($11, now()))
             ON CONFLICT (id) DO UPDATE SET
               question = EXCLUDED.question,
               options = EXCLUDED.options,
               commit_phase_end = EXCLUDED.commit_phase_end,
               reveal_phase_end = EXCLUDED.reveal_phase_end,
               membership_root = EXCLUDED.membership_root,
-              category = EXCLUDED.category
+              category = EXCLUDED.category,
+              indexed = true
             "#,
         )
         .bind(poll_id)
@@ -971,6 +4418,9 @@ impl PollIndexSink for PgStore {
         .bind(poll.membership_root)
         .bind(poll.category)
         .bind(poll.owner)
+        .bind(poll.visibility)
+        .bind(self.hash_scheme.as_str())
+        .bind(created_at)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
@@ -983,36 +4433,352 @@ impl PollIndexSink for PgStore {
         nullifier: &str,
         choice: u8,
     ) -> AppResult<()> {
-        sqlx::query(
+        let relayed = sqlx::query_scalar::<_, i32>(
             r#"
-            INSERT INTO votes (poll_id, nullifier, choice)
-            VALUES ($1, $2, $3)
+            SELECT 1 FROM commitments
+            WHERE poll_id = $1 AND nullifier = $2 AND onchain_submitted
+            LIMIT 1
+            "#,
+        )
+        .bind(poll_id)
+        .bind(nullifier)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?
+        .is_some();
+        let vote = StoredVote {
+            poll_id,
+            nullifier,
+            choice,
+            source: if relayed {
+                VoteSource::Relayer
+            } else {
+                VoteSource::Chain
+            },
+        };
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO votes (poll_id, nullifier, choice, source)
+            VALUES ($1, $2, $3, $4)
             ON CONFLICT (poll_id, nullifier) DO NOTHING
             "#,
         )
         .bind(poll_id)
         .bind(nullifier)
         .bind(choice as i16)
+        .bind(vote.source.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(missing_poll)?
+        .rows_affected();
+        if inserted == 0 {
+            self.note_vote_conflict(vote).await?;
+        }
+        Ok(())
+    }
+
+    async fn resolve_poll_from_chain(
+        &self,
+        poll_id: i64,
+        correct_option: u8,
+        tx_hash: Option<&str>,
+    ) -> AppResult<()> {
+        self.retry_transaction("chain poll resolution", || async {
+            let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+            let updated = sqlx::query(
+                r#"
+                UPDATE polls
+                SET resolved = true, correct_option = $2,
+                    resolve_tx_hash = COALESCE($3, resolve_tx_hash), resolved_at = now()
+                WHERE id = $1
+                "#,
+            )
+            .bind(poll_id)
+            .bind(correct_option as i16)
+            .bind(tx_hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?
+            .rows_affected();
+            if updated == 0 {
+                return Err(AppError::NotFound);
+            }
+            self.apply_poll_results(&mut tx, poll_id, Some(correct_option))
+                .await?;
+            tx.commit().await.map_err(AppError::Db)
+        })
+        .await
+    }
+
+    async fn upsert_commit_from_chain(&self, poll_id: i64, commitment: &str) -> AppResult<()> {
+        // The commitment stands in for the identity, as it does for legacy rows, to keep
+        // commitments_poll_identity_idx unique.
+        sqlx::query(
+            r#"
+            INSERT INTO commitments (poll_id, commitment, identity_secret, onchain_submitted, auto_reveal, source, sync_state)
+            SELECT $1, $2, $2, true, false, 'chain', 'synced'
+            WHERE NOT EXISTS (
+                SELECT 1 FROM commitments WHERE poll_id = $1 AND commitment = $2
+            )
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(poll_id)
+        .bind(commitment)
+        .execute(&self.pool)
+        .await
+        .map_err(missing_poll)?;
+        Ok(())
+    }
+
+    async fn confirm_reveal_from_chain(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        tx_hash: &str,
+    ) -> AppResult<()> {
+        let mut tx = self.pool.begin().await.map_err(AppError::Db)?;
+        let confirmed = sqlx::query_scalar::<_, i64>(
+            r#"
+            UPDATE commitments
+            SET onchain_submitted = true, sync_state = 'synced', reveal_tx_hash = $3
+            WHERE poll_id = $1 AND nullifier = $2 AND nullifier <> '' AND reveal_tx_hash <> $3
+            RETURNING id::BIGINT
+            "#,
+        )
+        .bind(poll_id)
+        .bind(nullifier)
+        .bind(tx_hash)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::Db)?;
+        if !confirmed.is_empty() {
+            let counted = sqlx::query(
+                r#"
+                UPDATE reveal_batches
+                SET item_count = item_count + $3, commit_ids = commit_ids || $4
+                WHERE poll_id = $1 AND tx_hash = $2
+                "#,
+            )
+            .bind(poll_id)
+            .bind(tx_hash)
+            .bind(confirmed.len() as i32)
+            .bind(&confirmed)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Db)?
+            .rows_affected();
+            if counted == 0 {
+                sqlx::query(
+                    r#"
+                    INSERT INTO reveal_batches (poll_id, tx_hash, item_count, commit_ids)
+                    VALUES ($1, $2, $3, $4)
+                    "#,
+                )
+                .bind(poll_id)
+                .bind(tx_hash)
+                .bind(confirmed.len() as i32)
+                .bind(&confirmed)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Db)?;
+            }
+        }
+        tx.commit().await.map_err(AppError::Db)
+    }
+
+    async fn log_processed(&self, tx_hash: &str, log_index: u64) -> AppResult<bool> {
+        let row = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT 1 FROM processed_logs WHERE tx_hash = $1 AND log_index = $2
+            "#,
+        )
+        .bind(tx_hash)
+        .bind(log_index as i64)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.is_some())
+    }
+
+    async fn record_processed_log(
+        &self,
+        tx_hash: &str,
+        log_index: u64,
+        block: u64,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO processed_logs (tx_hash, log_index, block_number)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (tx_hash, log_index) DO NOTHING
+            "#,
+        )
+        .bind(tx_hash)
+        .bind(log_index as i64)
+        .bind(block as i64)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
         Ok(())
     }
 
-    async fn resolve_poll_from_chain(&self, poll_id: i64, correct_option: u8) -> AppResult<()> {
+    async fn prune_processed_logs(&self, block: u64) -> AppResult<u64> {
+        let pruned = sqlx::query(r#"DELETE FROM processed_logs WHERE block_number < $1"#)
+            .bind(block as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Db)?
+            .rows_affected();
+        Ok(pruned)
+    }
+
+    async fn record_failed_event(
+        &self,
+        log: &serde_json::Value,
+        sender: Option<&str>,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
         sqlx::query(
             r#"
-            UPDATE polls
-            SET resolved = true, correct_option = $2
+            INSERT INTO failed_events (log, sender, error, attempts, next_retry_at, dead)
+            VALUES ($1, $2, $3, 1, COALESCE($4, now()), $4 IS NULL)
+            "#,
+        )
+        .bind(log)
+        .bind(sender)
+        .bind(error)
+        .bind(next_retry_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn due_failed_events(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<FailedEventRecord>> {
+        sqlx::query_as::<_, FailedEventRecord>(
+            r#"
+            SELECT id, log, sender, error, attempts, next_retry_at, dead
+            FROM failed_events
+            WHERE NOT dead AND next_retry_at <= $1
+            ORDER BY next_retry_at, id
+            LIMIT $2
+            "#,
+        )
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::Db)
+    }
+
+    async fn reschedule_failed_event(
+        &self,
+        id: i64,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE failed_events
+            SET error = $2,
+                attempts = attempts + 1,
+                next_retry_at = COALESCE($3, next_retry_at),
+                dead = $3 IS NULL,
+                updated_at = now()
             WHERE id = $1
             "#,
         )
-        .bind(poll_id)
-        .bind(correct_option as i16)
+        .bind(id)
+        .bind(error)
+        .bind(next_retry_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn delete_failed_event(&self, id: i64) -> AppResult<()> {
+        sqlx::query(r#"DELETE FROM failed_events WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::Db)?;
+        Ok(())
+    }
+
+    async fn failed_event_counts(&self) -> AppResult<(u64, u64)> {
+        let (waiting, dead): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FILTER (WHERE NOT dead), COUNT(*) FILTER (WHERE dead)
+            FROM failed_events
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok((waiting as u64, dead as u64))
+    }
+
+    async fn requeue_dead_events(&self, now: DateTime<Utc>) -> AppResult<u64> {
+        let requeued = sqlx::query(
+            r#"
+            UPDATE failed_events
+            SET dead = false, attempts = 0, next_retry_at = $1, updated_at = now()
+            WHERE dead
+            "#,
+        )
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::Db)?
+        .rows_affected();
+        Ok(requeued)
+    }
+
+    async fn load_checkpoint(&self, contract: &str) -> AppResult<Option<IndexerCheckpoint>> {
+        let row: Option<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT last_block, last_log_index FROM indexer_state
+            WHERE contract_address = $1
+            "#,
+        )
+        .bind(contract.to_ascii_lowercase())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::Db)?;
+        Ok(row.map(|(block, log_index)| IndexerCheckpoint {
+            block: block as u64,
+            log_index: log_index as u64,
+        }))
+    }
+
+    async fn save_checkpoint(
+        &self,
+        contract: &str,
+        checkpoint: IndexerCheckpoint,
+    ) -> AppResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO indexer_state (contract_address, last_block, last_log_index, updated_at)
+            VALUES ($1, $2, $3, now())
+            ON CONFLICT (contract_address) DO UPDATE
+            SET last_block = EXCLUDED.last_block,
+                last_log_index = EXCLUDED.last_log_index,
+                updated_at = now()
+            "#,
+        )
+        .bind(contract.to_ascii_lowercase())
+        .bind(checkpoint.block as i64)
+        .bind(checkpoint.log_index as i64)
         .execute(&self.pool)
         .await
         .map_err(AppError::Db)?;
-        self.apply_poll_results(poll_id, correct_option).await?;
         Ok(())
     }
 }
@@ -1030,7 +4796,15 @@ struct DbPoll {
     reveal_tx_hash: String,
     correct_option: Option<i16>,
     resolved: bool,
+    voided: bool,
     commit_sync_completed: bool,
+    auto_resolve: bool,
+    visibility: String,
+    anonymous_owner: bool,
+    option_meta: serde_json::Value,
+    membership_hash_scheme: String,
+    created_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
 }
 
 impl From<DbPoll> for PollRecord {
@@ -1040,16 +4814,26 @@ impl From<DbPoll> for PollRecord {
             id: value.id,
             question: value.question,
             options: opts,
+            option_meta: serde_json::from_value(value.option_meta).unwrap_or_default(),
             commit_phase_end: value.commit_phase_end,
             reveal_phase_end: value.reveal_phase_end,
             category: value.category,
             membership_root: value.membership_root,
+            membership_hash_scheme: value.membership_hash_scheme.parse().unwrap_or_default(),
             owner: value.owner,
+            anonymous_owner: value.anonymous_owner,
             reveal_tx_hash: value.reveal_tx_hash,
             correct_option: value.correct_option,
             resolved: value.resolved,
+            voided: value.voided,
             commit_sync_completed: value.commit_sync_completed,
+            auto_resolve: value.auto_resolve,
+            visibility: value.visibility,
+            created_at: value.created_at,
+            deleted_at: value.deleted_at,
             vote_counts: Vec::new(),
+            commit_count: 0,
+            revealed_count: 0,
         }
     }
 }
@@ -1059,6 +4843,7 @@ struct DbCommit {
     id: i32,
     poll_id: i64,
     choice: i16,
+    choice_enc: String,
     commitment: String,
     recorded_at: DateTime<Utc>,
     identity_secret: String,
@@ -1066,30 +4851,95 @@ struct DbCommit {
     nullifier: String,
     proof: String,
     public_inputs: Vec<String>,
+    proof_version: i32,
+    revision: i32,
+    auto_reveal: bool,
+    scrubbed_at: Option<DateTime<Utc>>,
 }
 
-impl From<DbCommit> for StoredCommitRecord {
-    fn from(value: DbCommit) -> Self {
-        StoredCommitRecord {
-            id: value.id as i64,
-            poll_id: value.poll_id,
-            choice: value.choice,
-            commitment: value.commitment,
-            recorded_at: value.recorded_at,
-            identity_secret: value.identity_secret,
-            secret: value.secret,
-            nullifier: value.nullifier,
-            proof: value.proof,
-            public_inputs: value.public_inputs,
-        }
-    }
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct DbCommitSyncRow {
+    id: i64,
+    poll_id: i64,
+    choice: i16,
+    choice_enc: String,
+    commitment: String,
+    secret: String,
+    nullifier: String,
+    proof: String,
+    public_inputs: Vec<String>,
+    proof_version: i32,
+}
+
+fn proof_version_from_db(value: i32) -> AppResult<u16> {
+    u16::try_from(value).map_err(|_| AppError::External(format!("corrupt proof version {value}")))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 struct DbVote {
     poll_id: i64,
     nullifier: String,
+    choice: i16,
     recorded_at: DateTime<Utc>,
+    source: String,
+}
+
+fn membership_tree_from_row(row: &sqlx::postgres::PgRow) -> AppResult<IncrementalTree> {
+    let filled: serde_json::Value = row.get("filled_subtrees");
+    Ok(IncrementalTree {
+        leaf_count: row.get::<i64, _>("leaf_count") as u64,
+        filled_subtrees: serde_json::from_value(filled)
+            .map_err(|e| AppError::External(format!("stored membership tree: {e}")))?,
+        root: row.get("root"),
+        scheme: row.get::<String, _>("scheme").parse()?,
+    })
+}
+
+/// The `membership_hash_scheme` a poll row recorded.
+fn stored_scheme(row: &sqlx::postgres::PgRow) -> AppResult<HashScheme> {
+    row.get::<String, _>("membership_hash_scheme").parse()
+}
+
+async fn save_membership_tree(
+    conn: &mut sqlx::PgConnection,
+    tree: &IncrementalTree,
+) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE membership_tree
+        SET leaf_count = $1, filled_subtrees = $2, root = $3, scheme = $4, updated_at = now()
+        WHERE id
+        "#,
+    )
+    .bind(tree.leaf_count as i64)
+    .bind(
+        serde_json::to_value(&tree.filled_subtrees)
+            .map_err(|e| AppError::External(e.to_string()))?,
+    )
+    .bind(&tree.root)
+    .bind(tree.scheme.as_str())
+    .execute(conn)
+    .await
+    .map_err(AppError::Db)?;
+    Ok(())
+}
+
+fn proof_job_from_row(row: &sqlx::postgres::PgRow) -> AppResult<ProofJobRecord> {
+    let bundle = row
+        .get::<Option<String>, _>("bundle")
+        .map(|raw| serde_json::from_str(&raw))
+        .transpose()
+        .map_err(|e| AppError::External(format!("stored proof bundle: {e}")))?;
+    Ok(ProofJobRecord {
+        id: row.get("id"),
+        poll_id: row.get("poll_id"),
+        identity_secret: row.get("identity_secret"),
+        status: ProofJobStatus::parse(row.get("status")),
+        bundle,
+        error: row.get("error"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
 }
 
 impl From<DbVote> for StoredVoteRecord {
@@ -1097,7 +4947,9 @@ impl From<DbVote> for StoredVoteRecord {
         StoredVoteRecord {
             poll_id: value.poll_id,
             nullifier: value.nullifier,
+            choice: value.choice,
             recorded_at: value.recorded_at,
+            source: VoteSource::parse(&value.source),
         }
     }
 }
@@ -1109,41 +4961,284 @@ pub struct InMemoryStore {
     polls: Arc<RwLock<HashMap<i64, PollRecord>>>,
     commits: Arc<RwLock<Vec<StoredCommitRecord>>>,
     votes: Arc<RwLock<Vec<StoredVoteRecord>>>,
+    vote_conflicts: Arc<RwLock<Vec<VoteConflictRecord>>>,
     members: Arc<RwLock<Vec<String>>>,
     poll_members: Arc<RwLock<HashMap<i64, Vec<String>>>>,
+    /// Poll id -> member -> path, mirroring `poll_members.path_bits`/`path_siblings`.
+    poll_member_paths: Arc<RwLock<HashMap<i64, HashMap<String, MerklePath>>>>,
     vote_nullifiers: Arc<RwLock<HashMap<(i64, String), ()>>>,
     commits_by_identity: Arc<RwLock<HashMap<(i64, String), ()>>>,
-    synced_commits: Arc<RwLock<HashSet<i64>>>,
+    /// Commit id -> reveal batch tx hash (empty when the revealer returned none).
+    synced_commits: Arc<RwLock<HashMap<i64, String>>>,
+    /// Commit ids that missed the reveal window plus grace without being submitted.
+    expired_commits: Arc<RwLock<HashSet<i64>>>,
+    /// Commit id -> why reveal sync gave up on it (`sync_state = 'dead'`).
+    dead_commits: Arc<RwLock<HashMap<i64, String>>>,
+    /// Mirrors `commitments.sync_attempts`/`next_attempt_at`/`sync_error`.
+    sync_retries: Arc<RwLock<HashMap<i64, SyncRetryState>>>,
+    /// Ids of commits indexed from the contract rather than posted to the API.
+    chain_commits: Arc<RwLock<HashSet<i64>>>,
     commit_seq: Arc<RwLock<i64>>,
     poll_secrets: Arc<RwLock<HashMap<(i64, String), String>>>,
     user_stats: Arc<RwLock<HashMap<String, UserStatsRecord>>>,
+    reveal_batches: Arc<RwLock<Vec<RevealBatchRecord>>>,
+    indexed_polls: Arc<RwLock<HashSet<i64>>>,
+    indexer_checkpoints: Arc<RwLock<HashMap<String, IndexerCheckpoint>>>,
+    /// (tx hash, log index) -> block, mirroring `processed_logs`.
+    processed_logs: Arc<RwLock<HashMap<(String, u64), u64>>>,
+    /// id -> event, mirroring `failed_events`.
+    failed_events: Arc<RwLock<BTreeMap<i64, FailedEventRecord>>>,
+    /// id -> (intent, sent as poll id and tx hash, stored), mirroring `pending_onchain_polls`.
+    poll_intents: Arc<RwLock<BTreeMap<i64, PollIntentState>>>,
+    /// `create_poll_with_id` calls left to fail, standing in for a database outage.
+    failing_poll_inserts: Arc<std::sync::atomic::AtomicUsize>,
+    /// Commit id -> on-chain commit relaying, mirroring the `commit_*` columns; absent is
+    /// `offchain`.
+    onchain_commits: Arc<RwLock<HashMap<i64, InMemoryOnchainCommit>>>,
+    /// Poll id -> its indexed `PollResolved`, mirroring `polls.resolve_tx_hash`/`resolved_at`.
+    resolve_txs: Arc<RwLock<HashMap<i64, ResolveTx>>>,
+    /// Poll id -> when its votes were counted towards `user_stats`, mirroring
+    /// `polls.results_applied_at`.
+    results_applied: Arc<RwLock<HashMap<i64, DateTime<Utc>>>>,
+    /// Polls retention scrubbed, mirroring `polls.secrets_scrubbed_at`; there is no audit trail.
+    scrubbed_polls: Arc<RwLock<HashSet<i64>>>,
+    proof_jobs: Arc<RwLock<HashMap<Uuid, ProofJobRecord>>>,
+    proof_cache: Arc<RwLock<ProofCache>>,
+    /// Builds trees when set; otherwise trees are the sha256 stand-in from `hashed_tree`.
+    merkle: Option<Arc<dyn MerkleRunner>>,
+    /// Members digest -> tree, mirroring `membership_snapshots`.
+    membership_snapshots: Arc<RwLock<HashMap<String, MerkleResult>>>,
+    /// Maintained as members join once a runner is set, mirroring `membership_tree`.
+    membership_tree: Arc<RwLock<Option<IncrementalTree>>>,
+    merkle_depth: u32,
+    hash_scheme: HashScheme,
+}
+
+/// (intent, the poll id and tx hash it was sent as, whether it is stored or failed).
+type PollIntentState = (PollIntent, Option<(i64, String)>, bool);
+
+/// (tx hash, when it was indexed).
+type ResolveTx = (String, DateTime<Utc>);
+
+/// (failed batch reveals, when to retry, last error).
+type SyncRetryState = (i32, Option<DateTime<Utc>>, String);
+
+#[derive(Debug, Clone, Default)]
+struct InMemoryOnchainCommit {
+    state: OnchainCommitState,
+    attempts: i32,
+    next_attempt_at: Option<DateTime<Utc>>,
+    error: String,
+    tx_hash: String,
+    confirmed_at: Option<DateTime<Utc>>,
+}
+
+/// The in-memory stand-in for `commitments.sync_state`.
+fn in_memory_sync_state(
+    id: i64,
+    synced: &HashMap<i64, String>,
+    dead: &HashMap<i64, String>,
+    retries: &HashMap<i64, SyncRetryState>,
+) -> CommitSyncState {
+    if synced.contains_key(&id) {
+        CommitSyncState::Synced
+    } else if dead.contains_key(&id) {
+        CommitSyncState::Dead
+    } else if retries
+        .get(&id)
+        .is_some_and(|(attempts, _, _)| *attempts > 0)
+    {
+        CommitSyncState::Failed
+    } else {
+        CommitSyncState::Pending
+    }
 }
 
+/// (poll, identity) -> (request hash, bundle).
+type ProofCache = HashMap<(i64, String), (String, ProofBundle)>;
+
 impl Default for InMemoryStore {
     fn default() -> Self {
         Self {
             polls: Arc::new(RwLock::new(HashMap::new())),
             commits: Arc::new(RwLock::new(Vec::new())),
             votes: Arc::new(RwLock::new(Vec::new())),
+            vote_conflicts: Arc::new(RwLock::new(Vec::new())),
             members: Arc::new(RwLock::new(Vec::new())),
             poll_members: Arc::new(RwLock::new(HashMap::new())),
+            poll_member_paths: Arc::new(RwLock::new(HashMap::new())),
             vote_nullifiers: Arc::new(RwLock::new(HashMap::new())),
             commits_by_identity: Arc::new(RwLock::new(HashMap::new())),
-            synced_commits: Arc::new(RwLock::new(HashSet::new())),
+            synced_commits: Arc::new(RwLock::new(HashMap::new())),
+            expired_commits: Arc::new(RwLock::new(HashSet::new())),
+            dead_commits: Arc::new(RwLock::new(HashMap::new())),
+            sync_retries: Arc::new(RwLock::new(HashMap::new())),
+            chain_commits: Arc::new(RwLock::new(HashSet::new())),
             commit_seq: Arc::new(RwLock::new(0)),
             poll_secrets: Arc::new(RwLock::new(HashMap::new())),
             user_stats: Arc::new(RwLock::new(HashMap::new())),
+            reveal_batches: Arc::new(RwLock::new(Vec::new())),
+            indexed_polls: Arc::new(RwLock::new(HashSet::new())),
+            indexer_checkpoints: Arc::new(RwLock::new(HashMap::new())),
+            processed_logs: Arc::new(RwLock::new(HashMap::new())),
+            failed_events: Arc::new(RwLock::new(BTreeMap::new())),
+            poll_intents: Arc::new(RwLock::new(BTreeMap::new())),
+            failing_poll_inserts: Arc::default(),
+            onchain_commits: Arc::default(),
+            resolve_txs: Arc::default(),
+            results_applied: Arc::default(),
+            scrubbed_polls: Arc::default(),
+            proof_jobs: Arc::new(RwLock::new(HashMap::new())),
+            proof_cache: Arc::new(RwLock::new(HashMap::new())),
+            merkle: None,
+            membership_snapshots: Arc::new(RwLock::new(HashMap::new())),
+            membership_tree: Arc::new(RwLock::new(None)),
+            merkle_depth: DEFAULT_MERKLE_DEPTH,
+            hash_scheme: HashScheme::default(),
         }
     }
 }
 
 impl InMemoryStore {
-    /// Test helper: pre-seed allowed members for membership_root calculation.
-    pub async fn add_member(&self, identity_secret: &str) {
+    async fn deleted_poll_ids(&self) -> HashSet<i64> {
+        let polls = self.polls.read().await;
+        polls
+            .values()
+            .filter(|p| p.deleted_at.is_some())
+            .map(|p| p.id)
+            .collect()
+    }
+
+    /// True once `commit()` for the commitment was sent, confirmed or given up on; only
+    /// `offchain` and `queued` commitments may still be replaced or withdrawn.
+    async fn commit_left_queue(&self, commit_id: i64) -> bool {
+        self.onchain_commits
+            .read()
+            .await
+            .get(&commit_id)
+            .is_some_and(|c| c.state != OnchainCommitState::Queued)
+    }
+
+    /// Fails the next `count` `create_poll_with_id` calls the way a dropped connection would.
+    #[allow(dead_code)]
+    pub fn fail_next_poll_inserts(&self, count: usize) {
+        self.failing_poll_inserts
+            .store(count, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    #[allow(dead_code)]
+    pub fn with_merkle_runner(mut self, runner: Arc<dyn MerkleRunner>) -> Self {
+        self.merkle = Some(runner);
+        self.membership_tree = Arc::new(RwLock::new(Some(IncrementalTree::new(
+            self.merkle_depth,
+            self.hash_scheme,
+        ))));
+        self
+    }
+
+    /// Sets the hash of the membership tree and of new polls; call before any member joins.
+    #[allow(dead_code)]
+    pub fn with_hash_scheme(mut self, scheme: HashScheme) -> Self {
+        self.hash_scheme = scheme;
+        if self.merkle.is_some() {
+            self.membership_tree = Arc::new(RwLock::new(Some(IncrementalTree::new(
+                self.merkle_depth,
+                scheme,
+            ))));
+        }
+        self
+    }
+
+    /// Sets the tree depth; call before any member joins.
+    #[allow(dead_code)]
+    pub fn with_merkle_depth(mut self, depth: u32) -> Self {
+        self.merkle_depth = depth;
+        if self.merkle.is_some() {
+            self.membership_tree = Arc::new(RwLock::new(Some(IncrementalTree::new(
+                depth,
+                self.hash_scheme,
+            ))));
+        }
+        self
+    }
+
+    async fn join_member(&self, identity_secret: &str) -> AppResult<()> {
         let mut members = self.members.write().await;
-        if !members.contains(&identity_secret.to_string()) {
-            members.push(identity_secret.to_string());
+        if members.iter().any(|m| m == identity_secret) {
+            return Ok(());
+        }
+        if let Some(tree) = self.membership_tree.write().await.as_mut() {
+            tree.push(identity_secret)?;
+        }
+        members.push(identity_secret.to_string());
+        Ok(())
+    }
+
+    async fn current_root(&self) -> String {
+        match self.membership_tree.read().await.as_ref() {
+            Some(tree) => tree.root.clone(),
+            None => hash_members(&self.members.read().await, self.merkle_depth),
+        }
+    }
+
+    async fn membership_tree(
+        &self,
+        members: &[String],
+        scheme: HashScheme,
+        force: bool,
+    ) -> AppResult<MerkleResult> {
+        let digest = merkle::members_digest(members, self.merkle_depth, scheme);
+        if !force {
+            if let Some(tree) = self.membership_snapshots.read().await.get(&digest) {
+                return Ok(tree.clone());
+            }
         }
+        let tree = self.build_tree(members, scheme).await?;
+        self.membership_snapshots
+            .write()
+            .await
+            .insert(digest, tree.clone());
+        Ok(tree)
+    }
+
+    /// A fresh tree from the runner, or the sha256 stand-in without one.
+    async fn build_tree(&self, members: &[String], scheme: HashScheme) -> AppResult<MerkleResult> {
+        match &self.merkle {
+            Some(runner) => runner.build(members, self.merkle_depth, scheme).await,
+            None => {
+                merkle::check_capacity(members.len() as u64, self.merkle_depth)?;
+                Ok(hashed_tree(members, self.merkle_depth))
+            }
+        }
+    }
+
+    async fn note_vote_conflict(&self, vote: StoredVote<'_>) -> Option<StoredVoteRecord> {
+        let votes = self.votes.read().await;
+        let kept = votes
+            .iter()
+            .find(|v| v.poll_id == vote.poll_id && v.nullifier == vote.nullifier)?
+            .clone();
+        if kept.choice == vote.choice as i16 {
+            return Some(kept);
+        }
+        self.vote_conflicts.write().await.push(VoteConflictRecord {
+            poll_id: vote.poll_id,
+            nullifier: vote.nullifier.to_string(),
+            kept_choice: kept.choice,
+            kept_source: kept.source,
+            rejected_choice: vote.choice as i16,
+            rejected_source: vote.source,
+            detected_at: Utc::now(),
+        });
+        Some(kept)
+    }
+
+    /// Test helper: pre-seed allowed members for membership_root calculation.
+    #[allow(dead_code)]
+    pub async fn add_member(&self, identity_secret: &str) {
+        self.join_member(identity_secret)
+            .await
+            .expect("membership tree has room");
         let mut stats = self.user_stats.write().await;
         stats
             .entry(identity_secret.to_string())
@@ -1181,12 +5276,14 @@ impl InMemoryStore {
         entry.tier = tier_for_xp(entry.xp).to_string();
     }
 
-    async fn finalize_poll_results(&self, poll_id: i64, correct_option: u8) {
+    async fn finalize_poll_results(&self, poll_id: i64, correct_option: Option<u8>) {
+        // Commitments indexed from the contract carry no choice or voter.
         let commits: Vec<StoredCommitRecord> = {
             let commits = self.commits.read().await;
+            let chain_commits = self.chain_commits.read().await;
             commits
                 .iter()
-                .filter(|c| c.poll_id == poll_id)
+                .filter(|c| c.poll_id == poll_id && !chain_commits.contains(&c.id))
                 .cloned()
                 .collect()
         };
@@ -1202,8 +5299,14 @@ impl InMemoryStore {
                 }
             }
         }
+        match self.results_applied.write().await.entry(poll_id) {
+            Entry::Occupied(_) => return,
+            Entry::Vacant(slot) => {
+                slot.insert(Utc::now());
+            }
+        }
         for commit in commits {
-            let correct = commit.choice as u8 == correct_option;
+            let correct = correct_option == Some(commit.choice as u8);
             self.bump_user_stats_local(&commit.identity_secret, correct)
                 .await;
         }
@@ -1214,7 +5317,7 @@ impl InMemoryStore {
 impl PollStore for InMemoryStore {
     async fn create_poll(&self, poll: NewPoll<'_>) -> AppResult<PollRecord> {
         let members = self.members.read().await.clone();
-        let root = hash_members(&members);
+        let root = self.current_root().await;
         let id = self.polls.read().await.len() as i64;
         self.create_poll_with_id(id, poll, root, members).await
     }
@@ -1226,50 +5329,172 @@ impl PollStore for InMemoryStore {
         membership_root: String,
         members: Vec<String>,
     ) -> AppResult<PollRecord> {
+        let failing = self.failing_poll_inserts.fetch_update(
+            std::sync::atomic::Ordering::SeqCst,
+            std::sync::atomic::Ordering::SeqCst,
+            |left| left.checked_sub(1),
+        );
+        if failing.is_ok() {
+            return Err(AppError::Db(sqlx::Error::PoolTimedOut));
+        }
+        if !members.is_empty() {
+            let tree = self
+                .membership_tree(&members, self.hash_scheme, false)
+                .await?;
+            self.poll_member_paths
+                .write()
+                .await
+                .insert(poll_id, tree.paths);
+        }
         let mut polls = self.polls.write().await;
         let record = PollRecord {
             id: poll_id,
             question: poll.question.to_string(),
             options: poll.options.to_vec(),
+            option_meta: poll.option_meta.to_vec(),
             commit_phase_end: poll.commit_phase_end,
             reveal_phase_end: poll.reveal_phase_end,
             category: poll.category.to_string(),
             membership_root: membership_root.clone(),
+            membership_hash_scheme: self.hash_scheme,
             owner: poll.owner.to_string(),
+            anonymous_owner: poll.anonymous_owner,
             reveal_tx_hash: String::new(),
             correct_option: None,
             resolved: false,
+            voided: false,
             commit_sync_completed: false,
+            auto_resolve: poll.auto_resolve,
+            visibility: poll.visibility.to_string(),
+            created_at: Utc::now(),
+            deleted_at: None,
             vote_counts: vec![0; poll.options.len()],
+            commit_count: 0,
+            revealed_count: 0,
         };
         polls.insert(poll_id, record.clone());
         self.poll_members.write().await.insert(poll_id, members);
         Ok(record)
     }
 
-    async fn list_polls(&self, limit: i64) -> AppResult<Vec<PollRecord>> {
+    async fn record_poll_intent(&self, intent: &PollIntent) -> AppResult<i64> {
+        let mut intents = self.poll_intents.write().await;
+        let id = intents.keys().next_back().map_or(1, |last| last + 1);
+        intents.insert(id, (intent.clone(), None, false));
+        Ok(id)
+    }
+
+    async fn mark_poll_intent_sent(&self, id: i64, poll_id: i64, tx_hash: &str) -> AppResult<()> {
+        if let Some((_, sent, _)) = self.poll_intents.write().await.get_mut(&id) {
+            *sent = Some((poll_id, tx_hash.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn mark_poll_intent_failed(&self, id: i64, _error: &str) -> AppResult<()> {
+        if let Some((_, _, done)) = self.poll_intents.write().await.get_mut(&id) {
+            *done = true;
+        }
+        Ok(())
+    }
+
+    async fn mark_poll_intent_stored(&self, id: i64) -> AppResult<()> {
+        if let Some((_, _, done)) = self.poll_intents.write().await.get_mut(&id) {
+            *done = true;
+        }
+        Ok(())
+    }
+
+    async fn sent_poll_intents(&self) -> AppResult<Vec<SentPollIntentRecord>> {
+        Ok(self
+            .poll_intents
+            .read()
+            .await
+            .iter()
+            .filter_map(|(id, (intent, sent, done))| {
+                let (poll_id, tx_hash) = sent.clone().filter(|_| !done)?;
+                Some(SentPollIntentRecord {
+                    id: *id,
+                    poll_id,
+                    tx_hash,
+                    intent: intent.clone(),
+                })
+            })
+            .collect())
+    }
+
+    async fn list_polls(
+        &self,
+        limit: i64,
+        before_id: Option<i64>,
+        filter: PollFilter,
+    ) -> AppResult<PollPage> {
         let polls = self.polls.read().await;
-        let mut vals: Vec<_> = polls.values().cloned().collect();
-        vals.sort_by_key(|p| -(p.id as i64));
-        vals.truncate(limit as usize);
-        Ok(vals)
+        let members = self.poll_members.read().await;
+        let sort = filter.sort;
+        let after = before_id.map(|id| polls.get(&id).map(|p| sort.key(p)));
+        let mut vals: Vec<_> = polls
+            .values()
+            .filter(|p| filter.include_deleted || p.deleted_at.is_none())
+            .filter(|p| filter.audience.can_see(p, &members))
+            .filter(|p| after.is_none_or(|after| after.is_some_and(|key| sort.key(p) < key)))
+            .filter(|p| filter.category.as_ref().is_none_or(|c| &p.category == c))
+            .filter(|p| filter.resolved.is_none_or(|r| p.resolved == r))
+            .cloned()
+            .collect();
+        vals.sort_by_key(|p| std::cmp::Reverse(sort.key(p)));
+        vals.truncate(limit.max(0) as usize + 1);
+        Ok(PollPage::from_fetched(vals, limit))
     }
 
     async fn get_poll(&self, poll_id: i64) -> AppResult<PollRecord> {
+        let polls = self.polls.read().await;
+        polls
+            .get(&poll_id)
+            .filter(|p| p.deleted_at.is_none())
+            .cloned()
+            .ok_or(AppError::NotFound)
+    }
+
+    async fn get_poll_including_deleted(&self, poll_id: i64) -> AppResult<PollRecord> {
         let polls = self.polls.read().await;
         polls.get(&poll_id).cloned().ok_or(AppError::NotFound)
     }
 
+    async fn soft_delete_poll(&self, poll_id: i64) -> AppResult<PollRecord> {
+        let mut polls = self.polls.write().await;
+        let poll = polls.get_mut(&poll_id).ok_or(AppError::NotFound)?;
+        poll.deleted_at.get_or_insert_with(Utc::now);
+        Ok(poll.clone())
+    }
+
+    async fn get_polls_by_ids(
+        &self,
+        ids: &[i64],
+        audience: &PollAudience,
+    ) -> AppResult<Vec<PollRecord>> {
+        let polls = self.polls.read().await;
+        let members = self.poll_members.read().await;
+        Ok(ids
+            .iter()
+            .filter_map(|id| polls.get(id))
+            .filter(|p| p.deleted_at.is_none() && audience.can_see(p, &members))
+            .cloned()
+            .collect())
+    }
+
     async fn record_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord> {
         {
             let commits = self.commits.read().await;
-            if commits
-                .iter()
-                .any(|c| c.poll_id == commit.poll_id && c.identity_secret == commit.identity_secret)
+            let existing = commits.iter().find(|c| {
+                c.poll_id == commit.poll_id && c.identity_secret == commit.identity_secret
+            });
+            if existing.is_some()
+                || commits
+                    .iter()
+                    .any(|c| c.poll_id == commit.poll_id && c.commitment == commit.commitment)
             {
-                return Err(AppError::Validation(
-                    "already committed for this poll".into(),
-                ));
+                return existing_commit_or_conflict(existing.cloned(), &commit);
             }
         }
         let mut seq = self.commit_seq.write().await;
@@ -1286,32 +5511,113 @@ impl PollStore for InMemoryStore {
             nullifier: commit.nullifier.to_string(),
             proof: commit.proof.to_string(),
             public_inputs: commit.public_inputs.to_vec(),
+            proof_version: commit.proof_version,
+            revision: 1,
+            auto_reveal: commit.auto_reveal,
+            scrubbed_at: None,
         };
         self.commits.write().await.push(rec.clone());
         self.commits_by_identity
             .write()
             .await
             .insert((commit.poll_id, commit.identity_secret.to_string()), ());
+        if commit.onchain_commit {
+            self.queue_onchain_commit(id).await?;
+        }
+        if let Some(poll) = self.polls.write().await.get_mut(&commit.poll_id) {
+            poll.commit_count += 1;
+        }
         Ok(rec)
     }
 
+    async fn replace_commit(&self, commit: StoredCommit<'_>) -> AppResult<StoredCommitRecord> {
+        let synced = self.synced_commits.read().await;
+        let mut commits = self.commits.write().await;
+        if commits.iter().any(|c| {
+            c.poll_id == commit.poll_id
+                && c.commitment == commit.commitment
+                && c.identity_secret != commit.identity_secret
+        }) {
+            return Err(duplicate_commitment());
+        }
+        let existing = commits
+            .iter_mut()
+            .find(|c| c.poll_id == commit.poll_id && c.identity_secret == commit.identity_secret)
+            .ok_or(AppError::NotFound)?;
+        if synced.contains_key(&existing.id) || self.commit_left_queue(existing.id).await {
+            return Err(AppError::Conflict(
+                "commitment already submitted on-chain".into(),
+            ));
+        }
+        existing.choice = commit.choice;
+        existing.commitment = commit.commitment.to_string();
+        existing.secret = commit.secret.to_string();
+        existing.nullifier = commit.nullifier.to_string();
+        existing.proof = commit.proof.to_string();
+        existing.public_inputs = commit.public_inputs.to_vec();
+        existing.proof_version = commit.proof_version;
+        existing.auto_reveal = commit.auto_reveal;
+        existing.revision += 1;
+        existing.recorded_at = Utc::now();
+        self.dead_commits.write().await.remove(&existing.id);
+        self.sync_retries.write().await.remove(&existing.id);
+        let mut onchain = self.onchain_commits.write().await;
+        onchain.remove(&existing.id);
+        if commit.onchain_commit {
+            onchain.insert(
+                existing.id,
+                InMemoryOnchainCommit {
+                    state: OnchainCommitState::Queued,
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(existing.clone())
+    }
+
+    async fn delete_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<()> {
+        let synced = self.synced_commits.read().await;
+        let mut commits = self.commits.write().await;
+        let idx = commits
+            .iter()
+            .position(|c| c.poll_id == poll_id && c.identity_secret == identity_secret)
+            .ok_or(AppError::NotFound)?;
+        if synced.contains_key(&commits[idx].id) || self.commit_left_queue(commits[idx].id).await {
+            return Err(AppError::Conflict(
+                "commitment already submitted on-chain".into(),
+            ));
+        }
+        commits.remove(idx);
+        self.commits_by_identity
+            .write()
+            .await
+            .remove(&(poll_id, identity_secret.to_string()));
+        if let Some(poll) = self.polls.write().await.get_mut(&poll_id) {
+            poll.commit_count -= 1;
+        }
+        Ok(())
+    }
+
     async fn record_vote(&self, vote: StoredVote<'_>) -> AppResult<StoredVoteRecord> {
-        {
-            let seen = self.vote_nullifiers.read().await;
-            if seen.contains_key(&(vote.poll_id, vote.nullifier.to_string())) {
-                return Err(AppError::Validation("nullifier already used".into()));
-            }
+        // Hold the nullifier set across check and insert, like the unique index does.
+        let mut seen = self.vote_nullifiers.write().await;
+        if seen.contains_key(&(vote.poll_id, vote.nullifier.to_string())) {
+            drop(seen);
+            return match self.note_vote_conflict(vote).await {
+                Some(kept) => existing_vote_or_conflict(kept, &vote),
+                None => Err(AppError::Conflict("nullifier already used".into())),
+            };
         }
+        seen.insert((vote.poll_id, vote.nullifier.to_string()), ());
         let rec = StoredVoteRecord {
             poll_id: vote.poll_id,
             nullifier: vote.nullifier.to_string(),
+            choice: vote.choice as i16,
             recorded_at: Utc::now(),
+            source: vote.source,
         };
         self.votes.write().await.push(rec.clone());
-        self.vote_nullifiers
-            .write()
-            .await
-            .insert((vote.poll_id, vote.nullifier.to_string()), ());
+        drop(seen);
         {
             let mut polls = self.polls.write().await;
             if let Some(poll) = polls.get_mut(&vote.poll_id) {
@@ -1322,14 +5628,124 @@ impl PollStore for InMemoryStore {
                 if idx < poll.vote_counts.len() {
                     poll.vote_counts[idx] += 1;
                 }
+                poll.revealed_count += 1;
             }
         }
         Ok(rec)
     }
 
     async fn membership_root_snapshot(&self) -> AppResult<String> {
-        let members = self.members.read().await;
-        Ok(hash_members(&members))
+        Ok(self.current_root().await)
+    }
+
+    async fn check_membership_tree(&self) -> AppResult<MembershipTreeCheck> {
+        let members = self.members.read().await.clone();
+        let incremental_root = self.current_root().await;
+        let rebuilt_root = self.build_tree(&members, self.hash_scheme).await?.root;
+        Ok(MembershipTreeCheck {
+            member_count: members.len(),
+            incremental_root,
+            rebuilt_root,
+        })
+    }
+
+    async fn recompute_membership_snapshot(&self) -> AppResult<MembershipSnapshotRecord> {
+        let members = self.members.read().await.clone();
+        if let Some(tree) = self.membership_tree.write().await.as_mut() {
+            *tree = IncrementalTree::from_members(&members, self.merkle_depth, self.hash_scheme)?;
+        }
+        let tree = self
+            .membership_tree(&members, self.hash_scheme, true)
+            .await?;
+        Ok(MembershipSnapshotRecord {
+            digest: merkle::members_digest(&members, self.merkle_depth, self.hash_scheme),
+            root: tree.root,
+            member_count: members.len(),
+            depth: tree.depth,
+        })
+    }
+
+    async fn poll_membership_root(&self, poll_id: i64) -> AppResult<PollMembershipRootRecord> {
+        let poll = self.get_poll(poll_id).await?;
+        let members = self
+            .poll_members
+            .read()
+            .await
+            .get(&poll_id)
+            .cloned()
+            .unwrap_or_default();
+        let snapshot_at = poll.created_at;
+        Ok(PollMembershipRootRecord {
+            root: poll.membership_root,
+            snapshot_digest: merkle::members_digest(
+                &members,
+                self.merkle_depth,
+                poll.membership_hash_scheme,
+            ),
+            member_count: members.len() as i64,
+            snapshot_at,
+        })
+    }
+
+    async fn rebuild_poll_root(&self, poll_id: i64) -> AppResult<Option<String>> {
+        let members = self
+            .poll_members
+            .read()
+            .await
+            .get(&poll_id)
+            .cloned()
+            .unwrap_or_default();
+        if members.is_empty() {
+            return Ok(None);
+        }
+        let scheme = self.get_poll(poll_id).await?.membership_hash_scheme;
+        Ok(Some(self.build_tree(&members, scheme).await?.root))
+    }
+
+    async fn check_stored_path(&self) -> AppResult<Option<StoredPathCheck>> {
+        let deleted = self.deleted_poll_ids().await;
+        let sampled = {
+            let stored = self.poll_member_paths.read().await;
+            let polls: Vec<i64> = stored
+                .keys()
+                .copied()
+                .filter(|id| !deleted.contains(id))
+                .collect();
+            if polls.is_empty() {
+                return Ok(None);
+            }
+            let poll_id = polls[(OsRng.next_u64() % polls.len() as u64) as usize];
+            let members = self
+                .poll_members
+                .read()
+                .await
+                .get(&poll_id)
+                .cloned()
+                .unwrap_or_default();
+            if members.is_empty() {
+                return Ok(None);
+            }
+            let leaf_index = OsRng.next_u64() % members.len() as u64;
+            let member = members[leaf_index as usize].clone();
+            let path = stored[&poll_id].get(&member).cloned();
+            (poll_id, leaf_index, member, members, path)
+        };
+        let (poll_id, leaf_index, member, members, stored) = sampled;
+        let scheme = self.get_poll(poll_id).await?.membership_hash_scheme;
+        let fresh = self.build_tree(&members, scheme).await?;
+        Ok(Some(StoredPathCheck {
+            poll_id,
+            leaf_index,
+            matches: stored.is_some() && fresh.paths.get(&member) == stored.as_ref(),
+        }))
+    }
+
+    async fn prune_merkle_nodes(&self) -> AppResult<u64> {
+        let deleted = self.deleted_poll_ids().await;
+        let mut stored = self.poll_member_paths.write().await;
+        let before = stored.len();
+        stored.retain(|poll_id, _| !deleted.contains(poll_id));
+        Ok((before - stored.len()) as u64)
     }
 
     async fn list_members(&self) -> AppResult<Vec<String>> {
@@ -1338,17 +5754,30 @@ impl PollStore for InMemoryStore {
 
     async fn merkle_path_for_member(
         &self,
-        _poll_id: i64,
-        _identity_secret: &str,
+        poll_id: i64,
+        identity_secret: &str,
     ) -> AppResult<Option<MerklePath>> {
-        Ok(None)
+        let Some(members) = self.poll_members.read().await.get(&poll_id).cloned() else {
+            return Ok(None);
+        };
+        if !members.iter().any(|m| m == identity_secret) {
+            return Ok(None);
+        }
+        if let Some(paths) = self.poll_member_paths.read().await.get(&poll_id) {
+            return Ok(paths.get(identity_secret).cloned());
+        }
+        let scheme = self.get_poll(poll_id).await?.membership_hash_scheme;
+        let tree = self.membership_tree(&members, scheme, false).await?;
+        let path = tree.paths.get(identity_secret).cloned();
+        self.poll_member_paths
+            .write()
+            .await
+            .insert(poll_id, tree.paths);
+        Ok(path)
     }
 
     async fn ensure_member(&self, _username: &str, identity_secret: &str) -> AppResult<()> {
-        let mut members = self.members.write().await;
-        if !members.contains(&identity_secret.to_string()) {
-            members.push(identity_secret.to_string());
-        }
+        self.join_member(identity_secret).await?;
         let mut stats = self.user_stats.write().await;
         stats
             .entry(identity_secret.to_string())
@@ -1373,14 +5802,162 @@ impl PollStore for InMemoryStore {
         }
     }
 
-    async fn nullifier_used(&self, poll_id: i64, nullifier: &str) -> AppResult<bool> {
-        let seen = self.vote_nullifiers.read().await;
-        Ok(seen.contains_key(&(poll_id, nullifier.to_string())))
-    }
-
-    async fn has_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool> {
-        let seen = self.commits_by_identity.read().await;
-        Ok(seen.contains_key(&(poll_id, identity_secret.to_string())))
+    async fn has_commit(&self, poll_id: i64, identity_secret: &str) -> AppResult<bool> {
+        let seen = self.commits_by_identity.read().await;
+        Ok(seen.contains_key(&(poll_id, identity_secret.to_string())))
+    }
+
+    async fn commitment_for_identity(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<StoredCommitRecord>> {
+        let commits = self.commits.read().await;
+        Ok(commits
+            .iter()
+            .find(|c| c.poll_id == poll_id && c.identity_secret == identity_secret)
+            .cloned())
+    }
+
+    async fn commitment_receipt_status(
+        &self,
+        poll_id: i64,
+        commitment: &str,
+    ) -> AppResult<Option<CommitReceiptStatus>> {
+        let commits = self.commits.read().await;
+        let synced = self.synced_commits.read().await;
+        Ok(commits
+            .iter()
+            .find(|c| c.poll_id == poll_id && c.commitment == commitment)
+            .map(|c| CommitReceiptStatus {
+                synced: synced.contains_key(&c.id),
+                scrubbed: c.scrubbed_at.is_some(),
+            }))
+    }
+
+    async fn commitment_exists(&self, poll_id: i64, commitment: &str) -> AppResult<bool> {
+        let commits = self.commits.read().await;
+        Ok(commits
+            .iter()
+            .any(|c| c.poll_id == poll_id && c.commitment == commitment))
+    }
+
+    async fn nullifier_taken(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        identity_secret: &str,
+    ) -> AppResult<bool> {
+        let commits = self.commits.read().await;
+        Ok(!nullifier.is_empty()
+            && commits.iter().any(|c| {
+                c.poll_id == poll_id
+                    && c.nullifier == nullifier
+                    && c.identity_secret != identity_secret
+            }))
+    }
+
+    async fn my_commit_status(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<Option<MyCommitStatusRecord>> {
+        let commits = self.commits.read().await;
+        let Some(commit) = commits
+            .iter()
+            .find(|c| c.poll_id == poll_id && c.identity_secret == identity_secret)
+        else {
+            return Ok(None);
+        };
+        let revealed = self
+            .vote_nullifiers
+            .read()
+            .await
+            .contains_key(&(poll_id, commit.nullifier.clone()));
+        let expired = self.expired_commits.read().await.contains(&commit.id);
+        let synced = self.synced_commits.read().await;
+        let dead = self.dead_commits.read().await;
+        let retries = self.sync_retries.read().await;
+        let sync_state = in_memory_sync_state(commit.id, &synced, &dead, &retries);
+        let (sync_attempts, next_sync_attempt_at, last_error) =
+            retries.get(&commit.id).cloned().unwrap_or_default();
+        let tx = synced.get(&commit.id).cloned();
+        Ok(Some(MyCommitStatusRecord {
+            onchain_submitted: tx.is_some(),
+            expired,
+            revealed,
+            reveal_tx_hash: tx.filter(|tx| !tx.is_empty()),
+            auto_reveal: commit.auto_reveal,
+            sync_error: dead
+                .get(&commit.id)
+                .cloned()
+                .or((!last_error.is_empty()).then_some(last_error)),
+            sync_attempts,
+            next_sync_attempt_at,
+            sync_state,
+        }))
+    }
+
+    async fn commit_count(&self, poll_id: i64) -> AppResult<i64> {
+        let commits = self.commits.read().await;
+        Ok(commits.iter().filter(|c| c.poll_id == poll_id).count() as i64)
+    }
+
+    async fn poll_member_count(&self, poll_id: i64) -> AppResult<i64> {
+        let pm = self.poll_members.read().await;
+        Ok(pm.get(&poll_id).map(|m| m.len() as i64).unwrap_or(0))
+    }
+
+    async fn participation(&self, poll_id: i64) -> AppResult<ParticipationRecord> {
+        let pm = self.poll_members.read().await;
+        let members: HashSet<&String> = pm.get(&poll_id).into_iter().flatten().collect();
+        let secrets = self.poll_secrets.read().await;
+        let commits = self.commits.read().await;
+        let synced_commits = self.synced_commits.read().await;
+        let chain_commits = self.chain_commits.read().await;
+        let votes = self.votes.read().await;
+        let mut record = ParticipationRecord {
+            eligible: members.len() as i64,
+            fetched_secret: secrets
+                .keys()
+                .filter(|(id, identity)| *id == poll_id && members.contains(identity))
+                .count() as i64,
+            ..Default::default()
+        };
+        for commit in commits
+            .iter()
+            .filter(|c| c.poll_id == poll_id && members.contains(&c.identity_secret))
+        {
+            let submitted = synced_commits.contains_key(&commit.id);
+            let vote = votes
+                .iter()
+                .find(|v| v.poll_id == poll_id && v.nullifier == commit.nullifier);
+            record.committed += 1;
+            if vote.is_some() || submitted {
+                record.revealed += 1;
+            }
+            if submitted || vote.is_some_and(|v| v.source != VoteSource::Api) {
+                record.synced += 1;
+            }
+            if !commit.auto_reveal {
+                record.self_reveal += 1;
+            }
+        }
+        record.committed += commits
+            .iter()
+            .filter(|c| c.poll_id == poll_id && chain_commits.contains(&c.id))
+            .count() as i64;
+        Ok(record)
+    }
+
+    async fn close_commit_phase(&self, poll_id: i64, now: DateTime<Utc>) -> AppResult<PollRecord> {
+        let mut polls = self.polls.write().await;
+        let poll = polls.get_mut(&poll_id).ok_or(AppError::NotFound)?;
+        if poll.resolved || poll.commit_phase_end <= now {
+            return Err(AppError::Validation("commit phase already closed".into()));
+        }
+        poll.commit_phase_end = now;
+        Ok(poll.clone())
     }
 
     async fn get_or_create_secret(&self, poll_id: i64, identity_secret: &str) -> AppResult<String> {
@@ -1394,36 +5971,193 @@ impl PollStore for InMemoryStore {
         Ok(secret)
     }
 
-    async fn resolve_poll(&self, poll_id: i64, correct_option: u8) -> AppResult<PollRecord> {
+    async fn resolve_poll(
+        &self,
+        poll_id: i64,
+        correct_option: Option<u8>,
+    ) -> AppResult<PollRecord> {
         {
             let mut polls = self.polls.write().await;
             let poll = polls.get_mut(&poll_id).ok_or(AppError::NotFound)?;
+            if poll.resolved {
+                return Err(AppError::Validation("poll already resolved".into()));
+            }
             poll.resolved = true;
-            poll.correct_option = Some(correct_option as i16);
+            poll.correct_option = correct_option.map(i16::from);
+            poll.voided = correct_option.is_none();
         }
         self.finalize_poll_results(poll_id, correct_option).await;
         let polls = self.polls.read().await;
         polls.get(&poll_id).cloned().ok_or(AppError::NotFound)
     }
 
+    async fn auto_resolve_candidates(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+    ) -> AppResult<Vec<i64>> {
+        let polls = self.polls.read().await;
+        let mut ids: Vec<i64> = polls
+            .values()
+            .filter(|p| p.auto_resolve && !p.resolved && p.commit_sync_completed)
+            .filter(|p| p.reveal_phase_end <= now - grace && p.deleted_at.is_none())
+            .map(|p| p.id)
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    async fn disable_auto_resolve(&self, poll_id: i64) -> AppResult<()> {
+        if let Some(poll) = self.polls.write().await.get_mut(&poll_id) {
+            poll.auto_resolve = false;
+        }
+        Ok(())
+    }
+
+    async fn prune_stale_polls(&self, before: DateTime<Utc>, dry_run: bool) -> AppResult<Vec<i64>> {
+        let polls = self.polls.read().await;
+        let indexed = self.indexed_polls.read().await;
+        let commits = self.commits.read().await;
+        let votes = self.votes.read().await;
+        let mut ids: Vec<i64> = polls
+            .values()
+            .filter(|p| {
+                p.deleted_at.is_none()
+                    && !p.resolved
+                    && !indexed.contains(&p.id)
+                    && p.reveal_tx_hash.is_empty()
+                    && p.created_at < before
+                    && p.reveal_phase_end < before
+                    && !commits.iter().any(|c| c.poll_id == p.id)
+                    && !votes.iter().any(|v| v.poll_id == p.id)
+            })
+            .map(|p| p.id)
+            .collect();
+        ids.sort();
+        drop(polls);
+        if !dry_run {
+            for id in &ids {
+                self.soft_delete_poll(*id).await?;
+            }
+        }
+        Ok(ids)
+    }
+
+    async fn secret_scrub_candidates(&self, before: DateTime<Utc>) -> AppResult<Vec<i64>> {
+        let polls = self.polls.read().await;
+        let applied = self.results_applied.read().await;
+        let scrubbed = self.scrubbed_polls.read().await;
+        let mut ids: Vec<i64> = polls
+            .values()
+            .filter(|p| {
+                p.resolved
+                    && p.commit_sync_completed
+                    && !scrubbed.contains(&p.id)
+                    && p.reveal_phase_end < before
+                    && applied.get(&p.id).copied().unwrap_or(p.reveal_phase_end) < before
+            })
+            .map(|p| p.id)
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    async fn scrub_poll_secrets(
+        &self,
+        poll_id: i64,
+        _requested_by: &str,
+        dry_run: bool,
+    ) -> AppResult<SecretScrubCounts> {
+        let poll = self.get_poll(poll_id).await?;
+        if !(poll.resolved && poll.commit_sync_completed) {
+            return Err(secrets_still_needed());
+        }
+        let mut secrets = self.poll_secrets.write().await;
+        let mut commits = self.commits.write().await;
+        let mut cache = self.proof_cache.write().await;
+        let counts = SecretScrubCounts {
+            secrets_deleted: secrets.keys().filter(|(id, _)| *id == poll_id).count() as u64,
+            commitments_scrubbed: commits
+                .iter()
+                .filter(|c| c.poll_id == poll_id && c.scrubbed_at.is_none())
+                .count() as u64,
+            cached_proofs_deleted: cache.keys().filter(|(id, _)| *id == poll_id).count() as u64,
+        };
+        if dry_run {
+            return Ok(counts);
+        }
+        let now = Utc::now();
+        secrets.retain(|(id, _), _| *id != poll_id);
+        cache.retain(|(id, _), _| *id != poll_id);
+        for commit in commits
+            .iter_mut()
+            .filter(|c| c.poll_id == poll_id && c.scrubbed_at.is_none())
+        {
+            commit.secret.clear();
+            commit.proof.clear();
+            commit.public_inputs.clear();
+            commit.scrubbed_at = Some(now);
+        }
+        self.scrubbed_polls.write().await.insert(poll_id);
+        Ok(counts)
+    }
+
+    async fn revealed_vote_counts(&self, poll_id: i64) -> AppResult<Vec<i64>> {
+        let options = self.get_poll(poll_id).await?.options.len();
+        let mut counts = vec![0; options];
+        for vote in self.votes.read().await.iter() {
+            if vote.poll_id != poll_id {
+                continue;
+            }
+            if let Some(slot) = counts.get_mut(vote.choice as usize) {
+                *slot += 1;
+            }
+        }
+        Ok(counts)
+    }
+
     async fn commits_to_sync(
         &self,
         now: DateTime<Utc>,
+        grace: chrono::Duration,
+        slack: chrono::Duration,
+        onchain_commits: bool,
         limit: i64,
     ) -> AppResult<Vec<CommitSyncRow>> {
         let polls = self.polls.read().await;
         let commits = self.commits.read().await;
         let synced = self.synced_commits.read().await;
+        let dead = self.dead_commits.read().await;
+        let retries = self.sync_retries.read().await;
+        let onchain = self.onchain_commits.read().await;
         let mut items = Vec::new();
         for commit in commits.iter() {
             if items.len() as i64 >= limit {
                 break;
             }
-            if synced.contains(&commit.id) {
+            if !commit.auto_reveal
+                || synced.contains_key(&commit.id)
+                || dead.contains_key(&commit.id)
+                || retries
+                    .get(&commit.id)
+                    .and_then(|(_, next, _)| *next)
+                    .is_some_and(|next| next > now)
+                || !match onchain
+                    .get(&commit.id)
+                    .map_or_else(Default::default, |c| c.state)
+                {
+                    OnchainCommitState::Confirmed => true,
+                    OnchainCommitState::Offchain => !onchain_commits,
+                    _ => false,
+                }
+            {
                 continue;
             }
             if let Some(poll) = polls.get(&commit.poll_id) {
-                if poll.commit_phase_end <= now && poll.reveal_phase_end > now {
+                if poll.commit_phase_end <= now
+                    && poll.reveal_phase_end > now - grace - slack
+                    && poll.deleted_at.is_none()
+                {
                     items.push(CommitSyncRow {
                         id: commit.id,
                         poll_id: commit.poll_id,
@@ -1433,6 +6167,7 @@ impl PollStore for InMemoryStore {
                         nullifier: commit.nullifier.clone(),
                         proof: commit.proof.clone(),
                         public_inputs: commit.public_inputs.clone(),
+                        proof_version: commit.proof_version,
                     });
                 }
             }
@@ -1440,11 +6175,172 @@ impl PollStore for InMemoryStore {
         Ok(items)
     }
 
-    async fn mark_commit_synced(&self, commit_id: i64) -> AppResult<()> {
-        self.synced_commits.write().await.insert(commit_id);
+    async fn expire_unsynced_commits(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+        slack: chrono::Duration,
+    ) -> AppResult<Vec<i64>> {
+        let polls = self.polls.read().await;
+        let commits = self.commits.read().await;
+        let synced = self.synced_commits.read().await;
+        let dead = self.dead_commits.read().await;
+        let mut expired = self.expired_commits.write().await;
+        let ids: Vec<i64> = commits
+            .iter()
+            .filter(|c| c.auto_reveal && !synced.contains_key(&c.id) && !expired.contains(&c.id))
+            .filter(|c| !dead.contains_key(&c.id))
+            .filter(|c| {
+                polls.get(&c.poll_id).is_some_and(|p| {
+                    !p.commit_sync_completed && p.reveal_phase_end <= now - grace - slack
+                })
+            })
+            .map(|c| c.id)
+            .collect();
+        expired.extend(&ids);
+        Ok(ids)
+    }
+
+    async fn mark_commit_synced(&self, commit_id: i64, tx_hash: Option<&str>) -> AppResult<()> {
+        self.synced_commits
+            .write()
+            .await
+            .insert(commit_id, tx_hash.unwrap_or_default().to_string());
+        Ok(())
+    }
+
+    async fn mark_commit_dead(&self, commit_id: i64, error: &str) -> AppResult<()> {
+        self.dead_commits
+            .write()
+            .await
+            .insert(commit_id, error.to_string());
+        Ok(())
+    }
+
+    async fn record_sync_failure(
+        &self,
+        commit_ids: &[i64],
+        error: &str,
+        now: DateTime<Utc>,
+        policy: &SyncRetryPolicy,
+    ) -> AppResult<Vec<i64>> {
+        let synced = self.synced_commits.read().await;
+        let mut dead = self.dead_commits.write().await;
+        let mut retries = self.sync_retries.write().await;
+        let mut given_up = Vec::new();
+        for &id in commit_ids {
+            if synced.contains_key(&id) || dead.contains_key(&id) {
+                continue;
+            }
+            let retry = retries.entry(id).or_default();
+            retry.0 += 1;
+            retry.1 = policy.next_attempt_at(retry.0, now);
+            retry.2 = error.to_string();
+            if retry.1.is_none() {
+                dead.insert(id, error.to_string());
+                given_up.push(id);
+            }
+        }
+        given_up.sort_unstable();
+        Ok(given_up)
+    }
+
+    async fn queue_onchain_commit(&self, commit_id: i64) -> AppResult<()> {
+        self.onchain_commits.write().await.insert(
+            commit_id,
+            InMemoryOnchainCommit {
+                state: OnchainCommitState::Queued,
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    async fn onchain_commits_to_send(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<OnchainCommitRow>> {
+        let commits = self.commits.read().await;
+        let onchain = self.onchain_commits.read().await;
+        Ok(commits
+            .iter()
+            .filter(|c| {
+                onchain.get(&c.id).is_some_and(|entry| {
+                    matches!(
+                        entry.state,
+                        OnchainCommitState::Queued | OnchainCommitState::Failed
+                    ) && entry.next_attempt_at.is_none_or(|next| next <= now)
+                })
+            })
+            .take(limit.max(0) as usize)
+            .map(|c| OnchainCommitRow {
+                id: c.id,
+                poll_id: c.poll_id,
+                commitment: c.commitment.clone(),
+            })
+            .collect())
+    }
+
+    async fn mark_onchain_commit_confirmed(
+        &self,
+        commit: &OnchainCommitRow,
+        tx_hash: &str,
+    ) -> AppResult<()> {
+        let commits = self.commits.read().await;
+        if !commits
+            .iter()
+            .any(|c| c.id == commit.id && c.commitment == commit.commitment)
+        {
+            return Ok(());
+        }
+        if let Some(entry) = self.onchain_commits.write().await.get_mut(&commit.id) {
+            if matches!(
+                entry.state,
+                OnchainCommitState::Queued | OnchainCommitState::Failed
+            ) {
+                entry.state = OnchainCommitState::Confirmed;
+                entry.tx_hash = tx_hash.to_string();
+                entry.confirmed_at = Some(Utc::now());
+                entry.error.clear();
+            }
+        }
         Ok(())
     }
 
+    async fn record_onchain_commit_failure(
+        &self,
+        commit_ids: &[i64],
+        error: &str,
+        now: DateTime<Utc>,
+        policy: &SyncRetryPolicy,
+    ) -> AppResult<Vec<i64>> {
+        let mut onchain = self.onchain_commits.write().await;
+        let mut given_up = Vec::new();
+        for id in commit_ids {
+            let Some(entry) = onchain.get_mut(id).filter(|entry| {
+                matches!(
+                    entry.state,
+                    OnchainCommitState::Queued | OnchainCommitState::Failed
+                )
+            }) else {
+                continue;
+            };
+            entry.attempts += 1;
+            entry.error = error.to_string();
+            entry.next_attempt_at = policy.next_attempt_at(entry.attempts, now);
+            entry.state = match entry.next_attempt_at {
+                Some(_) => OnchainCommitState::Failed,
+                None => {
+                    given_up.push(*id);
+                    OnchainCommitState::Dead
+                }
+            };
+        }
+        given_up.sort_unstable();
+        Ok(given_up)
+    }
+
     async fn set_reveal_tx_hash(&self, poll_id: i64, tx: &str) -> AppResult<()> {
         let mut polls = self.polls.write().await;
         if let Some(p) = polls.get_mut(&poll_id) {
@@ -1454,12 +6350,298 @@ impl PollStore for InMemoryStore {
         Ok(())
     }
 
-    async fn poll_has_pending_commits(&self, poll_id: i64) -> AppResult<bool> {
+    async fn record_reveal_batch(&self, batch: NewRevealBatch<'_>) -> AppResult<()> {
+        self.reveal_batches.write().await.push(RevealBatchRecord {
+            poll_id: batch.poll_id,
+            tx_hash: batch.tx_hash.unwrap_or_default().to_string(),
+            item_count: batch.commit_ids.len() as i32,
+            submitted_at: Utc::now(),
+            gas_used: batch.gas_used.map(|gas| gas as i64),
+            effective_gas_price: batch.effective_gas_price.map(|price| price as i64),
+            commit_ids: batch.commit_ids.to_vec(),
+        });
+        Ok(())
+    }
+
+    async fn list_reveal_batches(&self, poll_id: i64) -> AppResult<Vec<RevealBatchRecord>> {
+        let batches = self.reveal_batches.read().await;
+        Ok(batches
+            .iter()
+            .filter(|b| b.poll_id == poll_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn reveal_cost(&self, poll_id: i64) -> AppResult<RevealCostRecord> {
+        let batches = self.reveal_batches.read().await;
+        let mut cost = RevealCostRecord::default();
+        for batch in batches.iter().filter(|b| b.poll_id == poll_id) {
+            let (Some(gas), Some(price)) = (batch.gas_used, batch.effective_gas_price) else {
+                continue;
+            };
+            cost.batch_count += 1;
+            cost.item_count += i64::from(batch.item_count);
+            cost.gas_used += gas;
+            cost.cost_wei += gas as u128 * price as u128;
+        }
+        Ok(cost)
+    }
+
+    async fn list_poll_transactions(&self, poll_id: i64) -> AppResult<Vec<PollTransactionRecord>> {
+        let confirmed = |kind, tx_hash: &str, timestamp, item_count| PollTransactionRecord {
+            kind,
+            tx_hash: tx_hash.to_string(),
+            timestamp,
+            status: PollTransactionStatus::Confirmed,
+            item_count,
+        };
+        let created_at = self.polls.read().await.get(&poll_id).map(|p| p.created_at);
+        let mut txs: Vec<_> = self
+            .poll_intents
+            .read()
+            .await
+            .values()
+            .filter_map(|(_, sent, _)| sent.as_ref())
+            .filter(|(id, _)| *id == poll_id)
+            .map(|(_, tx_hash)| confirmed(PollTransactionKind::Create, tx_hash, created_at, None))
+            .collect();
+
+        let commits = self.commits.read().await;
+        let poll_commits = commits.iter().filter(|c| c.poll_id == poll_id);
+        let onchain = self.onchain_commits.read().await;
+        txs.extend(poll_commits.clone().filter_map(|c| {
+            let entry = onchain.get(&c.id)?;
+            (entry.state == OnchainCommitState::Confirmed && !entry.tx_hash.is_empty()).then(|| {
+                confirmed(
+                    PollTransactionKind::Commit,
+                    &entry.tx_hash,
+                    entry.confirmed_at,
+                    None,
+                )
+            })
+        }));
+
+        let batches = self.reveal_batches.read().await;
+        let batches: Vec<_> = batches
+            .iter()
+            .filter(|b| b.poll_id == poll_id && !b.tx_hash.is_empty())
+            .collect();
+        txs.extend(batches.iter().map(|b| PollTransactionRecord {
+            status: if b.gas_used.is_some() {
+                PollTransactionStatus::Confirmed
+            } else {
+                PollTransactionStatus::Indexed
+            },
+            ..confirmed(
+                PollTransactionKind::RevealBatch,
+                &b.tx_hash,
+                Some(b.submitted_at),
+                Some(b.item_count),
+            )
+        }));
+        // Legacy reveals: commitment and poll reveal hashes no batch covers.
+        let synced = self.synced_commits.read().await;
+        let mut legacy: BTreeMap<&str, i32> = BTreeMap::new();
+        for tx_hash in poll_commits.filter_map(|c| synced.get(&c.id)) {
+            if !tx_hash.is_empty() && !batches.iter().any(|b| &b.tx_hash == tx_hash) {
+                *legacy.entry(tx_hash).or_default() += 1;
+            }
+        }
+        let poll_tx = self
+            .polls
+            .read()
+            .await
+            .get(&poll_id)
+            .map(|p| p.reveal_tx_hash.clone())
+            .filter(|tx| !tx.is_empty())
+            .filter(|tx| !batches.iter().any(|b| &b.tx_hash == tx))
+            .filter(|tx| !legacy.contains_key(tx.as_str()));
+        txs.extend(legacy.into_iter().map(|(tx_hash, count)| {
+            confirmed(PollTransactionKind::RevealBatch, tx_hash, None, Some(count))
+        }));
+        txs.extend(
+            poll_tx
+                .map(|tx_hash| confirmed(PollTransactionKind::RevealBatch, &tx_hash, None, None)),
+        );
+
+        if let Some((tx_hash, at)) = self.resolve_txs.read().await.get(&poll_id) {
+            txs.push(PollTransactionRecord {
+                status: PollTransactionStatus::Indexed,
+                ..confirmed(PollTransactionKind::Resolve, tx_hash, Some(*at), None)
+            });
+        }
+        sort_poll_transactions(&mut txs);
+        Ok(txs)
+    }
+
+    async fn list_commits_redacted(&self, poll_id: i64) -> AppResult<Vec<RedactedCommitRecord>> {
         let commits = self.commits.read().await;
         let synced = self.synced_commits.read().await;
-        let pending = commits
+        let dead = self.dead_commits.read().await;
+        let retries = self.sync_retries.read().await;
+        let onchain = self.onchain_commits.read().await;
+        Ok(commits
+            .iter()
+            .filter(|c| c.poll_id == poll_id)
+            .map(|c| {
+                let retry = retries.get(&c.id);
+                let onchain = onchain.get(&c.id);
+                RedactedCommitRecord {
+                    pseudonym: commit_pseudonym(poll_id, &c.identity_secret),
+                    recorded_at: c.recorded_at,
+                    onchain_submitted: synced.contains_key(&c.id),
+                    sync_state: in_memory_sync_state(c.id, &synced, &dead, &retries),
+                    sync_attempts: retry.map_or(0, |(attempts, _, _)| *attempts),
+                    sync_error: dead.get(&c.id).or(retry.map(|(_, _, err)| err)).cloned(),
+                    commit_state: onchain.map(|c| c.state).unwrap_or_default(),
+                    commit_tx_hash: onchain
+                        .map(|c| c.tx_hash.clone())
+                        .filter(|hash| !hash.is_empty()),
+                    commit_error: onchain
+                        .map(|c| c.error.clone())
+                        .filter(|err| !err.is_empty()),
+                }
+            })
+            .collect())
+    }
+
+    async fn vote_source_counts(&self, poll_id: i64) -> AppResult<VoteSourceCounts> {
+        let votes = self.votes.read().await;
+        let mut counts = VoteSourceCounts::default();
+        for vote in votes.iter().filter(|v| v.poll_id == poll_id) {
+            match vote.source {
+                VoteSource::Api => counts.api += 1,
+                VoteSource::Relayer => counts.relayer += 1,
+                VoteSource::Chain => counts.chain += 1,
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn list_vote_conflicts(&self, poll_id: i64) -> AppResult<Vec<VoteConflictRecord>> {
+        let conflicts = self.vote_conflicts.read().await;
+        Ok(conflicts
             .iter()
-            .any(|c| c.poll_id == poll_id && !synced.contains(&c.id));
+            .filter(|c| c.poll_id == poll_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn create_proof_job(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+    ) -> AppResult<ProofJobRecord> {
+        let now = Utc::now();
+        let job = ProofJobRecord {
+            id: Uuid::new_v4(),
+            poll_id,
+            identity_secret: identity_secret.to_string(),
+            status: ProofJobStatus::Pending,
+            bundle: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.proof_jobs.write().await.insert(job.id, job.clone());
+        Ok(job)
+    }
+
+    async fn get_proof_job(&self, job_id: Uuid) -> AppResult<ProofJobRecord> {
+        self.proof_jobs
+            .read()
+            .await
+            .get(&job_id)
+            .cloned()
+            .ok_or(AppError::NotFound)
+    }
+
+    async fn start_proof_job(&self, job_id: Uuid) -> AppResult<()> {
+        if let Some(job) = self.proof_jobs.write().await.get_mut(&job_id) {
+            job.status = ProofJobStatus::Running;
+            job.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn complete_proof_job(&self, job_id: Uuid, bundle: &ProofBundle) -> AppResult<()> {
+        if let Some(job) = self.proof_jobs.write().await.get_mut(&job_id) {
+            job.status = ProofJobStatus::Done;
+            job.bundle = Some(bundle.clone());
+            job.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn fail_proof_job(&self, job_id: Uuid, error: &str) -> AppResult<()> {
+        if let Some(job) = self.proof_jobs.write().await.get_mut(&job_id) {
+            job.status = ProofJobStatus::Failed;
+            job.error = Some(error.to_string());
+            job.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn fail_orphaned_proof_jobs(&self) -> AppResult<u64> {
+        let mut jobs = self.proof_jobs.write().await;
+        let mut failed = 0;
+        for job in jobs
+            .values_mut()
+            .filter(|j| matches!(j.status, ProofJobStatus::Pending | ProofJobStatus::Running))
+        {
+            job.status = ProofJobStatus::Failed;
+            job.error = Some(ORPHANED_PROOF_JOB_ERROR.to_string());
+            job.updated_at = Utc::now();
+            failed += 1;
+        }
+        Ok(failed)
+    }
+
+    async fn cached_proof(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+        request_hash: &str,
+    ) -> AppResult<Option<ProofBundle>> {
+        let cache = self.proof_cache.read().await;
+        Ok(cache
+            .get(&(poll_id, identity_secret.to_string()))
+            .filter(|(hash, _)| hash == request_hash)
+            .map(|(_, bundle)| bundle.clone()))
+    }
+
+    async fn cache_proof(
+        &self,
+        poll_id: i64,
+        identity_secret: &str,
+        request_hash: &str,
+        bundle: &ProofBundle,
+    ) -> AppResult<()> {
+        self.proof_cache.write().await.insert(
+            (poll_id, identity_secret.to_string()),
+            (request_hash.to_string(), bundle.clone()),
+        );
+        Ok(())
+    }
+
+    async fn invalidate_cached_proof(&self, poll_id: i64, identity_secret: &str) -> AppResult<()> {
+        self.proof_cache
+            .write()
+            .await
+            .remove(&(poll_id, identity_secret.to_string()));
+        Ok(())
+    }
+
+    async fn poll_has_pending_commits(&self, poll_id: i64) -> AppResult<bool> {
+        let commits = self.commits.read().await;
+        let synced = self.synced_commits.read().await;
+        let dead = self.dead_commits.read().await;
+        let pending = commits.iter().any(|c| {
+            c.poll_id == poll_id
+                && c.auto_reveal
+                && !synced.contains_key(&c.id)
+                && !dead.contains_key(&c.id)
+        });
         Ok(pending)
     }
 
@@ -1471,6 +6653,89 @@ impl PollStore for InMemoryStore {
         Ok(())
     }
 
+    async fn commit_sync_state_counts(&self, poll_id: i64) -> AppResult<CommitSyncStateCounts> {
+        let commits = self.commits.read().await;
+        let synced = self.synced_commits.read().await;
+        let dead = self.dead_commits.read().await;
+        let retries = self.sync_retries.read().await;
+        let mut counts = CommitSyncStateCounts::default();
+        for commit in commits
+            .iter()
+            .filter(|c| c.poll_id == poll_id && c.auto_reveal)
+        {
+            match in_memory_sync_state(commit.id, &synced, &dead, &retries) {
+                CommitSyncState::Pending => counts.pending += 1,
+                CommitSyncState::Synced => counts.synced += 1,
+                CommitSyncState::Failed => counts.failed += 1,
+                CommitSyncState::Dead => counts.dead += 1,
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn pending_commit_count(&self) -> AppResult<i64> {
+        let commits = self.commits.read().await;
+        let synced = self.synced_commits.read().await;
+        let dead = self.dead_commits.read().await;
+        let expired = self.expired_commits.read().await;
+        let pending = commits
+            .iter()
+            .filter(|c| {
+                c.auto_reveal
+                    && !synced.contains_key(&c.id)
+                    && !dead.contains_key(&c.id)
+                    && !expired.contains(&c.id)
+            })
+            .count();
+        Ok(pending as i64)
+    }
+
+    async fn list_dead_commits(&self) -> AppResult<Vec<DeadCommitRecord>> {
+        let commits = self.commits.read().await;
+        let dead = self.dead_commits.read().await;
+        let retries = self.sync_retries.read().await;
+        let mut records: Vec<DeadCommitRecord> = commits
+            .iter()
+            .filter_map(|c| {
+                let error = dead.get(&c.id)?;
+                Some(DeadCommitRecord {
+                    id: c.id,
+                    poll_id: c.poll_id,
+                    commitment: c.commitment.clone(),
+                    recorded_at: c.recorded_at,
+                    sync_attempts: retries.get(&c.id).map_or(0, |(attempts, _, _)| *attempts),
+                    sync_error: (!error.is_empty()).then(|| error.clone()),
+                })
+            })
+            .collect();
+        records.sort_by_key(|r| (r.recorded_at, r.id));
+        Ok(records)
+    }
+
+    async fn requeue_dead_commit(&self, commit_id: i64) -> AppResult<i64> {
+        if self.dead_commits.write().await.remove(&commit_id).is_none() {
+            return Err(AppError::NotFound);
+        }
+        self.sync_retries.write().await.remove(&commit_id);
+        self.expired_commits.write().await.remove(&commit_id);
+        let poll_id = self
+            .commits
+            .read()
+            .await
+            .iter()
+            .find(|c| c.id == commit_id)
+            .map(|c| c.poll_id)
+            .ok_or(AppError::NotFound)?;
+        if let Some(p) = self.polls.write().await.get_mut(&poll_id) {
+            p.commit_sync_completed = false;
+        }
+        Ok(poll_id)
+    }
+
+    async fn encrypt_stored_secrets(&self) -> AppResult<u64> {
+        Ok(0)
+    }
+
     async fn backfill_user_stats(&self) -> AppResult<()> {
         {
             let mut stats = self.user_stats.write().await;
@@ -1481,32 +6746,41 @@ impl PollStore for InMemoryStore {
                 entry.tier = tier_for_xp(0).to_string();
             }
         }
-        let poll_entries: Vec<(i64, Option<i16>, bool)> = {
+        self.results_applied.write().await.clear();
+        let poll_entries: Vec<(i64, Option<i16>, bool, bool)> = {
             let polls = self.polls.read().await;
             polls
                 .iter()
-                .map(|(id, poll)| (*id, poll.correct_option, poll.resolved))
+                .map(|(id, poll)| (*id, poll.correct_option, poll.resolved, poll.voided))
                 .collect()
         };
-        for (poll_id, correct_option, resolved) in poll_entries {
-            if resolved {
-                if let Some(correct) = correct_option {
-                    self.finalize_poll_results(poll_id, correct as u8).await;
-                }
+        for (poll_id, correct_option, resolved, voided) in poll_entries {
+            if resolved && (correct_option.is_some() || voided) {
+                self.finalize_poll_results(poll_id, correct_option.map(|c| c as u8))
+                    .await;
             }
         }
         Ok(())
     }
 
-    async fn mark_polls_without_pending_commits(&self, now: DateTime<Utc>) -> AppResult<()> {
+    async fn mark_polls_without_pending_commits(
+        &self,
+        now: DateTime<Utc>,
+        grace: chrono::Duration,
+    ) -> AppResult<()> {
         let commits = self.commits.read().await;
         let synced = self.synced_commits.read().await;
+        let dead = self.dead_commits.read().await;
         let mut polls = self.polls.write().await;
         for poll in polls.values_mut() {
             if poll.commit_phase_end <= now && !poll.commit_sync_completed {
-                let pending = commits
-                    .iter()
-                    .any(|c| c.poll_id == poll.id && !synced.contains(&c.id));
+                let pending = poll.reveal_phase_end > now - grace
+                    && commits.iter().any(|c| {
+                        c.poll_id == poll.id
+                            && c.auto_reveal
+                            && !synced.contains_key(&c.id)
+                            && !dead.contains_key(&c.id)
+                    });
                 if !pending {
                     poll.commit_sync_completed = true;
                 }
@@ -1546,24 +6820,55 @@ impl PollStore for InMemoryStore {
 
 #[async_trait]
 impl PollIndexSink for InMemoryStore {
-    async fn upsert_poll_from_chain(&self, poll_id: i64, poll: NewPoll<'_>) -> AppResult<()> {
+    async fn upsert_poll_from_chain(
+        &self,
+        poll_id: i64,
+        poll: NewPoll<'_>,
+        created_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        self.indexed_polls.write().await.insert(poll_id);
         let mut polls = self.polls.write().await;
+        // Metadata and ownership are off-chain; keep whatever the API stored.
+        let (option_meta, owner, created_at, deleted_at) = match polls.get(&poll_id) {
+            Some(p) => (
+                p.option_meta.clone(),
+                p.owner.clone(),
+                p.created_at,
+                p.deleted_at,
+            ),
+            None => (
+                Vec::new(),
+                poll.owner.to_string(),
+                created_at.unwrap_or_else(Utc::now),
+                None,
+            ),
+        };
         polls.insert(
             poll_id,
             PollRecord {
                 id: poll_id,
                 question: poll.question.to_string(),
                 options: poll.options.to_vec(),
+                option_meta,
                 commit_phase_end: poll.commit_phase_end,
                 reveal_phase_end: poll.reveal_phase_end,
                 category: poll.category.to_string(),
                 membership_root: poll.membership_root.to_string(),
-                owner: poll.owner.to_string(),
+                membership_hash_scheme: self.hash_scheme,
+                owner,
+                anonymous_owner: poll.anonymous_owner,
                 reveal_tx_hash: String::new(),
                 correct_option: None,
                 resolved: false,
+                voided: false,
                 commit_sync_completed: false,
+                auto_resolve: poll.auto_resolve,
+                visibility: poll.visibility.to_string(),
+                created_at,
+                deleted_at,
                 vote_counts: vec![0; poll.options.len()],
+                commit_count: 0,
+                revealed_count: 0,
             },
         );
         Ok(())
@@ -1573,313 +6878,313 @@ impl PollIndexSink for InMemoryStore {
         &self,
         poll_id: i64,
         nullifier: &str,
-        _choice: u8,
+        choice: u8,
     ) -> AppResult<()> {
+        let relayed = {
+            let commits = self.commits.read().await;
+            let synced = self.synced_commits.read().await;
+            commits.iter().any(|c| {
+                c.poll_id == poll_id && c.nullifier == nullifier && synced.contains_key(&c.id)
+            })
+        };
+        let vote = StoredVote {
+            poll_id,
+            nullifier,
+            choice,
+            source: if relayed {
+                VoteSource::Relayer
+            } else {
+                VoteSource::Chain
+            },
+        };
+        // Mirrors the votes.poll_id foreign key.
+        if !self.polls.read().await.contains_key(&poll_id) {
+            return Err(AppError::NotFound);
+        }
+        let key = (poll_id, nullifier.to_string());
+        if self.vote_nullifiers.read().await.contains_key(&key) {
+            self.note_vote_conflict(vote).await;
+            return Ok(());
+        }
         self.votes.write().await.push(StoredVoteRecord {
             poll_id,
             nullifier: nullifier.to_string(),
+            choice: choice as i16,
             recorded_at: Utc::now(),
+            source: vote.source,
         });
+        self.vote_nullifiers.write().await.insert(key, ());
         Ok(())
     }
 
-    async fn resolve_poll_from_chain(&self, poll_id: i64, correct_option: u8) -> AppResult<()> {
+    async fn resolve_poll_from_chain(
+        &self,
+        poll_id: i64,
+        correct_option: u8,
+        tx_hash: Option<&str>,
+    ) -> AppResult<()> {
         {
             let mut polls = self.polls.write().await;
-            if let Some(p) = polls.get_mut(&poll_id) {
-                p.resolved = true;
-                p.correct_option = Some(correct_option as i16);
-            }
+            let p = polls.get_mut(&poll_id).ok_or(AppError::NotFound)?;
+            p.resolved = true;
+            p.correct_option = Some(correct_option as i16);
         }
-        self.finalize_poll_results(poll_id, correct_option).await;
+        if let Some(tx_hash) = tx_hash {
+            self.resolve_txs
+                .write()
+                .await
+                .insert(poll_id, (tx_hash.to_string(), Utc::now()));
+        }
+        self.finalize_poll_results(poll_id, Some(correct_option))
+            .await;
         Ok(())
     }
-}
-
-async fn init_schema(pool: &Pool<Postgres>) -> AppResult<()> {
-    // Minimal schema for metadata + bookkeeping
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS polls (
-            id BIGSERIAL PRIMARY KEY,
-            question TEXT NOT NULL,
-            options JSONB NOT NULL,
-            commit_phase_end TIMESTAMPTZ NOT NULL,
-            reveal_phase_end TIMESTAMPTZ NOT NULL,
-            category TEXT NOT NULL DEFAULT 'General',
-            membership_root TEXT NOT NULL,
-            correct_option SMALLINT,
-            resolved BOOLEAN NOT NULL DEFAULT false,
-            commit_sync_completed BOOLEAN NOT NULL DEFAULT false,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
-
-    sqlx::query(
-        r#"
-        ALTER TABLE polls
-        ADD COLUMN IF NOT EXISTS category TEXT NOT NULL DEFAULT 'General';
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
-
-    sqlx::query(
-        r#"
-        UPDATE polls
-        SET category = 'General'
-        WHERE category IS NULL OR category = '';
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
-
-    sqlx::query(
-        r#"
-        UPDATE polls
-        SET commit_sync_completed = false
-        WHERE commit_sync_completed IS NULL;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS members (
-            id SERIAL PRIMARY KEY,
-            identity_secret TEXT NOT NULL UNIQUE,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS poll_members (
-            poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
-            identity_secret TEXT NOT NULL,
-            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
-            UNIQUE(poll_id, identity_secret)
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
-
-    // Safety upgrade path: ensure polls.id is BIGINT (existing DBs created before BIGSERIAL)
-    sqlx::query(
-        r#"
-        DO $$
-        BEGIN
-            IF EXISTS (
-                SELECT 1
-                FROM information_schema.columns
-                WHERE table_name = 'polls' AND column_name = 'id' AND data_type = 'integer'
-            ) THEN
-                ALTER TABLE polls ALTER COLUMN id TYPE BIGINT;
-            END IF;
-        END$$;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
 
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS commitments (
-            id SERIAL PRIMARY KEY,
-            poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
-            commitment TEXT NOT NULL,
-            identity_secret TEXT NOT NULL,
-            choice SMALLINT NOT NULL DEFAULT 0,
-            nullifier TEXT NOT NULL DEFAULT '',
-            proof TEXT NOT NULL DEFAULT '',
-            public_inputs TEXT[] NOT NULL DEFAULT '{}',
-            recorded_at TIMESTAMPTZ NOT NULL DEFAULT now(),
-            onchain_submitted BOOLEAN NOT NULL DEFAULT false
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn upsert_commit_from_chain(&self, poll_id: i64, commitment: &str) -> AppResult<()> {
+        if !self.polls.read().await.contains_key(&poll_id) {
+            return Err(AppError::NotFound);
+        }
+        let mut commits = self.commits.write().await;
+        if commits
+            .iter()
+            .any(|c| c.poll_id == poll_id && c.commitment == commitment)
+        {
+            return Ok(());
+        }
+        let mut seq = self.commit_seq.write().await;
+        let id = *seq;
+        *seq += 1;
+        commits.push(StoredCommitRecord {
+            id,
+            poll_id,
+            choice: 0,
+            commitment: commitment.to_string(),
+            identity_secret: commitment.to_string(),
+            secret: String::new(),
+            recorded_at: Utc::now(),
+            nullifier: String::new(),
+            proof: String::new(),
+            public_inputs: Vec::new(),
+            proof_version: 1,
+            revision: 1,
+            auto_reveal: false,
+            scrubbed_at: None,
+        });
+        self.synced_commits.write().await.insert(id, String::new());
+        self.chain_commits.write().await.insert(id);
+        if let Some(poll) = self.polls.write().await.get_mut(&poll_id) {
+            poll.commit_count += 1;
+        }
+        Ok(())
+    }
 
-    sqlx::query(
-        r#"
-        DO $$
-        BEGIN
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name = 'commitments' AND column_name = 'id'
-            ) THEN
-                ALTER TABLE commitments ADD COLUMN id BIGSERIAL PRIMARY KEY;
-            END IF;
-        END$$;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn confirm_reveal_from_chain(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        tx_hash: &str,
+    ) -> AppResult<()> {
+        let commits = self.commits.read().await;
+        let mut synced = self.synced_commits.write().await;
+        let mut confirmed = Vec::new();
+        for commit in commits
+            .iter()
+            .filter(|c| c.poll_id == poll_id && !c.nullifier.is_empty() && c.nullifier == nullifier)
+        {
+            if synced.get(&commit.id).map(String::as_str) != Some(tx_hash) {
+                synced.insert(commit.id, tx_hash.to_string());
+                confirmed.push(commit.id);
+            }
+        }
+        if confirmed.is_empty() {
+            return Ok(());
+        }
+        let mut batches = self.reveal_batches.write().await;
+        match batches
+            .iter_mut()
+            .find(|b| b.poll_id == poll_id && b.tx_hash == tx_hash)
+        {
+            Some(batch) => {
+                batch.item_count += confirmed.len() as i32;
+                batch.commit_ids.extend(confirmed);
+            }
+            None => batches.push(RevealBatchRecord {
+                poll_id,
+                tx_hash: tx_hash.to_string(),
+                item_count: confirmed.len() as i32,
+                submitted_at: Utc::now(),
+                gas_used: None,
+                effective_gas_price: None,
+                commit_ids: confirmed,
+            }),
+        }
+        Ok(())
+    }
 
-    sqlx::query(
-        r#"
-        ALTER TABLE commitments
-        ADD COLUMN IF NOT EXISTS onchain_submitted BOOLEAN NOT NULL DEFAULT false;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn log_processed(&self, tx_hash: &str, log_index: u64) -> AppResult<bool> {
+        Ok(self
+            .processed_logs
+            .read()
+            .await
+            .contains_key(&(tx_hash.to_string(), log_index)))
+    }
 
-    sqlx::query(
-        r#"
-        ALTER TABLE commitments
-        ADD COLUMN IF NOT EXISTS choice SMALLINT NOT NULL DEFAULT 0;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn record_processed_log(
+        &self,
+        tx_hash: &str,
+        log_index: u64,
+        block: u64,
+    ) -> AppResult<()> {
+        self.processed_logs
+            .write()
+            .await
+            .insert((tx_hash.to_string(), log_index), block);
+        Ok(())
+    }
 
-    sqlx::query(
-        r#"
-        ALTER TABLE commitments
-        ADD COLUMN IF NOT EXISTS nullifier TEXT NOT NULL DEFAULT '';
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn prune_processed_logs(&self, block: u64) -> AppResult<u64> {
+        let mut logs = self.processed_logs.write().await;
+        let before = logs.len();
+        logs.retain(|_, logged_at| *logged_at >= block);
+        Ok((before - logs.len()) as u64)
+    }
 
-    sqlx::query(
-        r#"
-        ALTER TABLE commitments
-        ADD COLUMN IF NOT EXISTS proof TEXT NOT NULL DEFAULT '';
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn record_failed_event(
+        &self,
+        log: &serde_json::Value,
+        sender: Option<&str>,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        let mut events = self.failed_events.write().await;
+        let id = events.keys().next_back().map_or(1, |last| last + 1);
+        events.insert(
+            id,
+            FailedEventRecord {
+                id,
+                log: log.clone(),
+                sender: sender.map(str::to_string),
+                error: error.to_string(),
+                attempts: 1,
+                next_retry_at: next_retry_at.unwrap_or_else(Utc::now),
+                dead: next_retry_at.is_none(),
+            },
+        );
+        Ok(())
+    }
 
-    sqlx::query(
-        r#"
-        ALTER TABLE commitments
-        ADD COLUMN IF NOT EXISTS public_inputs TEXT[] NOT NULL DEFAULT '{}';
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn due_failed_events(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<FailedEventRecord>> {
+        let mut due: Vec<_> = self
+            .failed_events
+            .read()
+            .await
+            .values()
+            .filter(|e| !e.dead && e.next_retry_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|e| (e.next_retry_at, e.id));
+        due.truncate(limit.max(0) as usize);
+        Ok(due)
+    }
 
-    sqlx::query(
-        r#"
-        UPDATE commitments
-        SET onchain_submitted = false
-        WHERE onchain_submitted IS NULL;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn reschedule_failed_event(
+        &self,
+        id: i64,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        if let Some(event) = self.failed_events.write().await.get_mut(&id) {
+            event.error = error.to_string();
+            event.attempts += 1;
+            match next_retry_at {
+                Some(at) => event.next_retry_at = at,
+                None => event.dead = true,
+            }
+        }
+        Ok(())
+    }
 
-    // Backfill legacy rows: set empty identity_secret to commitment to avoid dup on index creation
-    sqlx::query(
-        r#"
-        UPDATE commitments
-        SET identity_secret = commitment
-        WHERE identity_secret IS NULL OR identity_secret = '';
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn delete_failed_event(&self, id: i64) -> AppResult<()> {
+        self.failed_events.write().await.remove(&id);
+        Ok(())
+    }
 
-    // Drop duplicate (poll_id, identity_secret), keep latest recorded_at
-    sqlx::query(
-        r#"
-        DELETE FROM commitments c
-        USING (
-            SELECT ctid, ROW_NUMBER() OVER (PARTITION BY poll_id, identity_secret ORDER BY recorded_at DESC, id DESC) AS rn
-            FROM commitments
-        ) d
-        WHERE c.ctid = d.ctid AND d.rn > 1;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn failed_event_counts(&self) -> AppResult<(u64, u64)> {
+        let events = self.failed_events.read().await;
+        let dead = events.values().filter(|e| e.dead).count() as u64;
+        Ok((events.len() as u64 - dead, dead))
+    }
 
-    sqlx::query(
-        r#"
-        DROP INDEX IF EXISTS commitments_poll_commitment_idx;
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn requeue_dead_events(&self, now: DateTime<Utc>) -> AppResult<u64> {
+        let mut requeued = 0;
+        for event in self.failed_events.write().await.values_mut() {
+            if event.dead {
+                event.dead = false;
+                event.attempts = 0;
+                event.next_retry_at = now;
+                requeued += 1;
+            }
+        }
+        Ok(requeued)
+    }
 
-    sqlx::query(
-        r#"
-        CREATE INDEX IF NOT EXISTS commitments_poll_commitment_idx ON commitments(poll_id, commitment)
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn load_checkpoint(&self, contract: &str) -> AppResult<Option<IndexerCheckpoint>> {
+        Ok(self
+            .indexer_checkpoints
+            .read()
+            .await
+            .get(&contract.to_ascii_lowercase())
+            .copied())
+    }
 
-    sqlx::query(
-        r#"
-        CREATE UNIQUE INDEX IF NOT EXISTS commitments_poll_identity_idx ON commitments(poll_id, identity_secret)
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+    async fn save_checkpoint(
+        &self,
+        contract: &str,
+        checkpoint: IndexerCheckpoint,
+    ) -> AppResult<()> {
+        self.indexer_checkpoints
+            .write()
+            .await
+            .insert(contract.to_ascii_lowercase(), checkpoint);
+        Ok(())
+    }
+}
 
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS votes (
-            id SERIAL PRIMARY KEY,
-            poll_id BIGINT NOT NULL REFERENCES polls(id) ON DELETE CASCADE,
-            nullifier TEXT NOT NULL,
-            choice SMALLINT NOT NULL,
-            recorded_at TIMESTAMPTZ NOT NULL DEFAULT now()
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+/// The migrations under `backend/migrations`, embedded at build time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
-    sqlx::query(
-        r#"
-        CREATE UNIQUE INDEX IF NOT EXISTS votes_poll_nullifier_idx ON votes(poll_id, nullifier)
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
+/// Applies every pending migration, after refusing a database already past this binary.
+pub async fn run_migrations(pool: &Pool<Postgres>) -> AppResult<()> {
+    check_schema_version(pool).await?;
+    MIGRATOR.run(pool).await.map_err(|e| AppError::Db(e.into()))
+}
 
-    sqlx::query(
-        r#"
-        CREATE UNIQUE INDEX IF NOT EXISTS votes_nullifier_idx ON votes(poll_id, nullifier)
-        "#,
-    )
-    .execute(pool)
-    .await
-    .map_err(AppError::Db)?;
-    Ok(())
+/// Fails when the database records a migration newer than any this binary embeds: a later
+/// release migrated it, and this one may misread or break its schema. Returns the latest
+/// migration applied, `None` for a database never migrated.
+pub async fn check_schema_version(pool: &Pool<Postgres>) -> AppResult<Option<i64>> {
+    let tracked: bool = sqlx::query_scalar(r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL"#)
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::Db)?;
+    if !tracked {
+        return Ok(None);
+    }
+    let applied: Option<i64> =
+        sqlx::query_scalar(r#"SELECT MAX(version) FROM _sqlx_migrations WHERE success"#)
+            .fetch_one(pool)
+            .await
+            .map_err(AppError::Db)?;
+    let latest = MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0);
+    match applied {
+        Some(applied) if applied > latest => Err(AppError::Validation(format!(
+            "the database is at migration {applied} but this build only knows up to {latest}; \
+             run the backend release that migrated it"
+        ))),
+        applied => Ok(applied),
+    }
 }
@@ -1,3 +1,4 @@
+use crate::zk::{default_proof_version, ProofBundle};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -26,10 +27,39 @@ impl Phase {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollVisibility {
+    #[default]
+    Public,
+    /// Only the owner and identities in the poll's member snapshot can see the poll.
+    Members,
+}
+
+impl PollVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PollVisibility::Public => "public",
+            PollVisibility::Members => "members",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "members" => PollVisibility::Members,
+            _ => PollVisibility::Public,
+        }
+    }
+}
+
 fn default_category() -> String {
     "General".to_string()
 }
 
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct CreatePollRequest {
     pub question: String,
@@ -38,6 +68,26 @@ pub struct CreatePollRequest {
     pub reveal_phase_end: DateTime<Utc>,
     #[serde(default = "default_category")]
     pub category: String,
+    #[serde(default)]
+    pub auto_resolve: bool,
+    #[serde(default)]
+    pub visibility: PollVisibility,
+    /// Hide the owner's username from everyone but the owner and admins.
+    #[serde(default)]
+    pub anonymous_owner: bool,
+    /// Display metadata per option; either empty or one entry per option. Never sent on-chain.
+    #[serde(default)]
+    pub option_meta: Vec<OptionMeta>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct OptionMeta {
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub subtitle: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -51,22 +101,58 @@ pub struct PollResponse {
     pub id: i64,
     pub question: String,
     pub options: Vec<String>,
+    pub option_meta: Vec<OptionMeta>,
     pub commit_phase_end: DateTime<Utc>,
     pub reveal_phase_end: DateTime<Utc>,
     pub category: String,
     pub membership_root: String,
+    /// Hash the membership tree behind `membership_root` was built with: `poseidon` or `keccak`.
+    pub membership_hash_scheme: String,
     pub owner: String,
+    pub anonymous_owner: bool,
     pub reveal_tx_hash: String,
     pub correct_option: Option<i16>,
     pub resolved: bool,
+    pub voided: bool,
     pub commit_sync_completed: bool,
+    pub auto_resolve: bool,
+    pub visibility: PollVisibility,
+    /// When the poll was created; the block time for polls indexed from the chain.
+    pub created_at: DateTime<Utc>,
+    /// Set on soft-deleted polls, which only admins see (`include_deleted=true`).
+    pub deleted_at: Option<DateTime<Utc>>,
     pub phase: Phase,
+    /// Per-option revealed tallies; all zero while the commit phase is open.
     pub vote_counts: Vec<i64>,
+    pub commit_count: i64,
+    pub revealed_count: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResolveOutcome {
+    /// The option at `index` is the correct answer.
+    Option { index: u8 },
+    /// The event was cancelled; nobody is awarded the correct-answer bonus.
+    Void,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ResolveRequest {
-    pub correct_option: u8,
+    /// Legacy shorthand for `outcome: { kind: "option", index }`.
+    #[serde(default)]
+    pub correct_option: Option<u8>,
+    #[serde(default)]
+    pub outcome: Option<ResolveOutcome>,
+}
+
+impl ResolveRequest {
+    pub fn outcome(&self) -> Option<ResolveOutcome> {
+        self.outcome.or_else(|| {
+            self.correct_option
+                .map(|index| ResolveOutcome::Option { index })
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -75,14 +161,35 @@ pub struct SecretResponse {
     pub secret: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CommitmentRequest {
+    pub choice: u8,
+}
+
+/// What `POST /polls/:id/commit` expects for the chosen option, hashed by the active backend.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CommitmentResponse {
+    pub poll_id: i64,
+    pub commitment: String,
+    pub nullifier: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct CommitRequest {
     pub choice: u8,
-    pub secret: String,
+    /// Deprecated: the server commits with its own per-poll secret. If sent, it must match.
+    #[serde(default)]
+    pub secret: Option<String>,
     pub commitment: String,
     pub nullifier: String,
     pub proof: String,
     pub public_inputs: Vec<String>,
+    /// `version` of the proof bundle; defaults to 1.
+    #[serde(default = "default_proof_version")]
+    pub version: u16,
+    /// When false the relayer never reveals this commitment; the member reveals it themselves.
+    #[serde(default = "default_true")]
+    pub auto_reveal: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -95,6 +202,51 @@ pub struct CommitResponse {
     pub proof: String,
     pub public_inputs: Vec<String>,
     pub choice: i16,
+    /// Starts at 1 and increments each time the commitment is replaced.
+    pub revision: i32,
+    pub auto_reveal: bool,
+    /// `<key_id>.<hmac>` over poll id, commitment and `recorded_at`; check it with
+    /// `GET /polls/:id/receipt/verify`.
+    pub receipt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ReceiptVerifyResponse {
+    pub poll_id: i64,
+    pub valid: bool,
+    /// Key that signed the receipt; set only when `valid`.
+    pub key_id: Option<String>,
+    /// The commitment is still recorded for this poll (always false for invalid receipts).
+    pub stored: bool,
+    /// The commitment was included in an on-chain batch reveal.
+    pub synced: bool,
+    /// Its secret and proof were removed by retention; the commitment itself is kept.
+    pub scrubbed: bool,
+}
+
+/// One entry of `POST /commits/batch`: a regular commit body tagged with its poll.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchCommitItem {
+    pub poll_id: i64,
+    #[serde(flatten)]
+    pub commit: CommitRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchCommitError {
+    /// HTTP status the same request would get from `POST /polls/:id/commit`.
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+}
+
+/// Outcome of one batch item; exactly one of `commit` and `error` is set.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct BatchCommitResult {
+    pub poll_id: i64,
+    pub commit: Option<CommitResponse>,
+    pub error: Option<BatchCommitError>,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -103,11 +255,15 @@ pub struct CommitStatusResponse {
     pub already_committed: bool,
 }
 
+/// Only the choice comes from the client: the server derives the caller's identity from the
+/// token and fills in their per-poll secret and membership path. A `secret` field sent by
+/// older clients is ignored.
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ProveRequest {
     pub choice: u8,
-    pub secret: String,
-    pub identity_secret: String,
+    /// Deprecated and otherwise ignored; if sent, it must be the caller's own identity.
+    #[serde(default)]
+    pub identity_secret: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -116,6 +272,9 @@ pub struct RevealRequest {
     pub public_inputs: Vec<String>,
     pub commitment: String,
     pub nullifier: String,
+    /// `version` of the proof bundle; defaults to 1.
+    #[serde(default = "default_proof_version")]
+    pub version: u16,
 }
 
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -130,10 +289,165 @@ pub struct MembershipStatusResponse {
     pub poll_id: i64,
     pub membership_root: String,
     pub is_member: bool,
+    /// Length of `path_bits` and `path_siblings`.
+    pub merkle_depth: u32,
     pub path_bits: Option<Vec<String>>,
     pub path_siblings: Option<Vec<String>>,
 }
 
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RevealBatchResponse {
+    pub poll_id: i64,
+    /// Empty for a batch the relayer only simulated.
+    pub tx_hash: String,
+    pub item_count: i32,
+    pub submitted_at: DateTime<Utc>,
+    /// From the receipt; unset for batches only seen through indexed reveal logs.
+    pub gas_used: Option<i64>,
+    /// Wei per gas paid.
+    pub effective_gas_price: Option<i64>,
+    /// Ids of the commitments the batch revealed.
+    pub commit_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PollTransactionResponse {
+    /// `create`, `commit`, `reveal_batch` or `resolve`.
+    pub kind: String,
+    /// Raw `0x…` hash; building explorer links is left to clients.
+    pub tx_hash: String,
+    /// Unset for reveals and on-chain commits recorded before their times were.
+    pub timestamp: Option<DateTime<Utc>>,
+    /// `confirmed` when the relayer got its receipt, `indexed` when only seen in contract logs.
+    pub status: String,
+    /// Commitments revealed, for reveal batches.
+    pub item_count: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MyStatusResponse {
+    pub poll_id: i64,
+    pub committed: bool,
+    /// The commitment was included in an on-chain batch reveal.
+    pub onchain_submitted: bool,
+    /// The reveal window, plus the relayer's grace period and the contract's slack, passed
+    /// before it was submitted.
+    pub expired: bool,
+    /// A vote with this commitment's nullifier has been recorded.
+    pub revealed: bool,
+    pub reveal_tx_hash: Option<String>,
+    /// The member opted out of relayer reveals and must reveal on their own.
+    pub self_reveal: bool,
+    /// The relayer's last error submitting the commitment.
+    pub sync_error: Option<String>,
+    /// Failed batch reveals that included the commitment.
+    pub sync_attempts: i32,
+    /// When the relayer retries after a failed batch reveal.
+    pub next_sync_attempt_at: Option<DateTime<Utc>>,
+    /// `pending`, `synced`, `failed` (retrying) or `dead`: the relayer will not submit the
+    /// commitment unless an admin requeues it, but it can still be revealed directly. Unset
+    /// when there is no commitment.
+    pub sync_state: Option<String>,
+}
+
+/// The caller's own stored commitment, enough to rebuild a self-reveal after losing local state.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MyCommitResponse {
+    pub poll_id: i64,
+    pub commitment: String,
+    pub nullifier: String,
+    pub proof: String,
+    pub public_inputs: Vec<String>,
+    pub choice: i16,
+    pub recorded_at: DateTime<Utc>,
+    pub revision: i32,
+    pub auto_reveal: bool,
+    /// The commitment was included in an on-chain batch reveal.
+    pub onchain_submitted: bool,
+    /// Set once retention removed the proof; `proof` and `public_inputs` are then empty.
+    pub scrubbed_at: Option<DateTime<Utc>>,
+}
+
+/// Owner-facing view of a commitment; choice, secret, nullifier and proof are never included.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RedactedCommitResponse {
+    pub pseudonym: String,
+    pub recorded_at: DateTime<Utc>,
+    pub onchain_submitted: bool,
+    /// `pending`, `synced`, `failed` or `dead`.
+    pub sync_state: String,
+    /// Failed batch reveals that included the commitment.
+    pub sync_attempts: i32,
+    /// The relayer's last error submitting it.
+    pub sync_error: Option<String>,
+    /// `offchain`, or with `ONCHAIN_COMMITS` where its `commit()` tx is: `queued`,
+    /// `confirmed`, `failed` or `dead`. Only confirmed commitments are revealed.
+    pub commit_state: String,
+    pub commit_tx_hash: Option<String>,
+    /// The relayer's last error sending `commit()`.
+    pub commit_error: Option<String>,
+}
+
+/// Reveal sync progress for a poll, and what its batch reveals cost.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PollSyncStatusResponse {
+    pub poll_id: i64,
+    /// Reveal sync is done with the poll.
+    pub sync_completed: bool,
+    /// Relayed commitments by sync state.
+    pub pending: i64,
+    pub failed: i64,
+    pub synced: i64,
+    pub dead: i64,
+    /// Batch reveals with a recorded receipt, and the commitments they carried.
+    pub batch_count: i64,
+    pub batched_items: i64,
+    pub gas_used: i64,
+    /// Gas used times effective gas price over those batches, in wei, as a decimal string.
+    pub total_cost_wei: String,
+}
+
+/// Owner-facing participation funnel: eligible → fetched_secret → committed → revealed → synced.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ParticipationResponse {
+    pub poll_id: i64,
+    pub stages: Vec<FunnelStage>,
+    /// Committed members who opted out of relayer reveals; they only reach `synced` by
+    /// revealing on-chain themselves.
+    pub self_reveal: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FunnelStage {
+    pub stage: String,
+    pub count: i64,
+    /// Share of eligible members, 0-100 (0 when the poll has no members).
+    pub percent_of_eligible: f64,
+    /// Share of the previous stage, 0-100.
+    pub percent_of_previous: f64,
+}
+
+/// Revealed votes per arrival path (`api`, `relayer`, `chain`) and any reveals that reused a
+/// nullifier with a different choice.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct VoteSourcesResponse {
+    pub poll_id: i64,
+    pub api: i64,
+    pub relayer: i64,
+    pub chain: i64,
+    pub conflicts: Vec<VoteConflictResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct VoteConflictResponse {
+    pub nullifier: String,
+    pub kept_choice: i16,
+    pub kept_source: String,
+    pub rejected_choice: i16,
+    pub rejected_source: String,
+    pub detected_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct LoginRequest {
     pub username: String,
@@ -164,3 +478,245 @@ pub struct UserStatsResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rank: Option<u32>,
 }
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PruneStalePollsResponse {
+    pub dry_run: bool,
+    pub poll_ids: Vec<i64>,
+}
+
+/// What `POST /admin/polls/:id/scrub_secrets` removed, or would remove on a dry run.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ScrubSecretsResponse {
+    pub poll_id: i64,
+    pub dry_run: bool,
+    /// `poll_secrets` rows.
+    pub secrets_deleted: u64,
+    /// Commitments whose secret, proof and public inputs were blanked.
+    pub commitments_scrubbed: u64,
+    pub cached_proofs_deleted: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RequeueDeadEventsResponse {
+    pub requeued: u64,
+}
+
+/// A commitment reveal sync gave up on.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeadCommitResponse {
+    pub id: i64,
+    pub poll_id: i64,
+    pub commitment: String,
+    pub recorded_at: DateTime<Utc>,
+    pub sync_attempts: i32,
+    /// Why the relayer gave up on it.
+    pub sync_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RequeueDeadCommitResponse {
+    pub id: i64,
+    pub poll_id: i64,
+}
+
+/// Whether reveal sync is paused, and by whom since when.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SyncControlResponse {
+    pub paused: bool,
+    pub paused_by: Option<String>,
+    pub paused_at: Option<DateTime<Utc>>,
+}
+
+/// What a reveal sync round triggered through `POST /admin/sync_reveals` did.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RevealSyncResponse {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Polls with commitments due this round.
+    pub polls: usize,
+    /// batchReveal transactions that went through.
+    pub batches: usize,
+    /// Commitments marked synced.
+    pub synced: usize,
+    /// Commitments whose batch failed, or dropped because they could not be batched.
+    pub failed: usize,
+    /// Polls whose sync stopped on an error; the logs say why.
+    pub failed_polls: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MembershipSnapshotResponse {
+    /// sha256 over the ordered member list the tree was built from.
+    pub digest: String,
+    pub root: String,
+    pub member_count: usize,
+    pub merkle_depth: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PollMembershipRootResponse {
+    pub poll_id: i64,
+    /// The root frozen at poll creation.
+    pub membership_root: String,
+    /// sha256 over the poll's ordered member list.
+    pub snapshot_digest: String,
+    pub member_count: i64,
+    pub snapshot_at: DateTime<Utc>,
+    /// Set when `verify=true` was asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<MembershipRootVerification>,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MembershipRootVerification {
+    /// Root of a fresh build over the poll's stored members.
+    pub rebuilt_root: String,
+    pub matches: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ProveJobAccepted {
+    pub job_id: String,
+    /// The bundle came from the proof cache; the job is already `done`.
+    pub cache_hit: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ProveJobResponse {
+    pub job_id: String,
+    pub poll_id: i64,
+    /// `pending`, `running`, `done` or `failed`.
+    pub status: String,
+    /// Set once `done`.
+    pub bundle: Option<ProofBundle>,
+    /// Set once `failed`.
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IndexerStatusResponse {
+    /// `disabled` when the indexer was never started (no RPC endpoint or contract address),
+    /// `connecting` while it has no live feed, `connected` otherwise.
+    pub status: String,
+    /// Whether the on-chain indexer is running; false when it was never started.
+    pub running: bool,
+    pub connected: bool,
+    /// Blocks mined on top of a log before it is applied.
+    pub confirmations: u64,
+    pub head_block: u64,
+    /// Block of the last applied log.
+    pub last_block: u64,
+    /// Logs applied since startup.
+    pub confirmed_events: u64,
+    /// Logs waiting for confirmations.
+    pub unconfirmed_events: u64,
+    /// Staged logs dropped because their block was reorged out.
+    pub discarded_events: u64,
+    /// `head_block - last_block`, once the feed has reported a head.
+    pub lag_blocks: Option<u64>,
+    pub poll_created_events: u64,
+    pub committed_events: u64,
+    pub vote_revealed_events: u64,
+    pub poll_resolved_events: u64,
+    /// Logs matching no known event signature.
+    pub unknown_logs: u64,
+    /// Logs the indexer failed to apply.
+    pub handler_errors: u64,
+    /// Reconnects after the feed dropped or failed.
+    pub reconnects: u64,
+    /// Logs held until the poll they name is indexed.
+    pub pending_logs: u64,
+    /// Logs dropped because their poll never got indexed.
+    pub dead_lettered_logs: u64,
+    /// Logs that failed to apply and wait for a retry.
+    pub failed_events: u64,
+    /// Failed logs out of retries; `POST /admin/indexer/failed_events/requeue` retries them.
+    pub dead_events: u64,
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Why the last connection failed or the last log could not be applied.
+    pub last_error: Option<String>,
+}
+
+/// The relayer account, its funds, reveal sync's last tick and the gas settings `createPoll`
+/// and `batchReveal` are priced with.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RelayerStatusResponse {
+    /// `disabled` without a relayer key and contract, `low_funds` when no wallet's balance is
+    /// over `RELAYER_MIN_BALANCE_WEI`, `degraded` when some wallet is skipped, `ok` otherwise.
+    pub status: String,
+    /// Whether a relayer key and contract are configured; the account and gas fields are unset
+    /// when not.
+    pub enabled: bool,
+    /// `0x…` address of the first relayer wallet; every wallet is listed under `wallets`.
+    pub address: Option<String>,
+    pub chain_id: Option<u64>,
+    /// The best-funded wallet's balance as a decimal string; unset when the provider could not
+    /// be asked.
+    pub balance_wei: Option<String>,
+    /// `RELAYER_MIN_BALANCE_WEI` as a decimal string.
+    pub min_balance_wei: Option<String>,
+    /// Every wallet is under `RELAYER_MIN_BALANCE_WEI`; batch reveals will start failing.
+    pub low_funds: bool,
+    /// Auto-reveal commitments still waiting to go on chain, across all polls.
+    pub pending_commits: i64,
+    /// `REVEAL_BATCH_SIZE`.
+    pub batch_size: usize,
+    /// Reveals are only simulated: nothing is sent on chain.
+    pub dry_run: bool,
+    /// When the last reveal sync tick finished.
+    pub last_sync_at: Option<DateTime<Utc>>,
+    /// `ok` or `failed`; unset before the first tick.
+    pub last_sync_result: Option<String>,
+    /// Why the last tick failed.
+    pub last_sync_error: Option<String>,
+    /// Reveal sync is paused through `POST /admin/sync/pause`; nothing is submitted.
+    pub sync_paused: bool,
+    pub sync_paused_by: Option<String>,
+    pub sync_paused_at: Option<DateTime<Utc>>,
+    /// `eip1559`, or `legacy` when the chain's blocks carry no base fee.
+    pub fee_mode: Option<String>,
+    /// `MAX_FEE_PER_GAS_GWEI`, which also caps the legacy gas price; unset uses the estimate.
+    pub max_fee_per_gas_gwei: Option<u64>,
+    /// `MAX_PRIORITY_FEE_GWEI`; unset uses the estimate. Ignored in legacy mode.
+    pub max_priority_fee_gwei: Option<u64>,
+    /// `GAS_LIMIT_CAP`: calls estimated above it fail instead of being sent.
+    pub gas_limit_cap: Option<u64>,
+    /// batchReveal calls estimated above this are split: `BATCH_REVEAL_MAX_GAS`, or
+    /// `GAS_LIMIT_CAP` when that is unset.
+    pub batch_reveal_max_gas: Option<u64>,
+    /// One entry per relayer key, in `RELAYER_PRIVATE_KEYS` order.
+    pub wallets: Vec<RelayerWalletResponse>,
+}
+
+/// A relayer wallet's health. Sends rotate over the healthy ones.
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RelayerWalletResponse {
+    pub address: String,
+    /// Last known balance as a decimal string.
+    pub balance_wei: Option<String>,
+    /// The balance is under `RELAYER_MIN_BALANCE_WEI`.
+    pub low_funds: bool,
+    /// Sends in a row that failed; at `RELAYER_MAX_CONSECUTIVE_FAILURES` the wallet is skipped
+    /// until one succeeds.
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub healthy: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ZkInfoResponse {
+    /// Active proving backend: `noop`, `noir` or `remote`.
+    pub backend: String,
+    /// Circuit file name, when the backend loads one.
+    pub circuit_name: Option<String>,
+    /// Noir compiler version the circuit was built with.
+    pub circuit_version: Option<String>,
+    /// sha256 of the verifying key loaded at startup; compare with the on-chain verifier.
+    pub vk_sha256: Option<String>,
+    pub merkle_depth: u32,
+    /// Hash new polls build their membership trees with (`MERKLE_HASH`).
+    pub merkle_hash: String,
+}
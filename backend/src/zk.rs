@@ -1,28 +1,259 @@
-use crate::error::{AppError, AppResult};
-use crate::repo::PollRecord;
+use crate::error::{AppError, AppResult, ProofErrorKind};
+use crate::merkle::HashScheme;
+use crate::repo::{MerklePath, PollRecord};
 use async_trait::async_trait;
+use num_bigint::BigUint;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tracing::warn;
+
+const DEFAULT_PROVER_SCRIPT: &str = "./scripts/noir_prover.mjs";
+const DEFAULT_REMOTE_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_PROVER_TIMEOUT_SECS: u64 = 300;
+/// Extra attempts after a 5xx from the remote prover.
+const REMOTE_MAX_RETRIES: u32 = 2;
+const REMOTE_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Proof format of bundles written before `version` existed.
+pub const LEGACY_PROOF_VERSION: u16 = 1;
+/// Proof format produced by the configured backends.
+pub const CURRENT_PROOF_VERSION: u16 = 1;
+/// Formats `verify` and the on-chain verifier accept.
+pub const SUPPORTED_PROOF_VERSIONS: &[u16] = &[LEGACY_PROOF_VERSION];
+
+pub fn default_proof_version() -> u16 {
+    LEGACY_PROOF_VERSION
+}
+
+/// Public inputs of a proof. On the wire and in the database they stay the positional array
+/// `[choice, commitment, nullifier, poll_id, membership_root]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "Vec<String>", try_from = "Vec<String>")]
+pub struct PublicInputs {
+    pub choice: u8,
+    pub commitment: String,
+    pub nullifier: String,
+    pub poll_id: String,
+    pub membership_root: String,
+}
+
+const PUBLIC_INPUT_NAMES: [&str; 5] = [
+    "choice",
+    "commitment",
+    "nullifier",
+    "poll_id",
+    "membership_root",
+];
+
+impl PublicInputs {
+    pub fn to_vec(&self) -> Vec<String> {
+        let mut values = vec![self.choice.to_string()];
+        values.extend(self.circuit_inputs().map(str::to_string));
+        values
+    }
+
+    pub fn try_from_vec(values: Vec<String>) -> AppResult<Self> {
+        if values.len() > PUBLIC_INPUT_NAMES.len() {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                format!(
+                    "public_inputs has {} elements, expected {}",
+                    values.len(),
+                    PUBLIC_INPUT_NAMES.len()
+                ),
+            ));
+        }
+        let mut values = values.into_iter();
+        let mut next = |idx: usize| {
+            values.next().ok_or_else(|| {
+                AppError::proof(
+                    ProofErrorKind::InvalidInputs,
+                    format!(
+                        "public_inputs[{idx}] ({}) is missing",
+                        PUBLIC_INPUT_NAMES[idx]
+                    ),
+                )
+            })
+        };
+        let choice = next(0)?;
+        Ok(Self {
+            choice: choice.parse().map_err(|_| {
+                AppError::proof(
+                    ProofErrorKind::InvalidInputs,
+                    format!("public_inputs[0] (choice) is not a u8: {choice}"),
+                )
+            })?,
+            commitment: next(1)?,
+            nullifier: next(2)?,
+            poll_id: next(3)?,
+            membership_root: next(4)?,
+        })
+    }
+
+    /// The circuit's own public inputs, in the order its verifiers expect them.
+    pub fn circuit_inputs(&self) -> [&str; 4] {
+        [
+            &self.commitment,
+            &self.nullifier,
+            &self.poll_id,
+            &self.membership_root,
+        ]
+    }
+}
+
+impl From<PublicInputs> for Vec<String> {
+    fn from(inputs: PublicInputs) -> Self {
+        inputs.to_vec()
+    }
+}
+
+impl TryFrom<Vec<String>> for PublicInputs {
+    type Error = AppError;
+
+    fn try_from(values: Vec<String>) -> AppResult<Self> {
+        Self::try_from_vec(values)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ProofBundle {
     pub proof: String,
-    pub public_inputs: Vec<String>,
+    /// `[choice, commitment, nullifier, poll_id, membership_root]`
+    #[schema(value_type = Vec<String>)]
+    pub public_inputs: PublicInputs,
     pub commitment: String,
     pub nullifier: String,
+    /// Proof format; bundles without it are version 1.
+    #[serde(default = "default_proof_version")]
+    pub version: u16,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl ProofBundle {
+    pub fn check_version(&self) -> AppResult<()> {
+        check_proof_version(self.version)
+    }
+}
+
+pub fn check_proof_version(version: u16) -> AppResult<()> {
+    if !SUPPORTED_PROOF_VERSIONS.contains(&version) {
+        return Err(AppError::proof(
+            ProofErrorKind::InvalidInputs,
+            format!(
+                "unsupported proof version {version} (supported: {SUPPORTED_PROOF_VERSIONS:?})"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct ProofRequest<'a> {
     pub poll_id: i64,
     pub choice: u8,
+    /// Option count of the poll; `choice` must be below it.
+    pub num_options: usize,
     pub secret: &'a str,
     pub identity_secret: &'a str,
     pub membership_root: &'a str,
+    /// The prover's leaf path in the poll's membership tree, resolved by the server.
+    pub path: Option<&'a MerklePath>,
+    /// Hash the poll's membership tree was built with; `path` and `membership_root` use it.
+    pub hash_scheme: HashScheme,
+}
+
+/// Inputs to the commitment and nullifier hashes.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CommitmentInput<'a> {
+    pub poll_id: i64,
+    pub choice: u8,
+    pub secret: &'a str,
+    pub identity_secret: &'a str,
+}
+
+/// The public values a proof binds; what `/commit` expects for a given choice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commitment {
+    pub commitment: String,
+    pub nullifier: String,
+}
+
+impl ProofRequest<'_> {
+    pub fn commitment_input(&self) -> CommitmentInput<'_> {
+        CommitmentInput {
+            poll_id: self.poll_id,
+            choice: self.choice,
+            secret: self.secret,
+            identity_secret: self.identity_secret,
+        }
+    }
+
+    /// Hash of every input that affects the proof, for caching bundles.
+    pub fn cache_key(&self) -> String {
+        let path = self.path.map_or(String::new(), |p| {
+            format!("{}/{}", p.bits.join(","), p.siblings.join(","))
+        });
+        hex_sha256(&format!(
+            "{}:{}:{}:{}:{}:{}:{}",
+            self.poll_id,
+            self.choice,
+            self.num_options,
+            self.secret,
+            self.identity_secret,
+            self.membership_root,
+            path
+        ))
+    }
+
+    fn check_choice(&self) -> AppResult<()> {
+        if usize::from(self.choice) >= self.num_options {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                format!("choice must be below {}", self.num_options),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Name, compiler version and Merkle depth of the loaded circuit, reported by `/zk/info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitInfo {
+    pub name: String,
+    pub version: String,
+    /// Length of the circuit's `path_bits` input, when its ABI declares one.
+    pub merkle_depth: Option<u32>,
 }
 
 #[async_trait]
 pub trait ZkBackend {
+    /// Short identifier reported by `/zk/info`.
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+    /// The circuit this backend proves, when it knows it.
+    fn circuit(&self) -> Option<CircuitInfo> {
+        None
+    }
+    /// Whether this backend can prove membership in a tree built with `scheme`. The circuit
+    /// hashes its path with Poseidon, so that is all a backend supports unless it says so.
+    fn supports_hash_scheme(&self, scheme: HashScheme) -> bool {
+        scheme == HashScheme::Poseidon
+    }
+    /// Commitment and nullifier exactly as `prove` would derive them.
+    async fn commitment(&self, input: CommitmentInput<'_>) -> AppResult<Commitment> {
+        let _ = input;
+        Err(AppError::External(format!(
+            "the {} backend cannot compute commitments",
+            self.name()
+        )))
+    }
     async fn prove(&self, req: ProofRequest<'_>) -> AppResult<ProofBundle>;
     async fn verify(&self, poll: &PollRecord, bundle: &ProofBundle) -> AppResult<()>;
 }
@@ -33,46 +264,770 @@ pub struct NoopZkBackend;
 
 #[async_trait]
 impl ZkBackend for NoopZkBackend {
+    fn name(&self) -> &'static str {
+        "noop"
+    }
+
+    fn supports_hash_scheme(&self, _scheme: HashScheme) -> bool {
+        true
+    }
+
+    async fn commitment(&self, input: CommitmentInput<'_>) -> AppResult<Commitment> {
+        Ok(Commitment {
+            commitment: hex_sha256(&format!("{}:{}", input.choice, input.secret)),
+            nullifier: hex_sha256(&format!("{}:{}", input.identity_secret, input.poll_id)),
+        })
+    }
+
     async fn prove(&self, req: ProofRequest<'_>) -> AppResult<ProofBundle> {
-        if req.choice > 1 {
-            return Err(AppError::Validation("choice must be 0 or 1".into()));
-        }
-        let commitment = hex_sha256(&format!("{}:{}", req.choice, req.secret));
-        let nullifier = hex_sha256(&format!("{}:{}", req.identity_secret, req.poll_id));
-        let proof = hex_sha256(&format!(
-            "{}:{}:{}:{}",
-            req.poll_id, req.membership_root, commitment, nullifier
-        ));
+        req.check_choice()?;
+        let Commitment {
+            commitment,
+            nullifier,
+        } = self.commitment(req.commitment_input()).await?;
+        let proof = mock_proof(req.poll_id, req.membership_root, &commitment, &nullifier);
         Ok(ProofBundle {
             proof,
-            public_inputs: vec![
-                req.choice.to_string(),
-                commitment.clone(),
-                nullifier.clone(),
-            ],
+            public_inputs: PublicInputs {
+                choice: req.choice,
+                commitment: commitment.clone(),
+                nullifier: nullifier.clone(),
+                poll_id: req.poll_id.to_string(),
+                membership_root: req.membership_root.to_string(),
+            },
             commitment,
             nullifier,
+            version: CURRENT_PROOF_VERSION,
         })
     }
 
     async fn verify(&self, poll: &PollRecord, bundle: &ProofBundle) -> AppResult<()> {
+        bundle.check_version()?;
         if poll.options.len() < 2 {
-            return Err(AppError::Validation("poll options invalid".into()));
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "poll options invalid",
+            ));
         }
-        if bundle.proof.is_empty() || bundle.public_inputs.is_empty() {
-            return Err(AppError::Validation("proof/public inputs empty".into()));
+        if bundle.proof.is_empty() {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "proof empty",
+            ));
         }
+        let inputs = &bundle.public_inputs;
         // In this mock backend we simply ensure the commitment/nullifier match the payload.
-        if bundle.commitment != *bundle.public_inputs.get(1).unwrap_or(&"".to_string()) {
-            return Err(AppError::Validation("commitment mismatch".into()));
+        if bundle.commitment != inputs.commitment {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "commitment mismatch",
+            ));
         }
-        if bundle.nullifier != *bundle.public_inputs.get(2).unwrap_or(&"".to_string()) {
-            return Err(AppError::Validation("nullifier mismatch".into()));
+        if bundle.nullifier != inputs.nullifier {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "nullifier mismatch",
+            ));
+        }
+        if inputs.poll_id != poll.id.to_string() {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "proof is for another poll",
+            ));
+        }
+        if inputs.membership_root != poll.membership_root {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "proof is for another membership root",
+            ));
+        }
+        // The mock proof binds the poll and its membership root, so bundles can't be replayed.
+        let expected = mock_proof(
+            poll.id,
+            &poll.membership_root,
+            &bundle.commitment,
+            &bundle.nullifier,
+        );
+        if bundle.proof != expected {
+            return Err(AppError::proof(
+                ProofErrorKind::VerificationFailed,
+                "proof does not match this poll",
+            ));
         }
         Ok(())
     }
 }
 
+/// Real backend: proves and verifies the compiled Noir circuit (UltraHonk) through the
+/// `noir_prover.mjs` node script.
+#[derive(Debug, Clone)]
+pub struct NoirZkBackend {
+    circuit_path: PathBuf,
+    prover_script: PathBuf,
+    /// Circuits without a `num_options` input hardcode a yes/no choice.
+    max_options: Option<usize>,
+    circuit: CircuitInfo,
+    vk: Option<VerificationKey>,
+    timeout: Duration,
+}
+
+impl NoirZkBackend {
+    pub fn new(circuit_path: PathBuf, prover_script: PathBuf) -> AppResult<Self> {
+        for (var, path) in [
+            ("ZK_CIRCUIT_PATH", &circuit_path),
+            ("ZK_PROVER_SCRIPT", &prover_script),
+        ] {
+            if !path.is_file() {
+                return Err(AppError::Validation(format!(
+                    "{var} {} does not exist",
+                    path.display()
+                )));
+            }
+        }
+        let circuit: serde_json::Value = std::fs::read(&circuit_path)
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .ok_or_else(|| {
+                AppError::Validation(format!(
+                    "ZK_CIRCUIT_PATH {} is not a compiled Noir circuit",
+                    circuit_path.display()
+                ))
+            })?;
+        let takes_num_options = circuit["abi"]["parameters"]
+            .as_array()
+            .is_some_and(|params| params.iter().any(|p| p["name"] == "num_options"));
+        let info = CircuitInfo {
+            name: circuit_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            version: circuit["noir_version"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string(),
+            merkle_depth: circuit["abi"]["parameters"].as_array().and_then(|params| {
+                let path = params.iter().find(|p| p["name"] == "path_bits")?;
+                path["type"]["length"].as_u64().map(|len| len as u32)
+            }),
+        };
+        Ok(Self {
+            circuit_path,
+            prover_script,
+            max_options: (!takes_num_options).then_some(2),
+            circuit: info,
+            vk: None,
+            timeout: Duration::from_secs(DEFAULT_PROVER_TIMEOUT_SECS),
+        })
+    }
+
+    /// Bounds how long one prover run may take before it is killed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Pins the verifying key proofs are checked against.
+    pub fn with_verification_key(mut self, vk: Option<VerificationKey>) -> Self {
+        self.vk = vk;
+        self
+    }
+
+    async fn run(&self, mode: &str, input: serde_json::Value) -> AppResult<serde_json::Value> {
+        // Inputs go in over stdin: they carry vote secrets, which must never be left on disk
+        // when the run is cancelled.
+        let mut cmd = Command::new("node");
+        cmd.arg(&self.prover_script)
+            .arg(mode)
+            .arg(&self.circuit_path);
+        let output = run_prover(cmd, input.to_string().as_bytes(), self.timeout).await?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(AppError::proof(
+                ProofErrorKind::ProverUnavailable,
+                format!("noir {mode} failed: {stderr}"),
+            ));
+        }
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| AppError::proof(ProofErrorKind::ProverUnavailable, e.to_string()))
+    }
+}
+
+#[async_trait]
+impl ZkBackend for NoirZkBackend {
+    fn name(&self) -> &'static str {
+        "noir"
+    }
+
+    fn circuit(&self) -> Option<CircuitInfo> {
+        Some(self.circuit.clone())
+    }
+
+    async fn commitment(&self, input: CommitmentInput<'_>) -> AppResult<Commitment> {
+        let out = self
+            .run(
+                "commitment",
+                serde_json::json!({
+                    "poll_id": input.poll_id,
+                    "choice": input.choice,
+                    "secret": input.secret,
+                    "identity_secret": input.identity_secret,
+                }),
+            )
+            .await?;
+        serde_json::from_value(out).map_err(|e| AppError::External(e.to_string()))
+    }
+
+    async fn prove(&self, req: ProofRequest<'_>) -> AppResult<ProofBundle> {
+        req.check_choice()?;
+        if let Some(max) = self.max_options.filter(|max| req.num_options > *max) {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                format!("the configured circuit only supports {max}-option polls"),
+            ));
+        }
+        let path = req.path.ok_or_else(|| {
+            AppError::proof(ProofErrorKind::InvalidInputs, "membership path is required")
+        })?;
+        if !self.supports_hash_scheme(req.hash_scheme) {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                format!(
+                    "the circuit cannot prove a {} membership tree",
+                    req.hash_scheme.as_str()
+                ),
+            ));
+        }
+        let out = self
+            .run(
+                "prove",
+                serde_json::json!({
+                    "poll_id": req.poll_id,
+                    "choice": req.choice,
+                    "num_options": req.num_options,
+                    "secret": req.secret,
+                    "identity_secret": req.identity_secret,
+                    "membership_root": req.membership_root,
+                    "path_bits": path.bits,
+                    "path_siblings": path.siblings,
+                }),
+            )
+            .await?;
+        serde_json::from_value(out).map_err(|e| AppError::External(e.to_string()))
+    }
+
+    async fn verify(&self, poll: &PollRecord, bundle: &ProofBundle) -> AppResult<()> {
+        bundle.check_version()?;
+        let circuit_inputs = bundle.public_inputs.circuit_inputs();
+        let [commitment, nullifier, poll_id, root] = circuit_inputs;
+        if !field_eq(commitment, &bundle.commitment) || !field_eq(nullifier, &bundle.nullifier) {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "commitment/nullifier do not match public inputs",
+            ));
+        }
+        if !field_eq(poll_id, &poll.id.to_string()) {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "proof is for another poll",
+            ));
+        }
+        if !field_eq(root, &poll.membership_root) {
+            return Err(AppError::proof(
+                ProofErrorKind::InvalidInputs,
+                "proof is for another membership root",
+            ));
+        }
+        let out = self
+            .run(
+                "verify",
+                serde_json::json!({
+                    "proof": bundle.proof,
+                    "public_inputs": circuit_inputs,
+                    "vk": self.vk.as_ref().map(VerificationKey::hex),
+                }),
+            )
+            .await?;
+        if out.get("valid").and_then(|v| v.as_bool()) != Some(true) {
+            return Err(AppError::proof(
+                ProofErrorKind::VerificationFailed,
+                "proof verification failed",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Delegates proving and verification to a prover service over HTTP:
+/// `POST {url}/prove` takes a `ProofRequest` and returns a `ProofBundle`, `POST {url}/verify`
+/// takes `{ poll_id, membership_root, bundle }` and returns `{ valid, error? }`.
+#[derive(Debug, Clone)]
+pub struct RemoteZkBackend {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RemoteVerifyResponse {
+    valid: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl RemoteZkBackend {
+    pub fn new(url: &str, api_key: Option<String>, timeout: Duration) -> AppResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| AppError::External(format!("remote prover client: {e}")))?;
+        Ok(Self {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+        })
+    }
+
+    /// POSTs `body` to `{url}/{path}`, retrying 5xx responses. Timeouts surface as a `Timeout`
+    /// proof error; transport failures, other non-200 statuses and undecodable bodies as
+    /// `ProverUnavailable`.
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> AppResult<T> {
+        let url = format!("{}/{path}", self.url);
+        let transport_error = |e: reqwest::Error| {
+            if e.is_timeout() {
+                AppError::proof(
+                    ProofErrorKind::Timeout,
+                    format!("remote prover {path} timed out"),
+                )
+            } else {
+                AppError::proof(
+                    ProofErrorKind::ProverUnavailable,
+                    format!("remote prover {path} failed: {e}"),
+                )
+            }
+        };
+        let mut attempt = 0;
+        loop {
+            let mut req = self.client.post(&url).json(body);
+            if let Some(key) = &self.api_key {
+                req = req.bearer_auth(key);
+            }
+            let res = req.send().await.map_err(transport_error)?;
+            let status = res.status();
+            if status.is_server_error() && attempt < REMOTE_MAX_RETRIES {
+                attempt += 1;
+                warn!(%status, attempt, path, "remote prover error, retrying");
+                tokio::time::sleep(REMOTE_RETRY_BACKOFF * attempt).await;
+                continue;
+            }
+            if status != reqwest::StatusCode::OK {
+                let detail = res.text().await.unwrap_or_default();
+                return Err(AppError::proof(
+                    ProofErrorKind::ProverUnavailable,
+                    format!("remote prover {path} returned {status}: {detail}"),
+                ));
+            }
+            let bytes = res.bytes().await.map_err(transport_error)?;
+            return serde_json::from_slice(&bytes).map_err(|e| {
+                AppError::proof(
+                    ProofErrorKind::ProverUnavailable,
+                    format!("remote prover {path} sent a malformed body: {e}"),
+                )
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl ZkBackend for RemoteZkBackend {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    async fn commitment(&self, input: CommitmentInput<'_>) -> AppResult<Commitment> {
+        self.post("commitment", &input).await
+    }
+
+    async fn prove(&self, req: ProofRequest<'_>) -> AppResult<ProofBundle> {
+        self.post("prove", &req).await
+    }
+
+    async fn verify(&self, poll: &PollRecord, bundle: &ProofBundle) -> AppResult<()> {
+        bundle.check_version()?;
+        let res: RemoteVerifyResponse = self
+            .post(
+                "verify",
+                &serde_json::json!({
+                    "poll_id": poll.id,
+                    "membership_root": poll.membership_root,
+                    "bundle": bundle,
+                }),
+            )
+            .await?;
+        if !res.valid {
+            return Err(AppError::proof(
+                ProofErrorKind::VerificationFailed,
+                format!(
+                    "proof verification failed: {}",
+                    res.error.unwrap_or_default()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Kills a prover's whole process group when dropped, unless it was disarmed after the prover
+/// exited. Covers timeouts as well as callers that stop polling (a client disconnecting, an
+/// aborted job), so `node` and anything it spawned never outlive the request.
+struct ProcessGroupGuard {
+    pid: Option<u32>,
+}
+
+impl ProcessGroupGuard {
+    fn kill(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.pid.take() {
+            // SAFETY: plain syscall; a negative pid targets the group `run_prover` created.
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+
+    fn disarm(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+async fn read_all(pipe: Option<impl AsyncRead + Unpin>) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut buf).await?;
+    }
+    Ok(buf)
+}
+
+async fn collect_output(child: &mut Child, input: &[u8]) -> std::io::Result<Output> {
+    let mut stdin = child.stdin.take();
+    let write = async move {
+        if let Some(stdin) = stdin.as_mut() {
+            stdin.write_all(input).await?;
+            stdin.shutdown().await?;
+        }
+        // Dropping the handle closes the pipe: the prover reads until EOF.
+        drop(stdin);
+        std::io::Result::Ok(())
+    };
+    let stdout = read_all(child.stdout.take());
+    let stderr = read_all(child.stderr.take());
+    let (written, status, stdout, stderr) = tokio::join!(write, child.wait(), stdout, stderr);
+    let output = Output {
+        status: status?,
+        stdout: stdout?,
+        stderr: stderr?,
+    };
+    // A prover that exited cleanly without reading its input is still an error.
+    if output.status.success() {
+        written?;
+    }
+    Ok(output)
+}
+
+/// Runs a prover subprocess in its own process group, feeds it `input` on stdin and collects
+/// its output. Past `timeout` the group is killed and reaped and a `Timeout` proof error is
+/// returned; dropping the future kills the group as well.
+pub async fn run_prover(mut cmd: Command, input: &[u8], timeout: Duration) -> AppResult<Output> {
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| AppError::proof(ProofErrorKind::ProverUnavailable, e.to_string()))?;
+    let mut guard = ProcessGroupGuard { pid: child.id() };
+    match tokio::time::timeout(timeout, collect_output(&mut child, input)).await {
+        Ok(output) => {
+            guard.disarm();
+            output.map_err(|e| AppError::proof(ProofErrorKind::ProverUnavailable, e.to_string()))
+        }
+        Err(_) => {
+            guard.kill();
+            let _ = child.start_kill();
+            let _ = child.wait().await;
+            Err(AppError::proof(
+                ProofErrorKind::Timeout,
+                format!("prover did not finish within {}s", timeout.as_secs()),
+            ))
+        }
+    }
+}
+
+/// Which prover `main` wires up, chosen by `ZK_BACKEND`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZkBackendConfig {
+    Noop,
+    Noir {
+        circuit_path: PathBuf,
+        prover_script: PathBuf,
+        timeout: Duration,
+    },
+    Remote {
+        url: String,
+        api_key: Option<String>,
+        timeout: Duration,
+    },
+}
+
+impl ZkBackendConfig {
+    /// Reads `ZK_BACKEND` (`noop` | `noir` | `remote`) and its sub-settings through `var`,
+    /// failing when the chosen backend's required settings are missing.
+    pub fn parse(var: impl Fn(&str) -> Option<String>) -> AppResult<Self> {
+        let var = |key: &str| var(key).filter(|v| !v.trim().is_empty());
+        let kind = var("ZK_BACKEND").unwrap_or_default();
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "" | "noop" => Ok(Self::Noop),
+            "noir" => {
+                let circuit_path = var("ZK_CIRCUIT_PATH").ok_or_else(|| {
+                    AppError::Validation("ZK_CIRCUIT_PATH is required when ZK_BACKEND=noir".into())
+                })?;
+                let timeout_secs = var("PROVER_TIMEOUT_SECS")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DEFAULT_PROVER_TIMEOUT_SECS);
+                Ok(Self::Noir {
+                    circuit_path: circuit_path.into(),
+                    prover_script: var("ZK_PROVER_SCRIPT")
+                        .unwrap_or_else(|| DEFAULT_PROVER_SCRIPT.to_string())
+                        .into(),
+                    timeout: Duration::from_secs(timeout_secs),
+                })
+            }
+            "remote" => {
+                let url = var("ZK_REMOTE_URL").ok_or_else(|| {
+                    AppError::Validation("ZK_REMOTE_URL is required when ZK_BACKEND=remote".into())
+                })?;
+                let timeout_secs = var("ZK_REMOTE_TIMEOUT_SECS")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DEFAULT_REMOTE_TIMEOUT_SECS);
+                Ok(Self::Remote {
+                    url,
+                    api_key: var("ZK_REMOTE_API_KEY"),
+                    timeout: Duration::from_secs(timeout_secs),
+                })
+            }
+            other => Err(AppError::Validation(format!(
+                "unknown ZK_BACKEND {other:?} (expected noop, noir or remote)"
+            ))),
+        }
+    }
+}
+
+/// Verifying key read once at startup, so verification never goes back to disk for it.
+#[derive(Debug, Clone)]
+pub struct VerificationKey {
+    bytes: Arc<[u8]>,
+    sha256: String,
+}
+
+impl VerificationKey {
+    /// Reads the key at `path`, failing when it is unreadable, empty or (if `expected_sha256`
+    /// is given) not the pinned key.
+    pub fn load(path: &Path, expected_sha256: Option<&str>) -> AppResult<Self> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            AppError::Validation(format!(
+                "ZK_VK_PATH {} could not be read: {e}",
+                path.display()
+            ))
+        })?;
+        if bytes.is_empty() {
+            return Err(AppError::Validation(format!(
+                "ZK_VK_PATH {} is empty",
+                path.display()
+            )));
+        }
+        let sha256 = hex::encode(Sha256::digest(&bytes));
+        if let Some(expected) = expected_sha256 {
+            let expected = expected
+                .trim()
+                .trim_start_matches("0x")
+                .to_ascii_lowercase();
+            if expected != sha256 {
+                return Err(AppError::Validation(format!(
+                    "ZK_VK_PATH {} has sha256 {sha256}, expected ZK_VK_SHA256 {expected}",
+                    path.display()
+                )));
+            }
+        }
+        Ok(Self {
+            bytes: bytes.into(),
+            sha256,
+        })
+    }
+
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    fn hex(&self) -> String {
+        format!("0x{}", hex::encode(&self.bytes))
+    }
+}
+
+/// Where the verifying key lives (`ZK_VK_PATH`) and the digest it must have (`ZK_VK_SHA256`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationKeyConfig {
+    pub path: Option<PathBuf>,
+    pub sha256: Option<String>,
+}
+
+impl VerificationKeyConfig {
+    pub fn parse(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |key: &str| var(key).filter(|v| !v.trim().is_empty());
+        Self {
+            path: var("ZK_VK_PATH").map(PathBuf::from),
+            sha256: var("ZK_VK_SHA256"),
+        }
+    }
+
+    /// Loads the key for `backend`: required for `noir`, optional for `remote` and ignored for
+    /// `noop`, whose mock proofs have no key.
+    pub fn load(&self, backend: &ZkBackendConfig) -> AppResult<Option<VerificationKey>> {
+        match (backend, &self.path) {
+            (ZkBackendConfig::Noop, _) => Ok(None),
+            (ZkBackendConfig::Noir { .. }, None) => Err(AppError::Validation(
+                "ZK_VK_PATH is required when ZK_BACKEND=noir".into(),
+            )),
+            (_, None) => Ok(None),
+            (_, Some(path)) => VerificationKey::load(path, self.sha256.as_deref()).map(Some),
+        }
+    }
+}
+
+/// Dispatches to whichever backend was configured, so `AppState` keeps one concrete type.
+#[derive(Clone)]
+pub enum AnyZkBackend {
+    Noop(NoopZkBackend),
+    Noir(NoirZkBackend),
+    Remote(RemoteZkBackend),
+    /// Any other implementation (tests, wrappers).
+    #[allow(dead_code)]
+    Custom(Arc<dyn ZkBackend + Send + Sync>),
+}
+
+impl AnyZkBackend {
+    pub fn from_config(cfg: ZkBackendConfig) -> AppResult<Self> {
+        match cfg {
+            ZkBackendConfig::Noop => Ok(Self::Noop(NoopZkBackend)),
+            ZkBackendConfig::Noir {
+                circuit_path,
+                prover_script,
+                timeout,
+            } => NoirZkBackend::new(circuit_path, prover_script)
+                .map(|b| Self::Noir(b.with_timeout(timeout))),
+            ZkBackendConfig::Remote {
+                url,
+                api_key,
+                timeout,
+            } => RemoteZkBackend::new(&url, api_key, timeout).map(Self::Remote),
+        }
+    }
+
+    pub fn with_verification_key(self, vk: Option<VerificationKey>) -> Self {
+        match self {
+            Self::Noir(b) => Self::Noir(b.with_verification_key(vk)),
+            other => other,
+        }
+    }
+}
+
+#[async_trait]
+impl ZkBackend for AnyZkBackend {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Noop(b) => b.name(),
+            Self::Noir(b) => b.name(),
+            Self::Remote(b) => b.name(),
+            Self::Custom(b) => b.name(),
+        }
+    }
+
+    fn circuit(&self) -> Option<CircuitInfo> {
+        match self {
+            Self::Noop(b) => b.circuit(),
+            Self::Noir(b) => b.circuit(),
+            Self::Remote(b) => b.circuit(),
+            Self::Custom(b) => b.circuit(),
+        }
+    }
+
+    fn supports_hash_scheme(&self, scheme: HashScheme) -> bool {
+        match self {
+            Self::Noop(b) => b.supports_hash_scheme(scheme),
+            Self::Noir(b) => b.supports_hash_scheme(scheme),
+            Self::Remote(b) => b.supports_hash_scheme(scheme),
+            Self::Custom(b) => b.supports_hash_scheme(scheme),
+        }
+    }
+
+    async fn commitment(&self, input: CommitmentInput<'_>) -> AppResult<Commitment> {
+        match self {
+            Self::Noop(b) => b.commitment(input).await,
+            Self::Noir(b) => b.commitment(input).await,
+            Self::Remote(b) => b.commitment(input).await,
+            Self::Custom(b) => b.commitment(input).await,
+        }
+    }
+
+    async fn prove(&self, req: ProofRequest<'_>) -> AppResult<ProofBundle> {
+        match self {
+            Self::Noop(b) => b.prove(req).await,
+            Self::Noir(b) => b.prove(req).await,
+            Self::Remote(b) => b.prove(req).await,
+            Self::Custom(b) => b.prove(req).await,
+        }
+    }
+
+    async fn verify(&self, poll: &PollRecord, bundle: &ProofBundle) -> AppResult<()> {
+        match self {
+            Self::Noop(b) => b.verify(poll, bundle).await,
+            Self::Noir(b) => b.verify(poll, bundle).await,
+            Self::Remote(b) => b.verify(poll, bundle).await,
+            Self::Custom(b) => b.verify(poll, bundle).await,
+        }
+    }
+}
+
+/// The noop backend's stand-in proof: a hash binding the poll, its root and the public values.
+pub fn mock_proof(
+    poll_id: i64,
+    membership_root: &str,
+    commitment: &str,
+    nullifier: &str,
+) -> String {
+    hex_sha256(&format!(
+        "{poll_id}:{membership_root}:{commitment}:{nullifier}"
+    ))
+}
+
+/// Compares two field elements written in decimal or `0x` hex.
+fn field_eq(a: &str, b: &str) -> bool {
+    fn parse(s: &str) -> Option<BigUint> {
+        let s = s.trim();
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+            None => BigUint::parse_bytes(s.as_bytes(), 10),
+        }
+    }
+    matches!((parse(a), parse(b)), (Some(a), Some(b)) if a == b)
+}
+
 fn hex_sha256(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
@@ -0,0 +1,54 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::ThrowawayDb;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, NewPoll, PgStore, PollIndexSink, PollStore};
+
+fn new_poll<'a>(options: &'a [String], owner: &'a str, anonymous_owner: bool) -> NewPoll<'a> {
+    NewPoll {
+        question: "Sensitive?",
+        options,
+        commit_phase_end: Utc::now() + Duration::minutes(10),
+        reveal_phase_end: Utc::now() + Duration::minutes(20),
+        membership_root: "root",
+        category: "General",
+        owner,
+        auto_resolve: false,
+        visibility: "public",
+        anonymous_owner,
+        option_meta: &[],
+    }
+}
+
+#[tokio::test]
+async fn api_create_after_the_indexer_keeps_the_owner_anonymous() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    let options = vec!["Yes".to_string(), "No".to_string()];
+
+    // PollCreated was indexed before the API stored the poll it sent.
+    store
+        .upsert_poll_from_chain(7, new_poll(&options, "0xabc", false), None)
+        .await
+        .unwrap();
+    let created = store
+        .create_poll_with_id(7, new_poll(&options, "alice", true), "root".into(), vec![])
+        .await
+        .unwrap();
+
+    assert!(created.anonymous_owner);
+    // The flag is what blanks the owner for everyone but alice and admins.
+    let poll = store.get_poll(7).await.unwrap();
+    assert!(poll.anonymous_owner);
+    assert_eq!(poll.owner, "alice");
+
+    db.drop().await;
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::ThrowawayDb;
+use std::time::Duration;
+use veilcast_backend::error::AppError;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollStore};
+
+#[tokio::test]
+async fn queries_stuck_behind_a_lock_give_up_at_the_statement_timeout() {
+    let db = ThrowawayDb::create().await;
+    let settings = DbSettings {
+        max_connections: 2,
+        min_connections: 1,
+        statement_timeout: Some(Duration::from_millis(200)),
+        ..DbSettings::default()
+    };
+    let store = PgStore::connect(
+        &db.url,
+        &settings,
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    // A migration or long report elsewhere holding the polls table.
+    let mut lock = db.pool.begin().await.unwrap();
+    sqlx::query(r#"LOCK TABLE polls IN ACCESS EXCLUSIVE MODE"#)
+        .execute(&mut *lock)
+        .await
+        .unwrap();
+
+    let err = tokio::time::timeout(Duration::from_secs(10), store.get_poll(1))
+        .await
+        .expect("statement_timeout should end the wait")
+        .unwrap_err();
+    assert!(matches!(err, AppError::Db(_)), "{err:?}");
+
+    lock.rollback().await.unwrap();
+    assert!(matches!(store.get_poll(1).await, Err(AppError::NotFound)));
+    db.drop().await;
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::ThrowawayDb;
+use veilcast_backend::crypto::FieldCipher;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollStore, StoredCommit};
+use veilcast_backend::zk::CURRENT_PROOF_VERSION;
+
+fn public_inputs(choice: &str) -> Vec<String> {
+    vec![
+        choice.into(),
+        "0xc".into(),
+        "0xn".into(),
+        "1".into(),
+        "root".into(),
+    ]
+}
+
+async fn stored_inputs(db: &ThrowawayDb, identity: &str) -> Vec<String> {
+    sqlx::query_scalar(r#"SELECT public_inputs FROM commitments WHERE identity_secret = $1"#)
+        .bind(identity)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn the_choice_in_public_inputs_is_sealed_at_rest() {
+    let db = ThrowawayDb::create().await;
+    let plain = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    let store = plain
+        .clone()
+        .with_cipher(FieldCipher::new(vec![("k1".to_string(), [1u8; 32])]).unwrap());
+    sqlx::query(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root)
+        VALUES (1, 'Sealed', '["Yes","No"]', now() - interval '1 minute', now() + interval '1 hour', 'root')
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    // A row written before FIELD_ENCRYPTION_KEYS was set.
+    let legacy_inputs = public_inputs("0");
+    plain
+        .record_commit(StoredCommit {
+            poll_id: 1,
+            choice: 0,
+            commitment: "0xlegacy",
+            identity_secret: "legacy",
+            secret: "legacy-secret",
+            nullifier: "0xlegacy",
+            proof: "0x00",
+            public_inputs: &legacy_inputs,
+            proof_version: CURRENT_PROOF_VERSION,
+            auto_reveal: true,
+            onchain_commit: false,
+        })
+        .await
+        .unwrap();
+    assert_eq!(stored_inputs(&db, "legacy").await, legacy_inputs);
+
+    let inputs = public_inputs("1");
+    let recorded = store
+        .record_commit(StoredCommit {
+            poll_id: 1,
+            choice: 1,
+            commitment: "0xfresh",
+            identity_secret: "fresh",
+            secret: "fresh-secret",
+            nullifier: "0xfresh",
+            proof: "0x00",
+            public_inputs: &inputs,
+            proof_version: CURRENT_PROOF_VERSION,
+            auto_reveal: true,
+            onchain_commit: false,
+        })
+        .await
+        .unwrap();
+    assert_eq!(recorded.public_inputs, inputs);
+    let raw = stored_inputs(&db, "fresh").await;
+    assert!(raw[0].starts_with("enc:k1:"), "{raw:?}");
+    assert_eq!(raw[1..], inputs[1..]);
+
+    assert_eq!(store.encrypt_stored_secrets().await.unwrap(), 1);
+    assert!(stored_inputs(&db, "legacy").await[0].starts_with("enc:k1:"));
+    assert_eq!(store.encrypt_stored_secrets().await.unwrap(), 0);
+
+    let synced = store
+        .commits_to_sync(Utc::now(), Duration::zero(), Duration::zero(), false, 10)
+        .await
+        .unwrap();
+    let synced: Vec<_> = synced.into_iter().map(|c| c.public_inputs).collect();
+    assert_eq!(synced, [legacy_inputs, inputs.clone()]);
+    let mine = store
+        .commitment_for_identity(1, "fresh")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(mine.public_inputs, inputs);
+
+    db.drop().await;
+}
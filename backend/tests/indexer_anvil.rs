@@ -1,18 +1,33 @@
+use std::collections::HashMap;
 use std::path::Path;
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use chrono::{DateTime, Utc};
 use ethers::abi::{self, Abi, Token};
 use ethers::contract::{Contract, ContractFactory};
 use ethers::middleware::{Middleware, SignerMiddleware};
 use ethers::providers::{Provider, Ws};
 use ethers::signers::{LocalWallet, Signer};
 use ethers::types::BigEndianHash;
-use ethers::types::{Address, Bytes, Log, H256, U256};
+use ethers::types::{Address, Bytes, Log, H256, U256, U64};
 use ethers::utils::Anvil;
 use serde_json::Value;
-use veilcast_backend::indexer;
-use veilcast_backend::repo::{InMemoryStore, PollStore};
+use std::sync::atomic::{AtomicU32, Ordering};
+use tokio::sync::{mpsc, watch};
+use veilcast_backend::error::{AppError, AppResult};
+use veilcast_backend::indexer::{
+    self, FeedEvent, IndexerConfig, IndexerStatus, IndexerTransport, LogFeed, LogOutcome,
+    PendingLogs,
+};
+use veilcast_backend::metrics::Metrics;
+use veilcast_backend::repo::{
+    FailedEventRecord, InMemoryStore, IndexerCheckpoint, NewPoll, PollIndexSink, PollStore,
+    StoredCommit,
+};
+
+/// A phase end inside the window the indexer accepts (2030-03-17).
+const PHASE_END: u64 = 1_900_000_000;
 
 // Helper: load abi/bytecode from forge artifact JSON.
 fn load_artifact(path: &Path) -> (Abi, Bytes) {
@@ -98,12 +113,240 @@ async fn indexer_captures_poll_created_on_anvil() {
         .await
         .expect("get_logs");
     for log in logs {
-        indexer::handle_log(&store, log).await.expect("handle log");
+        indexer::handle_log(&store, log, None, None)
+            .await
+            .expect("handle log");
     }
     let record = store.get_poll(0).await.expect("poll indexed");
     assert_eq!(record.question, "Test Q");
 }
 
+#[tokio::test]
+async fn indexer_backfills_logs_emitted_while_offline() {
+    let anvil = Anvil::new().spawn();
+    let provider = Provider::new(Ws::connect(anvil.ws_endpoint()).await.unwrap());
+    let wallet: LocalWallet = anvil.keys()[0].clone().into();
+    let wallet = wallet.with_chain_id(anvil.chain_id());
+    let client = Arc::new(SignerMiddleware::new(provider, wallet.clone()));
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../contracts/out");
+    let (polls_abi, polls_bytecode) =
+        load_artifact(&root.join("VeilCastPolls.sol/VeilCastPolls.json"));
+    let polls = ContractFactory::new(polls_abi.clone(), polls_bytecode, client.clone())
+        .deploy(wallet.address())
+        .expect("deploy args")
+        .send()
+        .await
+        .expect("deploy send");
+
+    // Both polls are created before the indexer exists; only a backfill can see them.
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let contract = Contract::new(polls.address(), polls_abi, client.clone());
+    for question in ["Offline 1", "Offline 2"] {
+        contract
+            .method::<_, ()>(
+                "createPoll",
+                (
+                    question.to_string(),
+                    vec![String::from("Yes"), String::from("No")],
+                    now + 300,
+                    now + 900,
+                    1234u64,
+                ),
+            )
+            .unwrap()
+            .send()
+            .await
+            .expect("createPoll tx");
+    }
+
+    let store = Arc::new(InMemoryStore::default());
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let cfg = IndexerConfig {
+        transport: IndexerTransport::Ws(anvil.ws_endpoint()),
+        contract_address: polls.address(),
+        from_block: Some(0),
+        max_backoff: Duration::from_secs(1),
+        // One block per call, so the catch-up spans several pages.
+        log_chunk_blocks: 1,
+        confirmations: 0,
+    };
+    let feed = indexer::WsLogFeed {
+        rpc_ws: anvil.ws_endpoint(),
+        contract_address: cfg.contract_address,
+        log_chunk_blocks: cfg.log_chunk_blocks,
+    };
+    let handle = tokio::spawn(indexer::run_indexer(
+        feed,
+        cfg,
+        store.clone(),
+        Arc::default(),
+        shutdown_rx,
+    ));
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while store.get_poll(1).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("offline polls are backfilled");
+    assert_eq!(store.get_poll(0).await.unwrap().question, "Offline 1");
+    assert_eq!(store.get_poll(1).await.unwrap().question, "Offline 2");
+    shutdown.send(true).unwrap();
+    handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn backfill_halves_ranges_the_provider_refuses() {
+    use ethers::providers::{JsonRpcError, MockResponse};
+    let (provider, mock) = Provider::mocked();
+    let addr = Address::repeat_byte(0xcd);
+    let log = |poll_id, block: u64| {
+        at(
+            make_poll_created_log(
+                addr,
+                poll_id,
+                "Q",
+                vec!["A".into(), "B".into()],
+                PHASE_END,
+                PHASE_END + 1,
+                3,
+            ),
+            block,
+            0,
+        )
+    };
+    // Responses pop from the back: 0..=9 is refused, then 0..=4 and 5..=9 succeed.
+    mock.push::<Vec<Log>, _>(vec![log(1, 7)]).unwrap();
+    mock.push::<Vec<Log>, _>(vec![log(0, 2)]).unwrap();
+    mock.push_response(MockResponse::Error(JsonRpcError {
+        code: -32005,
+        message: "query returned more than 10000 results".into(),
+        data: None,
+    }));
+
+    let (tx, mut rx) = mpsc::channel(8);
+    indexer::backfill_logs(&provider, addr, 0..=9, 10, &tx)
+        .await
+        .expect("backfill");
+    drop(tx);
+    let mut blocks = Vec::new();
+    while let Some(FeedEvent::Log(log)) = rx.recv().await {
+        blocks.push(log.block_number.unwrap().as_u64());
+    }
+    assert_eq!(blocks, vec![2, 7]);
+    let filter = |from: u64, to: u64| {
+        ethers::types::Filter::new()
+            .address(addr)
+            .from_block(from)
+            .to_block(to)
+    };
+    for (from, to) in [(0, 9), (0, 4), (5, 9)] {
+        mock.assert_request("eth_getLogs", [filter(from, to)])
+            .unwrap();
+    }
+
+    // Anything else fails the catch-up, and with it the connection.
+    mock.push_response(MockResponse::Error(JsonRpcError {
+        code: -32000,
+        message: "header not found".into(),
+        data: None,
+    }));
+    let (tx, _rx) = mpsc::channel(8);
+    assert!(indexer::backfill_logs(&provider, addr, 0..=9, 10, &tx)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn http_poll_feed_rereads_unconfirmed_blocks_without_double_applying() {
+    let (provider, mock) = Provider::mocked();
+    let addr = Address::repeat_byte(0xcd);
+    let log = |poll_id, block: u64, tx: u8| {
+        let mut log = at(
+            make_poll_created_log(
+                addr,
+                poll_id,
+                "Q",
+                vec!["A".into(), "B".into()],
+                PHASE_END,
+                PHASE_END + 1,
+                3,
+            ),
+            block,
+            0,
+        );
+        log.transaction_hash = Some(H256::repeat_byte(tx));
+        log
+    };
+    // Responses pop from the back. Each round re-reads the last two (unconfirmed) blocks, so
+    // poll 1's log at block 5 arrives three times.
+    mock.push::<Vec<Log>, _>(vec![log(1, 5, 0xb)]).unwrap();
+    mock.push::<U64, _>(U64::from(7)).unwrap();
+    mock.push::<Vec<Log>, _>(vec![log(1, 5, 0xb)]).unwrap();
+    mock.push::<U64, _>(U64::from(6)).unwrap();
+    mock.push::<Vec<Log>, _>(vec![log(0, 3, 0xa), log(1, 5, 0xb)])
+        .unwrap();
+    mock.push::<U64, _>(U64::from(5)).unwrap();
+
+    let cfg = IndexerConfig {
+        transport: IndexerTransport::HttpPoll {
+            url: String::new(),
+            interval: Duration::from_millis(10),
+        },
+        contract_address: addr,
+        from_block: Some(0),
+        max_backoff: Duration::from_millis(20),
+        log_chunk_blocks: 100,
+        confirmations: 2,
+    };
+    let feed = WithoutSenders(indexer::HttpPollLogFeed {
+        provider,
+        contract_address: addr,
+        log_chunk_blocks: cfg.log_chunk_blocks,
+        poll_interval: Duration::from_millis(10),
+        confirmations: cfg.confirmations,
+    });
+    let store = Arc::new(InMemoryStore::default());
+    let status = Arc::new(IndexerStatus::default());
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(indexer::run_indexer(
+        feed,
+        cfg,
+        store.clone(),
+        status.clone(),
+        shutdown_rx,
+    ));
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while status.head_block.load(Ordering::Relaxed) < 7 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("three polling rounds");
+    shutdown.send(true).unwrap();
+    handle.await.unwrap();
+
+    assert!(store.get_poll(0).await.is_ok());
+    assert!(store.get_poll(1).await.is_ok());
+    assert_eq!(status.confirmed_events.load(Ordering::Relaxed), 2);
+    assert_eq!(status.last_block.load(Ordering::Relaxed), 5);
+    let filter = |from: u64, to: u64| {
+        ethers::types::Filter::new()
+            .address(addr)
+            .from_block(from)
+            .to_block(to)
+    };
+    for (from, to) in [(0, 5), (4, 6), (5, 7)] {
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getLogs", [filter(from, to)])
+            .unwrap();
+    }
+}
+
 #[tokio::test]
 async fn indexer_handles_vote_and_resolve_logs() {
     let store = Arc::new(InMemoryStore::default());
@@ -115,23 +358,23 @@ async fn indexer_handles_vote_and_resolve_logs() {
         0,
         "Q2",
         vec!["Yes".into(), "No".into()],
-        123,
-        456,
+        PHASE_END + 123,
+        PHASE_END + 456,
         999,
     );
-    indexer::handle_log(&store, created_log)
+    indexer::handle_log(&store, created_log, None, None)
         .await
         .expect("poll created");
 
     // Feed VoteRevealed
     let vote_log = make_vote_revealed_log(polls_addr, 0, 1, 7777);
-    indexer::handle_log(&store, vote_log)
+    indexer::handle_log(&store, vote_log, None, None)
         .await
         .expect("vote handled");
 
     // Feed PollResolved
     let resolved_log = make_poll_resolved_log(polls_addr, 0, 1);
-    indexer::handle_log(&store, resolved_log)
+    indexer::handle_log(&store, resolved_log, None, None)
         .await
         .expect("resolved");
 
@@ -140,6 +383,1027 @@ async fn indexer_handles_vote_and_resolve_logs() {
     assert_eq!(updated.correct_option, Some(1));
 }
 
+#[tokio::test]
+async fn indexer_replays_vote_and_resolve_logs_that_beat_poll_created() {
+    let store = Arc::new(InMemoryStore::default());
+    let status = IndexerStatus::default();
+    let mut pending = PendingLogs::default();
+    let polls_addr = Address::random();
+
+    let vote_log = make_vote_revealed_log(polls_addr, 0, 1, 7777);
+    assert!(matches!(
+        indexer::handle_log(&store, vote_log.clone(), None, None).await,
+        Err(AppError::NotFound)
+    ));
+    for log in [vote_log, make_poll_resolved_log(polls_addr, 0, 1)] {
+        let outcome = pending
+            .apply(&store, log, None, None, &status)
+            .await
+            .expect("held");
+        assert_eq!(outcome, LogOutcome::Deferred);
+    }
+    assert_eq!(status.pending_logs.load(Ordering::Relaxed), 2);
+
+    let created_log = make_poll_created_log(
+        polls_addr,
+        0,
+        "Q2",
+        vec!["Yes".into(), "No".into()],
+        PHASE_END + 123,
+        PHASE_END + 456,
+        999,
+    );
+    let outcome = pending
+        .apply(&store, created_log, None, None, &status)
+        .await
+        .expect("poll created");
+    assert_eq!(outcome, LogOutcome::PollCreated);
+    assert!(pending.is_empty());
+    assert_eq!(status.pending_logs.load(Ordering::Relaxed), 0);
+    assert_eq!(status.vote_revealed_events.load(Ordering::Relaxed), 1);
+    assert_eq!(status.poll_resolved_events.load(Ordering::Relaxed), 1);
+
+    let updated = store.get_poll(0).await.expect("poll exists");
+    assert!(updated.resolved);
+    assert_eq!(updated.correct_option, Some(1));
+
+    // A poll that never shows up is given up on after a bounded number of heads.
+    let orphan = make_vote_revealed_log(polls_addr, 9, 0, 1);
+    pending
+        .apply(&store, orphan, None, None, &status)
+        .await
+        .expect("held");
+    for _ in 0..100 {
+        if pending.is_empty() {
+            break;
+        }
+        pending.retry(&store, &status).await;
+    }
+    assert!(pending.is_empty());
+    assert_eq!(status.dead_lettered_logs.load(Ordering::Relaxed), 1);
+    assert_eq!(status.handler_errors.load(Ordering::Relaxed), 0);
+}
+
+#[tokio::test]
+async fn indexer_records_commitments_submitted_on_chain() {
+    let store = Arc::new(InMemoryStore::default());
+    let polls_addr = Address::random();
+    let created = make_poll_created_log(
+        polls_addr,
+        0,
+        "Q",
+        vec!["A".into(), "B".into()],
+        PHASE_END,
+        PHASE_END + 1,
+        3,
+    );
+    indexer::handle_log(&store, created, None, None)
+        .await
+        .expect("poll created");
+    store
+        .record_commit(StoredCommit {
+            poll_id: 0,
+            choice: 1,
+            commitment: "5",
+            identity_secret: "alice",
+            secret: "s",
+            nullifier: "77",
+            proof: "0x",
+            public_inputs: &[],
+            proof_version: 1,
+            auto_reveal: true,
+            onchain_commit: false,
+        })
+        .await
+        .expect("api commit");
+
+    // 5 came in through the API too; 9 only exists on-chain and is indexed twice.
+    for commitment in [5, 9, 9] {
+        indexer::handle_log(
+            &store,
+            make_committed_log(polls_addr, 0, commitment),
+            None,
+            None,
+        )
+        .await
+        .expect("commit handled");
+    }
+
+    assert_eq!(store.commit_count(0).await.unwrap(), 2);
+    assert_eq!(store.get_poll(0).await.unwrap().commit_count, 2);
+    assert_eq!(
+        store
+            .commitment_receipt_status(0, "5")
+            .await
+            .unwrap()
+            .map(|s| s.synced),
+        Some(false)
+    );
+    assert_eq!(
+        store
+            .commitment_receipt_status(0, "9")
+            .await
+            .unwrap()
+            .map(|s| s.synced),
+        Some(true)
+    );
+    // Alice is not a poll member, so only the on-chain commitment counts.
+    assert_eq!(store.participation(0).await.unwrap().committed, 1);
+}
+
+#[tokio::test]
+async fn indexer_confirms_reveals_the_relayer_never_marked() {
+    let store = Arc::new(InMemoryStore::default());
+    let polls_addr = Address::random();
+    let created = make_poll_created_log(
+        polls_addr,
+        0,
+        "Q",
+        vec!["A".into(), "B".into()],
+        PHASE_END,
+        PHASE_END + 1,
+        3,
+    );
+    indexer::handle_log(&store, created, None, None)
+        .await
+        .expect("poll created");
+    store
+        .record_commit(StoredCommit {
+            poll_id: 0,
+            choice: 1,
+            commitment: "5",
+            identity_secret: "alice",
+            secret: "s",
+            nullifier: "77",
+            proof: "0x",
+            public_inputs: &[],
+            proof_version: 1,
+            auto_reveal: true,
+            onchain_commit: false,
+        })
+        .await
+        .expect("api commit");
+    assert_eq!(
+        store
+            .commitment_receipt_status(0, "5")
+            .await
+            .unwrap()
+            .map(|s| s.synced),
+        Some(false)
+    );
+
+    let tx_hash = H256::repeat_byte(0x42);
+    let mut revealed = make_vote_revealed_log(polls_addr, 0, 1, 77);
+    revealed.transaction_hash = Some(tx_hash);
+    // The second copy is a replay after a restart and must not count twice.
+    for _ in 0..2 {
+        indexer::handle_log(&store, revealed.clone(), None, None)
+            .await
+            .expect("vote handled");
+    }
+
+    assert_eq!(
+        store
+            .commitment_receipt_status(0, "5")
+            .await
+            .unwrap()
+            .map(|s| s.synced),
+        Some(true)
+    );
+    let status = store.my_commit_status(0, "alice").await.unwrap().unwrap();
+    assert!(status.revealed);
+    assert_eq!(status.reveal_tx_hash, Some(format!("{tx_hash:#x}")));
+    let batches = store.list_reveal_batches(0).await.unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(
+        (batches[0].tx_hash.clone(), batches[0].item_count),
+        (format!("{tx_hash:#x}"), 1)
+    );
+}
+
+#[tokio::test]
+async fn replayed_poll_resolved_log_awards_xp_once() {
+    let store = Arc::new(InMemoryStore::default());
+    let polls_addr = Address::random();
+    let created = make_poll_created_log(
+        polls_addr,
+        0,
+        "Q",
+        vec!["A".into(), "B".into()],
+        PHASE_END,
+        PHASE_END + 1,
+        3,
+    );
+    indexer::handle_log(&store, created, None, None)
+        .await
+        .expect("poll created");
+    store
+        .record_commit(StoredCommit {
+            poll_id: 0,
+            choice: 1,
+            commitment: "5",
+            identity_secret: "alice",
+            secret: "s",
+            nullifier: "77",
+            proof: "0x",
+            public_inputs: &[],
+            proof_version: 1,
+            auto_reveal: true,
+            onchain_commit: false,
+        })
+        .await
+        .expect("api commit");
+
+    let mut resolved = at(make_poll_resolved_log(polls_addr, 0, 1), 12, 3);
+    resolved.transaction_hash = Some(H256::repeat_byte(0x51));
+    // Once live, once more from an overlapping backfill.
+    for _ in 0..2 {
+        indexer::handle_log(&store, resolved.clone(), None, None)
+            .await
+            .expect("resolve handled");
+    }
+
+    let stats = store.user_stats("alice").await.unwrap();
+    assert_eq!((stats.total_votes, stats.correct_votes), (1, 1));
+    assert_eq!(store.prune_processed_logs(12).await.unwrap(), 0);
+    assert_eq!(store.prune_processed_logs(13).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn poll_resolved_log_after_an_api_resolution_awards_no_more_xp() {
+    let store = Arc::new(InMemoryStore::default());
+    let polls_addr = Address::random();
+    let created = make_poll_created_log(
+        polls_addr,
+        0,
+        "Q",
+        vec!["A".into(), "B".into()],
+        PHASE_END,
+        PHASE_END + 1,
+        3,
+    );
+    indexer::handle_log(&store, created, None, None)
+        .await
+        .expect("poll created");
+    store
+        .record_commit(StoredCommit {
+            poll_id: 0,
+            choice: 1,
+            commitment: "5",
+            identity_secret: "alice",
+            secret: "s",
+            nullifier: "77",
+            proof: "0x",
+            public_inputs: &[],
+            proof_version: 1,
+            auto_reveal: true,
+            onchain_commit: false,
+        })
+        .await
+        .expect("api commit");
+
+    store
+        .resolve_poll(0, Some(1))
+        .await
+        .expect("owner resolves");
+    let before = store.user_stats("alice").await.unwrap();
+    assert_eq!((before.total_votes, before.correct_votes), (1, 1));
+    let mut resolved = at(make_poll_resolved_log(polls_addr, 0, 1), 12, 3);
+    resolved.transaction_hash = Some(H256::repeat_byte(0x52));
+    indexer::handle_log(&store, resolved, None, None)
+        .await
+        .expect("resolve handled");
+
+    let after = store.user_stats("alice").await.unwrap();
+    assert_eq!(
+        (after.xp, after.total_votes, after.correct_votes),
+        (before.xp, 1, 1)
+    );
+    store.backfill_user_stats().await.unwrap();
+    assert_eq!(store.user_stats("alice").await.unwrap().xp, before.xp);
+}
+
+/// Feed whose first connection drops after one log; later ones replay that log, add another,
+/// and stay open.
+#[derive(Clone, Default)]
+struct DroppingFeed {
+    from_blocks: Arc<Mutex<Vec<u64>>>,
+}
+
+fn at(mut log: Log, block: u64, index: u64) -> Log {
+    log.block_number = Some(block.into());
+    log.log_index = Some(index.into());
+    log
+}
+
+#[async_trait::async_trait]
+impl LogFeed for DroppingFeed {
+    async fn stream(&self, from_block: u64, tx: mpsc::Sender<FeedEvent>) -> AppResult<()> {
+        let connection = {
+            let mut from_blocks = self.from_blocks.lock().unwrap();
+            from_blocks.push(from_block);
+            from_blocks.len()
+        };
+        let addr = Address::zero();
+        let first = make_poll_created_log(
+            addr,
+            0,
+            "Before",
+            vec!["A".into(), "B".into()],
+            PHASE_END,
+            PHASE_END + 1,
+            3,
+        );
+        tx.send(FeedEvent::Log(Box::new(at(first, 7, 0))))
+            .await
+            .unwrap();
+        if connection == 1 {
+            return Ok(());
+        }
+        let second = make_poll_created_log(
+            addr,
+            1,
+            "After",
+            vec!["A".into(), "B".into()],
+            PHASE_END,
+            PHASE_END + 1,
+            3,
+        );
+        tx.send(FeedEvent::Log(Box::new(at(second, 8, 0))))
+            .await
+            .unwrap();
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn indexer_resubscribes_after_the_stream_drops() {
+    let store = Arc::new(InMemoryStore::default());
+    let feed = DroppingFeed::default();
+    let cfg = IndexerConfig {
+        transport: IndexerTransport::Ws(String::new()),
+        contract_address: Address::repeat_byte(0xab),
+        from_block: Some(5),
+        max_backoff: Duration::from_millis(20),
+        log_chunk_blocks: 100,
+        confirmations: 0,
+    };
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(indexer::run_indexer(
+        feed.clone(),
+        cfg.clone(),
+        store.clone(),
+        Arc::default(),
+        shutdown_rx,
+    ));
+
+    let after = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(poll) = store.get_poll(1).await {
+                return poll;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("log after the reconnect is indexed");
+    assert_eq!(after.question, "After");
+    assert_eq!(store.get_poll(0).await.unwrap().question, "Before");
+    // The second subscription resumes from the last processed block.
+    assert_eq!(*feed.from_blocks.lock().unwrap(), vec![5, 7]);
+
+    // Still running while the stream is open; finishes only on shutdown.
+    assert!(!handle.is_finished());
+    shutdown.send(true).unwrap();
+    tokio::time::timeout(Duration::from_secs(1), handle)
+        .await
+        .expect("indexer stops on shutdown")
+        .unwrap();
+
+    // Shutting down saves the last processed log; a restart resumes there, not at from_block.
+    let contract = format!("{:?}", cfg.contract_address);
+    assert_eq!(
+        store.load_checkpoint(&contract).await.unwrap(),
+        Some(IndexerCheckpoint {
+            block: 8,
+            log_index: 0
+        })
+    );
+    let restarted = DroppingFeed::default();
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(indexer::run_indexer(
+        restarted.clone(),
+        cfg,
+        store.clone(),
+        Arc::default(),
+        shutdown_rx,
+    ));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    shutdown.send(true).unwrap();
+    handle.await.unwrap();
+    assert_eq!(restarted.from_blocks.lock().unwrap()[0], 8);
+}
+
+/// A feed's logs without its sender lookups, so a mock provider's answers stay in polling order.
+struct WithoutSenders<F>(F);
+
+#[async_trait::async_trait]
+impl<F: LogFeed> LogFeed for WithoutSenders<F> {
+    async fn stream(&self, from_block: u64, tx: mpsc::Sender<FeedEvent>) -> AppResult<()> {
+        self.0.stream(from_block, tx).await
+    }
+}
+
+/// Scripted feed that knows the senders of some transactions and fails to look up the rest.
+struct SenderFeed {
+    script: ScriptedFeed,
+    senders: HashMap<H256, Address>,
+    block_times: HashMap<u64, DateTime<Utc>>,
+}
+
+#[async_trait::async_trait]
+impl LogFeed for SenderFeed {
+    async fn stream(&self, from_block: u64, tx: mpsc::Sender<FeedEvent>) -> AppResult<()> {
+        self.script.stream(from_block, tx).await
+    }
+
+    async fn transaction_sender(&self, tx_hash: H256) -> AppResult<Option<Address>> {
+        match self.senders.get(&tx_hash) {
+            Some(sender) => Ok(Some(*sender)),
+            None => Err(AppError::External("transaction not found".into())),
+        }
+    }
+
+    async fn block_time(&self, block: u64) -> AppResult<Option<DateTime<Utc>>> {
+        Ok(self.block_times.get(&block).copied())
+    }
+}
+
+#[tokio::test]
+async fn indexed_polls_are_owned_by_their_creator_when_it_can_be_looked_up() {
+    let addr = Address::zero();
+    let creator = Address::repeat_byte(0xab);
+    let mined_at = DateTime::from_timestamp(1_800_000_000, 0).unwrap();
+    let created = |poll_id: u64, tx: u8| {
+        let mut log = at(
+            make_poll_created_log(
+                addr,
+                poll_id,
+                "Q",
+                vec!["A".into(), "B".into()],
+                PHASE_END,
+                PHASE_END + 1,
+                3,
+            ),
+            10,
+            poll_id,
+        );
+        log.transaction_hash = Some(H256::repeat_byte(tx));
+        FeedEvent::Log(Box::new(log))
+    };
+    let feed = SenderFeed {
+        script: ScriptedFeed(vec![created(0, 0xa), created(1, 0xb), FeedEvent::Head(10)]),
+        senders: HashMap::from([(H256::repeat_byte(0xa), creator)]),
+        block_times: HashMap::from([(10, mined_at)]),
+    };
+    let store = Arc::new(InMemoryStore::default());
+    let status = Arc::new(IndexerStatus::default());
+    let cfg = IndexerConfig {
+        transport: IndexerTransport::Ws(String::new()),
+        contract_address: Address::repeat_byte(0xef),
+        from_block: None,
+        max_backoff: Duration::from_millis(20),
+        log_chunk_blocks: 100,
+        confirmations: 0,
+    };
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(indexer::run_indexer(
+        feed,
+        cfg,
+        store.clone(),
+        status.clone(),
+        shutdown_rx,
+    ));
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while status.head_block.load(Ordering::Relaxed) < 10 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("script played");
+    shutdown.send(true).unwrap();
+    handle.await.unwrap();
+
+    let owned = store.get_poll(0).await.unwrap();
+    assert_eq!(owned.owner, format!("0x{}", "ab".repeat(20)));
+    // Dated by the block it was created in, not when it was indexed.
+    assert_eq!(owned.created_at, mined_at);
+    // The lookup failed: the poll is still indexed, with no owner.
+    assert_eq!(store.get_poll(1).await.unwrap().owner, "");
+}
+
+#[tokio::test]
+async fn http_poll_feed_looks_up_transaction_senders() {
+    let (provider, mock) = Provider::mocked();
+    let tx_hash = H256::repeat_byte(0x42);
+    let from = Address::repeat_byte(0xab);
+    mock.push(ethers::types::Transaction {
+        hash: tx_hash,
+        from,
+        ..Default::default()
+    })
+    .unwrap();
+    let feed = indexer::HttpPollLogFeed {
+        provider,
+        contract_address: Address::zero(),
+        log_chunk_blocks: 100,
+        poll_interval: Duration::from_millis(10),
+        confirmations: 0,
+    };
+
+    assert_eq!(feed.transaction_sender(tx_hash).await.unwrap(), Some(from));
+    mock.assert_request("eth_getTransactionByHash", [tx_hash])
+        .unwrap();
+    // Nothing left to answer with.
+    assert!(feed.transaction_sender(tx_hash).await.is_err());
+}
+
+/// In-memory sink whose `resolve_poll_from_chain` fails its first `failures` calls.
+struct FlakySink {
+    inner: InMemoryStore,
+    failures: AtomicU32,
+}
+
+impl FlakySink {
+    fn new(failures: u32) -> Self {
+        Self {
+            inner: InMemoryStore::default(),
+            failures: AtomicU32::new(failures),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PollIndexSink for FlakySink {
+    async fn upsert_poll_from_chain(
+        &self,
+        poll_id: i64,
+        poll: NewPoll<'_>,
+        created_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        self.inner
+            .upsert_poll_from_chain(poll_id, poll, created_at)
+            .await
+    }
+    async fn upsert_vote_from_chain(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        choice: u8,
+    ) -> AppResult<()> {
+        self.inner
+            .upsert_vote_from_chain(poll_id, nullifier, choice)
+            .await
+    }
+    async fn resolve_poll_from_chain(
+        &self,
+        poll_id: i64,
+        correct_option: u8,
+        tx_hash: Option<&str>,
+    ) -> AppResult<()> {
+        let failing = self
+            .failures
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok();
+        if failing {
+            return Err(AppError::External("database unavailable".into()));
+        }
+        self.inner
+            .resolve_poll_from_chain(poll_id, correct_option, tx_hash)
+            .await
+    }
+    async fn upsert_commit_from_chain(&self, poll_id: i64, commitment: &str) -> AppResult<()> {
+        self.inner
+            .upsert_commit_from_chain(poll_id, commitment)
+            .await
+    }
+    async fn confirm_reveal_from_chain(
+        &self,
+        poll_id: i64,
+        nullifier: &str,
+        tx_hash: &str,
+    ) -> AppResult<()> {
+        self.inner
+            .confirm_reveal_from_chain(poll_id, nullifier, tx_hash)
+            .await
+    }
+    async fn log_processed(&self, tx_hash: &str, log_index: u64) -> AppResult<bool> {
+        self.inner.log_processed(tx_hash, log_index).await
+    }
+    async fn record_processed_log(
+        &self,
+        tx_hash: &str,
+        log_index: u64,
+        block: u64,
+    ) -> AppResult<()> {
+        self.inner
+            .record_processed_log(tx_hash, log_index, block)
+            .await
+    }
+    async fn prune_processed_logs(&self, block: u64) -> AppResult<u64> {
+        self.inner.prune_processed_logs(block).await
+    }
+    async fn record_failed_event(
+        &self,
+        log: &Value,
+        sender: Option<&str>,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        self.inner
+            .record_failed_event(log, sender, error, next_retry_at)
+            .await
+    }
+    async fn due_failed_events(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<FailedEventRecord>> {
+        self.inner.due_failed_events(now, limit).await
+    }
+    async fn reschedule_failed_event(
+        &self,
+        id: i64,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> AppResult<()> {
+        self.inner
+            .reschedule_failed_event(id, error, next_retry_at)
+            .await
+    }
+    async fn delete_failed_event(&self, id: i64) -> AppResult<()> {
+        self.inner.delete_failed_event(id).await
+    }
+    async fn failed_event_counts(&self) -> AppResult<(u64, u64)> {
+        self.inner.failed_event_counts().await
+    }
+    async fn requeue_dead_events(&self, now: DateTime<Utc>) -> AppResult<u64> {
+        self.inner.requeue_dead_events(now).await
+    }
+    async fn load_checkpoint(&self, contract: &str) -> AppResult<Option<IndexerCheckpoint>> {
+        self.inner.load_checkpoint(contract).await
+    }
+    async fn save_checkpoint(
+        &self,
+        contract: &str,
+        checkpoint: IndexerCheckpoint,
+    ) -> AppResult<()> {
+        self.inner.save_checkpoint(contract, checkpoint).await
+    }
+}
+
+/// Runs the indexer over `script` until it has seen head `until`.
+async fn index_script<S>(
+    store: &Arc<S>,
+    status: &Arc<IndexerStatus>,
+    script: Vec<FeedEvent>,
+    until: u64,
+) where
+    S: PollIndexSink + Send + Sync + 'static,
+{
+    let cfg = IndexerConfig {
+        transport: IndexerTransport::Ws(String::new()),
+        contract_address: Address::repeat_byte(0xef),
+        from_block: None,
+        max_backoff: Duration::from_millis(20),
+        log_chunk_blocks: 100,
+        confirmations: 0,
+    };
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(indexer::run_indexer(
+        ScriptedFeed(script),
+        cfg,
+        store.clone(),
+        status.clone(),
+        shutdown_rx,
+    ));
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while status.head_block.load(Ordering::Relaxed) < until {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("script played");
+    shutdown.send(true).unwrap();
+    handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn failed_logs_are_retried_with_backoff_until_they_apply() {
+    let addr = Address::zero();
+    let script = || {
+        let created = make_poll_created_log(
+            addr,
+            0,
+            "Q",
+            vec!["A".into(), "B".into()],
+            PHASE_END,
+            PHASE_END + 1,
+            3,
+        );
+        let mut resolved = at(make_poll_resolved_log(addr, 0, 1), 10, 1);
+        resolved.transaction_hash = Some(H256::repeat_byte(0x51));
+        vec![
+            FeedEvent::Log(Box::new(at(created, 10, 0))),
+            FeedEvent::Log(Box::new(resolved)),
+            FeedEvent::Head(10),
+        ]
+    };
+
+    // Fails live and on the first retry; the second retry, the third attempt, goes through.
+    let store = Arc::new(FlakySink::new(2));
+    let status = Arc::new(IndexerStatus::default());
+    index_script(&store, &status, script(), 10).await;
+    assert_eq!(status.handler_errors.load(Ordering::Relaxed), 1);
+    assert_eq!(status.failed_events.load(Ordering::Relaxed), 1);
+    assert!(!store.inner.get_poll(0).await.unwrap().resolved);
+
+    let now = Utc::now();
+    // Not due yet: nothing is tried.
+    indexer::retry_failed_events(&store, &status, now)
+        .await
+        .unwrap();
+    assert_eq!(store.failures.load(Ordering::Relaxed), 1);
+
+    let later = now + chrono::Duration::hours(1);
+    indexer::retry_failed_events(&store, &status, later)
+        .await
+        .unwrap();
+    let queued = store
+        .due_failed_events(later + chrono::Duration::hours(1), 10)
+        .await
+        .unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].attempts, 2);
+    assert!(queued[0].next_retry_at > later);
+    assert!(!store.inner.get_poll(0).await.unwrap().resolved);
+
+    indexer::retry_failed_events(&store, &status, later + chrono::Duration::hours(1))
+        .await
+        .unwrap();
+    assert!(store.inner.get_poll(0).await.unwrap().resolved);
+    assert_eq!(store.failed_event_counts().await.unwrap(), (0, 0));
+    assert_eq!(status.poll_resolved_events.load(Ordering::Relaxed), 1);
+    assert_eq!(status.failed_events.load(Ordering::Relaxed), 0);
+
+    // One that never goes through ends up dead after the attempt limit.
+    let store = Arc::new(FlakySink::new(u32::MAX));
+    let status = Arc::new(IndexerStatus::default());
+    index_script(&store, &status, script(), 10).await;
+    let mut now = Utc::now();
+    for _ in 1..indexer::MAX_FAILED_EVENT_ATTEMPTS {
+        now += chrono::Duration::days(1);
+        indexer::retry_failed_events(&store, &status, now)
+            .await
+            .unwrap();
+    }
+    assert_eq!(store.failed_event_counts().await.unwrap(), (0, 1));
+    assert_eq!(status.dead_events.load(Ordering::Relaxed), 1);
+    assert_eq!(store.requeue_dead_events(now).await.unwrap(), 1);
+    assert_eq!(
+        store.due_failed_events(now, 10).await.unwrap()[0].attempts,
+        0
+    );
+}
+
+#[tokio::test]
+async fn poll_created_logs_with_out_of_range_phase_ends_are_dead_on_arrival() {
+    let addr = Address::zero();
+    // 2300-01-01: fits in i64 seconds, but no poll runs that long.
+    let far_future = U256::from(10_413_792_000u64);
+    let created = |poll_id: u64, commit_end: U256| {
+        make_poll_created_log_ending(
+            addr,
+            poll_id,
+            "Q",
+            vec!["A".into(), "B".into()],
+            commit_end,
+            U256::from(PHASE_END),
+            3,
+        )
+    };
+    for (commit_end, expected) in [
+        (U256::MAX, "does not fit in i64"),
+        (far_future, "outside years 2000-2200"),
+    ] {
+        let err = indexer::handle_log(
+            &Arc::new(InMemoryStore::default()),
+            created(0, commit_end),
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&err, AppError::Validation(msg) if msg.contains(expected)),
+            "{err}"
+        );
+    }
+
+    let store = Arc::new(InMemoryStore::default());
+    let status = Arc::new(IndexerStatus::default());
+    let mut script: Vec<FeedEvent> = [U256::MAX, far_future]
+        .into_iter()
+        .enumerate()
+        .map(|(i, end)| {
+            let mut log = at(created(i as u64, end), 10, i as u64);
+            log.transaction_hash = Some(H256::repeat_byte(i as u8 + 1));
+            FeedEvent::Log(Box::new(log))
+        })
+        .collect();
+    script.push(FeedEvent::Head(10));
+    index_script(&store, &status, script, 10).await;
+
+    assert!(store.get_poll(0).await.is_err());
+    assert!(store.get_poll(1).await.is_err());
+    assert_eq!(status.handler_errors.load(Ordering::Relaxed), 2);
+    assert_eq!(store.failed_event_counts().await.unwrap(), (0, 2));
+    assert_eq!(status.dead_events.load(Ordering::Relaxed), 2);
+    // Dead events are not retried.
+    let later = Utc::now() + chrono::Duration::days(30);
+    assert!(store.due_failed_events(later, 10).await.unwrap().is_empty());
+}
+
+/// Feed that plays a fixed script of events, then stays open.
+#[derive(Clone)]
+struct ScriptedFeed(Vec<FeedEvent>);
+
+#[async_trait::async_trait]
+impl LogFeed for ScriptedFeed {
+    async fn stream(&self, _from_block: u64, tx: mpsc::Sender<FeedEvent>) -> AppResult<()> {
+        for event in self.0.clone() {
+            tx.send(event).await.unwrap();
+        }
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn staged_logs_wait_for_confirmations_and_drop_on_reorg() {
+    let addr = Address::zero();
+    let created = |poll_id: u64, block: u64, hash: u8| {
+        let mut log = at(
+            make_poll_created_log(
+                addr,
+                poll_id,
+                "Q",
+                vec!["A".into(), "B".into()],
+                PHASE_END,
+                PHASE_END + 1,
+                3,
+            ),
+            block,
+            0,
+        );
+        log.block_hash = Some(H256::repeat_byte(hash));
+        FeedEvent::Log(Box::new(log))
+    };
+    let removed = |event: FeedEvent| match event {
+        FeedEvent::Log(mut log) => {
+            log.removed = Some(true);
+            FeedEvent::Log(log)
+        }
+        head => head,
+    };
+    let feed = ScriptedFeed(vec![
+        created(0, 10, 0xa),
+        created(1, 11, 0xb),
+        FeedEvent::Head(11),
+        // Block 11 is replaced: poll 1 was never canonical.
+        created(2, 11, 0xc),
+        FeedEvent::Head(12),
+        FeedEvent::Head(13),
+        // The node retracts poll 3's block before it is confirmed.
+        created(3, 14, 0xd),
+        removed(created(3, 14, 0xd)),
+        FeedEvent::Head(20),
+    ]);
+    let store = Arc::new(InMemoryStore::default());
+    let status = Arc::new(IndexerStatus::default());
+    let cfg = IndexerConfig {
+        transport: IndexerTransport::Ws(String::new()),
+        contract_address: Address::repeat_byte(0xef),
+        from_block: None,
+        max_backoff: Duration::from_millis(20),
+        log_chunk_blocks: 100,
+        confirmations: 2,
+    };
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(indexer::run_indexer(
+        feed,
+        cfg,
+        store.clone(),
+        status.clone(),
+        shutdown_rx,
+    ));
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while status.head_block.load(Ordering::Relaxed) < 20 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("script played");
+    shutdown.send(true).unwrap();
+    handle.await.unwrap();
+
+    assert!(store.get_poll(0).await.is_ok());
+    assert!(store.get_poll(1).await.is_err());
+    assert!(store.get_poll(2).await.is_ok());
+    assert!(store.get_poll(3).await.is_err());
+    assert_eq!(status.confirmed_events.load(Ordering::Relaxed), 2);
+    assert_eq!(status.unconfirmed_events.load(Ordering::Relaxed), 0);
+    assert_eq!(status.discarded_events.load(Ordering::Relaxed), 2);
+    assert_eq!(status.last_block.load(Ordering::Relaxed), 11);
+    assert!(!status.running.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+async fn indexer_counts_events_unknown_logs_and_handler_errors() {
+    let addr = Address::zero();
+    let unknown = Log {
+        address: addr,
+        topics: vec![H256::repeat_byte(0x99)],
+        ..Default::default()
+    };
+    // A timestamp chrono cannot represent makes the PollCreated handler fail.
+    let unstorable = make_poll_created_log(addr, 1, "Q", vec!["A".into()], i64::MAX as u64, 2, 3);
+    let logs = [
+        make_poll_created_log(
+            addr,
+            0,
+            "Q",
+            vec!["A".into(), "B".into()],
+            PHASE_END,
+            PHASE_END + 1,
+            3,
+        ),
+        make_committed_log(addr, 0, 5),
+        make_vote_revealed_log(addr, 0, 1, 77),
+        make_poll_resolved_log(addr, 0, 1),
+        unknown,
+        unstorable,
+    ];
+    let mut script: Vec<FeedEvent> = logs
+        .into_iter()
+        .enumerate()
+        .map(|(i, log)| FeedEvent::Log(Box::new(at(log, 10, i as u64))))
+        .collect();
+    script.push(FeedEvent::Head(15));
+    let store = Arc::new(InMemoryStore::default());
+    let status = Arc::new(IndexerStatus::default());
+    let cfg = IndexerConfig {
+        transport: IndexerTransport::Ws(String::new()),
+        contract_address: Address::repeat_byte(0xef),
+        from_block: None,
+        max_backoff: Duration::from_millis(20),
+        log_chunk_blocks: 100,
+        confirmations: 0,
+    };
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let handle = tokio::spawn(indexer::run_indexer(
+        ScriptedFeed(script),
+        cfg,
+        store.clone(),
+        status.clone(),
+        shutdown_rx,
+    ));
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while status.head_block.load(Ordering::Relaxed) < 15 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("script played");
+    shutdown.send(true).unwrap();
+    handle.await.unwrap();
+
+    let load = |counter: &std::sync::atomic::AtomicU64| counter.load(Ordering::Relaxed);
+    assert_eq!(load(&status.poll_created_events), 1);
+    assert_eq!(load(&status.committed_events), 1);
+    assert_eq!(load(&status.vote_revealed_events), 1);
+    assert_eq!(load(&status.poll_resolved_events), 1);
+    assert_eq!(load(&status.unknown_logs), 1);
+    assert_eq!(load(&status.handler_errors), 1);
+    assert_eq!(load(&status.reconnects), 0);
+    assert_eq!(status.lag_blocks(), Some(5));
+
+    let metrics = Metrics::default().render("noop", &status);
+    assert!(metrics.contains("veilcast_indexer_events_total{event=\"VoteRevealed\"} 1"));
+    assert!(metrics.contains("veilcast_indexer_unknown_logs_total 1"));
+    assert!(metrics.contains("veilcast_indexer_handler_errors_total 1"));
+    assert!(metrics.contains("veilcast_indexer_lag_blocks 5"));
+}
+
 fn make_poll_created_log(
     addr: Address,
     poll_id: u64,
@@ -148,6 +1412,26 @@ fn make_poll_created_log(
     commit_end: u64,
     reveal_end: u64,
     membership_root: u64,
+) -> Log {
+    make_poll_created_log_ending(
+        addr,
+        poll_id,
+        question,
+        options,
+        U256::from(commit_end),
+        U256::from(reveal_end),
+        membership_root,
+    )
+}
+
+fn make_poll_created_log_ending(
+    addr: Address,
+    poll_id: u64,
+    question: &str,
+    options: Vec<String>,
+    commit_end: U256,
+    reveal_end: U256,
+    membership_root: u64,
 ) -> Log {
     let sig = H256::from(ethers::utils::keccak256(
         "PollCreated(uint256,string,string[],uint256,uint256,uint256)",
@@ -156,8 +1440,8 @@ fn make_poll_created_log(
     let data = abi::encode(&[
         Token::String(question.into()),
         Token::Array(options.into_iter().map(Token::String).collect()),
-        Token::Uint(U256::from(commit_end)),
-        Token::Uint(U256::from(reveal_end)),
+        Token::Uint(commit_end),
+        Token::Uint(reveal_end),
         Token::Uint(U256::from(membership_root)),
     ]);
     Log {
@@ -186,6 +1470,20 @@ fn make_vote_revealed_log(addr: Address, poll_id: u64, choice_index: u8, nullifi
     }
 }
 
+fn make_committed_log(addr: Address, poll_id: u64, commitment: u64) -> Log {
+    let sig = H256::from(ethers::utils::keccak256("Committed(uint256,bytes32)"));
+    let topics = vec![sig, H256::from_uint(&U256::from(poll_id))];
+    let data = abi::encode(&[Token::FixedBytes(
+        H256::from_uint(&U256::from(commitment)).as_bytes().to_vec(),
+    )]);
+    Log {
+        address: addr,
+        topics,
+        data: data.into(),
+        ..Default::default()
+    }
+}
+
 fn make_poll_resolved_log(addr: Address, poll_id: u64, correct_option: u8) -> Log {
     let sig = H256::from(ethers::utils::keccak256("PollResolved(uint256,uint8)"));
     let topics = vec![sig];
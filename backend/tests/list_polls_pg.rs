@@ -0,0 +1,210 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::ThrowawayDb;
+use veilcast_backend::error::AppError;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollAudience, PollFilter, PollSort, PollStore};
+
+#[tokio::test]
+async fn list_polls_pages_by_id_without_gaps_or_repeats() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (question, options, commit_phase_end, reveal_phase_end, membership_root, category)
+        SELECT 'Poll ' || n, '["Yes","No"]', now(), now(), 'root',
+               CASE WHEN n % 2 = 0 THEN 'Sports' ELSE 'General' END
+        FROM generate_series(1, 7) AS n;
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    let mut pages = Vec::new();
+    let mut before_id = None;
+    loop {
+        let page = store
+            .list_polls(3, before_id, PollFilter::default())
+            .await
+            .unwrap();
+        let ids: Vec<i64> = page.polls.iter().map(|p| p.id).collect();
+        before_id = ids.last().copied();
+        pages.push(ids);
+        if !page.has_more {
+            break;
+        }
+    }
+    assert_eq!(pages, vec![vec![7, 6, 5], vec![4, 3, 2], vec![1]]);
+
+    let sports = PollFilter {
+        category: Some("Sports".into()),
+        ..PollFilter::default()
+    };
+    let page = store.list_polls(2, Some(6), sports).await.unwrap();
+    assert_eq!(
+        page.polls.iter().map(|p| p.id).collect::<Vec<_>>(),
+        vec![4, 2]
+    );
+    assert!(!page.has_more);
+
+    // Created in reverse: poll 1 is the newest and 7 the oldest. Pages keep to that order.
+    sqlx::query(r#"UPDATE polls SET created_at = now() - id * interval '1 hour'"#)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    let by_created = PollFilter {
+        sort: PollSort::CreatedAt,
+        ..PollFilter::default()
+    };
+    let first = store.list_polls(4, None, by_created.clone()).await.unwrap();
+    assert_eq!(
+        first.polls.iter().map(|p| p.id).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+    assert!(first.has_more);
+    let rest = store.list_polls(4, Some(4), by_created).await.unwrap();
+    assert_eq!(
+        rest.polls.iter().map(|p| p.id).collect::<Vec<_>>(),
+        vec![5, 6, 7]
+    );
+    assert!(!rest.has_more);
+    db.drop().await;
+}
+
+#[tokio::test]
+async fn soft_deleted_polls_are_hidden_but_keep_their_rows() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root)
+        VALUES (1, 'Kept', '["Yes","No"]', now() - interval '1 minute', now() + interval '1 hour', 'root'),
+               (2, 'Doomed', '["Yes","No"]', now() - interval '1 minute', now() + interval '1 hour', 'root');
+        INSERT INTO commitments (poll_id, commitment, identity_secret) VALUES
+            (1, 'c1', 'alice'), (2, 'c2', 'alice');
+        INSERT INTO votes (poll_id, nullifier, choice) VALUES (2, 'n2', 1);
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    let deleted = store.soft_delete_poll(2).await.unwrap();
+    let deleted_at = deleted.deleted_at.expect("deleted_at set");
+    // A second delete keeps the first timestamp.
+    let again = store.soft_delete_poll(2).await.unwrap();
+    assert_eq!(again.deleted_at, Some(deleted_at));
+
+    let listed = store
+        .list_polls(10, None, PollFilter::default())
+        .await
+        .unwrap()
+        .polls;
+    assert_eq!(listed.iter().map(|p| p.id).collect::<Vec<_>>(), vec![1]);
+    let everything = PollFilter {
+        include_deleted: true,
+        ..PollFilter::default()
+    };
+    let page = store.list_polls(10, None, everything).await.unwrap();
+    assert_eq!(
+        page.polls.iter().map(|p| p.id).collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+    assert_eq!(
+        store
+            .get_polls_by_ids(&[1, 2], &PollAudience::All)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(matches!(store.get_poll(2).await, Err(AppError::NotFound)));
+    let record = store.get_poll_including_deleted(2).await.unwrap();
+    assert_eq!(record.vote_counts, vec![0, 1]);
+    assert_eq!(record.commit_count, 1);
+
+    let pending = store
+        .commits_to_sync(Utc::now(), Duration::zero(), Duration::zero(), false, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        pending.iter().map(|c| c.poll_id).collect::<Vec<_>>(),
+        vec![1]
+    );
+    db.drop().await;
+}
+
+async fn listed(store: &PgStore, audience: PollAudience) -> Vec<i64> {
+    let filter = PollFilter {
+        audience,
+        ..PollFilter::default()
+    };
+    let page = store.list_polls(2, None, filter).await.unwrap();
+    page.polls.iter().map(|p| p.id).collect()
+}
+
+#[tokio::test]
+async fn list_polls_filters_members_only_polls_in_the_query() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    // One public poll behind a page's worth of newer members-only ones.
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root, owner, visibility)
+        VALUES (1, 'Public', '["Yes","No"]', now(), now(), 'root', 'carol', 'public'),
+               (2, 'Club', '["Yes","No"]', now(), now(), 'root', 'carol', 'members'),
+               (3, 'Board', '["Yes","No"]', now(), now(), 'root', 'bob', 'members'),
+               (4, 'Mine', '["Yes","No"]', now(), now(), 'root', 'alice', 'members');
+        INSERT INTO poll_members (poll_id, identity_secret) VALUES (2, 'alice-secret');
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    assert_eq!(listed(&store, PollAudience::Public).await, vec![1]);
+    let alice = PollAudience::Member {
+        username: "alice".into(),
+        identity_secret: "alice-secret".into(),
+    };
+    assert_eq!(listed(&store, alice.clone()).await, vec![4, 2]);
+    assert_eq!(listed(&store, PollAudience::All).await, vec![4, 3]);
+
+    let by_id = store.get_polls_by_ids(&[1, 2, 3], &alice).await.unwrap();
+    let mut by_id: Vec<_> = by_id.iter().map(|p| p.id).collect();
+    by_id.sort();
+    assert_eq!(by_id, vec![1, 2]);
+    assert_eq!(
+        store
+            .get_polls_by_ids(&[2, 3], &PollAudience::Public)
+            .await
+            .unwrap()
+            .len(),
+        0
+    );
+
+    db.drop().await;
+}
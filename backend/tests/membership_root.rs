@@ -6,6 +6,7 @@ use std::path::PathBuf;
 use std::process::Command;
 use tempfile::NamedTempFile;
 use toml::Value;
+use veilcast_backend::merkle::{build_tree, hash1, hash2, member_field, HashScheme};
 
 #[derive(Debug, Deserialize)]
 struct MerklePaths {
@@ -19,13 +20,177 @@ struct MerkleEntry {
     siblings: Vec<String>,
 }
 
+fn prover_toml() -> Value {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let toml_str =
+        fs::read_to_string(manifest_dir.join("../zk/Prover.toml")).expect("read Prover.toml");
+    toml::from_str(&toml_str).expect("parse Prover.toml")
+}
+
+fn field(value: &Value, key: &str) -> num_bigint::BigUint {
+    member_field(
+        value[key]
+            .as_str()
+            .unwrap_or_else(|| panic!("{key} in Prover.toml")),
+    )
+}
+
+#[test]
+fn native_poseidon_matches_prover_fixture() {
+    let value = prover_toml();
+    let identity_secret = value["identity_secret"].as_str().unwrap();
+
+    assert_eq!(
+        hash2(&field(&value, "choice"), &field(&value, "secret")).to_string(),
+        value["commitment"].as_str().unwrap()
+    );
+    assert_eq!(
+        hash2(&field(&value, "identity_secret"), &field(&value, "poll_id")).to_string(),
+        value["nullifier"].as_str().unwrap()
+    );
+
+    let tree = build_tree(&[identity_secret.to_string()], 20);
+    assert_eq!(tree.root, value["membership_root"].as_str().unwrap());
+    assert_eq!(tree.depth, 20);
+    let entry = tree.paths.get(identity_secret).expect("path for identity");
+    let expected = |key: &str| -> Vec<String> {
+        value[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(entry.bits, expected("path_bits"));
+    assert_eq!(entry.siblings, expected("path_siblings"));
+}
+
+#[test]
+fn poseidon_hasher_matches_prover_fixture() {
+    use veilcast_backend::merkle::{build_tree_with, MerkleHasher, PoseidonHasher};
+    let value = prover_toml();
+    let identity = field(&value, "identity_secret");
+    assert_eq!(PoseidonHasher.leaf(&identity), hash1(&identity));
+    assert_eq!(
+        PoseidonHasher
+            .node(&field(&value, "choice"), &field(&value, "secret"))
+            .to_string(),
+        value["commitment"].as_str().unwrap()
+    );
+    let identity_secret = value["identity_secret"].as_str().unwrap().to_string();
+    let tree = build_tree_with(&[identity_secret], 20, HashScheme::Poseidon.hasher());
+    assert_eq!(tree.root, value["membership_root"].as_str().unwrap());
+}
+
+#[test]
+fn keccak_hasher_matches_solidity_vectors() {
+    use num_bigint::BigUint;
+    use veilcast_backend::merkle::{build_tree_with, KeccakHasher, MerkleHasher};
+    let hex = |h: &str| BigUint::parse_bytes(h.as_bytes(), 16).unwrap();
+    let zero = BigUint::from(0u8);
+    let one = BigUint::from(1u8);
+    // keccak256(abi.encodePacked(bytes32(x))) and keccak256(abi.encodePacked(l, r)).
+    assert_eq!(
+        KeccakHasher.leaf(&zero),
+        hex("290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563")
+    );
+    assert_eq!(
+        KeccakHasher.leaf(&one),
+        hex("b10e2d527612073b26eecdfd717e6a320cf44b4afac2b0732d9fcbe2b7fa0cf6")
+    );
+    assert_eq!(
+        KeccakHasher.node(&zero, &zero),
+        hex("ad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5")
+    );
+
+    // A one-member tree hashes its leaf up past literal-zero siblings, left-first.
+    let tree = build_tree_with(&["1".to_string()], 2, &KeccakHasher);
+    let level1 = KeccakHasher.node(&KeccakHasher.leaf(&one), &zero);
+    assert_eq!(
+        tree.root,
+        KeccakHasher.node(&level1, &zero).to_str_radix(10)
+    );
+    assert_ne!(tree.root, build_tree(&["1".to_string()], 2).root);
+}
+
+#[test]
+fn keccak_incremental_tree_tracks_the_full_rebuild() {
+    use veilcast_backend::merkle::{build_tree_with, IncrementalTree, KeccakHasher};
+    let members: Vec<String> = (0..6).map(|i| format!("{}", 77 + i)).collect();
+    let mut tree = IncrementalTree::new(8, HashScheme::Keccak);
+    assert_eq!(tree.root, build_tree_with(&[], 8, &KeccakHasher).root);
+    for n in 1..=members.len() {
+        tree.push(&members[n - 1]).unwrap();
+        assert_eq!(
+            tree.root,
+            build_tree_with(&members[..n], 8, &KeccakHasher).root
+        );
+    }
+    let stored: IncrementalTree =
+        serde_json::from_value(serde_json::to_value(&tree).unwrap()).unwrap();
+    assert_eq!(stored.scheme, HashScheme::Keccak);
+
+    // Frontiers stored before the scheme existed are Poseidon ones.
+    let mut legacy = serde_json::to_value(IncrementalTree::new(8, HashScheme::Poseidon)).unwrap();
+    legacy.as_object_mut().unwrap().remove("scheme");
+    let legacy: IncrementalTree = serde_json::from_value(legacy).unwrap();
+    assert_eq!(legacy.scheme, HashScheme::Poseidon);
+}
+
+#[test]
+fn hash_scheme_is_chosen_by_merkle_hash() {
+    let parse = |v: Option<&str>| HashScheme::parse(|_| v.map(String::from));
+    assert_eq!(parse(None).unwrap(), HashScheme::Poseidon);
+    assert_eq!(parse(Some("")).unwrap(), HashScheme::Poseidon);
+    assert_eq!(parse(Some(" Keccak ")).unwrap(), HashScheme::Keccak);
+    assert!(parse(Some("sha256")).is_err());
+
+    // Poseidon digests keep their old values; other schemes get their own.
+    use veilcast_backend::merkle::members_digest;
+    let members = ["1".to_string(), "2".to_string()];
+    assert_ne!(
+        members_digest(&members, 20, HashScheme::Poseidon),
+        members_digest(&members, 20, HashScheme::Keccak)
+    );
+}
+
+#[test]
+fn native_paths_lead_every_member_to_the_root() {
+    let members: Vec<String> = ["123", "0x2a", "alice", "-1", "999999999999", ""]
+        .map(String::from)
+        .to_vec();
+    let tree = build_tree(&members, 20);
+    assert_eq!(tree.paths.len(), members.len());
+    for member in &members {
+        let path = &tree.paths[member];
+        assert_eq!(path.bits.len(), 20);
+        assert_eq!(path.siblings.len(), 20);
+        let mut node = hash1(&member_field(member));
+        for (bit, sibling) in path.bits.iter().zip(&path.siblings) {
+            let sibling = sibling.parse().expect("decimal sibling");
+            node = match bit.as_str() {
+                "0" => hash2(&node, &sibling),
+                "1" => hash2(&sibling, &node),
+                other => panic!("bit {other:?}"),
+            };
+        }
+        assert_eq!(node.to_string(), tree.root, "path of {member:?}");
+    }
+    // Reordering members moves leaves, and so the root.
+    let mut reversed = members.clone();
+    reversed.reverse();
+    assert_ne!(build_tree(&reversed, 20).root, tree.root);
+    assert_eq!(
+        member_field("-1").to_string(),
+        "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+    );
+}
+
 #[test]
 fn poseidon_merkle_runs_with_prover_identity() {
     // Read identity from Prover.toml to reuse current inputs.
     let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    let prover_toml = manifest_dir.join("../zk/Prover.toml");
-    let toml_str = fs::read_to_string(&prover_toml).expect("read Prover.toml");
-    let value: Value = toml::from_str(&toml_str).expect("parse Prover.toml");
+    let value = prover_toml();
     let identity_secret = value["identity_secret"]
         .as_str()
         .expect("identity_secret in Prover.toml");
@@ -64,4 +229,177 @@ fn poseidon_merkle_runs_with_prover_identity() {
     assert_eq!(entry.bits.len(), 20);
     assert!(entry.bits.iter().all(|b| b == "0"));
     assert_eq!(entry.siblings.len(), 20);
+
+    // The native tree must stay byte-identical to the script's.
+    let native = build_tree(&[identity_secret.to_string()], 20);
+    assert_eq!(native.root, res.root);
+    assert_eq!(native.paths[identity_secret].bits, entry.bits);
+    assert_eq!(native.paths[identity_secret].siblings, entry.siblings);
+}
+
+#[test]
+fn merkle_impl_defaults_to_native() {
+    use veilcast_backend::merkle::{MerkleImpl, NodeMerkleScript};
+    let parse = |v: Option<&str>| MerkleImpl::parse(|_| v.map(String::from));
+    assert_eq!(parse(None).unwrap(), MerkleImpl::Native);
+    assert_eq!(
+        parse(Some(" Node ")).unwrap(),
+        MerkleImpl::Node(NodeMerkleScript::default())
+    );
+    assert!(parse(Some("wasm")).is_err());
+
+    use veilcast_backend::merkle::parse_depth;
+    let depth = |v: Option<&str>| parse_depth(|_| v.map(String::from));
+    assert_eq!(depth(None).unwrap(), 20);
+    assert_eq!(depth(Some(" 8 ")).unwrap(), 8);
+    for bad in ["0", "33", "twenty"] {
+        assert!(depth(Some(bad)).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn incremental_tree_tracks_the_full_rebuild() {
+    use veilcast_backend::merkle::IncrementalTree;
+    let members: Vec<String> = (0..10).map(|i| format!("{}", 1000 + i * 7)).collect();
+    let mut tree = IncrementalTree::new(20, HashScheme::Poseidon);
+    assert_eq!(tree.root, build_tree(&[], 20).root);
+    for n in 1..=members.len() {
+        tree.push(&members[n - 1]).unwrap();
+        assert_eq!(tree.leaf_count, n as u64);
+        assert_eq!(tree.root, build_tree(&members[..n], 20).root, "{n} members");
+    }
+    assert_eq!(
+        IncrementalTree::from_members(&members, 20, HashScheme::Poseidon).unwrap(),
+        tree
+    );
+
+    // The frontier survives a round trip through its stored form.
+    let mut restored: IncrementalTree =
+        serde_json::from_value(serde_json::to_value(&tree).unwrap()).unwrap();
+    restored.push("extra").unwrap();
+    let mut all = members.clone();
+    all.push("extra".into());
+    assert_eq!(restored.root, build_tree(&all, 20).root);
+
+    // Shallower trees follow the same construction.
+    let shallow = IncrementalTree::from_members(&members, 8, HashScheme::Poseidon).unwrap();
+    assert_eq!(shallow.depth(), 8);
+    assert_eq!(shallow.root, build_tree(&members, 8).root);
+}
+
+#[test]
+fn member_lists_are_checked_against_the_tree() {
+    use veilcast_backend::error::MembershipError;
+    use veilcast_backend::merkle::{check_members, near_capacity, IncrementalTree};
+    let members: Vec<String> = ["1", "0x2a", "3", "4"].map(String::from).to_vec();
+    assert_eq!(check_members(&members, 2), Ok(()));
+    assert_eq!(
+        check_members(&members, 1),
+        Err(MembershipError::CapacityExceeded {
+            members: 4,
+            capacity: 2,
+            depth: 1
+        })
+    );
+    let modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+    for bad in ["alice", "", "-1", "0x", modulus] {
+        let mut list = members.clone();
+        list[2] = bad.to_string();
+        assert_eq!(
+            check_members(&list, 20),
+            Err(MembershipError::InvalidMember { index: 2 }),
+            "{bad:?}"
+        );
+    }
+
+    // A full incremental tree refuses the next member with the same error.
+    let mut tree = IncrementalTree::from_members(&members[..2], 1, HashScheme::Poseidon).unwrap();
+    assert!(matches!(
+        tree.push("3"),
+        Err(veilcast_backend::error::AppError::Membership(
+            MembershipError::CapacityExceeded { members: 3, .. }
+        ))
+    ));
+
+    assert!(!near_capacity(819, 10));
+    assert!(near_capacity(820, 10));
+}
+
+#[test]
+fn stored_tree_nodes_reassemble_every_path() {
+    use veilcast_backend::merkle::{assemble_path, tree_nodes};
+    for n in 1..=9 {
+        let members: Vec<String> = (0..n).map(|i| format!("{}", 500 + i * 3)).collect();
+        let tree = build_tree(&members, 6);
+        let nodes: HashMap<(u32, u64), String> = tree_nodes(&members, &tree)
+            .into_iter()
+            .map(|(level, index, hash)| ((level, index), hash))
+            .collect();
+        // Only the siblings paths actually use are kept, never the zero padding.
+        assert!(nodes.values().all(|hash| hash != "0"));
+        for (leaf, member) in members.iter().enumerate() {
+            let path = assemble_path(leaf as u64, 6, &nodes);
+            assert_eq!(path, tree.paths[member], "{n} members, leaf {leaf}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn node_script_reads_members_from_stdin_and_times_out() {
+    use std::time::Duration;
+    use veilcast_backend::merkle::NodeMerkleScript;
+    let dir = tempfile::tempdir().unwrap();
+    let stub = |name: &str, body: &str| {
+        let path = dir.path().join(name);
+        fs::write(&path, body).unwrap();
+        NodeMerkleScript {
+            script: path,
+            timeout: Duration::from_secs(10),
+            ..NodeMerkleScript::default()
+        }
+    };
+    // Echoes what it was sent: the member count as the root, the first member's path.
+    let echo = stub(
+        "echo.mjs",
+        r#"let raw = '';
+process.stdin.on('data', (chunk) => (raw += chunk));
+process.stdin.on('end', () => {
+  const { members, depth } = JSON.parse(raw);
+  const path = { bits: Array(depth).fill('0'), siblings: Array(depth).fill('0') };
+  process.stdout.write(JSON.stringify({ root: String(members.length), depth, paths: { [members[0]]: path } }));
+});
+"#,
+    );
+    let members: Vec<String> = (0..5000).map(|i| format!("member-{i}")).collect();
+    let tree = echo.build(&members, 8).await.unwrap();
+    assert_eq!(tree.root, "5000");
+    assert_eq!(tree.depth, 8);
+    assert_eq!(tree.paths["member-0"].bits.len(), 8);
+
+    let capped = NodeMerkleScript {
+        max_output_bytes: 16,
+        ..echo
+    };
+    let err = capped.build(&members, 8).await.unwrap_err().to_string();
+    assert!(err.contains("exceeds 16 bytes"), "{err}");
+
+    let failing = stub(
+        "fail.mjs",
+        "process.stderr.write('boom '.repeat(5000)); process.exit(3);\n",
+    );
+    let err = failing.build(&members, 8).await.unwrap_err().to_string();
+    assert!(
+        err.contains("boom") && err.contains("[stderr truncated]"),
+        "{err}"
+    );
+    assert!(err.len() < 5000, "stderr is capped");
+
+    let hanging = NodeMerkleScript {
+        timeout: Duration::from_millis(500),
+        ..stub("hang.mjs", "setInterval(() => {}, 1000);\n")
+    };
+    let started = std::time::Instant::now();
+    let err = hanging.build(&members, 8).await.unwrap_err().to_string();
+    assert!(err.contains("did not finish"), "{err}");
+    assert!(started.elapsed() < Duration::from_secs(5));
 }
@@ -0,0 +1,111 @@
+mod common;
+
+use common::ThrowawayDb;
+use sqlx::{Pool, Postgres};
+use veilcast_backend::error::AppError;
+use veilcast_backend::repo::{check_schema_version, run_migrations, MIGRATOR};
+
+async fn tables(pool: &Pool<Postgres>) -> Vec<String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT table_name::TEXT FROM information_schema.tables
+        WHERE table_schema = 'public' AND table_name <> '_sqlx_migrations'
+        ORDER BY table_name
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn migrations_build_the_schema_once_and_refuse_a_newer_database() {
+    let db = ThrowawayDb::create().await;
+    assert_eq!(check_schema_version(&db.pool).await.unwrap(), None);
+
+    run_migrations(&db.pool)
+        .await
+        .expect("migrate a fresh database");
+    let latest = MIGRATOR.iter().map(|m| m.version).max().unwrap();
+    assert_eq!(check_schema_version(&db.pool).await.unwrap(), Some(latest));
+    let created = tables(&db.pool).await;
+    for table in [
+        "commitments",
+        "polls",
+        "poll_secrets",
+        "reveal_batches",
+        "user_stats",
+    ] {
+        assert!(created.iter().any(|t| t == table), "{table} in {created:?}");
+    }
+    // Nothing is pending, so a second boot changes nothing.
+    run_migrations(&db.pool).await.expect("rerun migrations");
+    assert_eq!(tables(&db.pool).await, created);
+
+    sqlx::query(
+        r#"
+        INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+        VALUES ($1, 'from a later release', true, '\x00', 0)
+        "#,
+    )
+    .bind(latest + 1)
+    .execute(&db.pool)
+    .await
+    .unwrap();
+    for err in [
+        check_schema_version(&db.pool).await.unwrap_err(),
+        run_migrations(&db.pool).await.unwrap_err(),
+    ] {
+        let AppError::Validation(message) = err else {
+            panic!("expected a validation error, got {err:?}");
+        };
+        assert!(
+            message.contains(&format!("migration {}", latest + 1)),
+            "{message}"
+        );
+    }
+    db.drop().await;
+}
+
+#[tokio::test]
+async fn databases_set_up_before_migrations_keep_one_commitment_per_identity() {
+    let db = ThrowawayDb::create().await;
+    // The shape init_schema left, before the unique index: a legacy row with no identity
+    // and the same identity committed twice.
+    let baseline = MIGRATOR.iter().next().unwrap();
+    sqlx::raw_sql(&baseline.sql)
+        .execute(&db.pool)
+        .await
+        .expect("pre-migrations schema");
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (question, options, commit_phase_end, reveal_phase_end, membership_root)
+        VALUES ('Legacy', '["Yes","No"]', now(), now(), 'root');
+        INSERT INTO commitments (poll_id, commitment, identity_secret, recorded_at) VALUES
+            (1, 'c-legacy', '', now()),
+            (1, 'c-old', 'alice', now() - interval '1 hour'),
+            (1, 'c-new', 'alice', now());
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    run_migrations(&db.pool)
+        .await
+        .expect("migrate a pre-migrations database");
+    let kept: Vec<(String, String)> = sqlx::query_as(
+        r#"SELECT commitment, identity_secret FROM commitments ORDER BY commitment"#,
+    )
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        kept,
+        [
+            ("c-legacy".to_string(), "c-legacy".to_string()),
+            ("c-new".to_string(), "alice".to_string()),
+        ]
+    );
+    db.drop().await;
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::ThrowawayDb;
+use veilcast_backend::error::AppError;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollStore, StoredCommit};
+use veilcast_backend::zk::CURRENT_PROOF_VERSION;
+
+fn commit<'a>(identity: &'a str, onchain_commit: bool) -> StoredCommit<'a> {
+    StoredCommit {
+        poll_id: 1,
+        choice: 0,
+        commitment: identity,
+        identity_secret: identity,
+        secret: "server-secret",
+        nullifier: identity,
+        proof: "0x00",
+        public_inputs: &[],
+        proof_version: CURRENT_PROOF_VERSION,
+        auto_reveal: true,
+        onchain_commit,
+    }
+}
+
+async fn commit_state(db: &ThrowawayDb, identity: &str) -> String {
+    sqlx::query_scalar(r#"SELECT commit_state FROM commitments WHERE identity_secret = $1"#)
+        .bind(identity)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+async fn to_reveal(store: &PgStore, onchain_commits: bool) -> Vec<String> {
+    store
+        .commits_to_sync(
+            Utc::now(),
+            Duration::zero(),
+            Duration::zero(),
+            onchain_commits,
+            10,
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|c| c.commitment)
+        .collect()
+}
+
+#[tokio::test]
+async fn only_confirmed_commitments_are_revealed_with_onchain_commits() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    sqlx::query(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root)
+        VALUES (1, 'Chain', '["Yes","No"]', now() - interval '1 minute', now() + interval '1 hour', 'root')
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    // `legacy` predates ONCHAIN_COMMITS; `fresh` is queued by the insert itself.
+    store.record_commit(commit("legacy", false)).await.unwrap();
+    store.record_commit(commit("fresh", true)).await.unwrap();
+    assert_eq!(commit_state(&db, "legacy").await, "offchain");
+    assert_eq!(commit_state(&db, "fresh").await, "queued");
+    assert!(to_reveal(&store, true).await.is_empty());
+    assert_eq!(to_reveal(&store, false).await, ["legacy"]);
+
+    sqlx::query(r#"UPDATE commitments SET commit_state = 'confirmed' WHERE commitment = 'fresh'"#)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(to_reveal(&store, true).await, ["fresh"]);
+
+    let mut replaced = commit("legacy", true);
+    replaced.choice = 1;
+    store.replace_commit(replaced).await.unwrap();
+    assert_eq!(commit_state(&db, "legacy").await, "queued");
+
+    // `fresh` is on chain now: it can't be swapped or withdrawn.
+    let mut replaced = commit("fresh", true);
+    replaced.commitment = "other";
+    let err = store.replace_commit(replaced).await.unwrap_err();
+    assert!(matches!(err, AppError::Conflict(_)), "{err:?}");
+    let err = store.delete_commit(1, "fresh").await.unwrap_err();
+    assert!(matches!(err, AppError::Conflict(_)), "{err:?}");
+    assert_eq!(commit_state(&db, "fresh").await, "confirmed");
+    // A queued one still can.
+    store.delete_commit(1, "legacy").await.unwrap();
+    db.drop().await;
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::ThrowawayDb;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollStore};
+
+#[tokio::test]
+async fn prune_keeps_old_polls_that_are_still_open() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    // Both were created two days ago; only poll 1 has closed.
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root, created_at)
+        VALUES (1, 'Closed', '["Yes","No"]', now() - interval '2 hours', now() - interval '1 hour', 'root', now() - interval '2 days'),
+               (2, 'Two weeks', '["Yes","No"]', now() + interval '7 days', now() + interval '8 days', 'root', now() - interval '2 days');
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    let before = Utc::now() - Duration::hours(1) + Duration::minutes(1);
+    assert_eq!(store.prune_stale_polls(before, true).await.unwrap(), [1]);
+    assert_eq!(store.prune_stale_polls(before, false).await.unwrap(), [1]);
+    assert!(store.get_poll(2).await.is_ok());
+
+    db.drop().await;
+}
@@ -0,0 +1,113 @@
+use ethers::middleware::{Middleware, SignerMiddleware};
+use ethers::providers::{Http, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Eip1559TransactionRequest, U256};
+use ethers::utils::{Anvil, AnvilInstance};
+use veilcast_backend::error::AppError;
+use veilcast_backend::gas::{price_tx, FeeMode, GasConfig};
+
+fn relayer(anvil: &AnvilInstance) -> SignerMiddleware<Provider<Http>, LocalWallet> {
+    let provider = Provider::<Http>::try_from(anvil.endpoint()).expect("http provider");
+    let wallet: LocalWallet = anvil.keys()[0].clone().into();
+    SignerMiddleware::new(provider, wallet.with_chain_id(anvil.chain_id()))
+}
+
+fn transfer(anvil: &AnvilInstance) -> TypedTransaction {
+    Eip1559TransactionRequest::new()
+        .from(anvil.addresses()[0])
+        .to(anvil.addresses()[1])
+        .value(1u64)
+        .into()
+}
+
+#[tokio::test]
+async fn pre_london_chains_fall_back_to_legacy_gas_prices() {
+    // Berlin predates EIP-1559, so blocks carry no base fee.
+    let anvil = Anvil::new().args(["--hardfork", "berlin"]).spawn();
+    let client = relayer(&anvil);
+    let mode = FeeMode::detect(&client).await.expect("detect fee mode");
+    assert_eq!(mode, FeeMode::Legacy);
+
+    let config = GasConfig {
+        max_fee_per_gas_gwei: Some(1_000),
+        max_priority_fee_gwei: Some(2),
+        gas_limit_cap: Some(100_000),
+        batch_reveal_max_gas: None,
+    };
+    let mut tx = transfer(&anvil);
+    price_tx(&client, &mut tx, &config, mode, "transfer")
+        .await
+        .expect("price legacy tx");
+    let TypedTransaction::Legacy(ref legacy) = tx else {
+        panic!("expected a legacy tx, got {tx:?}");
+    };
+    let node_price = client.get_gas_price().await.unwrap();
+    assert_eq!(legacy.gas_price, Some(node_price));
+    assert_eq!(legacy.gas, Some(U256::from(21_000)));
+
+    let receipt = client
+        .send_transaction(tx, None)
+        .await
+        .expect("send legacy tx")
+        .await
+        .expect("legacy tx mined")
+        .expect("legacy tx receipt");
+    assert_eq!(receipt.status, Some(1u64.into()));
+}
+
+#[tokio::test]
+async fn london_chains_get_capped_eip1559_fees() {
+    let anvil = Anvil::new().spawn();
+    let client = relayer(&anvil);
+    let mode = FeeMode::detect(&client).await.expect("detect fee mode");
+    assert_eq!(mode, FeeMode::Eip1559);
+
+    let config = GasConfig {
+        max_fee_per_gas_gwei: Some(50),
+        max_priority_fee_gwei: Some(2),
+        gas_limit_cap: None,
+        batch_reveal_max_gas: None,
+    };
+    let mut tx = transfer(&anvil);
+    price_tx(&client, &mut tx, &config, mode, "transfer")
+        .await
+        .expect("price eip1559 tx");
+    let TypedTransaction::Eip1559(ref request) = tx else {
+        panic!("expected an eip1559 tx, got {tx:?}");
+    };
+    let gwei = U256::from(1_000_000_000u64);
+    assert_eq!(request.max_fee_per_gas, Some(gwei * 50));
+    assert_eq!(request.max_priority_fee_per_gas, Some(gwei * 2));
+    let receipt = client
+        .send_transaction(tx, None)
+        .await
+        .expect("send eip1559 tx")
+        .await
+        .expect("eip1559 tx mined")
+        .expect("eip1559 tx receipt");
+    assert_eq!(receipt.status, Some(1u64.into()));
+}
+
+#[tokio::test]
+async fn calls_estimated_above_the_gas_cap_are_not_sent() {
+    let anvil = Anvil::new().spawn();
+    let client = relayer(&anvil);
+    let config = GasConfig {
+        gas_limit_cap: Some(20_000),
+        ..GasConfig::default()
+    };
+    let mut tx = transfer(&anvil);
+    let err = price_tx(&client, &mut tx, &config, FeeMode::Eip1559, "transfer")
+        .await
+        .expect_err("a 21000 gas transfer is above the cap");
+    let AppError::External(message) = err else {
+        panic!("expected an external error, got {err:?}");
+    };
+    assert!(message.contains("GAS_LIMIT_CAP (20000)"), "{message}");
+    let nonce = client
+        .get_transaction_count(anvil.addresses()[0], None)
+        .await
+        .unwrap();
+    assert_eq!(nonce, U256::zero());
+}
@@ -0,0 +1,110 @@
+use ethers::middleware::{Middleware, SignerMiddleware};
+use ethers::providers::{Http, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{TransactionReceipt, TransactionRequest, U256};
+use ethers::utils::{Anvil, AnvilInstance};
+use veilcast_backend::error::{AppError, AppResult};
+use veilcast_backend::relayer::RelayerPool;
+
+type Wallet = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// Anvil's first dev account, then a key anvil never funded.
+fn funded_and_unfunded(anvil: &AnvilInstance) -> Vec<Wallet> {
+    let provider = Provider::<Http>::try_from(anvil.endpoint()).expect("http provider");
+    let funded: LocalWallet = anvil.keys()[0].clone().into();
+    let unfunded = LocalWallet::new(&mut rand::thread_rng());
+    [unfunded, funded]
+        .into_iter()
+        .map(|wallet| {
+            SignerMiddleware::new(provider.clone(), wallet.with_chain_id(anvil.chain_id()))
+        })
+        .collect()
+}
+
+/// Stands in for a batchReveal: any transaction the wallet has to pay for.
+async fn send_batch(wallet: &Wallet, to: ethers::types::Address) -> AppResult<TransactionReceipt> {
+    let tx = TransactionRequest::new().to(to).value(1u64);
+    wallet
+        .send_transaction(tx, None)
+        .await
+        .map_err(|e| AppError::External(format!("send batch failed: {e}")))?
+        .await
+        .map_err(|e| AppError::External(format!("batch pending failed: {e}")))?
+        .ok_or_else(|| AppError::External("batch dropped".into()))
+}
+
+#[tokio::test]
+async fn batches_land_through_the_funded_wallet_and_the_unfunded_one_is_flagged() {
+    let anvil = Anvil::new().spawn();
+    let wallets = funded_and_unfunded(&anvil);
+    let (unfunded, funded) = (wallets[0].address(), wallets[1].address());
+    let pool = RelayerPool::new(wallets, Some(1), 3).expect("two wallets");
+
+    let best = pool.refresh_balances().await.expect("balances read");
+    assert_eq!(
+        best,
+        pool.primary()
+            .provider()
+            .get_balance(funded, None)
+            .await
+            .unwrap()
+    );
+
+    let to = anvil.addresses()[1];
+    for _ in 0..4 {
+        let receipt = pool
+            .send(|wallet| send_batch(wallet, to))
+            .await
+            .expect("batch lands");
+        assert_eq!(receipt.from, funded);
+        assert_eq!(receipt.status, Some(1u64.into()));
+    }
+
+    let health = pool.health();
+    assert_eq!(health[0].address, unfunded);
+    assert_eq!(health[0].balance, Some(U256::zero()));
+    assert!(health[0].low_funds && !health[0].healthy);
+    assert_eq!(health[1].address, funded);
+    assert!(health[1].healthy);
+    assert_eq!(health[1].consecutive_failures, 0);
+
+    let provider = pool.primary().provider();
+    assert_eq!(
+        provider.get_transaction_count(funded, None).await.unwrap(),
+        4.into()
+    );
+    assert_eq!(
+        provider
+            .get_transaction_count(unfunded, None)
+            .await
+            .unwrap(),
+        0.into()
+    );
+}
+
+#[tokio::test]
+async fn wallets_whose_sends_keep_failing_are_skipped() {
+    let anvil = Anvil::new().spawn();
+    let wallets = funded_and_unfunded(&anvil);
+    let funded = wallets[1].address();
+    // No balance floor: the unfunded wallet is only found out by its sends failing.
+    let pool = RelayerPool::new(wallets, None, 1).expect("two wallets");
+
+    let to = anvil.addresses()[1];
+    pool.send(|wallet| send_batch(wallet, to))
+        .await
+        .expect_err("the unfunded wallet cannot pay for gas");
+    for _ in 0..3 {
+        let receipt = pool
+            .send(|wallet| send_batch(wallet, to))
+            .await
+            .expect("batch lands");
+        assert_eq!(receipt.from, funded);
+    }
+
+    let health = pool.health();
+    assert!(!health[0].healthy && !health[0].low_funds);
+    assert_eq!(health[0].consecutive_failures, 1);
+    assert!(health[0].last_error.is_some());
+    assert!(health[1].healthy);
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::ThrowawayDb;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollIndexSink, PollStore};
+
+async fn stats(db: &ThrowawayDb) -> Vec<(String, i64, i64)> {
+    sqlx::query_as(
+        r#"SELECT identity_secret, xp, total_votes FROM user_stats ORDER BY identity_secret"#,
+    )
+    .fetch_all(&db.pool)
+    .await
+    .unwrap()
+}
+
+async fn resolved(db: &ThrowawayDb, poll_id: i64) -> bool {
+    sqlx::query_scalar(r#"SELECT resolved FROM polls WHERE id = $1"#)
+        .bind(poll_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn a_failure_part_way_through_resolution_credits_nobody() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    // bob's choice is sealed but this store has no key, so crediting him fails after alice
+    // has been credited.
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root)
+        VALUES (1, 'Web', '["Yes","No"]', now(), now(), 'root'),
+               (2, 'Chain', '["Yes","No"]', now(), now(), 'root');
+        INSERT INTO user_stats (identity_secret, username) VALUES ('alice', 'alice'), ('bob', 'bob');
+        INSERT INTO commitments (poll_id, commitment, identity_secret, choice, choice_enc) VALUES
+            (1, 'c1', 'alice', 0, ''),
+            (1, 'c2', 'bob', -1, 'enc:sealed'),
+            (2, 'c3', 'alice', 0, ''),
+            (2, 'c4', 'bob', -1, 'enc:sealed');
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+    let untouched = vec![("alice".to_string(), 0, 0), ("bob".to_string(), 0, 0)];
+
+    assert!(store.resolve_poll(1, Some(0)).await.is_err());
+    assert!(!resolved(&db, 1).await);
+    assert!(store
+        .resolve_poll_from_chain(2, 1, Some("0xabc"))
+        .await
+        .is_err());
+    assert!(!resolved(&db, 2).await);
+    assert_eq!(stats(&db).await, untouched);
+
+    sqlx::query(
+        r#"UPDATE commitments SET choice = 1, choice_enc = '' WHERE identity_secret = 'bob'"#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+    store.resolve_poll(1, Some(0)).await.expect("resolve");
+    store
+        .resolve_poll_from_chain(2, 1, Some("0xabc"))
+        .await
+        .expect("resolve from chain");
+    assert!(resolved(&db, 1).await && resolved(&db, 2).await);
+    // alice was right once and bob once: each gets 20 + 5 xp over two votes.
+    assert_eq!(
+        stats(&db).await,
+        vec![("alice".to_string(), 25, 2), ("bob".to_string(), 25, 2)]
+    );
+    store.resolve_poll(1, Some(0)).await.unwrap_err();
+    assert_eq!(stats(&db).await[0], ("alice".to_string(), 25, 2));
+    db.drop().await;
+}
+
+#[tokio::test]
+async fn votes_count_once_however_many_times_a_poll_is_resolved() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root)
+        VALUES (1, 'Web', '["Yes","No"]', now(), now(), 'root');
+        INSERT INTO user_stats (identity_secret, username) VALUES ('alice', 'alice'), ('bob', 'bob');
+        INSERT INTO commitments (poll_id, commitment, identity_secret, choice) VALUES
+            (1, 'c1', 'alice', 0),
+            (1, 'c2', 'bob', 1);
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+    let credited = vec![("alice".to_string(), 20, 1), ("bob".to_string(), 5, 1)];
+
+    // The owner resolves, then the contract's PollResolved (twice, from a backfill) arrives.
+    store.resolve_poll(1, Some(0)).await.expect("resolve");
+    for _ in 0..2 {
+        store
+            .resolve_poll_from_chain(1, 0, Some("0xabc"))
+            .await
+            .expect("resolve from chain");
+    }
+    assert_eq!(stats(&db).await, credited);
+    store.backfill_user_stats().await.expect("backfill");
+    assert_eq!(stats(&db).await, credited);
+    db.drop().await;
+}
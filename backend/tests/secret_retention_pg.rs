@@ -0,0 +1,105 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::ThrowawayDb;
+use veilcast_backend::error::AppError;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollStore, SecretScrubCounts};
+
+#[tokio::test]
+async fn scrubbing_removes_secrets_and_proofs_and_leaves_an_audit_row() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    // Poll 1 was resolved and synced long ago, poll 2 only just, poll 3 still has reveals to go.
+    // Poll 4 was resolved before results_applied_at was tracked.
+    sqlx::raw_sql(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root,
+                           resolved, commit_sync_completed, results_applied_at)
+        VALUES (1, 'Old', '["Yes","No"]', now() - interval '40 days', now() - interval '40 days', 'root',
+                true, true, now() - interval '39 days'),
+               (2, 'Recent', '["Yes","No"]', now() - interval '40 days', now() - interval '40 days', 'root',
+                true, true, now()),
+               (3, 'Open', '["Yes","No"]', now(), now(), 'root', false, false, NULL),
+               (4, 'Legacy', '["Yes","No"]', now() - interval '90 days', now() - interval '90 days', 'root',
+                true, true, NULL);
+        INSERT INTO poll_secrets (poll_id, identity_secret, secret) VALUES
+            (1, 'alice', 's1'), (1, 'bob', 's2'), (3, 'alice', 's3');
+        INSERT INTO commitments (poll_id, commitment, identity_secret, secret, choice, proof, public_inputs) VALUES
+            (1, 'c1', 'alice', 's1', 0, '0xp1', '{1,c1}'),
+            (1, 'c2', 'bob', 's2', 1, '0xp2', '{1,c2}'),
+            (3, 'c3', 'alice', 's3', 0, '0xp3', '{1,c3}');
+        INSERT INTO proof_cache (poll_id, identity_secret, request_hash, bundle) VALUES
+            (1, 'alice', 'h', '{}');
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    let before = Utc::now() - Duration::days(30);
+    assert_eq!(store.secret_scrub_candidates(before).await.unwrap(), [1, 4]);
+    let expected = SecretScrubCounts {
+        secrets_deleted: 2,
+        commitments_scrubbed: 2,
+        cached_proofs_deleted: 1,
+    };
+    assert_eq!(
+        store.scrub_poll_secrets(1, "admin", true).await.unwrap(),
+        expected
+    );
+    let alice = store.commitment_for_identity(1, "alice").await.unwrap();
+    assert_eq!(alice.unwrap().proof, "0xp1");
+
+    assert_eq!(
+        store
+            .scrub_poll_secrets(1, "retention", false)
+            .await
+            .unwrap(),
+        expected
+    );
+    let alice = store
+        .commitment_for_identity(1, "alice")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(alice.secret.is_empty() && alice.proof.is_empty() && alice.public_inputs.is_empty());
+    assert!(alice.scrubbed_at.is_some());
+    assert_eq!(alice.choice, 0);
+    let status = store.commitment_receipt_status(1, "c2").await.unwrap();
+    assert!(status.is_some_and(|s| s.scrubbed));
+    let left: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM poll_secrets"#)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(left, 1);
+    let audit: Vec<(i64, i64, i64, i64, String)> = sqlx::query_as(
+        r#"
+        SELECT poll_id, secrets_deleted, commitments_scrubbed, cached_proofs_deleted, requested_by
+        FROM secret_scrubs
+        "#,
+    )
+    .fetch_all(&db.pool)
+    .await
+    .unwrap();
+    assert_eq!(audit, [(1, 2, 2, 1, "retention".to_string())]);
+    assert_eq!(store.secret_scrub_candidates(before).await.unwrap(), [4]);
+
+    let err = store
+        .scrub_poll_secrets(3, "admin", false)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some("secrets_still_needed"));
+    assert!(matches!(
+        store.scrub_poll_secrets(9, "admin", true).await,
+        Err(AppError::NotFound)
+    ));
+    db.drop().await;
+}
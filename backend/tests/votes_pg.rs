@@ -0,0 +1,59 @@
+mod common;
+
+use common::ThrowawayDb;
+use veilcast_backend::error::AppError;
+use veilcast_backend::merkle::{HashScheme, DEFAULT_MERKLE_DEPTH};
+use veilcast_backend::repo::{DbSettings, PgStore, PollStore, StoredVote, VoteSource};
+
+#[tokio::test]
+async fn concurrent_reveals_of_one_nullifier_store_exactly_one_vote() {
+    let db = ThrowawayDb::create().await;
+    let store = PgStore::connect(
+        &db.url,
+        &DbSettings::default(),
+        DEFAULT_MERKLE_DEPTH,
+        HashScheme::default(),
+    )
+    .await
+    .expect("connect");
+    sqlx::query(
+        r#"
+        INSERT INTO polls (id, question, options, commit_phase_end, reveal_phase_end, membership_root)
+        VALUES (1, 'Race', '["Yes","No"]', now(), now(), 'root')
+        "#,
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    for round in 0..20 {
+        let nullifier = format!("0xrace{round}");
+        let submit = |choice: u8| {
+            store.record_vote(StoredVote {
+                poll_id: 1,
+                nullifier: &nullifier,
+                choice,
+                source: VoteSource::Api,
+            })
+        };
+        let (a, b) = tokio::join!(submit(0), submit(1));
+        assert_eq!(a.is_ok() as u8 + b.is_ok() as u8, 1, "round {round}");
+        let err = a.err().or(b.err()).unwrap();
+        assert!(
+            matches!(
+                err,
+                AppError::CodedConflict {
+                    code: "nullifier_reused",
+                    ..
+                }
+            ),
+            "round {round}: {err:?}"
+        );
+    }
+    let stored: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM votes"#)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, 20);
+    db.drop().await;
+}
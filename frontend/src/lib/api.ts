@@ -77,11 +77,14 @@ export async function commitVote(
   pollId: number,
   payload: {
     choice: number;
-    secret: string;
+    /** Deprecated: the server uses its own copy; still checked if sent. */
+    secret?: string;
     commitment: string;
     nullifier: string;
     proof: string;
     public_inputs: string[];
+    /** Proof format version; the backend assumes 1 when omitted. */
+    version?: number;
   },
   token?: string,
 ) {
@@ -107,23 +110,75 @@ export async function fetchSecret(pollId: number, token: string): Promise<{ poll
   return res.json();
 }
 
-export async function proveVote(pollId: number, choice: number, secret: string, identitySecret: string) {
+/** Commitment and nullifier for `choice`, hashed server-side exactly as the prover does. */
+export async function computeCommitment(
+  pollId: number,
+  choice: number,
+  token: string,
+): Promise<{ poll_id: number; commitment: string; nullifier: string }> {
+  const res = await fetch(`${API_BASE}/polls/${pollId}/commitment`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ choice }),
+  });
+  if (!res.ok) throw new Error('failed to compute commitment');
+  return res.json();
+}
+
+type ProofBundle = {
+  proof: string;
+  public_inputs: string[];
+  commitment: string;
+  nullifier: string;
+  version?: number;
+};
+
+type ProveJob = {
+  job_id: string;
+  status: 'pending' | 'running' | 'done' | 'failed';
+  bundle?: ProofBundle | null;
+  error?: string | null;
+};
+
+// Proving runs as a background job on the server; poll until it finishes.
+// The server derives the caller's identity and per-poll secret from the token.
+export async function proveVote(pollId: number, choice: number, token: string) {
   const res = await fetch(`${API_BASE}/polls/${pollId}/prove`, {
     method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
-    body: JSON.stringify({ choice, secret, identity_secret: identitySecret }),
+    headers: {
+      'Content-Type': 'application/json',
+      Authorization: `Bearer ${token}`,
+    },
+    body: JSON.stringify({ choice }),
   });
   if (!res.ok) throw new Error('failed to prove');
-  return res.json() as Promise<{ proof: string; public_inputs: string[]; commitment: string; nullifier: string }>;
+  const { job_id } = (await res.json()) as { job_id: string };
+  for (;;) {
+    const jobRes = await fetch(`${API_BASE}/prove_jobs/${job_id}`, {
+      headers: { Authorization: `Bearer ${token}` },
+    });
+    if (!jobRes.ok) throw new Error('failed to fetch proof job');
+    const job = (await jobRes.json()) as ProveJob;
+    if (job.status === 'done' && job.bundle) return job.bundle;
+    if (job.status === 'failed') throw new Error(job.error ?? 'proof job failed');
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+  }
 }
 
 export async function revealVote(
   pollId: number,
-  payload: { proof: string; public_inputs: string[]; commitment: string; nullifier: string },
+  payload: { proof: string; public_inputs: string[]; commitment: string; nullifier: string; version?: number },
+  token: string,
 ) {
   const res = await fetch(`${API_BASE}/polls/${pollId}/reveal`, {
     method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
+    headers: {
+      'Content-Type': 'application/json',
+      Authorization: `Bearer ${token}`,
+    },
     body: JSON.stringify(payload),
   });
   if (!res.ok) throw new Error('failed to reveal');
@@ -78,7 +78,6 @@ export function PollDetailPage() {
         pollId,
         {
           choice,
-          secret: commitSecret,
           commitment: bundle.commitment,
           nullifier: bundle.nullifier,
           proof: bundle.proof,